@@ -0,0 +1,58 @@
+/*
+ * Lecture des lignes : désérialisation d'une ligne aux textes courts, puis
+ * parcours complet d'une table, comme un `select` sans filtre.
+ *
+ * `cargo bench --bench scan`
+ */
+
+use std::cell::RefCell;
+use std::hint::black_box;
+use std::rc::Rc;
+
+use criterion::{Criterion, criterion_group, criterion_main};
+
+use my_db::cursor::Cursor;
+use my_db::pager::Pager;
+use my_db::row::{Email, Id, Row, Username};
+use my_db::statement::Rows;
+use my_db::table::Table;
+
+const NB_ROWS: u64 = 500;
+
+fn row(id: u64) -> Row {
+    Row::new(
+        Id::new(id),
+        Username::new(format!("user{id}")),
+        Email::new(format!("user{id}@mail.com")),
+    )
+}
+
+fn bench_row_deserialize(c: &mut Criterion) {
+    let bytes = <[u8; Row::MAX_SIZE]>::from(row(42));
+    c.bench_function("row_deserialize", |b| {
+        b.iter(|| Row::try_from(black_box(&bytes[..])).ok());
+    });
+}
+
+fn bench_full_scan(c: &mut Criterion) {
+    let mut table = Table::new(Rc::new(Pager::default()));
+    for id in 1..=NB_ROWS {
+        assert!(table.insert(row(id)).is_ok());
+    }
+    let table = Rc::new(RefCell::new(table));
+
+    c.bench_function("full_scan", |b| {
+        b.iter(|| {
+            let Ok(cursor) = Cursor::at_start(table.clone()) else {
+                panic!("The table should be readable.");
+            };
+            let nb_rows = Rows::new(cursor, vec![0, 1, 2], None)
+                .filter(Result::is_ok)
+                .count();
+            assert_eq!(nb_rows, NB_ROWS as usize);
+        });
+    });
+}
+
+criterion_group!(benches, bench_row_deserialize, bench_full_scan);
+criterion_main!(benches);
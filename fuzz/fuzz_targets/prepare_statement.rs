@@ -0,0 +1,17 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use my_db::statement::{prepare_statement, split_statements};
+
+// Toute entrée doit être refusée par une erreur, jamais par une panique.
+fuzz_target!(|data: &[u8]| {
+    let Ok(buffer) = std::str::from_utf8(data) else {
+        return;
+    };
+    for statement in split_statements(buffer) {
+        if let Ok(statement) = prepare_statement(statement) {
+            let _ = statement.to_string();
+        }
+    }
+});
@@ -0,0 +1,35 @@
+#![no_main]
+
+use std::{cell::RefCell, rc::Rc};
+
+use libfuzzer_sys::fuzz_target;
+
+use my_db::btree::NodeView;
+use my_db::integrity::check_integrity;
+use my_db::open::{ReadDataError, read_data_from_bytes};
+use my_db::pager::Pager;
+use my_db::statement::{StatementOutput, execute_statement, prepare_statement};
+use my_db::table::Table;
+
+// Les octets sont lus comme un fichier de sauvegarde, puis l'arbre obtenu est
+// vérifié et parcouru en entier.
+fuzz_target!(|data: &[u8]| {
+    let pages = match read_data_from_bytes(data.to_vec()) {
+        Ok(pages) | Err(ReadDataError::FileIsCorrupted { pages, .. }) => pages,
+        Err(_) => return,
+    };
+    let pager = Rc::new(Pager::from_pages(pages));
+    let table = Rc::new(RefCell::new(Table::new(pager)));
+
+    let _ = check_integrity(&table.borrow());
+
+    let Ok(select) = prepare_statement("select") else {
+        return;
+    };
+    if let Ok(StatementOutput::Select(rows)) = execute_statement(table, select) {
+        // Une chaîne de feuilles qui boucle n'est pas détectée par le curseur :
+        // le parcours est borné par le nombre de lignes qu'un fichier peut contenir.
+        rows.take(Pager::MAX_PAGES * NodeView::LEAF_NODE_MAX_CELLS)
+            .for_each(drop);
+    }
+});
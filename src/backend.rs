@@ -0,0 +1,331 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::rc::Rc;
+
+/*
+ * Stockage du fichier de sauvegarde lu par le pager : le fichier ouvert, ou
+ * son contenu gardé en mémoire pour que les tests n'écrivent aucun fichier
+ * temporaire et se rejouent à l'identique.
+ */
+pub trait PagerBackend: Read + Write + Seek + std::fmt::Debug {
+    /// Taille du contenu en octets.
+    fn get_len(&self) -> io::Result<u64>;
+
+    /// Tronque ou allonge le contenu, comme `File::set_len`.
+    fn set_len(&mut self, len: u64) -> io::Result<()>;
+
+    /// Rend durables les écritures déjà faites, comme `File::sync_all`.
+    fn sync(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Verrou consultatif sur tout le contenu, refusé avec `WouldBlock` au
+    /// lieu d'attendre s'il est déjà pris. Il est rendu à la fermeture.
+    fn try_lock(&self, _mode: LockMode) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Remplit `buf` avec les octets à partir de `offset`.
+    fn read_at(&mut self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        let _ = self.seek(SeekFrom::Start(offset))?;
+        self.read_exact(buf)
+    }
+
+    /// Écrit tout `buf` à partir de `offset`, en allongeant le contenu si besoin.
+    fn write_at(&mut self, buf: &[u8], offset: u64) -> io::Result<()> {
+        let _ = self.seek(SeekFrom::Start(offset))?;
+        self.write_all(buf)
+    }
+}
+impl PagerBackend for File {
+    fn get_len(&self) -> io::Result<u64> {
+        self.metadata().map(|metadata| metadata.len())
+    }
+
+    fn set_len(&mut self, len: u64) -> io::Result<()> {
+        File::set_len(self, len)
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        self.sync_all()
+    }
+
+    fn try_lock(&self, mode: LockMode) -> io::Result<()> {
+        match mode {
+            LockMode::Shared => File::try_lock_shared(self),
+            LockMode::Exclusive => File::try_lock(self),
+        }
+        .map_err(io::Error::from)
+    }
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Clone, Copy, PartialEq)]
+pub enum LockMode {
+    /// Plusieurs lecteurs peuvent tenir le verrou en même temps.
+    Shared,
+    Exclusive,
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Clone, Copy, PartialEq)]
+pub enum OpenMode {
+    /// Le fichier doit exister, et n'est pas modifié.
+    ReadOnly,
+    /// Le fichier est créé s'il n'existe pas, son contenu est gardé sinon.
+    ReadWrite,
+    /// Le fichier est créé, ou vidé s'il existe.
+    Create,
+}
+
+/*
+ * Système de fichiers vu par le pager : ouverture des fichiers de sauvegarde
+ * et opérations sur les chemins utilisées par les sauvegardes atomiques. Le
+ * pager n'appelle jamais `std::fs` directement, une autre implémentation peut
+ * donc garder les fichiers en mémoire, injecter des pannes ou les stocker à
+ * distance.
+ */
+pub trait Vfs: std::fmt::Debug {
+    fn open(&self, path: &str, mode: OpenMode) -> io::Result<Box<dyn PagerBackend>>;
+
+    fn exists(&self, path: &str) -> bool;
+
+    /// Remplace `to` par `from` en une seule opération.
+    fn rename(&self, from: &str, to: &str) -> io::Result<()>;
+
+    fn remove(&self, path: &str) -> io::Result<()>;
+
+    /// Rend durables la création ou le renommage de `path` dans son dossier.
+    fn sync_dir(&self, path: &str) -> io::Result<()>;
+}
+
+/// Les fichiers du système, l'implémentation par défaut.
+#[derive(Debug, Default)]
+pub struct StdVfs;
+impl Vfs for StdVfs {
+    fn open(&self, path: &str, mode: OpenMode) -> io::Result<Box<dyn PagerBackend>> {
+        let mut options = OpenOptions::new();
+        let _ = match mode {
+            OpenMode::ReadOnly => options.read(true),
+            OpenMode::ReadWrite => options.read(true).write(true).create(true).truncate(false),
+            OpenMode::Create => options.read(true).write(true).create(true).truncate(true),
+        };
+        Ok(Box::new(options.open(path)?))
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        Path::new(path).exists()
+    }
+
+    fn rename(&self, from: &str, to: &str) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    fn remove(&self, path: &str) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    #[cfg(unix)]
+    fn sync_dir(&self, path: &str) -> io::Result<()> {
+        let parent = match Path::new(path).parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        File::open(parent)?.sync_all()
+    }
+
+    /// Les dossiers ne peuvent pas être synchronisés sur les autres plateformes.
+    #[cfg(not(unix))]
+    fn sync_dir(&self, _path: &str) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Contenu d'un fichier de `MemoryVfs`, partagé par ses ouvertures.
+type FileBytes = Rc<RefCell<Vec<u8>>>;
+
+/*
+ * Fichiers gardés en mémoire sous leur chemin. Les clones partagent les mêmes
+ * fichiers, et un fichier ouvert garde son contenu après un renommage ou une
+ * suppression, comme sous Unix.
+ */
+#[derive(Debug, Default, Clone)]
+pub struct MemoryVfs {
+    files: Rc<RefCell<HashMap<String, FileBytes>>>,
+}
+impl MemoryVfs {
+    /// Copie du contenu du fichier `path`, `None` s'il n'existe pas.
+    pub fn get_bytes(&self, path: &str) -> Option<Vec<u8>> {
+        self.files
+            .borrow()
+            .get(path)
+            .map(|bytes| bytes.borrow().clone())
+    }
+
+    pub fn set_bytes(&self, path: &str, bytes: Vec<u8>) {
+        let _ = self
+            .files
+            .borrow_mut()
+            .insert(path.to_owned(), Rc::new(RefCell::new(bytes)));
+    }
+}
+impl Vfs for MemoryVfs {
+    fn open(&self, path: &str, mode: OpenMode) -> io::Result<Box<dyn PagerBackend>> {
+        let mut files = self.files.borrow_mut();
+        let bytes = match (mode, files.get(path)) {
+            (OpenMode::ReadOnly | OpenMode::ReadWrite, Some(bytes)) => bytes.clone(),
+            (OpenMode::ReadOnly, None) => return Err(io::ErrorKind::NotFound.into()),
+            (OpenMode::ReadWrite | OpenMode::Create, _) => {
+                let bytes = Rc::new(RefCell::new(Vec::new()));
+                let _ = files.insert(path.to_owned(), bytes.clone());
+                bytes
+            }
+        };
+        Ok(Box::new(MemoryFile {
+            bytes,
+            position: 0,
+            writable: mode != OpenMode::ReadOnly,
+        }))
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.files.borrow().contains_key(path)
+    }
+
+    fn rename(&self, from: &str, to: &str) -> io::Result<()> {
+        let mut files = self.files.borrow_mut();
+        let bytes = files.remove(from).ok_or(io::ErrorKind::NotFound)?;
+        let _ = files.insert(to.to_owned(), bytes);
+        Ok(())
+    }
+
+    fn remove(&self, path: &str) -> io::Result<()> {
+        let _ = self
+            .files
+            .borrow_mut()
+            .remove(path)
+            .ok_or(io::ErrorKind::NotFound)?;
+        Ok(())
+    }
+
+    fn sync_dir(&self, _path: &str) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Fichier ouvert par un `MemoryVfs`.
+#[derive(Debug)]
+struct MemoryFile {
+    bytes: FileBytes,
+    position: u64,
+    writable: bool,
+}
+impl MemoryFile {
+    fn check_writable(&self) -> io::Result<()> {
+        if self.writable {
+            Ok(())
+        } else {
+            Err(io::ErrorKind::PermissionDenied.into())
+        }
+    }
+}
+impl Read for MemoryFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let bytes = self.bytes.borrow();
+        let start = usize::try_from(self.position).map_or(bytes.len(), |p| p.min(bytes.len()));
+        let len = buf.len().min(bytes.len() - start);
+        buf[..len].copy_from_slice(&bytes[start..start + len]);
+        self.position += len as u64;
+        Ok(len)
+    }
+}
+impl Write for MemoryFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.check_writable()?;
+        let mut bytes = self.bytes.borrow_mut();
+        let mut cursor = io::Cursor::new(&mut *bytes);
+        cursor.set_position(self.position);
+        let len = cursor.write(buf)?;
+        self.position = cursor.position();
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+impl Seek for MemoryFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let len = self.bytes.borrow().len() as u64;
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => len.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+        self.position = position.ok_or(io::ErrorKind::InvalidInput)?;
+        Ok(self.position)
+    }
+}
+impl PagerBackend for MemoryFile {
+    fn get_len(&self) -> io::Result<u64> {
+        Ok(self.bytes.borrow().len() as u64)
+    }
+
+    fn set_len(&mut self, len: u64) -> io::Result<()> {
+        self.check_writable()?;
+        let len = usize::try_from(len).map_err(|_| io::ErrorKind::OutOfMemory)?;
+        self.bytes.borrow_mut().resize(len, 0);
+        Ok(())
+    }
+}
+
+/// Contenu d'un fichier de sauvegarde, entièrement en mémoire.
+#[derive(Debug, Default)]
+pub struct MemoryBackend(io::Cursor<Vec<u8>>);
+impl MemoryBackend {
+    /// Nom du contenu dans les messages d'erreur, faute de chemin.
+    pub const NAME: &str = ":memory:";
+
+    pub fn new(bytes: Vec<u8>) -> Self {
+        Self(io::Cursor::new(bytes))
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0.into_inner()
+    }
+}
+impl Read for MemoryBackend {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+impl Write for MemoryBackend {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+impl Seek for MemoryBackend {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.0.seek(pos)
+    }
+}
+impl PagerBackend for MemoryBackend {
+    fn get_len(&self) -> io::Result<u64> {
+        Ok(self.0.get_ref().len() as u64)
+    }
+
+    fn set_len(&mut self, len: u64) -> io::Result<()> {
+        let len = usize::try_from(len).map_err(|_| io::ErrorKind::OutOfMemory)?;
+        self.0.get_mut().resize(len, 0);
+        Ok(())
+    }
+}
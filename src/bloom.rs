@@ -0,0 +1,79 @@
+/*
+ * Filtre de Bloom sur les clés de la table : `may_contain` peut se tromper en
+ * répondant `true` pour une clé absente, jamais en répondant `false` pour une
+ * clé présente. Une clé absente est donc le plus souvent écartée sans lire
+ * l'arbre.
+ *
+ * La taille est fixe : la table tient au plus dans `Pager::MAX_PAGES` pages,
+ * soit quelques centaines de lignes, pour moins de 1 % de faux positifs.
+ */
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Clone)]
+pub struct BloomFilter {
+    bits: Box<[u64]>,
+}
+impl BloomFilter {
+    pub const NB_BITS: usize = 8192;
+    pub const NB_HASHES: u64 = 4;
+
+    /// Positions des bits de la clé, par double hachage.
+    fn bit_positions(key: u64) -> impl Iterator<Item = usize> {
+        let hash = splitmix64(key);
+        let (h1, h2) = (hash & 0xffff_ffff, (hash >> 32) | 1);
+        (0..Self::NB_HASHES)
+            .map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % Self::NB_BITS as u64) as usize)
+    }
+
+    pub fn insert(&mut self, key: u64) {
+        for bit in Self::bit_positions(key) {
+            self.bits[bit / 64] |= 1 << (bit % 64);
+        }
+    }
+
+    pub fn may_contain(&self, key: u64) -> bool {
+        Self::bit_positions(key).all(|bit| self.bits[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+
+    pub fn clear(&mut self) {
+        self.bits.fill(0);
+    }
+}
+impl Default for BloomFilter {
+    fn default() -> Self {
+        Self {
+            bits: vec![0; Self::NB_BITS / 64].into_boxed_slice(),
+        }
+    }
+}
+
+/// Mélange les bits de la clé : des ids qui se suivent donnent des
+/// positions sans rapport entre elles.
+fn splitmix64(key: u64) -> u64 {
+    let mut z = key.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod bloom_test {
+    use super::*;
+
+    #[test]
+    fn test_bloom_filter() {
+        let mut filter = BloomFilter::default();
+        for key in 1..=700 {
+            filter.insert(key);
+        }
+        assert!((1..=700).all(|key| filter.may_contain(key)));
+
+        let false_positives = (1_000..11_000)
+            .filter(|key| filter.may_contain(*key))
+            .count();
+        assert!(false_positives < 100, "{false_positives} false positives.");
+
+        filter.clear();
+        assert!(!filter.may_contain(1));
+    }
+}
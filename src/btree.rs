@@ -1,15 +1,34 @@
 use std::mem;
+use std::ops::{Deref, DerefMut, Range};
 
-use crate::pager::Page;
+use crate::page_view::{PageView, PageViewMut, ReadBytes, WriteBytes};
+use crate::pager::{GetPageError, Page, Pager};
 use crate::row::Row;
-use crate::slice_pointer::{SlicePointer, SlicePointerMut};
 
-pub struct Cell(SlicePointer);
-impl Cell {
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Clone, Copy)]
+pub enum NodeType {
+    Leaf = 0,
+    Internal = 1,
+}
+impl std::convert::TryFrom<u8> for NodeType {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Leaf),
+            1 => Ok(Self::Internal),
+            _ => Err(value),
+        }
+    }
+}
+
+pub struct Cell<'a>(&'a [u8]);
+impl Cell<'_> {
     /*
      * Disposition du corps des nœuds aux extrémités de l'arbre.
      */
-    pub const KEY_SIZE: usize = mem::size_of::<u32>();
+    pub const KEY_SIZE: usize = mem::size_of::<u64>();
     pub const KEY_OFFSET: usize = 0;
 
     pub const VALUE_SIZE: usize = Row::MAX_SIZE;
@@ -17,107 +36,415 @@ impl Cell {
 
     pub const SIZE: usize = Self::KEY_SIZE + Self::VALUE_SIZE;
 }
-impl Cell {
-    pub fn get_key(&self) -> SlicePointer {
-        let mut key = self.0.clone();
-        key.set_len(Self::KEY_SIZE);
-        key
+impl Cell<'_> {
+    pub fn get_key(&self) -> u64 {
+        self.0.read_u64_be(Self::KEY_OFFSET)
     }
 
-    pub fn get_value(&self) -> SlicePointer {
-        let mut value = self.0.clone();
-        value += Self::KEY_SIZE;
-        value.set_len(Self::VALUE_SIZE);
-        value
+    pub fn get_value(&self) -> &[u8] {
+        &self.0[Self::VALUE_OFFSET..(Self::VALUE_OFFSET + Self::VALUE_SIZE)]
     }
 }
-pub struct CellMut(SlicePointerMut);
-impl CellMut {
-    pub fn get_mut_key(&self) -> SlicePointerMut {
-        let mut key = self.0.clone();
-        key.set_len(Cell::KEY_SIZE);
-        key
+pub struct CellMut<'a>(&'a mut [u8]);
+impl CellMut<'_> {
+    pub fn set_key(&mut self, key: u64) {
+        self.0.write_u64_be(Cell::KEY_OFFSET, key);
     }
 
-    pub fn get_mut_value(&self) -> SlicePointerMut {
-        let mut value = self.0.clone();
-        value += Cell::KEY_SIZE;
-        value.set_len(Cell::VALUE_SIZE);
-        value
+    pub fn get_mut_value(&mut self) -> &mut [u8] {
+        &mut self.0[Cell::VALUE_OFFSET..(Cell::VALUE_OFFSET + Cell::VALUE_SIZE)]
     }
 }
 
-pub enum Node {
-    Internal(SlicePointer),
-    Leaf(SlicePointer),
+/*
+ * Nœud de l'arbre stocké dans une page, lu à travers une `PageView` ou
+ * modifié à travers une `PageViewMut`.
+ *
+ * La racine est toujours la page `Table::ROOT_PAGE_NUM` et une page remplie
+ * de zéros est une feuille vide : une base neuve n'a rien à initialiser.
+ */
+pub enum Node<P> {
+    Internal(P),
+    Leaf(P),
 }
-impl Node {
+pub type NodeView<'a> = Node<PageView<'a>>;
+pub type NodeViewMut<'a> = Node<PageViewMut<'a>>;
+
+impl NodeView<'_> {
     /*
      * Disposition de l'entête commune des nœuds.
      */
     pub const NODE_TYPE_SIZE: usize = mem::size_of::<u8>();
     pub const NODE_TYPE_OFFSET: usize = 0;
 
-    pub const IS_ROOT_SIZE: usize = mem::size_of::<u8>();
-    pub const IS_ROOT_OFFSET: usize = Self::NODE_TYPE_SIZE;
-
     pub const PARENT_POINTER_SIZE: usize = mem::size_of::<u32>();
-    pub const PARENT_POINTER_OFFSET: usize = Self::IS_ROOT_OFFSET + Self::IS_ROOT_SIZE;
+    pub const PARENT_POINTER_OFFSET: usize = Self::NODE_TYPE_OFFSET + Self::NODE_TYPE_SIZE;
 
-    pub const COMMON_NODE_HEADER_SIZE: usize =
-        Self::NODE_TYPE_SIZE + Self::IS_ROOT_SIZE + Self::PARENT_POINTER_SIZE;
+    pub const COMMON_NODE_HEADER_SIZE: usize = Self::NODE_TYPE_SIZE + Self::PARENT_POINTER_SIZE;
 
     /*
      * Disposition de l'entête des nœuds aux extrémités de l'arbre.
      */
     pub const LEAF_NODE_NB_CELLS_SIZE: usize = mem::size_of::<u32>();
     pub const LEAF_NODE_NB_CELLS_OFFSET: usize = Self::COMMON_NODE_HEADER_SIZE;
-    pub const LEAF_NODE_HEADER_SIZE: usize =
-        Self::COMMON_NODE_HEADER_SIZE + Self::LEAF_NODE_NB_CELLS_SIZE;
+    /// Feuille suivante dans l'ordre des clés, 0 pour la dernière feuille.
+    pub const LEAF_NODE_NEXT_LEAF_SIZE: usize = mem::size_of::<u32>();
+    pub const LEAF_NODE_NEXT_LEAF_OFFSET: usize =
+        Self::LEAF_NODE_NB_CELLS_OFFSET + Self::LEAF_NODE_NB_CELLS_SIZE;
+    pub const LEAF_NODE_HEADER_SIZE: usize = Self::COMMON_NODE_HEADER_SIZE
+        + Self::LEAF_NODE_NB_CELLS_SIZE
+        + Self::LEAF_NODE_NEXT_LEAF_SIZE;
 
     /*
      * Disposition du corps des nœuds aux extrémités de l'arbre.
      */
-    pub const LEAF_NODE_SPACE_FOR_CELLS: usize = Page::SIZE - Node::LEAF_NODE_HEADER_SIZE;
-    pub const LEAF_NODE_MAX_CELLS: usize =
-        Self::LEAF_NODE_SPACE_FOR_CELLS / Cell::SIZE;
+    pub const LEAF_NODE_SPACE_FOR_CELLS: usize = Page::SIZE - Self::LEAF_NODE_HEADER_SIZE;
+    pub const LEAF_NODE_MAX_CELLS: usize = Self::LEAF_NODE_SPACE_FOR_CELLS / Cell::SIZE;
+    /// Lors d'une division, la nouvelle feuille reçoit la moitié haute des cellules.
+    pub const LEAF_NODE_RIGHT_SPLIT_COUNT: usize = Self::LEAF_NODE_MAX_CELLS.div_ceil(2);
+    pub const LEAF_NODE_LEFT_SPLIT_COUNT: usize =
+        (Self::LEAF_NODE_MAX_CELLS + 1) - Self::LEAF_NODE_RIGHT_SPLIT_COUNT;
+
+    /*
+     * Disposition de l'entête des nœuds internes.
+     */
+    pub const INTERNAL_NODE_NB_KEYS_SIZE: usize = mem::size_of::<u32>();
+    pub const INTERNAL_NODE_NB_KEYS_OFFSET: usize = Self::COMMON_NODE_HEADER_SIZE;
+    pub const INTERNAL_NODE_RIGHT_CHILD_SIZE: usize = mem::size_of::<u32>();
+    pub const INTERNAL_NODE_RIGHT_CHILD_OFFSET: usize =
+        Self::INTERNAL_NODE_NB_KEYS_OFFSET + Self::INTERNAL_NODE_NB_KEYS_SIZE;
+    pub const INTERNAL_NODE_HEADER_SIZE: usize = Self::COMMON_NODE_HEADER_SIZE
+        + Self::INTERNAL_NODE_NB_KEYS_SIZE
+        + Self::INTERNAL_NODE_RIGHT_CHILD_SIZE;
+
+    /*
+     * Disposition du corps des nœuds internes : chaque cellule contient un
+     * enfant et la plus grande clé de cet enfant.
+     */
+    pub const INTERNAL_NODE_CHILD_SIZE: usize = mem::size_of::<u32>();
+    pub const INTERNAL_NODE_KEY_SIZE: usize = mem::size_of::<u64>();
+    pub const INTERNAL_NODE_CELL_SIZE: usize =
+        Self::INTERNAL_NODE_CHILD_SIZE + Self::INTERNAL_NODE_KEY_SIZE;
+    pub const INTERNAL_NODE_MAX_KEYS: usize =
+        (Page::SIZE - Self::INTERNAL_NODE_HEADER_SIZE) / Self::INTERNAL_NODE_CELL_SIZE;
+
+    /// Position de la valeur d'une cellule dans la page d'une feuille.
+    pub fn leaf_node_value_range(cell_num: usize) -> Range<usize> {
+        let offset = Self::LEAF_NODE_HEADER_SIZE + cell_num * Cell::SIZE + Cell::VALUE_OFFSET;
+        offset..(offset + Cell::VALUE_SIZE)
+    }
 }
-impl Node {
-    pub fn leaf_node_get_nb_cells(&self) -> u32 {
-        let Node::Leaf(slice_pointer) = self else {
+
+/// Lit le nœud stocké dans une page du pager.
+pub fn read_node(pager: &Pager, page_num: usize) -> Result<NodeView<'_>, GetPageError> {
+    let page = PageView::from(pager.read_page(page_num)?);
+    Node::new(page).map_err(|_| GetPageError::Corrupted(page_num))
+}
+
+/// Ouvre en écriture le nœud stocké dans une page du pager.
+pub fn write_node(pager: &Pager, page_num: usize) -> Result<NodeViewMut<'_>, GetPageError> {
+    let page = PageViewMut::from(pager.write_page(page_num)?);
+    Node::new(page).map_err(|_| GetPageError::Corrupted(page_num))
+}
+
+impl<P: Deref<Target = [u8]>> Node<P> {
+    /// Renvoie `Err` avec l'octet de type si la page ne contient pas un nœud.
+    pub fn new(page: P) -> Result<Self, u8> {
+        match NodeType::try_from(page[NodeView::NODE_TYPE_OFFSET])? {
+            NodeType::Leaf => Ok(Self::Leaf(page)),
+            NodeType::Internal => Ok(Self::Internal(page)),
+        }
+    }
+
+    /// Octets de la page, pour lire l'entête sans les bornes appliquées par les accesseurs.
+    pub fn get_page(&self) -> &[u8] {
+        match self {
+            Self::Internal(page) | Self::Leaf(page) => page,
+        }
+    }
+
+    pub fn into_page(self) -> P {
+        match self {
+            Self::Internal(page) | Self::Leaf(page) => page,
+        }
+    }
+
+    pub fn get_node_type(&self) -> NodeType {
+        match self {
+            Self::Internal(_) => NodeType::Internal,
+            Self::Leaf(_) => NodeType::Leaf,
+        }
+    }
+
+    pub fn get_parent(&self) -> usize {
+        self.get_page().read_u32_be(NodeView::PARENT_POINTER_OFFSET) as usize
+    }
+
+    fn leaf_page(&self) -> &[u8] {
+        let Self::Leaf(page) = self else {
             panic!("Not a leaf");
         };
+        page
+    }
+
+    /// Le nombre lu est borné par la capacité d'une feuille.
+    pub fn leaf_node_get_nb_cells(&self) -> usize {
+        let nb_cells = self
+            .leaf_page()
+            .read_u32_be(NodeView::LEAF_NODE_NB_CELLS_OFFSET);
+        (nb_cells as usize).min(NodeView::LEAF_NODE_MAX_CELLS)
+    }
 
-        let mut num_cells_ptr = slice_pointer + Self::LEAF_NODE_NB_CELLS_OFFSET;
-        num_cells_ptr.set_len(Self::LEAF_NODE_NB_CELLS_SIZE);
+    pub fn leaf_node_get_next_leaf(&self) -> usize {
+        self.leaf_page()
+            .read_u32_be(NodeView::LEAF_NODE_NEXT_LEAF_OFFSET) as usize
+    }
 
-        let num_cells_bytes = <&[u8]>::from(num_cells_ptr);
-        let num_cells_bytes = <[u8; 4]>::try_from(num_cells_bytes).unwrap();
-        u32::from_be_bytes(num_cells_bytes)
+    pub fn leaf_node_get_cell(&self, cell_num: usize) -> Cell<'_> {
+        let offset = NodeView::LEAF_NODE_HEADER_SIZE + cell_num * Cell::SIZE;
+        Cell(&self.leaf_page()[offset..(offset + Cell::SIZE)])
     }
 
-    pub fn leaf_node_get_cell(&self, cell_num: usize) -> Cell {
-        let Node::Leaf(slice_pointer) = self else {
-            panic!("Not a leaf");
+    /// Position de la première cellule dont la clé est supérieure ou égale à `key`.
+    pub fn leaf_node_find(&self, key: u64) -> usize {
+        let (mut low, mut high) = (0, self.leaf_node_get_nb_cells());
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if self.leaf_node_get_cell(middle).get_key() < key {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+        low
+    }
+
+    fn internal_page(&self) -> &[u8] {
+        let Self::Internal(page) = self else {
+            panic!("Not an internal node");
         };
+        page
+    }
 
-        let offset = Self::LEAF_NODE_HEADER_SIZE + cell_num * Cell::SIZE;
-        let mut cell_ptr = slice_pointer + offset;
-        cell_ptr.set_len(Cell::SIZE);
+    /// Le nombre lu est borné par la capacité d'un nœud interne.
+    pub fn internal_node_get_nb_keys(&self) -> usize {
+        let nb_keys = self
+            .internal_page()
+            .read_u32_be(NodeView::INTERNAL_NODE_NB_KEYS_OFFSET);
+        (nb_keys as usize).min(NodeView::INTERNAL_NODE_MAX_KEYS)
+    }
+
+    pub fn internal_node_get_right_child(&self) -> usize {
+        self.internal_page()
+            .read_u32_be(NodeView::INTERNAL_NODE_RIGHT_CHILD_OFFSET) as usize
+    }
 
-        Cell(cell_ptr)
+    /// L'enfant d'indice `nb_keys` est l'enfant de droite.
+    pub fn internal_node_get_child(&self, child_num: usize) -> usize {
+        if child_num == self.internal_node_get_nb_keys() {
+            return self.internal_node_get_right_child();
+        }
+        let offset =
+            NodeView::INTERNAL_NODE_HEADER_SIZE + child_num * NodeView::INTERNAL_NODE_CELL_SIZE;
+        self.internal_page().read_u32_be(offset) as usize
     }
 
-    pub fn leaf_node_get_mut_cell(&self, cell_num: usize) -> CellMut {
-        let Node::Leaf(slice_pointer) = self else {
+    pub fn internal_node_get_key(&self, key_num: usize) -> u64 {
+        let offset = NodeView::INTERNAL_NODE_HEADER_SIZE
+            + key_num * NodeView::INTERNAL_NODE_CELL_SIZE
+            + NodeView::INTERNAL_NODE_CHILD_SIZE;
+        self.internal_page().read_u64_be(offset)
+    }
+
+    /// Indice de l'enfant qui contient `key` s'il existe.
+    pub fn internal_node_find_child(&self, key: u64) -> usize {
+        let (mut low, mut high) = (0, self.internal_node_get_nb_keys());
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if self.internal_node_get_key(middle) < key {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+        low
+    }
+}
+
+impl<P: DerefMut<Target = [u8]>> Node<P> {
+    /// Transforme la page en feuille vide.
+    pub fn initialize_leaf(mut page: P) -> Self {
+        page[..NodeView::LEAF_NODE_HEADER_SIZE].fill(0);
+        page[NodeView::NODE_TYPE_OFFSET] = NodeType::Leaf as u8;
+        Self::Leaf(page)
+    }
+
+    /// Transforme la page en nœud interne sans clé.
+    pub fn initialize_internal(mut page: P) -> Self {
+        page[..NodeView::INTERNAL_NODE_HEADER_SIZE].fill(0);
+        page[NodeView::NODE_TYPE_OFFSET] = NodeType::Internal as u8;
+        Self::Internal(page)
+    }
+
+    fn bytes_mut(&mut self) -> &mut [u8] {
+        match self {
+            Self::Internal(page) | Self::Leaf(page) => page,
+        }
+    }
+
+    pub fn set_parent(&mut self, parent: usize) {
+        self.bytes_mut()
+            .write_u32_be(NodeView::PARENT_POINTER_OFFSET, parent as u32);
+    }
+
+    fn leaf_page_mut(&mut self) -> &mut [u8] {
+        let Self::Leaf(page) = self else {
             panic!("Not a leaf");
         };
+        page
+    }
+
+    pub fn leaf_node_set_nb_cells(&mut self, nb_cells: usize) {
+        self.leaf_page_mut()
+            .write_u32_be(NodeView::LEAF_NODE_NB_CELLS_OFFSET, nb_cells as u32);
+    }
+
+    pub fn leaf_node_set_next_leaf(&mut self, next_leaf: usize) {
+        self.leaf_page_mut()
+            .write_u32_be(NodeView::LEAF_NODE_NEXT_LEAF_OFFSET, next_leaf as u32);
+    }
+
+    pub fn leaf_node_get_mut_cell(&mut self, cell_num: usize) -> CellMut<'_> {
+        let offset = NodeView::LEAF_NODE_HEADER_SIZE + cell_num * Cell::SIZE;
+        CellMut(&mut self.leaf_page_mut()[offset..(offset + Cell::SIZE)])
+    }
+
+    /// Insère une cellule à la position `cell_num` en décalant les suivantes.
+    pub fn leaf_node_insert_cell(&mut self, cell_num: usize, key: u64, value: &[u8]) {
+        let nb_cells = self.leaf_node_get_nb_cells();
+        assert!(nb_cells < NodeView::LEAF_NODE_MAX_CELLS, "Leaf node full.");
+
+        let start = NodeView::LEAF_NODE_HEADER_SIZE + cell_num * Cell::SIZE;
+        let end = NodeView::LEAF_NODE_HEADER_SIZE + nb_cells * Cell::SIZE;
+        self.leaf_page_mut()
+            .copy_within(start..end, start + Cell::SIZE);
+
+        let mut cell = self.leaf_node_get_mut_cell(cell_num);
+        cell.set_key(key);
+        cell.get_mut_value().copy_from_slice(value);
+        self.leaf_node_set_nb_cells(nb_cells + 1);
+    }
+
+    /// Retire la cellule `cell_num` en décalant les suivantes. La place libérée
+    /// en fin de feuille est remise à zéro.
+    pub fn leaf_node_remove_cell(&mut self, cell_num: usize) {
+        let nb_cells = self.leaf_node_get_nb_cells();
+        assert!(cell_num < nb_cells, "Cell out of the leaf node.");
+
+        let start = NodeView::LEAF_NODE_HEADER_SIZE + (cell_num + 1) * Cell::SIZE;
+        let end = NodeView::LEAF_NODE_HEADER_SIZE + nb_cells * Cell::SIZE;
+        let page = self.leaf_page_mut();
+        page.copy_within(start..end, start - Cell::SIZE);
+        page[(end - Cell::SIZE)..end].fill(0);
+        self.leaf_node_set_nb_cells(nb_cells - 1);
+    }
+
+    fn internal_page_mut(&mut self) -> &mut [u8] {
+        let Self::Internal(page) = self else {
+            panic!("Not an internal node");
+        };
+        page
+    }
+
+    pub fn internal_node_set_nb_keys(&mut self, nb_keys: usize) {
+        self.internal_page_mut()
+            .write_u32_be(NodeView::INTERNAL_NODE_NB_KEYS_OFFSET, nb_keys as u32);
+    }
+
+    pub fn internal_node_set_right_child(&mut self, child: usize) {
+        self.internal_page_mut()
+            .write_u32_be(NodeView::INTERNAL_NODE_RIGHT_CHILD_OFFSET, child as u32);
+    }
+
+    pub fn internal_node_set_cell(&mut self, key_num: usize, child: usize, key: u64) {
+        let offset =
+            NodeView::INTERNAL_NODE_HEADER_SIZE + key_num * NodeView::INTERNAL_NODE_CELL_SIZE;
+        let page = self.internal_page_mut();
+        page.write_u32_be(offset, child as u32);
+        page.write_u64_be(offset + NodeView::INTERNAL_NODE_CHILD_SIZE, key);
+    }
+
+    pub fn internal_node_set_key(&mut self, key_num: usize, key: u64) {
+        let child = self.internal_node_get_child(key_num);
+        self.internal_node_set_cell(key_num, child, key);
+    }
+
+    /// Insère une cellule à la position `key_num` en décalant les suivantes.
+    pub fn internal_node_insert_cell(&mut self, key_num: usize, child: usize, key: u64) {
+        let nb_keys = self.internal_node_get_nb_keys();
+        assert!(
+            nb_keys < NodeView::INTERNAL_NODE_MAX_KEYS,
+            "Internal node full."
+        );
+
+        let start =
+            NodeView::INTERNAL_NODE_HEADER_SIZE + key_num * NodeView::INTERNAL_NODE_CELL_SIZE;
+        let end = NodeView::INTERNAL_NODE_HEADER_SIZE + nb_keys * NodeView::INTERNAL_NODE_CELL_SIZE;
+        self.internal_page_mut()
+            .copy_within(start..end, start + NodeView::INTERNAL_NODE_CELL_SIZE);
+
+        self.internal_node_set_cell(key_num, child, key);
+        self.internal_node_set_nb_keys(nb_keys + 1);
+    }
+}
+
+#[cfg(test)]
+mod btree_test {
+    use super::*;
+
+    use crate::backend::MemoryBackend;
+
+    #[test]
+    fn test_leaf_insert_keeps_keys_sorted() {
+        let mut page = Page::default();
+        let mut leaf = Node::initialize_leaf(&mut page[..]);
+        let value = [0u8; Cell::VALUE_SIZE];
+        for key in [5, 1, 3] {
+            let cell_num = leaf.leaf_node_find(key);
+            leaf.leaf_node_insert_cell(cell_num, key, &value);
+        }
+
+        let keys: Vec<u64> = (0..leaf.leaf_node_get_nb_cells())
+            .map(|cell_num| leaf.leaf_node_get_cell(cell_num).get_key())
+            .collect();
+        assert_eq!(keys, vec![1, 3, 5]);
+        assert_eq!(leaf.leaf_node_find(4), 2);
+    }
+
+    #[test]
+    fn test_zeroed_page_is_empty_leaf() {
+        let page = Page::default();
+        let node = Node::new(&page[..]).unwrap();
+        assert!(node.get_node_type() == NodeType::Leaf);
+        assert_eq!(node.leaf_node_get_nb_cells(), 0);
+    }
 
-        let offset = Self::LEAF_NODE_HEADER_SIZE + cell_num * Cell::SIZE;
-        let mut slice_pointer_mut = SlicePointerMut::from(slice_pointer);
-        slice_pointer_mut += offset;
-        slice_pointer_mut.set_len(Cell::SIZE);
+    #[test]
+    fn test_nodes_survive_reload() {
+        let pager = Pager::default();
+        let value = [0u8; Cell::VALUE_SIZE];
+        {
+            let mut leaf = write_node(&pager, 1).unwrap();
+            leaf.set_parent(2);
+            leaf.leaf_node_insert_cell(0, 7, &value);
+        }
+        let mut backend = MemoryBackend::default();
+        pager.write_to(&mut backend, 1).unwrap();
 
-        CellMut(slice_pointer_mut)
+        let pager = Pager::with_backend(Box::new(backend)).unwrap();
+        let leaf = read_node(&pager, 1).unwrap();
+        assert!(leaf.get_node_type() == NodeType::Leaf);
+        assert_eq!(leaf.get_parent(), 2);
+        assert_eq!(leaf.leaf_node_get_cell(0).get_key(), 7);
     }
 }
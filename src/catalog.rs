@@ -0,0 +1,290 @@
+use crate::pager::Page;
+use crate::row::{Email, Row, Username};
+use crate::table::Table;
+
+/*
+ * Catalogue des tables, stocké dans la page `Table::CATALOG_PAGE_NUM` : le
+ * fichier décrit lui-même les tables qu'il contient.
+ *
+ * Disposition, entiers en gros-boutiste :
+ *   MAGIC, nombre de tables (u32), puis pour chaque table :
+ *     nom, page racine (u32), nombre de colonnes (u16), puis pour chaque
+ *     colonne : nom, type (u8), taille maximale (u32),
+ *     nombre d'index (u16), puis le nom de chaque index.
+ * Un nom est sa longueur en octets (u16) suivie de son texte UTF-8.
+ */
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(PartialEq)]
+pub enum CatalogError {
+    /// La page ne commence pas par `Catalog::MAGIC`.
+    InvalidMagic,
+    /// La description s'arrête avant la fin, ou contient un nom qui n'est pas de l'UTF-8.
+    Corrupted,
+    UnknownColumnType(u8),
+    /// Le catalogue ne tient pas dans une page.
+    TooLarge,
+    /// Les tables du fichier ne sont pas celles que ce programme sait lire.
+    SchemaMismatch,
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Clone, Copy)]
+pub enum ColumnType {
+    Integer,
+    /// Texte d'au plus `max_size` octets.
+    Text {
+        max_size: usize,
+    },
+}
+impl ColumnType {
+    const INTEGER_TAG: u8 = 0;
+    const TEXT_TAG: u8 = 1;
+}
+impl std::fmt::Display for ColumnType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Integer => write!(f, "integer"),
+            Self::Text { max_size } => write!(f, "text({max_size})"),
+        }
+    }
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Clone)]
+pub struct Column {
+    pub name: String,
+    pub column_type: ColumnType,
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Clone)]
+pub struct TableSchema {
+    pub name: String,
+    /// La première colonne est la clé de l'arbre.
+    pub columns: Vec<Column>,
+    pub root_page_num: usize,
+    pub indexes: Vec<String>,
+}
+impl TableSchema {
+    /// Instruction qui créerait la table, à la manière de `sqlite_master`.
+    pub fn get_sql(&self) -> String {
+        let columns: Vec<String> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(position, column)| {
+                let primary_key = if position == 0 { " primary key" } else { "" };
+                format!("{} {}{primary_key}", column.name, column.column_type)
+            })
+            .collect();
+        format!("create table {} ({})", self.name, columns.join(", "))
+    }
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Clone)]
+pub struct Catalog {
+    tables: Vec<TableSchema>,
+}
+impl Catalog {
+    pub const MAGIC: [u8; 8] = *b"catalog\0";
+
+    /// Nom de l'unique table, celle des `Row`.
+    pub const TABLE_NAME: &str = "users";
+
+    /// Table virtuelle qui expose le catalogue, à la manière de `sqlite_master`.
+    pub const MASTER_TABLE_NAME: &str = "my_db_master";
+    pub const MASTER_COLUMN_NAMES: [&str; 5] = ["type", "name", "tbl_name", "rootpage", "sql"];
+
+    /// Catalogue de la table que ce programme sait lire et écrire.
+    pub fn compiled() -> Self {
+        let [id, username, email] = Row::COLUMN_NAMES.map(str::to_owned);
+        let columns = vec![
+            Column {
+                name: id,
+                column_type: ColumnType::Integer,
+            },
+            Column {
+                name: username,
+                column_type: ColumnType::Text {
+                    max_size: Username::MAX_SIZE,
+                },
+            },
+            Column {
+                name: email,
+                column_type: ColumnType::Text {
+                    max_size: Email::MAX_SIZE,
+                },
+            },
+        ];
+        Self {
+            tables: vec![TableSchema {
+                name: Self::TABLE_NAME.to_owned(),
+                columns,
+                root_page_num: Table::ROOT_PAGE_NUM,
+                indexes: Vec::new(),
+            }],
+        }
+    }
+
+    pub fn get_tables(&self) -> &[TableSchema] {
+        &self.tables
+    }
+
+    /// Une ligne de `my_db_master` par objet du catalogue, dans l'ordre de
+    /// `MASTER_COLUMN_NAMES`.
+    pub fn get_master_records(&self) -> Vec<[String; Self::MASTER_COLUMN_NAMES.len()]> {
+        self.tables
+            .iter()
+            .map(|table| {
+                [
+                    "table".to_owned(),
+                    table.name.clone(),
+                    table.name.clone(),
+                    table.root_page_num.to_string(),
+                    table.get_sql(),
+                ]
+            })
+            .collect()
+    }
+}
+impl std::convert::TryFrom<&Catalog> for Page {
+    type Error = CatalogError;
+
+    fn try_from(catalog: &Catalog) -> Result<Self, Self::Error> {
+        let mut bytes = Vec::from(Catalog::MAGIC);
+        bytes.extend_from_slice(&(catalog.tables.len() as u32).to_be_bytes());
+        for table in &catalog.tables {
+            push_name(&mut bytes, &table.name)?;
+            bytes.extend_from_slice(&(table.root_page_num as u32).to_be_bytes());
+            bytes.extend_from_slice(&(table.columns.len() as u16).to_be_bytes());
+            for column in &table.columns {
+                push_name(&mut bytes, &column.name)?;
+                let (tag, max_size) = match column.column_type {
+                    ColumnType::Integer => (ColumnType::INTEGER_TAG, 0),
+                    ColumnType::Text { max_size } => (ColumnType::TEXT_TAG, max_size),
+                };
+                bytes.push(tag);
+                bytes.extend_from_slice(&(max_size as u32).to_be_bytes());
+            }
+            bytes.extend_from_slice(&(table.indexes.len() as u16).to_be_bytes());
+            for index in &table.indexes {
+                push_name(&mut bytes, index)?;
+            }
+        }
+
+        let mut page = Page::default();
+        page.get_mut(..bytes.len())
+            .ok_or(CatalogError::TooLarge)?
+            .copy_from_slice(&bytes);
+        Ok(page)
+    }
+}
+impl std::convert::TryFrom<&[u8]> for Catalog {
+    type Error = CatalogError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        if bytes.get(..Self::MAGIC.len()) != Some(&Self::MAGIC[..]) {
+            return Err(CatalogError::InvalidMagic);
+        }
+        let mut reader = Reader {
+            bytes,
+            offset: Self::MAGIC.len(),
+        };
+
+        let nb_tables = reader.read::<4>().map(u32::from_be_bytes)?;
+        let mut tables = Vec::new();
+        for _ in 0..nb_tables {
+            let name = reader.read_name()?;
+            let root_page_num = reader.read::<4>().map(u32::from_be_bytes)? as usize;
+            let nb_columns = reader.read::<2>().map(u16::from_be_bytes)?;
+            let mut columns = Vec::new();
+            for _ in 0..nb_columns {
+                let name = reader.read_name()?;
+                let [tag] = reader.read::<1>()?;
+                let max_size = reader.read::<4>().map(u32::from_be_bytes)? as usize;
+                let column_type = match tag {
+                    ColumnType::INTEGER_TAG => ColumnType::Integer,
+                    ColumnType::TEXT_TAG => ColumnType::Text { max_size },
+                    _ => return Err(CatalogError::UnknownColumnType(tag)),
+                };
+                columns.push(Column { name, column_type });
+            }
+            let nb_indexes = reader.read::<2>().map(u16::from_be_bytes)?;
+            let indexes = (0..nb_indexes)
+                .map(|_| reader.read_name())
+                .collect::<Result<Vec<String>, CatalogError>>()?;
+            tables.push(TableSchema {
+                name,
+                columns,
+                root_page_num,
+                indexes,
+            });
+        }
+
+        Ok(Self { tables })
+    }
+}
+
+fn push_name(bytes: &mut Vec<u8>, name: &str) -> Result<(), CatalogError> {
+    let len = u16::try_from(name.len()).map_err(|_| CatalogError::TooLarge)?;
+    bytes.extend_from_slice(&len.to_be_bytes());
+    bytes.extend_from_slice(name.as_bytes());
+    Ok(())
+}
+
+/// Lecture séquentielle qui signale la fin des octets au lieu de paniquer.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+impl Reader<'_> {
+    fn read<const N: usize>(&mut self) -> Result<[u8; N], CatalogError> {
+        let arr = self
+            .bytes
+            .get(self.offset..)
+            .and_then(<[u8]>::first_chunk::<N>)
+            .ok_or(CatalogError::Corrupted)?;
+        self.offset += N;
+        Ok(*arr)
+    }
+
+    fn read_name(&mut self) -> Result<String, CatalogError> {
+        let len = usize::from(self.read::<2>().map(u16::from_be_bytes)?);
+        let name = self
+            .bytes
+            .get(self.offset..(self.offset + len))
+            .ok_or(CatalogError::Corrupted)?;
+        self.offset += len;
+        String::from_utf8(name.to_vec()).map_err(|_| CatalogError::Corrupted)
+    }
+}
+
+#[cfg(test)]
+mod catalog_test {
+    use super::*;
+
+    #[test]
+    fn test_catalog_round_trip() {
+        let catalog = Catalog::compiled();
+        let page = Page::try_from(&catalog).unwrap();
+        assert_eq!(&page[..Catalog::MAGIC.len()], b"catalog\0");
+        assert_eq!(Catalog::try_from(&page[..]), Ok(catalog));
+
+        assert_eq!(
+            Catalog::try_from(&Page::default()[..]),
+            Err(CatalogError::InvalidMagic)
+        );
+        assert_eq!(Catalog::try_from(&page[..20]), Err(CatalogError::Corrupted));
+        assert_eq!(
+            Catalog::compiled().get_tables()[0].get_sql(),
+            "create table users (id integer primary key, username text(32), email text(255))"
+        );
+        assert_eq!(
+            Catalog::compiled().get_master_records()[0][..4],
+            ["table", "users", "users", "1"]
+        );
+    }
+}
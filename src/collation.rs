@@ -0,0 +1,85 @@
+use std::cmp::Ordering;
+
+/// Façon de comparer deux textes, choisie avec `collate` dans un `order by`.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Clone, Copy, Default)]
+pub enum Collation {
+    /// Comparaison des octets, comme `str::cmp`.
+    #[default]
+    Binary,
+    /// Ignore la casse des lettres ASCII seulement, comme le `NOCASE` de SQLite.
+    NoCase,
+    /// Ignore la casse de toutes les lettres, en comparant leurs minuscules.
+    Unicode,
+}
+impl Collation {
+    pub const ALL: [Collation; 3] = [Self::Binary, Self::NoCase, Self::Unicode];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Binary => "binary",
+            Self::NoCase => "nocase",
+            Self::Unicode => "unicode",
+        }
+    }
+
+    /// Deux textes égaux pour la collation sont ensuite départagés octet par
+    /// octet, pour que l'ordre obtenu ne dépende pas de l'ordre d'insertion.
+    pub fn compare(self, a: &str, b: &str) -> Ordering {
+        let ordering = match self {
+            Self::Binary => Ordering::Equal,
+            Self::NoCase => a
+                .bytes()
+                .map(|c| c.to_ascii_lowercase())
+                .cmp(b.bytes().map(|c| c.to_ascii_lowercase())),
+            Self::Unicode => a
+                .chars()
+                .flat_map(char::to_lowercase)
+                .cmp(b.chars().flat_map(char::to_lowercase)),
+        };
+        ordering.then_with(|| a.cmp(b))
+    }
+}
+impl std::convert::TryFrom<&str> for Collation {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::ALL
+            .into_iter()
+            .find(|collation| collation.as_str().eq_ignore_ascii_case(value))
+            .ok_or(())
+    }
+}
+impl std::fmt::Display for Collation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod collation_test {
+    use super::*;
+
+    #[test]
+    fn test_compare() {
+        let sorted = |collation: Collation| {
+            let mut names = vec!["bob", "éa", "Alice", "Éb", "Zoé"];
+            names.sort_by(|a, b| collation.compare(a, b));
+            names
+        };
+        assert_eq!(
+            sorted(Collation::Binary),
+            vec!["Alice", "Zoé", "bob", "Éb", "éa"]
+        );
+        assert_eq!(
+            sorted(Collation::NoCase),
+            vec!["Alice", "bob", "Zoé", "Éb", "éa"]
+        );
+        assert_eq!(
+            sorted(Collation::Unicode),
+            vec!["Alice", "bob", "Zoé", "éa", "Éb"]
+        );
+        assert_eq!(Collation::NoCase.compare("bob", "BOB"), Ordering::Greater);
+        assert_eq!(Collation::try_from("NOCASE"), Ok(Collation::NoCase));
+    }
+}
@@ -0,0 +1,126 @@
+use rustyline::Context;
+use rustyline::completion::Completer;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+
+use crate::catalog::Catalog;
+use crate::lexer::Keyword;
+use crate::meta_command::META_COMMANDS;
+use crate::row::Row;
+use crate::statement::PRAGMA_NAMES;
+
+/*
+ * Complétion du REPL : une commande en début de ligne commençant par un point,
+ * sinon un mot-clé, un nom de table ou de colonne. Les listes viennent des modules qui
+ * reconnaissent ces mots, la complétion suit donc la grammaire.
+ */
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct ReplHelper;
+
+/// Renvoie le début du mot sous le curseur et les mots qui le complètent.
+/// Un mot tapé en majuscules est complété en majuscules.
+pub fn complete_word(line: &str, pos: usize) -> (usize, Vec<String>) {
+    let start = line[..pos]
+        .rfind(char::is_whitespace)
+        .map_or(0, |space| space + 1);
+    let word = &line[start..pos];
+    if word.is_empty() {
+        return (start, Vec::new());
+    }
+
+    let candidates: Vec<&str> = if start == 0 && word.starts_with('.') {
+        META_COMMANDS.to_vec()
+    } else {
+        Keyword::ALL
+            .map(Keyword::as_str)
+            .iter()
+            .chain(&PRAGMA_NAMES)
+            .chain(&Row::COLUMN_NAMES)
+            .chain(&[Catalog::TABLE_NAME, Catalog::MASTER_TABLE_NAME])
+            .chain(&Catalog::MASTER_COLUMN_NAMES)
+            .copied()
+            .collect()
+    };
+    let lowercase = word.to_lowercase();
+    let is_uppercase = word.chars().any(char::is_alphabetic) && word == word.to_uppercase();
+    let completions = candidates
+        .into_iter()
+        .filter(|candidate| candidate.starts_with(&lowercase))
+        .map(|candidate| {
+            if is_uppercase {
+                candidate.to_uppercase()
+            } else {
+                candidate.to_owned()
+            }
+        })
+        .collect();
+    (start, completions)
+}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(complete_word(line, pos))
+    }
+}
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+impl Highlighter for ReplHelper {}
+impl Validator for ReplHelper {}
+impl rustyline::Helper for ReplHelper {}
+
+#[cfg(test)]
+mod completion_test {
+    use super::*;
+
+    #[test]
+    fn test_complete_word() {
+        assert_eq!(complete_word(".sa", 3), (0, vec![".save".to_owned()]));
+        assert_eq!(
+            complete_word("select where id between 1 an", 28),
+            (26, vec!["and".to_owned()])
+        );
+        assert_eq!(complete_word("SEL", 3), (0, vec!["SELECT".to_owned()]));
+        assert_eq!(complete_word("select ", 7), (7, Vec::new()));
+    }
+
+    #[test]
+    fn test_every_pragma_is_completed() {
+        use crate::statement::{Pragma, StatementType, prepare_statement};
+
+        // Le `match` oblige à compléter ce test quand un réglage est ajouté.
+        let name = |pragma: &Pragma| match pragma {
+            Pragma::Synchronous(_) => "synchronous",
+            Pragma::WriteMode(_) => "write_mode",
+            Pragma::CacheSize(_) => "cache_size",
+            Pragma::CommitDelay(_) => "commit_delay",
+            Pragma::FlushInterval(_) => "flush_interval",
+            Pragma::BloomFilter(_) => "bloom_filter",
+            Pragma::IntegrityCheck => "integrity_check",
+        };
+        for pragma in [
+            Pragma::Synchronous(None),
+            Pragma::WriteMode(None),
+            Pragma::CacheSize(None),
+            Pragma::CommitDelay(None),
+            Pragma::FlushInterval(None),
+            Pragma::BloomFilter(None),
+            Pragma::IntegrityCheck,
+        ] {
+            let name = name(&pragma);
+            assert!(PRAGMA_NAMES.contains(&name), "{name}");
+            assert!(matches!(
+                prepare_statement(&format!("pragma {name}")),
+                Ok(StatementType::Pragma(parsed)) if parsed == pragma
+            ));
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use std::io;
+use std::io::{ErrorKind, Read, Seek, SeekFrom};
+use std::mem;
+
+use crate::header::FileHeader;
+use crate::pager::Page;
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq)]
+pub enum DecompressError {
+    InvalidData,
+    InvalidPageSize(usize),
+}
+
+/// Position d'une page compressée dans le fichier.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Clone)]
+pub struct PageLocation {
+    pub offset: u64,
+    pub len: u32,
+}
+
+/*
+ * Répertoire écrit juste après l'entête des fichiers compressés : le nombre
+ * de pages puis, pour chaque page, sa position et sa taille compressée.
+ */
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Clone, Default)]
+pub struct PageDirectory(Vec<PageLocation>);
+impl PageDirectory {
+    pub const NB_PAGES_SIZE: usize = mem::size_of::<u32>();
+    pub const OFFSET_SIZE: usize = mem::size_of::<u64>();
+    pub const LEN_SIZE: usize = mem::size_of::<u32>();
+    pub const ENTRY_SIZE: usize = Self::OFFSET_SIZE + Self::LEN_SIZE;
+
+    /// Construit le répertoire de pages compressées écrites à la suite du répertoire.
+    pub fn for_pages(compressed_pages: &[Vec<u8>]) -> Self {
+        let mut offset = (FileHeader::SIZE + Self::size_for(compressed_pages.len())) as u64;
+        let locations = compressed_pages
+            .iter()
+            .map(|page| {
+                let location = PageLocation {
+                    offset,
+                    len: page.len() as u32,
+                };
+                offset += page.len() as u64;
+                location
+            })
+            .collect();
+        Self(locations)
+    }
+
+    pub fn size_for(nb_pages: usize) -> usize {
+        Self::NB_PAGES_SIZE + nb_pages * Self::ENTRY_SIZE
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn get(&self, page_num: usize) -> Option<&PageLocation> {
+        self.0.get(page_num)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &PageLocation> {
+        self.0.iter()
+    }
+
+    /// Lit le répertoire placé après l'entête du fichier.
+    pub fn read_from<R: Read + Seek + ?Sized>(file: &mut R) -> io::Result<Self> {
+        let _ = file.seek(SeekFrom::Start(FileHeader::SIZE as u64))?;
+        let mut nb_pages_bytes = [0; Self::NB_PAGES_SIZE];
+        file.read_exact(&mut nb_pages_bytes)?;
+        let nb_pages = u32::from_be_bytes(nb_pages_bytes) as usize;
+
+        let mut bytes = vec![0; Self::size_for(nb_pages)];
+        bytes[..Self::NB_PAGES_SIZE].copy_from_slice(&nb_pages_bytes);
+        file.read_exact(&mut bytes[Self::NB_PAGES_SIZE..])?;
+
+        Self::try_from(&bytes[..])
+            .map_err(|()| io::Error::new(ErrorKind::InvalidData, "Invalid page directory."))
+    }
+}
+impl std::convert::From<&PageDirectory> for Vec<u8> {
+    fn from(directory: &PageDirectory) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(PageDirectory::size_for(directory.len()));
+        bytes.extend_from_slice(&(directory.len() as u32).to_be_bytes());
+        for location in directory.iter() {
+            bytes.extend_from_slice(&location.offset.to_be_bytes());
+            bytes.extend_from_slice(&location.len.to_be_bytes());
+        }
+        bytes
+    }
+}
+impl std::convert::TryFrom<&[u8]> for PageDirectory {
+    type Error = ();
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        let nb_pages_bytes = bytes.first_chunk::<{ Self::NB_PAGES_SIZE }>().ok_or(())?;
+        let nb_pages = u32::from_be_bytes(*nb_pages_bytes) as usize;
+
+        let entries = bytes
+            .get(Self::NB_PAGES_SIZE..Self::size_for(nb_pages))
+            .ok_or(())?;
+        let locations = entries
+            .chunks_exact(Self::ENTRY_SIZE)
+            .map(|entry| {
+                let (offset, len) = entry.split_at(Self::OFFSET_SIZE);
+                PageLocation {
+                    offset: u64::from_be_bytes(offset.try_into().unwrap_or_default()),
+                    len: u32::from_be_bytes(len.try_into().unwrap_or_default()),
+                }
+            })
+            .collect();
+        Ok(Self(locations))
+    }
+}
+
+pub fn compress_page(page: &[u8]) -> Vec<u8> {
+    lz4_flex::compress(page)
+}
+
+pub fn decompress_page(bytes: &[u8]) -> Result<Page, DecompressError> {
+    let mut page = Page::default();
+    let size = lz4_flex::decompress_into(bytes, &mut page[..])
+        .map_err(|_| DecompressError::InvalidData)?;
+    if size != Page::SIZE {
+        return Err(DecompressError::InvalidPageSize(size));
+    }
+    Ok(page)
+}
+
+#[cfg(test)]
+mod compression_test {
+    use super::*;
+
+    #[test]
+    fn test_page_compression_round_trip() {
+        let mut page = Page::default();
+        page[..5].copy_from_slice(b"my_db");
+
+        let compressed = compress_page(&page[..]);
+        assert!(compressed.len() < Page::SIZE / 10);
+        assert_eq!(&decompress_page(&compressed).unwrap()[..], &page[..]);
+        assert_eq!(
+            decompress_page(&compressed[..3]).err(),
+            Some(DecompressError::InvalidData)
+        );
+    }
+
+    #[test]
+    fn test_page_directory_from_into_bytes() {
+        let directory = PageDirectory::for_pages(&[vec![0; 10], vec![0; 20]]);
+        let first_offset = (FileHeader::SIZE + PageDirectory::size_for(2)) as u64;
+        assert_eq!(directory.get(1).unwrap().offset, first_offset + 10);
+
+        let bytes = Vec::<u8>::from(&directory);
+        assert_eq!(PageDirectory::try_from(&bytes[..]), Ok(directory));
+        assert_eq!(PageDirectory::try_from(&bytes[..10]), Err(()));
+    }
+}
@@ -0,0 +1,165 @@
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader};
+
+use crate::provider::{ProviderRows, TableProvider};
+use crate::row::{Email, Id, Row, TextError, Username};
+use crate::table::GetRowError;
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum CsvError {
+    /// Le fichier, et l'erreur rencontrée en l'ouvrant ou en le lisant.
+    Open(String, io::Error),
+    /// Numéro de la ligne, à partir de 1, et ce qui l'empêche d'être lue.
+    Line(usize, CsvLineError),
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum CsvLineError {
+    /// Nombre de champs trouvés.
+    WrongNumberOfFields(usize),
+    InvalidId(String),
+    /// Colonne et taille maximale.
+    StringTooLong(&'static str, usize),
+    /// Colonne et caractère de contrôle trouvé dans sa valeur.
+    ControlCharacter(&'static str, char),
+    UnterminatedQuote,
+}
+
+/*
+ * Table virtuelle lisant un fichier CSV dont chaque ligne est un `Row` :
+ * id, username et email. Le fichier est relu à chaque `select`, il peut donc
+ * changer sans recréer la table. Une première ligne `id,username,email` est
+ * ignorée, comme les lignes vides.
+ */
+#[derive(Debug)]
+pub struct CsvTable {
+    file_path: String,
+}
+impl CsvTable {
+    /// Vérifie seulement que le fichier peut être ouvert : ses lignes ne sont
+    /// lues qu'au premier `select`.
+    pub fn open(file_path: &str) -> Result<Self, CsvError> {
+        File::open(file_path).map_err(|e| CsvError::Open(file_path.to_owned(), e))?;
+        Ok(Self {
+            file_path: file_path.to_owned(),
+        })
+    }
+
+    fn read_rows(&self) -> Result<impl Iterator<Item = Result<Row, CsvError>>, CsvError> {
+        let file =
+            File::open(&self.file_path).map_err(|e| CsvError::Open(self.file_path.clone(), e))?;
+        let file_path = self.file_path.clone();
+        let rows = BufReader::new(file)
+            .lines()
+            .enumerate()
+            .filter_map(move |(index, line)| {
+                let line_num = index + 1;
+                let line = match line {
+                    Ok(line) => line,
+                    Err(e) => return Some(Err(CsvError::Open(file_path.clone(), e))),
+                };
+                let line = line.trim_end_matches('\r');
+                if line.trim().is_empty() {
+                    return None;
+                }
+                let fields = match split_csv_line(line) {
+                    Some(fields) => fields,
+                    None => {
+                        return Some(Err(CsvError::Line(
+                            line_num,
+                            CsvLineError::UnterminatedQuote,
+                        )));
+                    }
+                };
+                if line_num == 1 && is_header(&fields) {
+                    return None;
+                }
+                Some(parse_row(fields).map_err(|e| CsvError::Line(line_num, e)))
+            });
+        Ok(rows)
+    }
+}
+impl TableProvider for CsvTable {
+    fn scan(&self) -> ProviderRows<'_> {
+        match self.read_rows() {
+            Ok(rows) => Box::new(rows.map(|row| row.map_err(GetRowError::Csv))),
+            Err(e) => Box::new(std::iter::once(Err(GetRowError::Csv(e)))),
+        }
+    }
+}
+
+fn is_header(fields: &[String]) -> bool {
+    fields.len() == Row::COLUMN_NAMES.len()
+        && fields
+            .iter()
+            .zip(Row::COLUMN_NAMES)
+            .all(|(field, name)| field.trim().eq_ignore_ascii_case(name))
+}
+
+/// Les mêmes vérifications qu'une insertion.
+fn parse_row(fields: Vec<String>) -> Result<Row, CsvLineError> {
+    let nb_fields = fields.len();
+    let Ok([id, username, email]) = <[String; 3]>::try_from(fields) else {
+        return Err(CsvLineError::WrongNumberOfFields(nb_fields));
+    };
+    let Ok(id) = id.trim().parse::<u64>() else {
+        return Err(CsvLineError::InvalidId(id));
+    };
+    let username = Username::try_new(username).map_err(|e| text_error("username", e))?;
+    let email = Email::try_new(email).map_err(|e| text_error("email", e))?;
+    Ok(Row::new(Id::new(id), username, email))
+}
+
+fn text_error(column: &'static str, e: TextError) -> CsvLineError {
+    match e {
+        TextError::TooLong(max_size) => CsvLineError::StringTooLong(column, max_size),
+        TextError::ControlCharacter(c) => CsvLineError::ControlCharacter(column, c),
+    }
+}
+
+/// Champs séparés par des virgules. Un champ entre guillemets doubles peut
+/// contenir des virgules, et `""` y désigne un guillemet. Renvoie `None` si un
+/// guillemet n'est pas refermé : un champ ne peut pas tenir sur plusieurs lignes.
+pub fn split_csv_line(line: &str) -> Option<Vec<String>> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (in_quotes, c) {
+            (true, '"') if chars.next_if_eq(&'"').is_some() => field.push('"'),
+            (true, '"') => in_quotes = false,
+            (false, '"') if field.is_empty() => in_quotes = true,
+            (false, ',') => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    if in_quotes {
+        return None;
+    }
+    fields.push(field);
+    Some(fields)
+}
+
+#[cfg(test)]
+mod csv_test {
+    use super::*;
+
+    #[test]
+    fn test_split_csv_line() {
+        assert_eq!(
+            split_csv_line(r#"1,bob,"bob,""b""@mail.com""#),
+            Some(vec![
+                "1".to_owned(),
+                "bob".to_owned(),
+                r#"bob,"b"@mail.com"#.to_owned()
+            ])
+        );
+        assert_eq!(
+            split_csv_line("1,,"),
+            Some(vec!["1".to_owned(), String::new(), String::new()])
+        );
+        assert_eq!(split_csv_line(r#"1,"bob"#), None);
+    }
+}
@@ -1,42 +1,492 @@
+use std::ops::RangeInclusive;
 use std::{cell::RefCell, rc::Rc};
 
-use crate::table::Table;
+use crate::btree::{Node, NodeView, read_node};
+use crate::pager::{GetPageError, Pager};
+use crate::row::Row;
+use crate::table::{GetRowError, Table};
 
+#[cfg_attr(debug_assertions, derive(Debug))]
 pub enum CursorError {
-    PoisonedTable,
+    /// Le curseur est après la dernière ligne de sa plage.
+    EndOfTable,
+    /// Une écriture sous le curseur changerait la clé de la cellule.
+    KeyChanged(u64),
+    GetPage(GetPageError),
+}
+impl From<GetPageError> for CursorError {
+    fn from(e: GetPageError) -> Self {
+        Self::GetPage(e)
+    }
 }
 
+/*
+ * Position dans l'arbre : une cellule d'une feuille. Les feuilles sont
+ * chaînées dans l'ordre des clés, le curseur passe de l'une à l'autre sans
+ * remonter dans l'arbre.
+ */
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub struct Cursor {
-    table: Rc<RefCell<Table>>,
-    row_num: usize,
+    pager: Rc<Pager>,
+    page_num: usize,
+    cell_num: usize,
+    /// Le curseur ne revient pas avant la première clé supérieure ou égale à
+    /// `start_key`.
+    start_key: u64,
+    /// Le curseur s'arrête après la dernière clé inférieure ou égale à `end_key`.
+    end_key: u64,
+    end_of_table: bool,
 }
 impl Cursor {
-    pub fn at_start(table: Rc<RefCell<Table>>) -> Self {
-        Self { table, row_num: 0 }
+    /// Curseur sur la première ligne de la table, dans l'ordre des clés.
+    pub fn at_start(table: Rc<RefCell<Table>>) -> Result<Self, GetPageError> {
+        Self::range(table, 0..=u64::MAX)
+    }
+
+    /// Curseur sur la première ligne dont la clé est supérieure ou égale à `key`.
+    pub fn find(table: Rc<RefCell<Table>>, key: u64) -> Result<Self, GetPageError> {
+        Self::range(table, key..=u64::MAX)
     }
 
-    pub fn at_end(table: Rc<RefCell<Table>>) -> Self {
-        let row_num = table.borrow().get_nb_rows();
+    /// Curseur sur les seules lignes dont la clé est dans `keys` : il est placé
+    /// sur la première par une descente de l'arbre et s'arrête après la dernière.
+    pub fn range(
+        table: Rc<RefCell<Table>>,
+        keys: RangeInclusive<u64>,
+    ) -> Result<Self, GetPageError> {
+        let table = table.borrow();
+        let (page_num, cell_num) = table.find(*keys.start())?;
 
-        Self { table, row_num }
+        let mut cursor = Self {
+            pager: table.get_pager(),
+            page_num,
+            cell_num,
+            start_key: *keys.start(),
+            end_key: *keys.end(),
+            end_of_table: keys.is_empty(),
+        };
+        cursor.skip_to_next_leaf()?;
+        Ok(cursor)
     }
 
     pub fn is_end_of_table(&self) -> bool {
-        self.table.borrow().get_nb_rows() <= self.row_num
+        self.end_of_table
+    }
+
+    fn leaf(&self) -> Result<NodeView<'_>, GetPageError> {
+        self.leaf_at(self.page_num)
+    }
+
+    fn leaf_at(&self, page_num: usize) -> Result<NodeView<'_>, GetPageError> {
+        match read_node(&self.pager, page_num)? {
+            node @ Node::Leaf(_) => Ok(node),
+            Node::Internal(_) => Err(GetPageError::Corrupted(page_num)),
+        }
+    }
+
+    /// Feuille la plus à gauche de l'arbre, celle de la plus petite clé.
+    fn first_leaf(&self) -> Result<usize, GetPageError> {
+        let mut page_num = Table::ROOT_PAGE_NUM;
+        for _ in 0..Pager::MAX_PAGES {
+            match read_node(&self.pager, page_num)? {
+                Node::Leaf(_) => return Ok(page_num),
+                node @ Node::Internal(_) => page_num = node.internal_node_get_child(0),
+            }
+        }
+        Err(GetPageError::Corrupted(page_num))
+    }
+
+    /// Passe à la feuille suivante tant que le curseur est après la dernière
+    /// cellule, et marque la fin de la table après la dernière clé demandée.
+    fn skip_to_next_leaf(&mut self) -> Result<(), GetPageError> {
+        if self.end_of_table {
+            return Ok(());
+        }
+
+        // Chaque page ne peut être traversée qu'une fois.
+        for _ in 0..Pager::MAX_PAGES {
+            let (nb_cells, next_leaf) = {
+                let leaf = self.leaf()?;
+                (
+                    leaf.leaf_node_get_nb_cells(),
+                    leaf.leaf_node_get_next_leaf(),
+                )
+            };
+            if self.cell_num < nb_cells {
+                let key = self.leaf()?.leaf_node_get_cell(self.cell_num).get_key();
+                self.end_of_table = key > self.end_key;
+                return Ok(());
+            }
+            if next_leaf == 0 {
+                self.end_of_table = true;
+                return Ok(());
+            }
+            self.page_num = next_leaf;
+            self.cell_num = 0;
+        }
+        Err(GetPageError::Corrupted(self.page_num))
+    }
+
+    pub fn get_key(&self) -> Result<u64, CursorError> {
+        if self.is_end_of_table() {
+            return Err(CursorError::EndOfTable);
+        }
+        Ok(self.leaf()?.leaf_node_get_cell(self.cell_num).get_key())
     }
 
-    pub fn get(&self) -> &[u8] {
-        let slice_pointer = self.table.borrow().get(self.row_num);
-        <&[u8]>::from(slice_pointer)
+    /// Copie de la ligne stockée sous le curseur.
+    pub fn get(&self) -> Result<Vec<u8>, CursorError> {
+        if self.is_end_of_table() {
+            return Err(CursorError::EndOfTable);
+        }
+
+        // Vérifie que la page est bien une feuille.
+        self.leaf()?;
+        let range = NodeView::leaf_node_value_range(self.cell_num);
+        Ok(self
+            .pager
+            .read_at(self.page_num, range.start, range.len())?)
+    }
+
+    /// Remplace la ligne sous le curseur. Son id doit rester celui de la
+    /// cellule : l'arbre est trié par clé.
+    pub fn set(&self, row: Row) -> Result<(), CursorError> {
+        let key = self.get_key()?;
+        if **row.get_id() != key {
+            return Err(CursorError::KeyChanged(key));
+        }
+        let range = NodeView::leaf_node_value_range(self.cell_num);
+        self.pager.write_at(
+            self.page_num,
+            range.start,
+            &<[u8; Row::MAX_SIZE]>::from(row),
+        )?;
+        Ok(())
+    }
+
+    /// Place le curseur sur la ligne `n` de la table, à partir de 0, quelle que
+    /// soit sa position. Les feuilles précédentes sont sautées sans lire leurs
+    /// cellules. Une ligne avant la plage du curseur le place sur la première
+    /// de la plage : `seek(position())` ne le déplace pas.
+    pub fn seek(&mut self, n: usize) -> Result<(), GetPageError> {
+        self.seek_in_table(n)?;
+        while !self.end_of_table
+            && self.leaf()?.leaf_node_get_cell(self.cell_num).get_key() < self.start_key
+        {
+            self.advance()?;
+        }
+        Ok(())
+    }
+
+    fn seek_in_table(&mut self, n: usize) -> Result<(), GetPageError> {
+        let mut page_num = self.first_leaf()?;
+        let mut remaining = n;
+        for _ in 0..Pager::MAX_PAGES {
+            let (nb_cells, next_leaf) = {
+                let leaf = self.leaf_at(page_num)?;
+                (
+                    leaf.leaf_node_get_nb_cells(),
+                    leaf.leaf_node_get_next_leaf(),
+                )
+            };
+            if remaining < nb_cells || next_leaf == 0 {
+                self.page_num = page_num;
+                self.cell_num = remaining.min(nb_cells);
+                self.end_of_table = false;
+                return self.skip_to_next_leaf();
+            }
+            remaining -= nb_cells;
+            page_num = next_leaf;
+        }
+        Err(GetPageError::Corrupted(page_num))
+    }
+
+    /// Numéro dans la table de la ligne sous le curseur, à partir de 0 : le
+    /// nombre de lignes des feuilles précédentes est ajouté à sa cellule.
+    pub fn position(&self) -> Result<usize, GetPageError> {
+        let mut page_num = self.first_leaf()?;
+        let mut position = 0;
+        for _ in 0..Pager::MAX_PAGES {
+            if page_num == self.page_num {
+                return Ok(position + self.cell_num);
+            }
+            let leaf = self.leaf_at(page_num)?;
+            position += leaf.leaf_node_get_nb_cells();
+            page_num = leaf.leaf_node_get_next_leaf();
+            if page_num == 0 {
+                break;
+            }
+        }
+        Err(GetPageError::Corrupted(self.page_num))
+    }
+
+    pub fn advance(&mut self) -> Result<(), GetPageError> {
+        self.cell_num += 1;
+        self.skip_to_next_leaf()
+    }
+}
+
+/*
+ * Lignes d'une plage de clés, lisibles par les deux bouts. L'avant suit le
+ * chaînage des feuilles avec un `Cursor`. L'arrière n'a pas de chaînage :
+ * chaque ligne est cherchée depuis la racine, comme la plus grande clé sous
+ * la dernière rendue. Les deux bouts s'arrêtent quand ils se rejoignent.
+ */
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct RowIter {
+    front: Cursor,
+    /// Plus petite clé que l'arrière peut encore rendre.
+    back_start: u64,
+    finished: bool,
+}
+impl RowIter {
+    pub fn new(table: Rc<RefCell<Table>>) -> Result<Self, GetPageError> {
+        Self::range(table, 0..=u64::MAX)
     }
 
-    pub fn get_mut(&mut self) -> &mut [u8] {
-        let slice_pointer_mut = self.table.borrow_mut().get_mut(self.row_num);
-        <&mut [u8]>::from(slice_pointer_mut)
+    pub fn range(
+        table: Rc<RefCell<Table>>,
+        keys: RangeInclusive<u64>,
+    ) -> Result<Self, GetPageError> {
+        let back_start = *keys.start();
+        Ok(Self {
+            front: Cursor::range(table, keys)?,
+            back_start,
+            finished: false,
+        })
     }
 
-    pub fn advance(&mut self) {
-        self.row_num += 1;
+    /// Feuille où mène `key` depuis la racine, et clé du plus proche sous-arbre
+    /// à gauche du chemin suivi : toutes les clés de la feuille sont plus grandes.
+    fn descend(&self, key: u64) -> Result<(usize, Option<u64>), GetPageError> {
+        let mut lower_bound = None;
+        let mut page_num = Table::ROOT_PAGE_NUM;
+        for _ in 0..Pager::MAX_PAGES {
+            match read_node(&self.front.pager, page_num)? {
+                Node::Leaf(_) => return Ok((page_num, lower_bound)),
+                node @ Node::Internal(_) => {
+                    let child_num = node.internal_node_find_child(key);
+                    if child_num > 0 {
+                        lower_bound = Some(node.internal_node_get_key(child_num - 1));
+                    }
+                    page_num = node.internal_node_get_child(child_num);
+                }
+            }
+        }
+        Err(GetPageError::Corrupted(page_num))
+    }
+
+    /// Page et cellule de la plus grande clé inférieure ou égale à `key`.
+    fn find_last(&self, key: u64) -> Result<Option<(usize, usize)>, GetPageError> {
+        let mut key = key;
+        let mut page_num = Table::ROOT_PAGE_NUM;
+        // Chaque nouvelle descente mène à une feuille plus à gauche.
+        for _ in 0..Pager::MAX_PAGES {
+            let lower_bound;
+            (page_num, lower_bound) = self.descend(key)?;
+            let leaf = read_node(&self.front.pager, page_num)?;
+            let cell_num = leaf.leaf_node_find(key);
+            if cell_num < leaf.leaf_node_get_nb_cells()
+                && leaf.leaf_node_get_cell(cell_num).get_key() == key
+            {
+                return Ok(Some((page_num, cell_num)));
+            }
+            if cell_num > 0 {
+                return Ok(Some((page_num, cell_num - 1)));
+            }
+            // La feuille n'a pas de clé assez petite, elle peut même être vide
+            // après des suppressions : la recherche reprend à gauche.
+            match lower_bound {
+                Some(lower_bound) if lower_bound < key => key = lower_bound,
+                Some(_) => return Err(GetPageError::Corrupted(page_num)),
+                None => return Ok(None),
+            }
+        }
+        Err(GetPageError::Corrupted(page_num))
+    }
+
+    fn read_back(&mut self) -> Result<Option<Row>, GetRowError> {
+        let Some((page_num, cell_num)) = self
+            .find_last(self.front.end_key)
+            .map_err(GetRowError::GetPage)?
+        else {
+            return Ok(None);
+        };
+        let key = read_node(&self.front.pager, page_num)
+            .map_err(GetRowError::GetPage)?
+            .leaf_node_get_cell(cell_num)
+            .get_key();
+        if key < self.back_start {
+            return Ok(None);
+        }
+        let range = NodeView::leaf_node_value_range(cell_num);
+        let row_bytes = self
+            .front
+            .pager
+            .read_at(page_num, range.start, range.len())
+            .map_err(GetRowError::GetPage)?;
+        let row = Row::try_from(&row_bytes[..]).map_err(GetRowError::Deserialize)?;
+
+        // L'avant s'arrête désormais avant cette clé.
+        match key.checked_sub(1) {
+            Some(end_key) => {
+                self.front.end_key = end_key;
+                if !self.front.end_of_table {
+                    self.front.end_of_table = self.front.get_key()? > end_key;
+                }
+            }
+            None => self.finished = true,
+        }
+        Ok(Some(row))
+    }
+
+    fn read_front(&mut self) -> Result<Option<Row>, GetRowError> {
+        if self.front.is_end_of_table() {
+            return Ok(None);
+        }
+        let key = self.front.get_key()?;
+        let row_bytes = self.front.get()?;
+        let row = Row::try_from(&row_bytes[..]).map_err(GetRowError::Deserialize)?;
+        self.front.advance().map_err(GetRowError::GetPage)?;
+        // L'arrière s'arrête désormais après cette clé.
+        match key.checked_add(1) {
+            Some(back_start) => self.back_start = back_start,
+            None => self.finished = true,
+        }
+        Ok(Some(row))
+    }
+}
+impl Iterator for RowIter {
+    type Item = Result<Row, GetRowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        // Après une erreur, ni l'avant ni l'arrière ne rendent plus rien.
+        let row = self.read_front().transpose();
+        self.finished = !matches!(row, Some(Ok(_)));
+        row
+    }
+}
+impl DoubleEndedIterator for RowIter {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let row = self.read_back().transpose();
+        self.finished = !matches!(row, Some(Ok(_)));
+        row
+    }
+}
+
+#[cfg(test)]
+mod cursor_test {
+    use super::*;
+
+    use crate::backend::MemoryBackend;
+    use crate::row::{Email, Id, Row, Username};
+    use crate::table::table_test::table_with_ids;
+
+    /// Les pages sont relues depuis le contenu sauvegardé, à mesure que le
+    /// curseur passe d'une feuille à l'autre.
+    #[test]
+    fn test_range_across_reloaded_leaves() {
+        let table = table_with_ids(1..=40);
+        let mut backend = MemoryBackend::default();
+        table
+            .borrow()
+            .get_pager()
+            .write_to(&mut backend, 40)
+            .unwrap();
+
+        let pager = Rc::new(Pager::with_backend(Box::new(backend)).unwrap());
+        let table = Rc::new(RefCell::new(Table::new(pager)));
+        let mut cursor = Cursor::range(table, 10..=30).unwrap();
+        let mut keys = Vec::new();
+        while !cursor.is_end_of_table() {
+            keys.push(cursor.get_key().unwrap());
+            cursor.advance().unwrap();
+        }
+        assert_eq!(keys, (10..=30).collect::<Vec<u64>>());
+    }
+
+    #[test]
+    fn test_seek_and_position() {
+        let table = table_with_ids((1..=40).map(|id| id * 10));
+
+        let mut cursor = Cursor::range(table, 50..=300).unwrap();
+        assert_eq!(cursor.position().unwrap(), 4);
+        cursor.seek(25).unwrap();
+        assert_eq!(cursor.get_key().unwrap(), 260);
+        assert_eq!(cursor.position().unwrap(), 25);
+        cursor.advance().unwrap();
+        assert_eq!(cursor.position().unwrap(), 26);
+
+        cursor.seek(cursor.position().unwrap()).unwrap();
+        assert_eq!(cursor.get_key().unwrap(), 270);
+
+        // Le début et la fin de la plage sont gardés.
+        cursor.seek(30).unwrap();
+        assert!(cursor.is_end_of_table());
+        cursor.seek(0).unwrap();
+        assert_eq!(cursor.get_key().unwrap(), 50);
+        assert_eq!(cursor.position().unwrap(), 4);
+        cursor.seek(100).unwrap();
+        assert!(cursor.is_end_of_table());
+    }
+
+    #[test]
+    fn test_row_iter_from_both_ends() {
+        let table = table_with_ids((1..=40).map(|id| id * 10));
+        let ids = |rows: &mut dyn Iterator<Item = Result<Row, GetRowError>>| {
+            rows.map(|row| **row.unwrap().get_id())
+                .collect::<Vec<u64>>()
+        };
+
+        let mut rows = RowIter::new(table.clone()).unwrap();
+        assert_eq!(ids(&mut rows.by_ref().rev().take(3)), vec![400, 390, 380]);
+        assert_eq!(ids(&mut rows.by_ref().take(2)), vec![10, 20]);
+        assert_eq!(
+            ids(&mut rows.by_ref().rev()),
+            (3..=37).rev().map(|id| id * 10).collect::<Vec<u64>>()
+        );
+        assert!(rows.next().is_none());
+
+        let mut rows = RowIter::range(table, 55..=305).unwrap();
+        assert_eq!(
+            rows.next_back().map(|row| **row.unwrap().get_id()),
+            Some(300)
+        );
+        assert_eq!(
+            ids(&mut rows),
+            (6..=29).map(|id| id * 10).collect::<Vec<u64>>()
+        );
+        assert!(rows.next_back().is_none());
+    }
+
+    #[test]
+    fn test_get_and_set_under_the_cursor() {
+        let table = Rc::new(RefCell::new(Table::new(Rc::new(Pager::default()))));
+        let row = |id, username: &str| {
+            Row::new(
+                Id::new(id),
+                Username::new(username.to_owned()),
+                Email::new(format!("{username}@mail.com")),
+            )
+        };
+        table.borrow_mut().insert(row(1, "alice")).unwrap();
+
+        let mut cursor = Cursor::at_start(table).unwrap();
+        cursor.set(row(1, "bob")).unwrap();
+        assert!(matches!(
+            cursor.set(row(2, "bob")),
+            Err(CursorError::KeyChanged(1))
+        ));
+        assert!(Row::try_from(&cursor.get().unwrap()[..]).unwrap() == row(1, "bob"));
+
+        cursor.advance().unwrap();
+        assert!(matches!(cursor.get(), Err(CursorError::EndOfTable)));
+        assert!(matches!(cursor.get_key(), Err(CursorError::EndOfTable)));
     }
 }
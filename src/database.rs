@@ -0,0 +1,142 @@
+use std::io::Write;
+use std::{cell::RefCell, rc::Rc};
+
+use crate::backend::{MemoryBackend, PagerBackend, Vfs};
+use crate::hooks::Hooks;
+use crate::pager::{Pager, PagerOpenError, SaveToDiskError};
+use crate::provider::TableProvider;
+use crate::table::Table;
+
+/// Base de données ouverte : la table et le pager qui la stocke.
+///
+/// Une base peut être associée à un fichier de sauvegarde, ou vivre
+/// uniquement en mémoire (voir [`Database::open_in_memory`]).
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct Database {
+    table: Rc<RefCell<Table>>,
+}
+impl Database {
+    /// Chemin spécial désignant une base en mémoire, comme avec SQLite.
+    pub const MEMORY_PATH: &str = ":memory:";
+
+    /// Ouvre le fichier `file_path`, en le créant s'il n'existe pas.
+    ///
+    /// Le chemin [`Database::MEMORY_PATH`] ouvre une base en mémoire.
+    pub fn open(file_path: &str) -> Result<Self, PagerOpenError> {
+        if file_path == Self::MEMORY_PATH {
+            return Ok(Self::open_in_memory());
+        }
+
+        let pager = Pager::new(Some(file_path))?;
+        Self::from_pager(pager).checked(file_path)
+    }
+
+    /// Comme `open`, le fichier étant lu et sauvegardé par `vfs`, par exemple
+    /// un `ObjectStoreVfs` pour partager la base entre plusieurs machines.
+    pub fn open_with_vfs(vfs: Box<dyn Vfs>, file_path: &str) -> Result<Self, PagerOpenError> {
+        let pager = Pager::with_vfs(vfs, Some(file_path))?;
+        Self::from_pager(pager).checked(file_path)
+    }
+
+    /// Ouvre le fichier `file_path` sans jamais le modifier : les insertions et
+    /// suppressions sont refusées, et le fichier doit déjà exister.
+    pub fn open_read_only(file_path: &str) -> Result<Self, PagerOpenError> {
+        let file_path = Some(file_path).filter(|file_path| *file_path != Self::MEMORY_PATH);
+        let pager = Pager::new_read_only(file_path)?;
+        Self::from_pager(pager).checked(file_path.unwrap_or(Self::MEMORY_PATH))
+    }
+
+    /// Ouvre une base qui ne lit ni n'écrit jamais sur le disque.
+    ///
+    /// `.save` n'a pas de fichier par défaut : il faut lui donner un chemin
+    /// explicite pour conserver les données.
+    pub fn open_in_memory() -> Self {
+        Self::from_pager(Pager::default())
+    }
+
+    /// Ouvre une base lue depuis `backend` au lieu d'un fichier, par exemple
+    /// un `MemoryBackend` rempli avec ce que le navigateur a gardé d'une
+    /// session précédente. Les modifications restent en mémoire jusqu'à
+    /// `write_to`.
+    pub fn open_backend(backend: Box<dyn PagerBackend>) -> Result<Self, PagerOpenError> {
+        let pager = Pager::with_backend(backend)?;
+        Self::from_pager(pager).checked(MemoryBackend::NAME)
+    }
+
+    /// Écrit la base au format d'un fichier de sauvegarde, relisible par
+    /// `open_backend`, sans passer par le disque.
+    pub fn write_to(&self, out: &mut dyn Write) -> Result<(), SaveToDiskError> {
+        let table = self.table.borrow();
+        table.get_pager().write_to(out, table.get_nb_rows())
+    }
+
+    /// Refuse un fichier dont le catalogue ne décrit pas la table des `Row`.
+    fn checked(self, file_path: &str) -> Result<Self, PagerOpenError> {
+        self.table
+            .borrow()
+            .check_catalog()
+            .map_err(|e| PagerOpenError::Catalog(file_path.to_owned(), e))?;
+        Ok(self)
+    }
+
+    fn from_pager(pager: Pager) -> Self {
+        let table = Table::new(Rc::new(pager));
+        Self {
+            table: Rc::new(RefCell::new(table)),
+        }
+    }
+
+    pub fn get_table(&self) -> Rc<RefCell<Table>> {
+        self.table.clone()
+    }
+
+    /// Les hooks sont appelés après chaque insertion ou suppression réussie.
+    pub fn add_hooks(&self, hooks: Rc<dyn Hooks>) {
+        self.table.borrow_mut().add_hooks(hooks);
+    }
+
+    /// Rend la source lisible par `select from <name>`.
+    pub fn register_provider(&self, name: &str, provider: Rc<dyn TableProvider>) {
+        self.table.borrow_mut().register_provider(name, provider);
+    }
+
+    pub fn is_in_memory(&self) -> bool {
+        !self.table.borrow().get_pager().has_save_file()
+    }
+}
+
+#[cfg(test)]
+mod database_test {
+    use super::*;
+
+    use crate::row::{Email, Id, Row, Username};
+    use crate::typed_table::TypedTable;
+
+    #[test]
+    fn test_open_memory_path() {
+        let database = Database::open(Database::MEMORY_PATH).unwrap();
+        assert!(database.is_in_memory());
+        assert!(!std::path::Path::new(Database::MEMORY_PATH).exists());
+    }
+
+    #[test]
+    fn test_open_backend_round_trip() {
+        let database = Database::open_in_memory();
+        let row = Row::new(
+            Id::new(1),
+            Username::new("bob".to_owned()),
+            Email::new("bob@mail.com".to_owned()),
+        );
+        database
+            .get_table()
+            .borrow_mut()
+            .insert(row.clone())
+            .unwrap();
+        let mut bytes = Vec::new();
+        database.write_to(&mut bytes).unwrap();
+
+        let database = Database::open_backend(Box::new(MemoryBackend::new(bytes))).unwrap();
+        let rows = TypedTable::<Row>::new(database.get_table());
+        assert!(rows.get(1).unwrap() == Some(row));
+    }
+}
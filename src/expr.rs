@@ -0,0 +1,379 @@
+use std::cmp::Ordering;
+
+use crate::row::RowRef;
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(untagged)
+)]
+#[derive(PartialEq, Clone)]
+pub enum Value {
+    Number(u64),
+    Text(String),
+    /// Résultat d'un calcul impossible : dépassement, division par zéro, ou
+    /// colonne inconnue. Il n'est égal à rien, pas même à lui-même.
+    Null,
+}
+impl Value {
+    /// Une condition est vérifiée par un nombre non nul.
+    pub fn is_true(&self) -> bool {
+        matches!(self, Self::Number(number) if *number != 0)
+    }
+
+    fn from_bool(value: bool) -> Self {
+        Self::Number(u64::from(value))
+    }
+
+    /// Les nombres se comparent entre eux comme des nombres, et comme du texte
+    /// face à du texte : `username = 5` vaut pour le nom `5`.
+    fn compare(&self, other: &Value) -> Option<Ordering> {
+        match (self, other) {
+            (Self::Null, _) | (_, Self::Null) => None,
+            (Self::Number(a), Self::Number(b)) => Some(a.cmp(b)),
+            (Self::Text(a), Self::Text(b)) => Some(a.cmp(b)),
+            (Self::Number(a), Self::Text(b)) => Some(a.to_string().cmp(b)),
+            (Self::Text(a), Self::Number(b)) => Some(a.cmp(&b.to_string())),
+        }
+    }
+}
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Number(number) => write!(f, "{number}"),
+            Self::Text(text) => write!(f, "{text}"),
+            Self::Null => write!(f, "NULL"),
+        }
+    }
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Clone, Copy)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+impl Comparison {
+    /// Comparaison équivalente une fois les deux côtés échangés : `5 < id`
+    /// devient `id > 5`.
+    pub fn flip(self) -> Self {
+        match self {
+            Self::Eq | Self::Ne => self,
+            Self::Lt => Self::Gt,
+            Self::Le => Self::Ge,
+            Self::Gt => Self::Lt,
+            Self::Ge => Self::Le,
+        }
+    }
+
+    pub fn is_satisfied(self, ordering: Ordering) -> bool {
+        match self {
+            Self::Eq => ordering.is_eq(),
+            Self::Ne => ordering.is_ne(),
+            Self::Lt => ordering.is_lt(),
+            Self::Le => ordering.is_le(),
+            Self::Gt => ordering.is_gt(),
+            Self::Ge => ordering.is_ge(),
+        }
+    }
+}
+impl std::fmt::Display for Comparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let operator = match self {
+            Self::Eq => "=",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+        };
+        write!(f, "{operator}")
+    }
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Clone, Copy)]
+pub enum Arithmetic {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+impl Arithmetic {
+    /// Les nombres sont positifs : un résultat négatif donne `Null`.
+    fn apply(self, a: u64, b: u64) -> Option<u64> {
+        match self {
+            Self::Add => a.checked_add(b),
+            Self::Sub => a.checked_sub(b),
+            Self::Mul => a.checked_mul(b),
+            Self::Div => a.checked_div(b),
+        }
+    }
+}
+impl std::fmt::Display for Arithmetic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let operator = match self {
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Mul => "*",
+            Self::Div => "/",
+        };
+        write!(f, "{operator}")
+    }
+}
+
+/// Fonctions utilisables dans une expression.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Clone, Copy)]
+pub enum Function {
+    /// Nombre de caractères.
+    Length,
+    Lower,
+    Upper,
+}
+impl Function {
+    pub fn get_nb_arguments(self) -> usize {
+        match self {
+            Self::Length | Self::Lower | Self::Upper => 1,
+        }
+    }
+
+    fn call(self, arguments: &[Value]) -> Value {
+        let text = match arguments {
+            [Value::Null] | [] => return Value::Null,
+            [argument, ..] => argument.to_string(),
+        };
+        match self {
+            Self::Length => Value::Number(text.chars().count() as u64),
+            Self::Lower => Value::Text(text.to_lowercase()),
+            Self::Upper => Value::Text(text.to_uppercase()),
+        }
+    }
+}
+impl std::convert::TryFrom<&str> for Function {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "length" => Ok(Self::Length),
+            "lower" => Ok(Self::Lower),
+            "upper" => Ok(Self::Upper),
+            _ => Err(()),
+        }
+    }
+}
+
+/*
+ * Expression calculée sur une ligne : constantes, colonnes, comparaisons,
+ * calculs et appels de fonction. Le parser construit l'arbre tel qu'il a été
+ * écrit, les noms de colonnes et de fonctions sont vérifiés en préparant
+ * l'instruction ; un nom inconnu donne `Null` à l'évaluation.
+ */
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Clone)]
+pub enum Expr {
+    Column(String),
+    Value(Value),
+    Compare(Box<Expr>, Comparison, Box<Expr>),
+    Between {
+        expr: Box<Expr>,
+        low: Box<Expr>,
+        high: Box<Expr>,
+    },
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Arithmetic(Box<Expr>, Arithmetic, Box<Expr>),
+    Call {
+        function: String,
+        arguments: Vec<Expr>,
+    },
+}
+impl Expr {
+    /// Une comparaison vaut 1 si elle est vérifiée, 0 sinon, et `Null` si
+    /// l'un des côtés est `Null`.
+    pub fn eval(&self, row: &RowRef<'_>) -> Value {
+        match self {
+            Self::Column(column) => match column.to_lowercase().as_str() {
+                "id" => Value::Number(row.get_id()),
+                "username" => Value::Text(row.get_username().to_owned()),
+                "email" => Value::Text(row.get_email().to_owned()),
+                _ => Value::Null,
+            },
+            Self::Value(value) => value.clone(),
+            Self::Compare(left, comparison, right) => {
+                match left.eval(row).compare(&right.eval(row)) {
+                    Some(ordering) => Value::from_bool(comparison.is_satisfied(ordering)),
+                    None => Value::Null,
+                }
+            }
+            Self::Between { expr, low, high } => {
+                let value = expr.eval(row);
+                match (
+                    value.compare(&low.eval(row)),
+                    value.compare(&high.eval(row)),
+                ) {
+                    (Some(low), Some(high)) => Value::from_bool(low.is_ge() && high.is_le()),
+                    _ => Value::Null,
+                }
+            }
+            Self::And(left, right) => {
+                Value::from_bool(left.eval(row).is_true() && right.eval(row).is_true())
+            }
+            Self::Or(left, right) => {
+                Value::from_bool(left.eval(row).is_true() || right.eval(row).is_true())
+            }
+            Self::Arithmetic(left, arithmetic, right) => match (left.eval(row), right.eval(row)) {
+                (Value::Number(a), Value::Number(b)) => {
+                    arithmetic.apply(a, b).map_or(Value::Null, Value::Number)
+                }
+                _ => Value::Null,
+            },
+            Self::Call {
+                function,
+                arguments,
+            } => match Function::try_from(function.as_str()) {
+                Ok(function) => {
+                    let arguments: Vec<Value> = arguments
+                        .iter()
+                        .map(|argument| argument.eval(row))
+                        .collect();
+                    function.call(&arguments)
+                }
+                Err(()) => Value::Null,
+            },
+        }
+    }
+
+    /// Plus la priorité est haute, plus l'opérateur lie fort.
+    fn get_precedence(&self) -> u8 {
+        match self {
+            Self::Or(..) => 1,
+            Self::And(..) => 2,
+            Self::Compare(..) | Self::Between { .. } => 3,
+            Self::Arithmetic(_, Arithmetic::Add | Arithmetic::Sub, _) => 4,
+            Self::Arithmetic(_, Arithmetic::Mul | Arithmetic::Div, _) => 5,
+            Self::Column(_) | Self::Value(_) | Self::Call { .. } => 6,
+        }
+    }
+
+    /// Écrit `operand` entre parenthèses s'il lie moins fort que `self`, ou
+    /// aussi fort à droite : `a - (b - c)`.
+    fn write_operand(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        operand: &Expr,
+        is_right: bool,
+    ) -> std::fmt::Result {
+        let (precedence, operand_precedence) = (self.get_precedence(), operand.get_precedence());
+        if operand_precedence < precedence || (is_right && operand_precedence == precedence) {
+            write!(f, "({operand})")
+        } else {
+            write!(f, "{operand}")
+        }
+    }
+}
+impl std::fmt::Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (left, operator, right) = match self {
+            Self::Column(column) => return write!(f, "{column}"),
+            Self::Value(Value::Text(text)) => {
+                return write!(f, "'{}'", text.replace('\'', "''"));
+            }
+            Self::Value(value) => return write!(f, "{value}"),
+            Self::Between { expr, low, high } => {
+                self.write_operand(f, expr, false)?;
+                write!(f, " between ")?;
+                self.write_operand(f, low, true)?;
+                write!(f, " and ")?;
+                return self.write_operand(f, high, true);
+            }
+            Self::Call {
+                function,
+                arguments,
+            } => {
+                let arguments: Vec<String> = arguments.iter().map(ToString::to_string).collect();
+                return write!(f, "{function}({})", arguments.join(", "));
+            }
+            Self::Compare(left, comparison, right) => (left, comparison.to_string(), right),
+            Self::And(left, right) => (left, "and".to_owned(), right),
+            Self::Or(left, right) => (left, "or".to_owned(), right),
+            Self::Arithmetic(left, arithmetic, right) => (left, arithmetic.to_string(), right),
+        };
+        self.write_operand(f, left, false)?;
+        write!(f, " {operator} ")?;
+        self.write_operand(f, right, true)
+    }
+}
+
+#[cfg(test)]
+mod expr_test {
+    use super::*;
+
+    use crate::row::{Email, Id, Row, Username};
+
+    #[test]
+    fn test_eval() {
+        let row = Row::new(
+            Id::new(7),
+            Username::new("Bob".to_owned()),
+            Email::new("bob@mail.com".to_owned()),
+        );
+        let row = row.as_row_ref();
+        let column = |name: &str| Box::new(Expr::Column(name.to_owned()));
+        let number = |number| Box::new(Expr::Value(Value::Number(number)));
+
+        let id_times_two = Expr::Arithmetic(column("id"), Arithmetic::Mul, number(2));
+        assert_eq!(id_times_two.eval(&row), Value::Number(14));
+        assert_eq!(
+            Expr::Arithmetic(number(1), Arithmetic::Sub, column("id")).eval(&row),
+            Value::Null
+        );
+        assert_eq!(
+            Expr::Arithmetic(column("id"), Arithmetic::Div, number(0)).eval(&row),
+            Value::Null
+        );
+
+        let length = Expr::Call {
+            function: "LENGTH".to_owned(),
+            arguments: vec![Expr::Column("email".to_owned())],
+        };
+        assert_eq!(length.eval(&row), Value::Number(12));
+        let lower = Expr::Call {
+            function: "lower".to_owned(),
+            arguments: vec![Expr::Column("username".to_owned())],
+        };
+        assert_eq!(lower.eval(&row), Value::Text("bob".to_owned()));
+
+        let condition = Expr::Or(
+            Box::new(Expr::Compare(
+                Box::new(id_times_two),
+                Comparison::Gt,
+                number(20),
+            )),
+            Box::new(Expr::Compare(
+                Box::new(lower),
+                Comparison::Eq,
+                Box::new(Expr::Value(Value::Text("bob".to_owned()))),
+            )),
+        );
+        assert!(condition.eval(&row).is_true());
+        assert_eq!(
+            condition.to_string(),
+            "id * 2 > 20 or lower(username) = 'bob'"
+        );
+        assert!(
+            !Expr::Compare(
+                number(1),
+                Comparison::Eq,
+                Box::new(Expr::Value(Value::Null))
+            )
+            .eval(&row)
+            .is_true()
+        );
+    }
+}
@@ -0,0 +1,247 @@
+use std::cell::{Cell, RefCell};
+use std::io;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::rc::Rc;
+
+use crate::backend::{LockMode, MemoryVfs, OpenMode, PagerBackend, Vfs};
+
+/// Pannes simulées, communes à tous les fichiers d'un `FaultyVfs`.
+#[derive(Debug, Default)]
+struct Faults {
+    /// Nombre d'appels à `write` depuis la création du `FaultyVfs`.
+    nb_writes: Cell<usize>,
+    /// Numéro, à partir de 1, de l'écriture qui échoue.
+    fail_write: Cell<Option<usize>>,
+    /// La machine s'arrête après ce nombre d'écritures : les suivantes, les
+    /// renommages et les suppressions sont ignorés sans erreur.
+    crash_after: Cell<Option<usize>>,
+    /// Un `sync` coupe le fichier à la moitié de sa taille, comme un disque
+    /// qui aurait perdu des écritures pourtant déclarées durables.
+    truncate_on_sync: Cell<bool>,
+}
+impl Faults {
+    fn has_crashed(&self) -> bool {
+        self.crash_after
+            .get()
+            .is_some_and(|nb_writes| self.nb_writes.get() >= nb_writes)
+    }
+}
+
+/*
+ * Vfs des tests de résistance aux pannes : les fichiers sont ceux d'un
+ * `MemoryVfs`, que les tests rouvrent directement pour voir ce qu'une machine
+ * redémarrée trouverait sur le disque.
+ */
+#[derive(Debug, Default)]
+pub struct FaultyVfs {
+    inner: MemoryVfs,
+    faults: Rc<Faults>,
+}
+impl FaultyVfs {
+    pub fn new(inner: MemoryVfs) -> Self {
+        Self {
+            inner,
+            faults: Rc::default(),
+        }
+    }
+
+    pub fn fail_write(self, nb_write: usize) -> Self {
+        self.faults.fail_write.set(Some(nb_write));
+        self
+    }
+
+    pub fn crash_after(self, nb_writes: usize) -> Self {
+        self.faults.crash_after.set(Some(nb_writes));
+        self
+    }
+
+    pub fn truncate_on_sync(self) -> Self {
+        self.faults.truncate_on_sync.set(true);
+        self
+    }
+}
+impl Vfs for FaultyVfs {
+    fn open(&self, path: &str, mode: OpenMode) -> io::Result<Box<dyn PagerBackend>> {
+        // Après l'arrêt, un fichier existant n'est plus vidé.
+        let mode = match mode {
+            OpenMode::Create if self.faults.has_crashed() => OpenMode::ReadWrite,
+            mode => mode,
+        };
+        Ok(Box::new(FaultyFile {
+            inner: self.inner.open(path, mode)?,
+            faults: self.faults.clone(),
+        }))
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.inner.exists(path)
+    }
+
+    fn rename(&self, from: &str, to: &str) -> io::Result<()> {
+        if self.faults.has_crashed() {
+            return Ok(());
+        }
+        self.inner.rename(from, to)
+    }
+
+    fn remove(&self, path: &str) -> io::Result<()> {
+        if self.faults.has_crashed() {
+            return Ok(());
+        }
+        self.inner.remove(path)
+    }
+
+    fn sync_dir(&self, path: &str) -> io::Result<()> {
+        self.inner.sync_dir(path)
+    }
+}
+
+#[derive(Debug)]
+struct FaultyFile {
+    inner: Box<dyn PagerBackend>,
+    faults: Rc<Faults>,
+}
+impl Read for FaultyFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+impl Write for FaultyFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.faults.has_crashed() {
+            return Ok(buf.len());
+        }
+        let nb_writes = self.faults.nb_writes.get() + 1;
+        self.faults.nb_writes.set(nb_writes);
+        if self.faults.fail_write.get() == Some(nb_writes) {
+            return Err(io::Error::other("injected write failure"));
+        }
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+impl Seek for FaultyFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+impl PagerBackend for FaultyFile {
+    fn get_len(&self) -> io::Result<u64> {
+        self.inner.get_len()
+    }
+
+    fn set_len(&mut self, len: u64) -> io::Result<()> {
+        if self.faults.has_crashed() {
+            return Ok(());
+        }
+        self.inner.set_len(len)
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        if self.faults.truncate_on_sync.get() {
+            let len = self.inner.get_len()?;
+            self.inner.set_len(len / 2)?;
+        }
+        self.inner.sync()
+    }
+
+    fn try_lock(&self, mode: LockMode) -> io::Result<()> {
+        self.inner.try_lock(mode)
+    }
+}
+
+mod fault_vfs_test {
+    use super::*;
+
+    use crate::cursor::RowIter;
+    use crate::integrity::check_integrity;
+    use crate::pager::Pager;
+    use crate::table::Table;
+    use crate::table::table_test::row;
+
+    /// Base de 20 lignes sauvegardée sans panne dans `a.db`.
+    fn saved_database() -> MemoryVfs {
+        let vfs = MemoryVfs::default();
+        let mut table = open_table(Box::new(vfs.clone()));
+        insert_rows(&mut table, 1..=20);
+        table.save_to_disk(None).unwrap();
+        vfs
+    }
+
+    fn open_table(vfs: Box<dyn Vfs>) -> Table {
+        Table::new(Rc::new(Pager::with_vfs(vfs, Some("a.db")).unwrap()))
+    }
+
+    fn insert_rows(table: &mut Table, ids: std::ops::RangeInclusive<u64>) {
+        for id in ids {
+            table.insert(row(id)).unwrap();
+        }
+    }
+
+    /// Ids lus en rouvrant `a.db`, après avoir vérifié l'intégrité de l'arbre.
+    fn reopen_ids(vfs: &MemoryVfs) -> Vec<u64> {
+        let table = open_table(Box::new(vfs.clone()));
+        assert!(check_integrity(&table).is_empty());
+        RowIter::new(Rc::new(RefCell::new(table)))
+            .unwrap()
+            .map(|row| **row.unwrap().get_id())
+            .collect()
+    }
+
+    #[test]
+    fn test_failed_write_keeps_previous_save() {
+        let mut nb_write = 1;
+        loop {
+            let vfs = saved_database();
+            let mut table = open_table(Box::new(FaultyVfs::new(vfs.clone()).fail_write(nb_write)));
+            insert_rows(&mut table, 21..=50);
+            let saved = table.save_to_disk(None).is_ok();
+            let expected = if saved { 1..=50 } else { 1..=20 };
+            assert_eq!(reopen_ids(&vfs), expected.collect::<Vec<u64>>());
+            assert!(!vfs.exists("a.db.tmp"));
+            if saved {
+                break;
+            }
+            nb_write += 1;
+        }
+        assert!(nb_write > 2);
+    }
+
+    #[test]
+    fn test_crash_during_save_keeps_one_version() {
+        let mut nb_writes = 0;
+        loop {
+            let vfs = saved_database();
+            let mut table =
+                open_table(Box::new(FaultyVfs::new(vfs.clone()).crash_after(nb_writes)));
+            insert_rows(&mut table, 21..=50);
+            // La machine s'arrête sans que la sauvegarde ne voie d'erreur.
+            table.save_to_disk(None).unwrap();
+            let ids = reopen_ids(&vfs);
+            if ids.len() == 50 {
+                break;
+            }
+            assert_eq!(ids, (1..=20).collect::<Vec<u64>>());
+            nb_writes += 1;
+        }
+        assert!(nb_writes > 2);
+    }
+
+    #[test]
+    fn test_lost_synced_writes_are_detected() {
+        let vfs = saved_database();
+        let mut table = open_table(Box::new(FaultyVfs::new(vfs.clone()).truncate_on_sync()));
+        insert_rows(&mut table, 21..=50);
+        table.save_to_disk(None).unwrap();
+
+        // La base coupée est refusée à l'ouverture ou signalée par
+        // `pragma integrity_check`.
+        if let Ok(pager) = Pager::with_vfs(Box::new(vfs), Some("a.db")) {
+            let table = Table::new(Rc::new(pager));
+            assert!(!check_integrity(&table).is_empty());
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use std::mem;
+use std::ops::Range;
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq)]
+pub enum HeaderError {
+    InvalidMagic,
+    UnsupportedVersion(u32),
+    UnsupportedFlags(u32),
+}
+
+/*
+ * Entête placé au début du fichier de sauvegarde, avant les pages.
+ */
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Clone)]
+pub struct FileHeader {
+    version: u32,
+    flags: u32,
+    nb_rows: u64,
+}
+impl FileHeader {
+    pub const MAGIC: [u8; 8] = *b"my_db\0\0\0";
+    pub const MAGIC_OFFSET: usize = 0;
+    pub const MAGIC_RANGE: Range<usize> =
+        Self::MAGIC_OFFSET..(Self::MAGIC_OFFSET + Self::MAGIC.len());
+
+    pub const VERSION_SIZE: usize = mem::size_of::<u32>();
+    pub const VERSION_OFFSET: usize = Self::MAGIC_OFFSET + Self::MAGIC.len();
+    pub const VERSION_RANGE: Range<usize> =
+        Self::VERSION_OFFSET..(Self::VERSION_OFFSET + Self::VERSION_SIZE);
+
+    pub const FLAGS_SIZE: usize = mem::size_of::<u32>();
+    pub const FLAGS_OFFSET: usize = Self::VERSION_OFFSET + Self::VERSION_SIZE;
+    pub const FLAGS_RANGE: Range<usize> =
+        Self::FLAGS_OFFSET..(Self::FLAGS_OFFSET + Self::FLAGS_SIZE);
+
+    pub const NB_ROWS_SIZE: usize = mem::size_of::<u64>();
+    pub const NB_ROWS_OFFSET: usize = Self::FLAGS_OFFSET + Self::FLAGS_SIZE;
+    pub const NB_ROWS_RANGE: Range<usize> =
+        Self::NB_ROWS_OFFSET..(Self::NB_ROWS_OFFSET + Self::NB_ROWS_SIZE);
+
+    /// Les pages sont compressées et précédées d'un répertoire de pages.
+    pub const FLAG_COMPRESSED: u32 = 1;
+
+    /// Options que cette version du programme sait lire.
+    #[cfg(feature = "compression")]
+    pub const SUPPORTED_FLAGS: u32 = Self::FLAG_COMPRESSED;
+    #[cfg(not(feature = "compression"))]
+    pub const SUPPORTED_FLAGS: u32 = 0;
+
+    /// Taille réservée sur le disque, le reste de l'entête est laissé à zéro.
+    pub const SIZE: usize = 64;
+
+    /// Version écrite par cette version du programme.
+    pub const CURRENT_VERSION: u32 = 4;
+
+    pub fn new() -> Self {
+        Self::with_version(Self::CURRENT_VERSION)
+    }
+
+    pub fn with_version(version: u32) -> Self {
+        Self {
+            version,
+            flags: 0,
+            nb_rows: 0,
+        }
+    }
+
+    pub fn with_nb_rows(mut self, nb_rows: u64) -> Self {
+        self.nb_rows = nb_rows;
+        self
+    }
+
+    pub fn with_compression(mut self, is_compressed: bool) -> Self {
+        if is_compressed {
+            self.flags |= Self::FLAG_COMPRESSED;
+        } else {
+            self.flags &= !Self::FLAG_COMPRESSED;
+        }
+        self
+    }
+
+    pub fn get_version(&self) -> u32 {
+        self.version
+    }
+
+    /// Nombre de lignes de la table au moment de la sauvegarde.
+    pub fn get_nb_rows(&self) -> u64 {
+        self.nb_rows
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.flags & Self::FLAG_COMPRESSED != 0
+    }
+
+    pub fn has_magic(bytes: &[u8]) -> bool {
+        bytes.get(Self::MAGIC_RANGE) == Some(&Self::MAGIC[..])
+    }
+}
+impl Default for FileHeader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl std::convert::From<FileHeader> for [u8; FileHeader::SIZE] {
+    fn from(header: FileHeader) -> [u8; FileHeader::SIZE] {
+        let mut bytes = [0; FileHeader::SIZE];
+        bytes[FileHeader::MAGIC_RANGE].copy_from_slice(&FileHeader::MAGIC);
+        bytes[FileHeader::VERSION_RANGE].copy_from_slice(&header.version.to_be_bytes());
+        bytes[FileHeader::FLAGS_RANGE].copy_from_slice(&header.flags.to_be_bytes());
+        bytes[FileHeader::NB_ROWS_RANGE].copy_from_slice(&header.nb_rows.to_be_bytes());
+        bytes
+    }
+}
+impl std::convert::TryFrom<&[u8; FileHeader::SIZE]> for FileHeader {
+    type Error = HeaderError;
+
+    fn try_from(bytes: &[u8; FileHeader::SIZE]) -> Result<Self, Self::Error> {
+        if !Self::has_magic(bytes) {
+            return Err(HeaderError::InvalidMagic);
+        }
+
+        let mut version_bytes = [0; Self::VERSION_SIZE];
+        version_bytes.copy_from_slice(&bytes[Self::VERSION_RANGE]);
+        let version = u32::from_be_bytes(version_bytes);
+        if version > Self::CURRENT_VERSION {
+            return Err(HeaderError::UnsupportedVersion(version));
+        }
+
+        let mut flags_bytes = [0; Self::FLAGS_SIZE];
+        flags_bytes.copy_from_slice(&bytes[Self::FLAGS_RANGE]);
+        let flags = u32::from_be_bytes(flags_bytes);
+        if flags & !Self::SUPPORTED_FLAGS != 0 {
+            return Err(HeaderError::UnsupportedFlags(flags));
+        }
+
+        let mut nb_rows_bytes = [0; Self::NB_ROWS_SIZE];
+        nb_rows_bytes.copy_from_slice(&bytes[Self::NB_ROWS_RANGE]);
+        let nb_rows = u64::from_be_bytes(nb_rows_bytes);
+
+        Ok(Self {
+            version,
+            flags,
+            nb_rows,
+        })
+    }
+}
+
+#[cfg(test)]
+mod header_test {
+    use super::*;
+
+    #[test]
+    fn test_header_from_into_u8_array() {
+        let header = FileHeader::new().with_nb_rows(3);
+        let bytes = <[u8; FileHeader::SIZE]>::from(header.clone());
+        assert_eq!(&bytes[FileHeader::MAGIC_RANGE], b"my_db\0\0\0");
+        assert_eq!(&bytes[FileHeader::VERSION_RANGE], &[0, 0, 0, 4]);
+        assert_eq!(FileHeader::try_from(&bytes), Ok(header));
+    }
+
+    #[test]
+    fn test_header_rejects_unknown_files() {
+        let mut bytes = [0; FileHeader::SIZE];
+        assert_eq!(FileHeader::try_from(&bytes), Err(HeaderError::InvalidMagic));
+
+        bytes = <[u8; FileHeader::SIZE]>::from(FileHeader::new());
+        bytes[FileHeader::VERSION_RANGE].copy_from_slice(&42u32.to_be_bytes());
+        assert_eq!(
+            FileHeader::try_from(&bytes),
+            Err(HeaderError::UnsupportedVersion(42))
+        );
+
+        bytes = <[u8; FileHeader::SIZE]>::from(FileHeader::new());
+        bytes[FileHeader::FLAGS_RANGE].copy_from_slice(&0x80u32.to_be_bytes());
+        assert_eq!(
+            FileHeader::try_from(&bytes),
+            Err(HeaderError::UnsupportedFlags(0x80))
+        );
+    }
+}
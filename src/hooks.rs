@@ -0,0 +1,14 @@
+use crate::row::Row;
+
+/*
+ * Fonctions appelées après chaque modification réussie de la table, pour
+ * tenir un cache, un journal d'audit ou prévenir un autre composant sans
+ * relire la table. Elles sont enregistrées avec `Database::add_hooks`.
+ */
+pub trait Hooks: std::fmt::Debug {
+    /// Appelée avec la ligne qui vient d'être insérée.
+    fn on_insert(&self, _row: &Row) {}
+
+    /// Appelée avec chaque ligne supprimée, dans l'ordre des clés.
+    fn on_delete(&self, _row: &Row) {}
+}
@@ -0,0 +1,278 @@
+use std::collections::HashSet;
+
+use crate::btree::{Node, NodeView};
+use crate::catalog::CatalogError;
+use crate::page_view::ReadBytes;
+use crate::pager::Pager;
+use crate::row::RowRef;
+use crate::table::Table;
+
+/*
+ * Vérification de `pragma integrity_check` : l'arbre est parcouru en entier
+ * depuis la racine et chaque problème est noté, sans s'arrêter au premier.
+ * Le sous-arbre d'une page illisible n'est pas parcouru.
+ *
+ * Le format de fichier ne stocke pas de somme de contrôle des pages : seule la
+ * cohérence de leur contenu peut être vérifiée.
+ */
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(PartialEq)]
+pub enum IntegrityProblem {
+    Catalog(CatalogError),
+    UnreadablePage(usize),
+    /// La page ne contient pas un nœud, ici l'octet de type lu.
+    UnknownNodeType(usize, u8),
+    /// La page est l'enfant de plusieurs nœuds, ou de son propre sous-arbre.
+    PageReferencedTwice(usize),
+    /// Page, parent stocké dans la page, parent qui la référence.
+    WrongParent(usize, usize, usize),
+    /// Page et nombre de cellules de son entête, plus que la page n'en contient.
+    TooManyCells(usize, usize),
+    /// Page et position de la première clé qui n'est pas supérieure à la précédente.
+    UnsortedKeys(usize, usize),
+    /// Page et clé en dehors des bornes données par les nœuds parents.
+    KeyOutOfBounds(usize, u64),
+    /// Page et position de la cellule dont la ligne ne peut pas être lue.
+    UnreadableRow(usize, usize),
+    /// Page, clé de la cellule et id de sa ligne.
+    KeyIdMismatch(usize, u64, u64),
+    /// Feuille, feuille suivante stockée, feuille suivante dans l'arbre (0 pour la dernière).
+    BrokenLeafChain(usize, usize, usize),
+    /// Page allouée qu'aucun nœud ne référence.
+    OrphanPage(usize),
+    /// Nombre de lignes de l'entête du fichier et nombre de lignes de l'arbre.
+    WrongRowCount(usize, usize),
+}
+
+/// Clés permises dans un sous-arbre : supérieures à `low` et au plus `high`.
+#[derive(Clone, Copy)]
+struct KeyBounds {
+    low: Option<u64>,
+    high: Option<u64>,
+}
+impl KeyBounds {
+    fn contains(self, key: u64) -> bool {
+        self.low.is_none_or(|low| key > low) && self.high.is_none_or(|high| key <= high)
+    }
+}
+
+/// État du parcours, partagé entre les nœuds.
+struct Check<'a> {
+    pager: &'a Pager,
+    visited: HashSet<usize>,
+    /// Feuilles dans l'ordre des clés.
+    leaves: Vec<(usize, usize)>,
+    nb_rows: usize,
+    problems: Vec<IntegrityProblem>,
+}
+
+/// Renvoie tous les problèmes trouvés, aucun pour une base saine.
+pub fn check_integrity(table: &Table) -> Vec<IntegrityProblem> {
+    let pager = table.get_pager();
+    let mut check = Check {
+        pager: &pager,
+        visited: HashSet::from([Table::CATALOG_PAGE_NUM]),
+        leaves: Vec::new(),
+        nb_rows: 0,
+        problems: Vec::new(),
+    };
+    if let Err(e) = table.check_catalog() {
+        check.problems.push(IntegrityProblem::Catalog(e));
+    }
+
+    let bounds = KeyBounds {
+        low: None,
+        high: None,
+    };
+    check.visit(Table::ROOT_PAGE_NUM, None, bounds);
+
+    let next_leaves = check.leaves.iter().skip(1).map(|(page_num, _)| *page_num);
+    for ((page_num, next_leaf), expected) in check.leaves.iter().zip(next_leaves.chain([0])) {
+        if *next_leaf != expected {
+            check.problems.push(IntegrityProblem::BrokenLeafChain(
+                *page_num, *next_leaf, expected,
+            ));
+        }
+    }
+
+    if let Ok(nb_pages) = pager.page_count() {
+        let orphans = (0..nb_pages).filter(|page_num| !check.visited.contains(page_num));
+        check
+            .problems
+            .extend(orphans.map(IntegrityProblem::OrphanPage));
+    }
+    if check.nb_rows != table.get_nb_rows() {
+        check.problems.push(IntegrityProblem::WrongRowCount(
+            table.get_nb_rows(),
+            check.nb_rows,
+        ));
+    }
+    check.problems
+}
+
+impl Check<'_> {
+    /// `parent` est `None` pour la racine, dont le pointeur de parent n'est pas utilisé.
+    fn visit(&mut self, page_num: usize, parent: Option<usize>, bounds: KeyBounds) {
+        if !self.visited.insert(page_num) {
+            self.problems
+                .push(IntegrityProblem::PageReferencedTwice(page_num));
+            return;
+        }
+        // La page est relâchée avant de visiter ses enfants.
+        let children = {
+            let Ok(page) = self.pager.read_page(page_num) else {
+                self.problems
+                    .push(IntegrityProblem::UnreadablePage(page_num));
+                return;
+            };
+            let node = match Node::new(&page[..]) {
+                Ok(node) => node,
+                Err(node_type) => {
+                    self.problems
+                        .push(IntegrityProblem::UnknownNodeType(page_num, node_type));
+                    return;
+                }
+            };
+            if let Some(parent) = parent
+                && node.get_parent() != parent
+            {
+                self.problems.push(IntegrityProblem::WrongParent(
+                    page_num,
+                    node.get_parent(),
+                    parent,
+                ));
+            }
+            match node {
+                Node::Leaf(_) => {
+                    self.check_leaf(page_num, &node, bounds);
+                    return;
+                }
+                Node::Internal(_) => self.check_internal(page_num, &node, bounds),
+            }
+        };
+        for (child, bounds) in children {
+            self.visit(child, Some(page_num), bounds);
+        }
+    }
+
+    fn check_leaf(&mut self, page_num: usize, node: &Node<&[u8]>, bounds: KeyBounds) {
+        let nb_cells = node
+            .get_page()
+            .read_u32_be(NodeView::LEAF_NODE_NB_CELLS_OFFSET) as usize;
+        if nb_cells > NodeView::LEAF_NODE_MAX_CELLS {
+            self.problems
+                .push(IntegrityProblem::TooManyCells(page_num, nb_cells));
+        }
+
+        let mut previous_key = None;
+        for cell_num in 0..node.leaf_node_get_nb_cells() {
+            let cell = node.leaf_node_get_cell(cell_num);
+            let key = cell.get_key();
+            if previous_key.is_some_and(|previous_key| key <= previous_key) {
+                self.problems
+                    .push(IntegrityProblem::UnsortedKeys(page_num, cell_num));
+            }
+            if !bounds.contains(key) {
+                self.problems
+                    .push(IntegrityProblem::KeyOutOfBounds(page_num, key));
+            }
+            match RowRef::try_from(cell.get_value()) {
+                Ok(row) if row.get_id() != key => self
+                    .problems
+                    .push(IntegrityProblem::KeyIdMismatch(page_num, key, row.get_id())),
+                Ok(_) => {}
+                Err(_) => self
+                    .problems
+                    .push(IntegrityProblem::UnreadableRow(page_num, cell_num)),
+            }
+            previous_key = Some(key);
+        }
+        self.nb_rows += node.leaf_node_get_nb_cells();
+        self.leaves.push((page_num, node.leaf_node_get_next_leaf()));
+    }
+
+    /// Renvoie les enfants du nœud et les bornes des clés de chacun : la clé
+    /// d'un enfant est la plus grande de son sous-arbre.
+    fn check_internal(
+        &mut self,
+        page_num: usize,
+        node: &Node<&[u8]>,
+        bounds: KeyBounds,
+    ) -> Vec<(usize, KeyBounds)> {
+        let nb_keys = node
+            .get_page()
+            .read_u32_be(NodeView::INTERNAL_NODE_NB_KEYS_OFFSET) as usize;
+        if nb_keys > NodeView::INTERNAL_NODE_MAX_KEYS {
+            self.problems
+                .push(IntegrityProblem::TooManyCells(page_num, nb_keys));
+        }
+
+        let mut children = Vec::new();
+        let mut low = bounds.low;
+        for key_num in 0..node.internal_node_get_nb_keys() {
+            let key = node.internal_node_get_key(key_num);
+            if low.is_some_and(|low| key <= low) {
+                self.problems
+                    .push(IntegrityProblem::UnsortedKeys(page_num, key_num));
+            }
+            if !bounds.contains(key) {
+                self.problems
+                    .push(IntegrityProblem::KeyOutOfBounds(page_num, key));
+            }
+            let high = Some(key);
+            children.push((
+                node.internal_node_get_child(key_num),
+                KeyBounds { low, high },
+            ));
+            low = high;
+        }
+        let high = bounds.high;
+        children.push((
+            node.internal_node_get_right_child(),
+            KeyBounds { low, high },
+        ));
+        children
+    }
+}
+
+#[cfg(test)]
+mod integrity_test {
+    use super::*;
+
+    use std::rc::Rc;
+
+    use crate::btree::write_node;
+    use crate::table::table_test::row;
+
+    #[test]
+    fn test_check_integrity() {
+        let mut table = Table::new(Rc::new(Pager::default()));
+        for id in 1..=20 {
+            table.insert(row(id)).unwrap();
+        }
+        assert_eq!(check_integrity(&table), Vec::new());
+
+        // La deuxième cellule de la première feuille prend la clé de la première.
+        let pager = table.get_pager();
+        let leaf_page_num = match crate::btree::read_node(&pager, Table::ROOT_PAGE_NUM).unwrap() {
+            node @ Node::Internal(_) => node.internal_node_get_child(0),
+            Node::Leaf(_) => panic!("The root should have been split."),
+        };
+        write_node(&pager, leaf_page_num)
+            .unwrap()
+            .leaf_node_get_mut_cell(1)
+            .set_key(1);
+        table.set_nb_rows(21);
+
+        assert_eq!(
+            check_integrity(&table),
+            vec![
+                IntegrityProblem::UnsortedKeys(leaf_page_num, 1),
+                IntegrityProblem::KeyIdMismatch(leaf_page_num, 1, 2),
+                IntegrityProblem::WrongRowCount(21, 20),
+            ]
+        );
+    }
+}
@@ -0,0 +1,61 @@
+use std::io;
+use std::sync::Arc;
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/*
+ * Ctrl-C n'arrête pas le processus : le signal lève un drapeau que les
+ * parcours de la table consultent entre deux lignes pour abandonner la
+ * requête en cours.
+ */
+static INTERRUPTED: LazyLock<Arc<AtomicBool>> = LazyLock::new(|| Arc::new(AtomicBool::new(false)));
+
+/// Remplace l'arrêt du processus sur SIGINT par la levée du drapeau.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn install_handler() -> io::Result<()> {
+    let _ = signal_hook::flag::register(signal_hook::consts::SIGINT, INTERRUPTED.clone())?;
+    Ok(())
+}
+
+/// Sans signaux dans le navigateur, le drapeau n'est levé par personne.
+#[cfg(target_arch = "wasm32")]
+pub fn install_handler() -> io::Result<()> {
+    Ok(())
+}
+
+pub fn is_interrupted() -> bool {
+    INTERRUPTED.load(Ordering::Relaxed)
+}
+
+/// À appeler avant chaque requête : un Ctrl-C tapé à l'invite ne doit pas
+/// interrompre la requête suivante.
+pub fn clear() {
+    INTERRUPTED.store(false, Ordering::Relaxed);
+}
+
+/// Appelle `on_signal` depuis un thread dédié à la réception de SIGTERM ou SIGHUP,
+/// pour que la session se termine proprement quand le terminal est fermé.
+#[cfg(unix)]
+pub fn on_termination<F>(on_signal: F) -> io::Result<()>
+where
+    F: Fn() + Send + 'static,
+{
+    use signal_hook::consts::{SIGHUP, SIGTERM};
+    use signal_hook::iterator::Signals;
+
+    let mut signals = Signals::new([SIGTERM, SIGHUP])?;
+    let _ = std::thread::spawn(move || {
+        for _ in signals.forever() {
+            on_signal();
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn on_termination<F>(_on_signal: F) -> io::Result<()>
+where
+    F: Fn() + Send + 'static,
+{
+    Ok(())
+}
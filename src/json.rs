@@ -0,0 +1,329 @@
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader};
+
+use crate::row::{Email, Id, Row, TextError, Username, check_text};
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum JsonError {
+    /// Le fichier, et l'erreur rencontrée en l'ouvrant ou en le lisant.
+    Open(String, io::Error),
+    /// Numéro de la ligne, à partir de 1, et ce qui l'empêche d'être lue.
+    Line(usize, JsonLineError),
+}
+
+#[cfg_attr(debug_assertions, derive(Debug, PartialEq))]
+pub enum JsonLineError {
+    /// Colonne, à partir de 1, où le JSON n'est plus valide.
+    Syntax(usize),
+    NotAnObject,
+    /// Clé dont la valeur est un objet ou un tableau.
+    NestedValue(String),
+    UnknownKey(String),
+    DuplicateKey(String),
+    MissingKey(&'static str),
+    InvalidId(String),
+    /// Colonne dont la valeur n'a pas le type attendu.
+    WrongType(&'static str),
+    /// Colonne et taille maximale.
+    StringTooLong(&'static str, usize),
+    /// Colonne et caractère de contrôle trouvé dans sa valeur.
+    ControlCharacter(&'static str, char),
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+enum JsonValue {
+    String(String),
+    /// Le nombre tel qu'il est écrit.
+    Number(String),
+    /// `true`, `false` ou `null`.
+    Literal,
+}
+
+/// Lit un fichier JSON Lines : un objet par ligne, dont les clés sont les
+/// colonnes d'un `Row`. Les lignes vides sont ignorées.
+pub fn read_json_rows(file_path: &str) -> Result<Vec<Result<Row, JsonError>>, JsonError> {
+    let file = File::open(file_path).map_err(|e| JsonError::Open(file_path.to_owned(), e))?;
+    let mut rows = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| JsonError::Open(file_path.to_owned(), e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let row = Parser::new(&line).object().and_then(parse_row);
+        rows.push(row.map_err(|e| JsonError::Line(index + 1, e)));
+    }
+    Ok(rows)
+}
+
+/// Les mêmes vérifications qu'une insertion. Le nom des clés ne tient pas
+/// compte de la casse.
+fn parse_row(members: Vec<(String, JsonValue)>) -> Result<Row, JsonLineError> {
+    let mut values: [Option<JsonValue>; 3] = Default::default();
+    for (key, value) in members {
+        let Some(index) = Row::COLUMN_NAMES
+            .iter()
+            .position(|name| name.eq_ignore_ascii_case(&key))
+        else {
+            return Err(JsonLineError::UnknownKey(key));
+        };
+        if values[index].replace(value).is_some() {
+            return Err(JsonLineError::DuplicateKey(key));
+        }
+    }
+    let [id, username, email] = values;
+    let id = match id {
+        Some(JsonValue::Number(number)) => match number.parse::<u64>() {
+            Ok(id) => id,
+            Err(_) => return Err(JsonLineError::InvalidId(number)),
+        },
+        Some(_) => return Err(JsonLineError::WrongType("id")),
+        None => return Err(JsonLineError::MissingKey("id")),
+    };
+    let username = string_value(username, "username", Username::MAX_SIZE)?;
+    let email = string_value(email, "email", Email::MAX_SIZE)?;
+    Ok(Row::new(
+        Id::new(id),
+        Username::new(username),
+        Email::new(email),
+    ))
+}
+
+fn string_value(
+    value: Option<JsonValue>,
+    column: &'static str,
+    max_size: usize,
+) -> Result<String, JsonLineError> {
+    match value {
+        Some(JsonValue::String(value)) => match check_text(&value, max_size) {
+            Ok(()) => Ok(value),
+            Err(TextError::TooLong(max_size)) => {
+                Err(JsonLineError::StringTooLong(column, max_size))
+            }
+            Err(TextError::ControlCharacter(c)) => Err(JsonLineError::ControlCharacter(column, c)),
+        },
+        Some(_) => Err(JsonLineError::WrongType(column)),
+        None => Err(JsonLineError::MissingKey(column)),
+    }
+}
+
+/*
+ * Lecture d'un objet JSON sans objet ni tableau imbriqué, le seul format
+ * d'une ligne de `.import-json`.
+ */
+struct Parser<'a> {
+    line: &'a str,
+    pos: usize,
+}
+impl<'a> Parser<'a> {
+    fn new(line: &'a str) -> Self {
+        Self { line, pos: 0 }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.line[self.pos..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(' ' | '\t' | '\r' | '\n') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn error(&self) -> JsonLineError {
+        JsonLineError::Syntax(self.line[..self.pos].chars().count() + 1)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), JsonLineError> {
+        self.skip_whitespace();
+        if self.peek() != Some(expected) {
+            return Err(self.error());
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn object(mut self) -> Result<Vec<(String, JsonValue)>, JsonLineError> {
+        self.skip_whitespace();
+        if self.peek() != Some('{') {
+            return Err(JsonLineError::NotAnObject);
+        }
+        self.pos += 1;
+        let mut members = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+        } else {
+            loop {
+                self.skip_whitespace();
+                let key = self.string()?;
+                self.expect(':')?;
+                let value = self.value(&key)?;
+                members.push((key, value));
+                self.skip_whitespace();
+                match self.peek() {
+                    Some(',') => self.pos += 1,
+                    Some('}') => {
+                        self.pos += 1;
+                        break;
+                    }
+                    _ => return Err(self.error()),
+                }
+            }
+        }
+        self.skip_whitespace();
+        if self.pos != self.line.len() {
+            return Err(self.error());
+        }
+        Ok(members)
+    }
+
+    fn value(&mut self, key: &str) -> Result<JsonValue, JsonLineError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('"') => self.string().map(JsonValue::String),
+            Some('{' | '[') => Err(JsonLineError::NestedValue(key.to_owned())),
+            Some('-' | '0'..='9') => {
+                let start = self.pos;
+                while let Some('0'..='9' | '-' | '+' | '.' | 'e' | 'E') = self.peek() {
+                    self.pos += 1;
+                }
+                let number = &self.line[start..self.pos];
+                if number.parse::<f64>().is_err() {
+                    self.pos = start;
+                    return Err(self.error());
+                }
+                Ok(JsonValue::Number(number.to_owned()))
+            }
+            _ => {
+                for literal in ["true", "false", "null"] {
+                    if self.line[self.pos..].starts_with(literal) {
+                        self.pos += literal.len();
+                        return Ok(JsonValue::Literal);
+                    }
+                }
+                Err(self.error())
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, JsonLineError> {
+        if self.peek() != Some('"') {
+            return Err(self.error());
+        }
+        self.pos += 1;
+        let mut value = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(value),
+                Some('\\') => {
+                    let c = match self.next() {
+                        Some(c @ ('"' | '\\' | '/')) => c,
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => self.unicode_escape()?,
+                        _ => return Err(self.error()),
+                    };
+                    value.push(c);
+                }
+                Some(c) if c >= ' ' => value.push(c),
+                _ => return Err(self.error()),
+            }
+        }
+    }
+
+    /// `\uXXXX`, ou deux à la suite pour une paire de substitution UTF-16.
+    fn unicode_escape(&mut self) -> Result<char, JsonLineError> {
+        let high = self.hex4()?;
+        let code = if (0xD800..0xDC00).contains(&high) {
+            if !self.line[self.pos..].starts_with("\\u") {
+                return Err(self.error());
+            }
+            self.pos += 2;
+            let low = self.hex4()?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return Err(self.error());
+            }
+            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+        } else {
+            high
+        };
+        char::from_u32(code).ok_or_else(|| self.error())
+    }
+
+    fn hex4(&mut self) -> Result<u32, JsonLineError> {
+        let digits = self
+            .line
+            .get(self.pos..self.pos + 4)
+            .ok_or_else(|| self.error())?;
+        if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(self.error());
+        }
+        self.pos += 4;
+        Ok(u32::from_str_radix(digits, 16).expect("Four hexadecimal digits."))
+    }
+}
+
+#[cfg(test)]
+mod json_test {
+    use super::*;
+
+    fn parse_line(line: &str) -> Result<Row, JsonLineError> {
+        Parser::new(line).object().and_then(parse_row)
+    }
+
+    #[test]
+    fn test_parse_line() {
+        let row = parse_line(r#" {"email": "b\"oé😀", "ID": 2, "username": "bob"} "#).unwrap();
+        let expected = Row::new(
+            Id::new(2),
+            Username::new("bob".to_owned()),
+            Email::new("b\"oé😀".to_owned()),
+        );
+        assert!(row == expected);
+
+        let errors = [
+            (r#"[1, "bob"]"#, JsonLineError::NotAnObject),
+            (r#"{"id": 1,}"#, JsonLineError::Syntax(10)),
+            (r#"{"id": 1} x"#, JsonLineError::Syntax(11)),
+            (
+                r#"{"id": -1, "username": "", "email": ""}"#,
+                JsonLineError::InvalidId("-1".to_owned()),
+            ),
+            (r#"{"id": "1"}"#, JsonLineError::WrongType("id")),
+            (
+                r#"{"id": 1, "username": null}"#,
+                JsonLineError::WrongType("username"),
+            ),
+            (
+                r#"{"id": 1, "email": ""}"#,
+                JsonLineError::MissingKey("username"),
+            ),
+            (
+                r#"{"id": 1, "id": 2}"#,
+                JsonLineError::DuplicateKey("id".to_owned()),
+            ),
+            (r#"{"age": 3}"#, JsonLineError::UnknownKey("age".to_owned())),
+            (
+                r#"{"id": 1, "username": "bob", "email": "b\u0000@mail.com"}"#,
+                JsonLineError::ControlCharacter("email", '\0'),
+            ),
+            (
+                r#"{"tags": []}"#,
+                JsonLineError::NestedValue("tags".to_owned()),
+            ),
+        ];
+        for (line, error) in errors {
+            assert_eq!(parse_line(line).err(), Some(error), "{line}");
+        }
+    }
+}
@@ -0,0 +1,325 @@
+/// Mots réservés du langage. Ils ne sont reconnus qu'à la lecture des
+/// instructions : le lexer ne produit que des mots, qui peuvent aussi servir
+/// de valeurs (`insert 1 select select@mail.com`).
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Clone, Copy)]
+pub enum Keyword {
+    Select,
+    Insert,
+    Delete,
+    Truncate,
+    Pragma,
+    Explain,
+    From,
+    Where,
+    Between,
+    And,
+    Or,
+    Order,
+    By,
+    Asc,
+    Collate,
+    Limit,
+    Returning,
+    Create,
+    Virtual,
+    Table,
+    Using,
+}
+impl Keyword {
+    pub const ALL: [Keyword; 21] = [
+        Self::Select,
+        Self::Insert,
+        Self::Delete,
+        Self::Truncate,
+        Self::Pragma,
+        Self::Explain,
+        Self::From,
+        Self::Where,
+        Self::Between,
+        Self::And,
+        Self::Or,
+        Self::Order,
+        Self::By,
+        Self::Asc,
+        Self::Collate,
+        Self::Limit,
+        Self::Returning,
+        Self::Create,
+        Self::Virtual,
+        Self::Table,
+        Self::Using,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Select => "select",
+            Self::Insert => "insert",
+            Self::Delete => "delete",
+            Self::Truncate => "truncate",
+            Self::Pragma => "pragma",
+            Self::Explain => "explain",
+            Self::From => "from",
+            Self::Where => "where",
+            Self::Between => "between",
+            Self::And => "and",
+            Self::Or => "or",
+            Self::Order => "order",
+            Self::By => "by",
+            Self::Asc => "asc",
+            Self::Collate => "collate",
+            Self::Limit => "limit",
+            Self::Returning => "returning",
+            Self::Create => "create",
+            Self::Virtual => "virtual",
+            Self::Table => "table",
+            Self::Using => "using",
+        }
+    }
+}
+impl std::convert::TryFrom<&str> for Keyword {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Self::ALL
+            .into_iter()
+            .find(|keyword| keyword.as_str().eq_ignore_ascii_case(value))
+            .ok_or(())
+    }
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Clone)]
+pub enum TokenKind {
+    /// Suite de caractères sans espace ni ponctuation : mot-clé, nom ou valeur
+    /// comme `bob@mail.com`.
+    Word(String),
+    Number(u64),
+    /// Texte entre guillemets simples ou doubles, sans les guillemets.
+    String(String),
+    Semicolon,
+    Comma,
+    Equals,
+    /// `!=` ou `<>`.
+    NotEquals,
+    Less,
+    LessOrEquals,
+    Greater,
+    GreaterOrEquals,
+    Star,
+    Plus,
+    Minus,
+    Slash,
+    LeftParen,
+    RightParen,
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Clone)]
+pub struct Token {
+    pub kind: TokenKind,
+    /// Position du premier octet du token dans l'instruction.
+    pub offset: usize,
+}
+impl Token {
+    pub fn get_keyword(&self) -> Option<Keyword> {
+        match &self.kind {
+            TokenKind::Word(word) => Keyword::try_from(word.as_str()).ok(),
+            _ => None,
+        }
+    }
+
+    pub fn is_keyword(&self, keyword: Keyword) -> bool {
+        self.get_keyword() == Some(keyword)
+    }
+}
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            TokenKind::Word(word) => write!(f, "{word}"),
+            TokenKind::Number(number) => write!(f, "{number}"),
+            TokenKind::String(string) => write!(f, "'{}'", string.replace('\'', "''")),
+            TokenKind::Semicolon => write!(f, ";"),
+            TokenKind::Comma => write!(f, ","),
+            TokenKind::Equals => write!(f, "="),
+            TokenKind::NotEquals => write!(f, "!="),
+            TokenKind::Less => write!(f, "<"),
+            TokenKind::LessOrEquals => write!(f, "<="),
+            TokenKind::Greater => write!(f, ">"),
+            TokenKind::GreaterOrEquals => write!(f, ">="),
+            TokenKind::Star => write!(f, "*"),
+            TokenKind::Plus => write!(f, "+"),
+            TokenKind::Minus => write!(f, "-"),
+            TokenKind::Slash => write!(f, "/"),
+            TokenKind::LeftParen => write!(f, "("),
+            TokenKind::RightParen => write!(f, ")"),
+        }
+    }
+}
+
+/// Les positions sont celles du début du token fautif.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq)]
+pub enum LexError {
+    UnterminatedString(usize),
+    NumberTooLarge(usize),
+}
+
+fn is_punctuation(c: char) -> bool {
+    matches!(
+        c,
+        ';' | ',' | '=' | '<' | '>' | '*' | '(' | ')' | '\'' | '"'
+    )
+}
+
+/// Découpe une instruction en tokens. Dans une chaîne, le guillemet ouvrant
+/// doublé (`'l''arbre'`) représente le guillemet lui-même.
+///
+/// Un `!` n'est une ponctuation que suivi de `=` : `bob!` reste un mot. De
+/// même, `+`, `-` et `/` ne sont des opérateurs que suivis d'un espace ou de
+/// `(` : `-1` et `bob-x@mail.com` restent des mots.
+pub fn tokenize(input: &str) -> Result<Vec<Token>, LexError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some((offset, c)) = chars.next() {
+        let kind = match c {
+            c if c.is_whitespace() => continue,
+            ';' => TokenKind::Semicolon,
+            ',' => TokenKind::Comma,
+            '=' => TokenKind::Equals,
+            '!' if chars.next_if(|(_, next)| *next == '=').is_some() => TokenKind::NotEquals,
+            '<' if chars.next_if(|(_, next)| *next == '=').is_some() => TokenKind::LessOrEquals,
+            '<' if chars.next_if(|(_, next)| *next == '>').is_some() => TokenKind::NotEquals,
+            '<' => TokenKind::Less,
+            '>' if chars.next_if(|(_, next)| *next == '=').is_some() => TokenKind::GreaterOrEquals,
+            '>' => TokenKind::Greater,
+            '*' => TokenKind::Star,
+            '+' | '-' | '/'
+                if chars
+                    .peek()
+                    .is_none_or(|(_, next)| next.is_whitespace() || *next == '(') =>
+            {
+                match c {
+                    '+' => TokenKind::Plus,
+                    '-' => TokenKind::Minus,
+                    _ => TokenKind::Slash,
+                }
+            }
+            '(' => TokenKind::LeftParen,
+            ')' => TokenKind::RightParen,
+            '\'' | '"' => {
+                let quote = c;
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, c)) if c == quote => {
+                            if chars.next_if(|(_, next)| *next == quote).is_none() {
+                                break;
+                            }
+                            string.push(quote);
+                        }
+                        Some((_, c)) => string.push(c),
+                        None => return Err(LexError::UnterminatedString(offset)),
+                    }
+                }
+                TokenKind::String(string)
+            }
+            _ => {
+                let mut end = offset + c.len_utf8();
+                while let Some((next_offset, next)) = chars.next_if(|(next_offset, next)| {
+                    !next.is_whitespace()
+                        && !is_punctuation(*next)
+                        && !input[*next_offset..].starts_with("!=")
+                }) {
+                    end = next_offset + next.len_utf8();
+                }
+                let word = &input[offset..end];
+                if word.chars().all(|c| c.is_ascii_digit()) {
+                    let number = word
+                        .parse::<u64>()
+                        .map_err(|_| LexError::NumberTooLarge(offset))?;
+                    TokenKind::Number(number)
+                } else {
+                    TokenKind::Word(word.to_owned())
+                }
+            }
+        };
+        tokens.push(Token { kind, offset });
+    }
+
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod lexer_test {
+    use super::*;
+
+    #[test]
+    fn test_tokenize() {
+        let kinds = |input| {
+            tokenize(input)
+                .unwrap()
+                .into_iter()
+                .map(|token| token.kind)
+                .collect::<Vec<TokenKind>>()
+        };
+        assert_eq!(
+            kinds("insert  1 bob bob@mail.com"),
+            vec![
+                TokenKind::Word("insert".to_owned()),
+                TokenKind::Number(1),
+                TokenKind::Word("bob".to_owned()),
+                TokenKind::Word("bob@mail.com".to_owned()),
+            ]
+        );
+        assert_eq!(
+            kinds("'l''arbre';\"a b\""),
+            vec![
+                TokenKind::String("l'arbre".to_owned()),
+                TokenKind::Semicolon,
+                TokenKind::String("a b".to_owned()),
+            ]
+        );
+        assert_eq!(
+            tokenize("insert 'bob"),
+            Err(LexError::UnterminatedString(7))
+        );
+        assert_eq!(
+            tokenize("99999999999999999999"),
+            Err(LexError::NumberTooLarge(0))
+        );
+        assert_eq!(
+            kinds("id>=1 and bob!=a<>b<c"),
+            vec![
+                TokenKind::Word("id".to_owned()),
+                TokenKind::GreaterOrEquals,
+                TokenKind::Number(1),
+                TokenKind::Word("and".to_owned()),
+                TokenKind::Word("bob".to_owned()),
+                TokenKind::NotEquals,
+                TokenKind::Word("a".to_owned()),
+                TokenKind::NotEquals,
+                TokenKind::Word("b".to_owned()),
+                TokenKind::Less,
+                TokenKind::Word("c".to_owned()),
+            ]
+        );
+        assert_eq!(
+            kinds("id - 1 + (2) / -1 bob-x"),
+            vec![
+                TokenKind::Word("id".to_owned()),
+                TokenKind::Minus,
+                TokenKind::Number(1),
+                TokenKind::Plus,
+                TokenKind::LeftParen,
+                TokenKind::Number(2),
+                TokenKind::RightParen,
+                TokenKind::Slash,
+                TokenKind::Word("-1".to_owned()),
+                TokenKind::Word("bob-x".to_owned()),
+            ]
+        );
+        assert!(tokenize("SELECT").unwrap()[0].is_keyword(Keyword::Select));
+    }
+}
@@ -0,0 +1,43 @@
+//#![deny(clippy::unwrap_used, clippy::expect_used)]
+#![allow(dead_code)]
+#![forbid(unsafe_code)]
+
+pub mod backend;
+pub mod bloom;
+pub mod btree;
+pub mod catalog;
+pub mod collation;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod completion;
+#[cfg(feature = "compression")]
+pub mod compression;
+pub mod csv;
+pub mod cursor;
+pub mod database;
+pub mod expr;
+#[cfg(test)]
+mod fault_vfs;
+pub mod header;
+pub mod hooks;
+pub mod integrity;
+pub mod interrupt;
+pub mod json;
+pub mod lexer;
+pub mod message;
+pub mod meta_command;
+pub mod migration;
+#[cfg(all(feature = "object-store", not(target_arch = "wasm32")))]
+pub mod object_store;
+pub mod open;
+pub mod output;
+pub mod page_view;
+pub mod pager;
+pub mod parser;
+pub mod provider;
+pub mod row;
+pub mod sqlite;
+pub mod statement;
+pub mod table;
+pub mod typed_table;
+
+pub const EXIT_SUCCESS: i32 = 0;
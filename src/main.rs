@@ -1,36 +1,61 @@
 //#![deny(clippy::unwrap_used, clippy::expect_used)]
 #![allow(dead_code)]
-
-mod btree;
-mod cursor;
-mod meta_command;
-mod pager;
-mod row;
-mod slice_pointer;
-mod statement;
-mod table;
+#![forbid(unsafe_code)]
 
 use std::env;
+use std::fs;
 use std::io;
-use std::io::Write;
+use std::io::IsTerminal;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Instant;
 use std::{cell::RefCell, rc::Rc};
 
-use crate::meta_command::{
-    MetaCommandError, MetaCommandSaveError, do_meta_command, is_meta_command,
+use clap::{ArgAction, Parser};
+use rustyline::Editor;
+use rustyline::error::ReadlineError;
+use rustyline::history::FileHistory;
+use tracing::Level;
+
+use my_db::EXIT_SUCCESS;
+use my_db::catalog::{Catalog, CatalogError};
+use my_db::completion::ReplHelper;
+use my_db::csv::{CsvError, CsvLineError};
+use my_db::cursor::CursorError;
+use my_db::database::Database;
+use my_db::header::HeaderError;
+use my_db::integrity::IntegrityProblem;
+use my_db::interrupt;
+use my_db::json::{JsonError, JsonLineError};
+use my_db::lexer::LexError;
+use my_db::message::{Lang, Message};
+use my_db::meta_command::{
+    MetaCommandBackupError, MetaCommandError, MetaCommandImportError, MetaCommandOutputError,
+    MetaCommandPageDumpError, MetaCommandRecoverError, MetaCommandSaveError,
+    MetaCommandSnapshotError, MetaCommandUndoError, do_meta_command, is_meta_command,
+};
+use my_db::migration::MigrationError;
+use my_db::open::ReadDataError;
+use my_db::output::{ColumnHeader, Output};
+use my_db::pager::{
+    GetPageError, PagerOpenError, SaveToDiskError, SetOpenSaveFileError, WriteMode,
 };
-use crate::pager::{GetPageError, Pager, SaveToDiskError};
-use crate::row::DeserializeError;
-use crate::statement::{
+use my_db::parser::ParseError;
+use my_db::row::DeserializeError;
+use my_db::sqlite::{SqliteError, SqliteRecordError};
+use my_db::statement::{
     PrepareStatementError, StatementOutput, StatementOutputError, execute_statement,
-    prepare_statement,
+    prepare_statement, split_statements,
 };
-use crate::table::{GetRowError, Table, WriteRowError};
+use my_db::table::{DeleteRowError, GetRowError, Table, WriteRowError};
 
 const PROMPT: &str = "my_db> ";
-const EXIT_SUCCESS: i32 = 0;
-
-const POISONED_TABLE_ERROR_STR: &str = "An error occured while loading the save file.";
-const POISONED_PAGER_ERROR_STR: &str = "An error occured while loading the pager.";
+const EXIT_FAILURE: i32 = 1;
+/// Fichier de configuration lu au démarrage, dans le dossier personnel.
+const RC_FILE_NAME: &str = ".mydbrc";
+/// Historique des commandes, dans le dossier personnel.
+const HISTORY_FILE_NAME: &str = ".my_db_history";
 
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub enum CreateTableError {
@@ -41,134 +66,825 @@ pub enum CreateTableError {
     PoisonedTable,
 }
 
+/// Options de la ligne de commande, validées avant le démarrage du REPL.
+#[derive(Parser)]
+#[command(version, about = "A small database with an interactive prompt.")]
+struct Cli {
+    #[arg(
+        default_value = Database::MEMORY_PATH,
+        help = "Database file, created if missing; ':memory:' keeps the database in memory"
+    )]
+    path: String,
+    #[arg(long, help = "Open the database without ever writing to it")]
+    readonly: bool,
+    #[arg(
+        long,
+        conflicts_with = "readonly",
+        help = "Save the database file after every statement that modifies it"
+    )]
+    write_through: bool,
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Keep at most N pages of the database in memory"
+    )]
+    page_cache_size: Option<NonZeroUsize>,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Run FILE at startup instead of ~/.mydbrc, to configure the prompt"
+    )]
+    rcfile: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Keep the command history in FILE instead of ~/.my_db_history"
+    )]
+    history_file: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "SCRIPT",
+        help = "Run the statements and meta commands of SCRIPT before the prompt"
+    )]
+    init: Option<PathBuf>,
+    #[arg(
+        short = 'c',
+        value_name = "STATEMENT",
+        help = "Run STATEMENT and exit instead of starting the prompt"
+    )]
+    command: Option<String>,
+    #[arg(short, action = ArgAction::Count, help = "Log to stderr, -vv for more details")]
+    verbose: u8,
+    #[arg(
+        long,
+        value_name = "LEVEL",
+        conflicts_with = "verbose",
+        help = "Log to stderr from LEVEL: error, warn, info, debug or trace"
+    )]
+    log_level: Option<Level>,
+}
+impl Cli {
+    /// Les journaux sont désactivés par défaut.
+    fn get_log_level(&self) -> Option<Level> {
+        match self.verbose {
+            0 => self.log_level,
+            1 => Some(Level::DEBUG),
+            _ => Some(Level::TRACE),
+        }
+    }
+}
+
 fn main() -> ! {
-    let args: Vec<String> = env::args().collect();
+    let cli = Cli::parse();
+
+    if let Some(log_level) = cli.get_log_level() {
+        tracing_subscriber::fmt()
+            .with_max_level(log_level)
+            .with_writer(io::stderr)
+            .with_ansi(io::stderr().is_terminal())
+            .init();
+    }
+
+    let database = if cli.readonly {
+        Database::open_read_only(&cli.path)
+    } else {
+        Database::open(&cli.path)
+    };
+    let database = match database {
+        Ok(database) => database,
+        Err(e) => {
+            handle_pager_open_error(Lang::from_env(), &e);
+            std::process::exit(EXIT_FAILURE)
+        }
+    };
+
+    // Sans gestionnaire, Ctrl-C arrête le processus comme avant.
+    let _ = interrupt::install_handler();
+
+    let table = database.get_table();
+    if cli.write_through {
+        table
+            .borrow()
+            .get_pager()
+            .set_write_mode(WriteMode::WriteThrough);
+    }
+    if let Some(cache_size) = cli.page_cache_size {
+        table.borrow().get_pager().set_cache_size(cache_size.get());
+    }
+    let mut output = Output::default();
+    match &cli.rcfile {
+        Some(rc_file_path) => run_script(&table, &mut output, rc_file_path),
+        None => {
+            // Le fichier par défaut est facultatif.
+            let rc_file_path = env::home_dir().map(|home| home.join(RC_FILE_NAME));
+            if let Some(rc_file_path) = rc_file_path.filter(|path| path.exists()) {
+                run_script(&table, &mut output, &rc_file_path);
+            }
+        }
+    }
+    if let Some(script_path) = &cli.init {
+        run_script(&table, &mut output, script_path);
+    }
 
-    let file: Option<&str> = args.get(1).map(|s| s.as_str());
+    if let Some(command) = &cli.command {
+        execute_line(&table, &mut output, command);
+        flush_pending_write(output.get_lang(), &table);
+        std::process::exit(EXIT_SUCCESS)
+    }
+
+    let history_path = cli
+        .history_file
+        .or_else(|| env::home_dir().map(|home| home.join(HISTORY_FILE_NAME)));
+    main_loop(table, output, history_path)
+}
 
-    let pager = Rc::new(RefCell::new(Pager::new(file)));
-    let table = Rc::new(RefCell::new(Table::new(pager.clone())));
+/// Exécute chaque ligne du script. Un script illisible arrête le programme
+/// avant le démarrage du REPL.
+fn run_script(table: &Rc<RefCell<Table>>, output: &mut Output, script_path: &Path) {
+    let script = match fs::read_to_string(script_path) {
+        Ok(script) => script,
+        Err(e) => {
+            let script_path = script_path.to_string_lossy();
+            print_message(
+                output.get_lang(),
+                &Message::UnableToReadScript(&script_path, &e),
+            );
+            std::process::exit(EXIT_FAILURE)
+        }
+    };
+    for line in script.lines() {
+        execute_line(table, output, line);
+    }
+}
 
-    main_loop(table)
+/// Ligne lue par le thread de saisie, ou signal de fin reçu entre deux lignes.
+enum Input {
+    Line(String),
+    Invalid,
+    Closed,
+    Terminated,
 }
 
-fn main_loop(table: Rc<RefCell<Table>>) -> ! {
-    let stdin = std::io::stdin();
-    let mut buffer = String::new();
+fn main_loop(table: Rc<RefCell<Table>>, mut output: Output, history_path: Option<PathBuf>) -> ! {
+    let (input_sender, inputs) = mpsc::channel::<Input>();
+    let read_requests = spawn_input_reader(input_sender.clone(), history_path);
+    let _ = interrupt::on_termination(move || {
+        let _ = input_sender.send(Input::Terminated);
+    });
 
     loop {
-        print!("{PROMPT}");
-        let _ = std::io::stdout().flush();
-        buffer.clear();
-        let Ok(_) = stdin.read_line(&mut buffer) else {
-            println!("Invalid input.");
-            continue;
+        let lang = output.get_lang();
+        let _ = read_requests.send(());
+        let mut buffer = match receive_input(lang, &table, &inputs) {
+            Some(Input::Line(line)) => line,
+            Some(Input::Invalid) => {
+                print_message(lang, &Message::InvalidInput);
+                continue;
+            }
+            Some(Input::Closed) | None => {
+                flush_pending_write(lang, &table);
+                println!();
+                std::process::exit(EXIT_SUCCESS)
+            }
+            Some(Input::Terminated) => save_before_exit(lang, &table),
         };
 
         remove_trailing_newline(&mut buffer);
+        let buffer = match expand_history_event(&output, &buffer) {
+            Ok(Some(line)) => {
+                // La ligne exécutée est affichée, comme dans bash.
+                println!("{line}");
+                line
+            }
+            Ok(None) => buffer,
+            Err(()) => {
+                print_message(lang, &Message::NoHistoryEntry(buffer.trim()));
+                continue;
+            }
+        };
+        output.add_history(&buffer);
+        execute_line(&table, &mut output, &buffer);
+    }
+}
+
+/// `!!` est la ligne précédente et `!N` la ligne numéro `N` de `.history`.
+/// `None` si la ligne n'est pas l'une des deux.
+fn expand_history_event(output: &Output, buffer: &str) -> Result<Option<String>, ()> {
+    let Some(event) = buffer.trim().strip_prefix('!') else {
+        return Ok(None);
+    };
+    let history = output.get_history();
+    let line = if event == "!" {
+        history.last()
+    } else if !event.is_empty() && event.bytes().all(|b| b.is_ascii_digit()) {
+        event
+            .parse::<usize>()
+            .ok()
+            .and_then(|line_num| history.get(line_num.checked_sub(1)?))
+    } else {
+        return Ok(None);
+    };
+    line.cloned().map(Some).ok_or(())
+}
+
+/// Attend l'entrée suivante. Les modifications du mode write-through en attente
+/// sont sauvegardées dès que leur délai est écoulé, sans attendre l'entrée.
+fn receive_input(
+    lang: Lang,
+    table: &Rc<RefCell<Table>>,
+    inputs: &mpsc::Receiver<Input>,
+) -> Option<Input> {
+    loop {
+        let deadline = table.borrow().get_pager().get_write_deadline();
+        let Some(deadline) = deadline else {
+            return inputs.recv().ok();
+        };
+        match inputs.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(input) => return Some(input),
+            Err(mpsc::RecvTimeoutError::Timeout) => flush_pending_write(lang, table),
+            Err(mpsc::RecvTimeoutError::Disconnected) => return None,
+        }
+    }
+}
+
+fn flush_pending_write(lang: Lang, table: &Rc<RefCell<Table>>) {
+    if let Err(e) = table.borrow().flush_pending_write() {
+        handle_save_to_disk_error(lang, &e);
+    }
+}
+
+/// Exécute une ligne du REPL, du script `--init` ou de `-c`, et affiche son résultat.
+fn execute_line(table: &Rc<RefCell<Table>>, output: &mut Output, buffer: &str) {
+    let lang = output.get_lang();
+    if buffer.is_empty() {
+        return;
+    }
+    if output.get_echo() {
+        println!("{buffer}");
+    }
 
-        if buffer.is_empty() {
-            continue;
+    if is_meta_command(buffer) {
+        if let Err(meta_command_error) = do_meta_command(table.clone(), output, buffer) {
+            handle_meta_command_error(lang, meta_command_error, buffer);
         }
+        return;
+    }
 
-        if is_meta_command(&buffer) {
-            if let Err(meta_command_error) = do_meta_command(table.clone(), &buffer) {
-                handle_meta_command_error(meta_command_error, &buffer);
+    let start = Instant::now();
+    let statements = split_statements(buffer);
+    let nb_statements = statements.len();
+    for (statement_num, statement) in statements.into_iter().enumerate() {
+        interrupt::clear();
+        if let Err(e) = run_statement(table, output, statement) {
+            // Les instructions suivantes de la ligne ne sont pas exécutées.
+            if nb_statements > 1 {
+                print_message(lang, &Message::InStatement(statement_num + 1));
             }
-            continue;
+            handle_statement_error(lang, &e, statement);
+            break;
         }
+    }
+    if output.get_timer() {
+        print_message(lang, &Message::RunTime(start.elapsed().as_secs_f64()));
+    }
+}
+
+/// Erreur d'une instruction, qu'elle soit mal formée ou que son exécution ait échoué.
+enum StatementError {
+    Prepare(PrepareStatementError),
+    Execute(StatementOutputError),
+}
 
-        let statement = prepare_statement(&buffer);
-        match statement {
-            Ok(statement) => match execute_statement(table.clone(), statement) {
-                Ok(StatementOutput::Select(rows)) => {
-                    for row in rows {
-                        println!("{row}");
+fn run_statement(
+    table: &Rc<RefCell<Table>>,
+    output: &mut Output,
+    buffer: &str,
+) -> Result<(), StatementError> {
+    let lang = output.get_lang();
+    let statement = prepare_statement(buffer).map_err(StatementError::Prepare)?;
+    match execute_statement(table.clone(), statement).map_err(StatementError::Execute)? {
+        StatementOutput::Select(result) => {
+            let skipped_rows = output
+                .print_result(*result)
+                .map_err(|e| StatementError::Execute(StatementOutputError::Select(e)))?;
+            if skipped_rows != 0 {
+                print_message(lang, &Message::CorruptedRowsSkipped(skipped_rows));
+            }
+        }
+        StatementOutput::InsertSuccessfull
+        | StatementOutput::DeleteSuccessfull
+        | StatementOutput::PragmaSet
+        | StatementOutput::TableCreated => {}
+        StatementOutput::PragmaValue(value) => println!("{value}"),
+        StatementOutput::Plan(plan) => println!("{plan}"),
+        StatementOutput::IntegrityReport(problems) => print_integrity_report(lang, &problems),
+        StatementOutput::Master { columns, records } => {
+            let headers: Vec<ColumnHeader> = columns
+                .iter()
+                .map(|column| {
+                    let name = Catalog::MASTER_COLUMN_NAMES[*column];
+                    ColumnHeader {
+                        name,
+                        is_numeric: name == "rootpage",
                     }
-                    println!("Executed.");
-                }
-                Ok(StatementOutput::InsertSuccessfull) => {
-                    println!("Executed.");
-                }
-                Err(StatementOutputError::PoisonedTable) => println!("{POISONED_TABLE_ERROR_STR}"),
-                Err(StatementOutputError::Select(rows, get_row_error)) => {
-                    for row in rows {
-                        println!("{row}");
+                })
+                .collect();
+            output
+                .print_records(&headers, records.into_iter().map(Ok))
+                .map_err(|e| StatementError::Execute(StatementOutputError::Select(e)))?;
+        }
+    }
+    print_message(lang, &Message::Executed);
+    Ok(())
+}
+
+fn handle_statement_error(lang: Lang, error: &StatementError, buffer: &str) {
+    match error {
+        StatementError::Prepare(e) => handle_prepare_statement_error(lang, e, buffer),
+        StatementError::Execute(StatementOutputError::PoisonedTable) => {
+            print_message(lang, &Message::PoisonedTable);
+        }
+        StatementError::Execute(StatementOutputError::Select(e)) => handle_get_row_error(lang, e),
+        StatementError::Execute(StatementOutputError::Insert(e)) => {
+            handle_write_row_error(lang, e);
+        }
+        StatementError::Execute(StatementOutputError::Delete(e)) => {
+            handle_get_page_error(lang, e);
+        }
+        StatementError::Execute(StatementOutputError::Catalog(e)) => handle_catalog_error(lang, e),
+        StatementError::Execute(StatementOutputError::UnknownTable(name)) => {
+            print_message(lang, &Message::UnknownTable(name));
+        }
+        StatementError::Execute(StatementOutputError::Csv(e)) => handle_csv_error(lang, e),
+        StatementError::Execute(StatementOutputError::Sqlite(e)) => handle_sqlite_error(lang, e),
+        StatementError::Execute(StatementOutputError::WriteThrough(e)) => {
+            handle_save_to_disk_error(lang, e);
+        }
+    }
+}
+
+/// Le thread ne lit une ligne que sur demande : entre deux demandes, l'entrée
+/// standard reste disponible pour l'invite `--More--`.
+///
+/// Chaque ligne est ajoutée à la fin de `history_path` dès sa lecture : le
+/// programme peut quitter de partout sans perdre l'historique.
+fn spawn_input_reader(
+    inputs: mpsc::Sender<Input>,
+    history_path: Option<PathBuf>,
+) -> mpsc::Sender<()> {
+    let (read_requests, requests) = mpsc::channel::<()>();
+    let _ = std::thread::spawn(move || {
+        // Deux lignes identiques à la suite ne sont gardées qu'une fois.
+        let config = rustyline::Config::builder()
+            .history_ignore_dups(true)
+            .map(rustyline::config::Builder::build)
+            .unwrap_or_default();
+        let Ok(mut editor) = Editor::<ReplHelper, FileHistory>::with_config(config) else {
+            let _ = inputs.send(Input::Closed);
+            return;
+        };
+        editor.set_helper(Some(ReplHelper));
+        if let Some(history_path) = &history_path {
+            let _ = editor.load_history(history_path);
+        }
+
+        for () in requests {
+            let input = match editor.readline(PROMPT) {
+                Ok(line) => {
+                    if !line.trim().is_empty()
+                        && editor.add_history_entry(line.as_str()).is_ok()
+                        && let Some(history_path) = &history_path
+                    {
+                        let _ = editor.append_history(history_path);
                     }
-                    handle_get_row_error(&get_row_error);
+                    Input::Line(line)
                 }
-                Err(StatementOutputError::Insert(e)) => handle_write_row_error(&e),
-            },
-            Err(PrepareStatementError::UnrecognizedStatement) => {
-                println!("Unrecognized keyword at start of '{buffer}'.");
-            }
-            Err(PrepareStatementError::InvalidInsert) => {
-                println!("Insert statement malformed.");
-            }
-            Err(PrepareStatementError::StringTooLong(name, max)) => {
-                println!("'{name}' is too long, max: '{max}'.");
+                // Ctrl-C à l'invite abandonne seulement la ligne en cours.
+                Err(ReadlineError::Interrupted) => Input::Line(String::new()),
+                Err(ReadlineError::Eof) => Input::Closed,
+                Err(_) => Input::Invalid,
+            };
+            if inputs.send(input).is_err() {
+                break;
             }
         }
+    });
+    read_requests
+}
+
+/// Sur SIGTERM ou SIGHUP, la base est sauvegardée dans son fichier avant de
+/// quitter, pour ne pas perdre la session quand le terminal est fermé.
+fn save_before_exit(lang: Lang, table: &Rc<RefCell<Table>>) -> ! {
+    // C'est la base qui est sauvegardée, pas l'instantané ouvert.
+    let _ = table.borrow_mut().close_snapshot();
+    let table = table.borrow();
+    let pager = table.get_pager();
+    if !pager.has_save_file() || pager.is_read_only() {
+        std::process::exit(EXIT_SUCCESS)
+    }
+    match table.save_to_disk(None) {
+        Ok(()) => std::process::exit(EXIT_SUCCESS),
+        Err(e) => {
+            handle_save_to_disk_error(lang, &e);
+            std::process::exit(EXIT_FAILURE)
+        }
     }
 }
 
 fn remove_trailing_newline(buffer: &mut String) {
-    let _ = buffer.pop();
+    if buffer.ends_with('\n') {
+        let _ = buffer.pop();
+    }
+}
+
+fn print_message(lang: Lang, message: &Message<'_>) {
+    println!("{}", message.text(lang));
+}
+
+fn handle_prepare_statement_error(lang: Lang, error: &PrepareStatementError, buffer: &str) {
+    let message = match error {
+        PrepareStatementError::Parse(ParseError::UnrecognizedStatement) => {
+            Message::UnrecognizedStatement(buffer)
+        }
+        // Les colonnes sont comptées à partir de 1.
+        PrepareStatementError::Parse(ParseError::Lex(LexError::UnterminatedString(offset))) => {
+            Message::UnterminatedString(offset + 1)
+        }
+        PrepareStatementError::Parse(ParseError::Lex(LexError::NumberTooLarge(offset))) => {
+            Message::NumberTooLarge(offset + 1)
+        }
+        PrepareStatementError::Parse(ParseError::UnexpectedToken(_, expected, Some(found))) => {
+            Message::UnexpectedToken(expected, found)
+        }
+        PrepareStatementError::Parse(ParseError::UnexpectedToken(_, expected, None)) => {
+            Message::UnexpectedEnd(expected)
+        }
+        PrepareStatementError::Parse(ParseError::TooDeep(offset)) => Message::TooDeep(offset + 1),
+        PrepareStatementError::StringTooLong(name, max) => Message::StringTooLong(name, *max),
+        PrepareStatementError::ControlCharacter(name, c) => Message::ControlCharacter(name, *c),
+        PrepareStatementError::UnknownPragma(name) => Message::UnknownPragma(name),
+        PrepareStatementError::InvalidPragmaValue(name, value) => {
+            Message::InvalidPragmaValue(name, value)
+        }
+        PrepareStatementError::UnknownColumn(column) => Message::UnknownColumn(column),
+        PrepareStatementError::WrongNumberOfValues(nb_values) => {
+            Message::WrongNumberOfValues(*nb_values)
+        }
+        PrepareStatementError::InvalidId(value) => Message::InvalidId(value),
+        PrepareStatementError::UnsupportedFilter(filter) => Message::UnsupportedFilter(filter),
+        PrepareStatementError::UnknownCollation(name) => Message::UnknownCollation(name),
+        PrepareStatementError::UnknownFunction(name) => Message::UnknownFunction(name),
+        PrepareStatementError::WrongNumberOfArguments(name, nb_arguments) => {
+            Message::WrongNumberOfArguments(name, *nb_arguments)
+        }
+        PrepareStatementError::TableExists(name) => Message::TableExists(name),
+        PrepareStatementError::UnknownModule(name) => Message::UnknownModule(name),
+        PrepareStatementError::CsvArguments => Message::CsvArguments,
+        PrepareStatementError::SqliteArguments => Message::SqliteArguments,
+    };
+    print_message(lang, &message);
+    if let PrepareStatementError::Parse(e) = error
+        && let Some(offset) = e.get_offset()
+    {
+        print_caret(buffer, offset);
+    }
+}
+
+/// Affiche la ligne de l'instruction contenant `offset` et un `^` sous le
+/// caractère à cette position. Les tabulations sont gardées pour que le `^`
+/// reste aligné.
+fn print_caret(buffer: &str, offset: usize) {
+    let line_start = buffer[..offset].rfind('\n').map_or(0, |index| index + 1);
+    let line_end = buffer[offset..]
+        .find('\n')
+        .map_or(buffer.len(), |index| offset + index);
+    let padding: String = buffer[line_start..offset]
+        .chars()
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    println!("{}", &buffer[line_start..line_end]);
+    println!("{padding}^");
 }
 
-fn handle_meta_command_error(error: MetaCommandError, buffer: &str) {
+fn handle_pager_open_error(lang: Lang, error: &PagerOpenError) {
     match error {
-        MetaCommandError::MetaCommandSave(e) => handle_meta_command_save_error(&e),
-        MetaCommandError::UnknownMetaCommandError => println!("Unrecognized command: '{buffer}'."),
+        PagerOpenError::IoError(file_path, e) => {
+            print_message(lang, &Message::UnableToOpen(file_path, e));
+        }
+        PagerOpenError::Migration(file_path, e) => {
+            print_message(lang, &Message::UnableToRead(file_path));
+            handle_migration_error(lang, e);
+        }
+        PagerOpenError::Catalog(file_path, e) => {
+            print_message(lang, &Message::UnableToRead(file_path));
+            handle_catalog_error(lang, e);
+        }
+    }
+}
+
+fn handle_meta_command_error(lang: Lang, error: MetaCommandError, buffer: &str) {
+    match error {
+        MetaCommandError::MetaCommandSave(e) => handle_meta_command_save_error(lang, &e),
+        MetaCommandError::MetaCommandRecover(e) => handle_meta_command_recover_error(lang, &e),
+        MetaCommandError::MetaCommandBackup(e) => handle_meta_command_backup_error(lang, &e),
+        MetaCommandError::MetaCommandOutput(e) => handle_meta_command_output_error(lang, &e),
+        MetaCommandError::MetaCommandPageDump(e) => handle_meta_command_pagedump_error(lang, &e),
+        MetaCommandError::MetaCommandSnapshot(e) => handle_meta_command_snapshot_error(lang, &e),
+        MetaCommandError::MetaCommandImport(e) => handle_meta_command_import_error(lang, &e),
+        MetaCommandError::MetaCommandUndo(e) => handle_meta_command_undo_error(lang, &e),
+        MetaCommandError::MetaCommandSize(e) => {
+            print_message(lang, &Message::UnableToReadSize(&e));
+        }
+        MetaCommandError::UnknownMetaCommandError => {
+            print_message(lang, &Message::UnrecognizedCommand(buffer));
+        }
+    }
+}
+
+fn handle_meta_command_save_error(lang: Lang, error: &MetaCommandSaveError) {
+    match error {
+        MetaCommandSaveError::PoisonedPager => print_message(lang, &Message::PoisonedPager),
+        MetaCommandSaveError::SaveToDisk(e) => handle_save_to_disk_error(lang, e),
+    }
+}
+
+fn handle_meta_command_backup_error(lang: Lang, error: &MetaCommandBackupError) {
+    match error {
+        MetaCommandBackupError::NoFileProvided => print_message(lang, &Message::NoBackupFile),
+        MetaCommandBackupError::SaveToDisk(e) => handle_save_to_disk_error(lang, e),
+    }
+}
+
+fn handle_meta_command_import_error(lang: Lang, error: &MetaCommandImportError) {
+    match error {
+        MetaCommandImportError::SqliteUsage => print_message(lang, &Message::ImportSqliteUsage),
+        MetaCommandImportError::JsonUsage => print_message(lang, &Message::ImportJsonUsage),
+        MetaCommandImportError::Sqlite(e) => handle_sqlite_error(lang, e),
+        MetaCommandImportError::Json(errors) => {
+            for e in errors {
+                handle_json_error(lang, e);
+            }
+        }
+        MetaCommandImportError::Read(e) => handle_get_row_error(lang, e),
+        MetaCommandImportError::WriteRow(e) => handle_write_row_error(lang, e),
+        MetaCommandImportError::WriteThrough(e) => handle_save_to_disk_error(lang, e),
+    }
+}
+
+fn handle_meta_command_undo_error(lang: Lang, error: &MetaCommandUndoError) {
+    match error {
+        MetaCommandUndoError::NothingToUndo => print_message(lang, &Message::NothingToUndo),
+        MetaCommandUndoError::DeleteRow(e) => handle_delete_row_error(lang, e),
+        MetaCommandUndoError::WriteRow(e) => handle_write_row_error(lang, e),
+        MetaCommandUndoError::WriteThrough(e) => handle_save_to_disk_error(lang, e),
+    }
+}
+
+fn handle_meta_command_output_error(lang: Lang, error: &MetaCommandOutputError) {
+    let message = match error {
+        MetaCommandOutputError::UnknownMode(mode) => Message::UnknownMode(mode),
+        MetaCommandOutputError::InvalidWidth(width) => Message::InvalidWidth(width),
+        MetaCommandOutputError::UnknownLang(value) => Message::UnknownLang(value),
+        MetaCommandOutputError::InvalidSwitch(value) => Message::InvalidSwitch(value),
+        MetaCommandOutputError::InvalidHistoryCount(value) => Message::InvalidHistoryCount(value),
+        MetaCommandOutputError::NoOutputFile => Message::NoOutputFile,
+        MetaCommandOutputError::UnableToOpenOutput(file_path, e) => {
+            Message::UnableToOpenOutput(file_path, e)
+        }
+    };
+    print_message(lang, &message);
+}
+
+fn handle_meta_command_pagedump_error(lang: Lang, error: &MetaCommandPageDumpError) {
+    match error {
+        MetaCommandPageDumpError::NoPageProvided => print_message(lang, &Message::NoPageNum),
+        MetaCommandPageDumpError::InvalidPageNum(value) => {
+            print_message(lang, &Message::InvalidPageNum(value));
+        }
+        MetaCommandPageDumpError::PageOutOfRange(page_num, nb_pages) => {
+            print_message(lang, &Message::PageOutOfRange(*page_num, *nb_pages));
+        }
+        MetaCommandPageDumpError::GetPage(e) => handle_get_page_error(lang, e),
     }
 }
 
-fn handle_meta_command_save_error(error: &MetaCommandSaveError) {
+fn handle_meta_command_snapshot_error(lang: Lang, error: &MetaCommandSnapshotError) {
     match error {
-        MetaCommandSaveError::PoisonedPager => println!("{POISONED_PAGER_ERROR_STR}"),
-        MetaCommandSaveError::SaveToDisk(e) => handle_save_to_disk_error(e),
+        MetaCommandSnapshotError::UnknownAction(_) => print_message(lang, &Message::SnapshotUsage),
+        MetaCommandSnapshotError::NoNameProvided => print_message(lang, &Message::NoSnapshotName),
+        MetaCommandSnapshotError::InvalidName(name) => {
+            print_message(lang, &Message::InvalidSnapshotName(name));
+        }
+        MetaCommandSnapshotError::NoSnapshotOpen => print_message(lang, &Message::NoSnapshotOpen),
+        MetaCommandSnapshotError::Create(e) => handle_save_to_disk_error(lang, e),
+        MetaCommandSnapshotError::Open(e) => handle_pager_open_error(lang, e),
+    }
+}
+
+fn handle_meta_command_recover_error(lang: Lang, error: &MetaCommandRecoverError) {
+    match error {
+        MetaCommandRecoverError::NoFileProvided => print_message(lang, &Message::NoRecoverFile),
+        MetaCommandRecoverError::ReadData(e) => handle_read_data_error(lang, e),
+        MetaCommandRecoverError::ClosePager(e) => handle_set_open_save_file_error(lang, e),
+        MetaCommandRecoverError::WriteRow(e) => handle_write_row_error(lang, e),
+    }
+}
+
+fn handle_set_open_save_file_error(lang: Lang, error: &SetOpenSaveFileError) {
+    match error {
+        SetOpenSaveFileError::IoError(e) => println!("{e}"),
+        SetOpenSaveFileError::Migration(e) => handle_migration_error(lang, e),
+        SetOpenSaveFileError::Catalog(e) => handle_catalog_error(lang, e),
+        SetOpenSaveFileError::PoisonedTable => print_message(lang, &Message::PoisonedTable),
+        SetOpenSaveFileError::PageInUse => print_message(lang, &Message::PageInUse),
     }
 }
 
-fn handle_save_to_disk_error(error: &SaveToDiskError) {
+fn handle_read_data_error(lang: Lang, error: &ReadDataError) {
     match error {
-        SaveToDiskError::NoFileToWriteProvided => println!("No file to save provided."),
-        SaveToDiskError::PoisonedTable => println!("{POISONED_TABLE_ERROR_STR}"),
+        ReadDataError::IoError(e) => println!("{e}"),
+        ReadDataError::Migration(e) => handle_migration_error(lang, e),
+        ReadDataError::FileIsCorrupted { lost_bytes, .. } => {
+            print_message(lang, &Message::FileIsCorrupted(*lost_bytes));
+        }
+    }
+}
+
+fn handle_migration_error(lang: Lang, error: &MigrationError) {
+    match error {
+        MigrationError::IoError(e) => println!("{e}"),
+        MigrationError::Header(HeaderError::InvalidMagic) => {
+            print_message(lang, &Message::NotADatabaseFile);
+        }
+        MigrationError::Header(HeaderError::UnsupportedVersion(version)) => {
+            print_message(lang, &Message::UnsupportedVersion(*version));
+        }
+        MigrationError::Header(HeaderError::UnsupportedFlags(flags)) => {
+            print_message(lang, &Message::UnsupportedFlags(*flags));
+        }
+    }
+}
+
+fn handle_catalog_error(lang: Lang, error: &CatalogError) {
+    print_message(lang, &Message::from(error));
+}
+
+/// Une ligne par problème, ou `ok` comme SQLite.
+fn print_integrity_report(lang: Lang, problems: &[IntegrityProblem]) {
+    if problems.is_empty() {
+        print_message(lang, &Message::IntegrityOk);
+    }
+    for problem in problems {
+        print_message(lang, &Message::from(problem));
+    }
+}
+
+fn handle_save_to_disk_error(lang: Lang, error: &SaveToDiskError) {
+    match error {
+        SaveToDiskError::NoFileToWriteProvided => print_message(lang, &Message::NoSaveFile),
+        SaveToDiskError::PoisonedTable => print_message(lang, &Message::PoisonedTable),
         SaveToDiskError::IoError(e) => println!("{e}"),
-        SaveToDiskError::NotAllBytesWritten => println!("Not all data written to file."),
+        SaveToDiskError::WriteFailed(offset, e) => {
+            print_message(lang, &Message::WriteFailed(*offset, e));
+        }
+        SaveToDiskError::PageInUse => print_message(lang, &Message::PageInUse),
+        SaveToDiskError::GetPage(e) => handle_get_page_error(lang, e),
+        SaveToDiskError::Reopen(e) => handle_pager_open_error(lang, e),
+        SaveToDiskError::ReadOnly => print_message(lang, &Message::ReadOnly),
+        SaveToDiskError::Catalog(e) => handle_catalog_error(lang, e),
+    }
+}
+
+fn handle_get_row_error(lang: Lang, error: &GetRowError) {
+    match error {
+        GetRowError::PoisonedPager => print_message(lang, &Message::PoisonedPager),
+        GetRowError::GetPage(e) => handle_get_page_error(lang, e),
+        GetRowError::Deserialize(e) => handle_deserialize_error(lang, e),
+        GetRowError::Interrupted => print_message(lang, &Message::Interrupted),
+        GetRowError::Provider(e) => print_message(lang, &Message::VirtualTableError(e)),
+        GetRowError::Csv(e) => handle_csv_error(lang, e),
+        GetRowError::Sqlite(e) => handle_sqlite_error(lang, e),
+        GetRowError::Cursor(e) => handle_cursor_error(lang, e),
     }
 }
 
-fn handle_get_row_error(error: &GetRowError) {
+fn handle_cursor_error(lang: Lang, error: &CursorError) {
     match error {
-        GetRowError::PoisonedPager => println!("{POISONED_PAGER_ERROR_STR}"),
-        GetRowError::GetPage(e) => handle_get_page_error(e),
-        GetRowError::Deserialize(e) => handle_deserialize_error(e),
+        CursorError::EndOfTable => print_message(lang, &Message::EndOfTable),
+        CursorError::KeyChanged(key) => print_message(lang, &Message::KeyChanged(*key)),
+        CursorError::GetPage(e) => handle_get_page_error(lang, e),
     }
 }
 
-fn handle_write_row_error(error: &WriteRowError) {
+fn handle_sqlite_error(lang: Lang, error: &SqliteError) {
     match error {
-        WriteRowError::TableFull => println!("Error: Table full."),
-        WriteRowError::PoisonedPager => println!("{POISONED_PAGER_ERROR_STR}"),
-        WriteRowError::GetPage(e) => handle_get_page_error(e),
+        SqliteError::Open(file_path, e) => {
+            print_message(lang, &Message::UnableToOpenSqlite(file_path, e));
+        }
+        SqliteError::NotASqliteFile => print_message(lang, &Message::NotASqliteFile),
+        SqliteError::UnsupportedEncoding(_) => print_message(lang, &Message::UnsupportedEncoding),
+        SqliteError::CorruptedPage(page_num) => {
+            print_message(lang, &Message::SqlitePageCorrupted(*page_num));
+        }
+        SqliteError::NoTable(name) => print_message(lang, &Message::SqliteNoTable(name.as_deref())),
+        SqliteError::Record(rowid, e) => {
+            print_message(lang, &Message::SqliteRow(*rowid));
+            let message = match e {
+                SqliteRecordError::WrongNumberOfValues(nb_values) => {
+                    Message::WrongNumberOfValues(*nb_values)
+                }
+                SqliteRecordError::InvalidId(value) => Message::InvalidId(value),
+                SqliteRecordError::StringTooLong(name, max) => Message::StringTooLong(name, *max),
+                SqliteRecordError::ControlCharacter(name, c) => Message::ControlCharacter(name, *c),
+                SqliteRecordError::UnsupportedValue(name) => Message::UnsupportedValue(name),
+            };
+            print_message(lang, &message);
+        }
     }
 }
 
-fn handle_get_page_error(error: &GetPageError) {
+fn handle_csv_error(lang: Lang, error: &CsvError) {
     match error {
-        GetPageError::MaxPageReached => println!("Max page reached."),
+        CsvError::Open(file_path, e) => {
+            print_message(lang, &Message::UnableToOpenCsv(file_path, e));
+        }
+        CsvError::Line(line_num, e) => {
+            print_message(lang, &Message::CsvLine(*line_num));
+            let message = match e {
+                CsvLineError::WrongNumberOfFields(nb_fields) => {
+                    Message::WrongNumberOfValues(*nb_fields)
+                }
+                CsvLineError::InvalidId(value) => Message::InvalidId(value),
+                CsvLineError::StringTooLong(name, max) => Message::StringTooLong(name, *max),
+                CsvLineError::ControlCharacter(name, c) => Message::ControlCharacter(name, *c),
+                CsvLineError::UnterminatedQuote => Message::UnterminatedQuote,
+            };
+            print_message(lang, &message);
+        }
+    }
+}
+
+fn handle_json_error(lang: Lang, error: &JsonError) {
+    match error {
+        JsonError::Open(file_path, e) => {
+            print_message(lang, &Message::UnableToOpenJson(file_path, e));
+        }
+        JsonError::Line(line_num, e) => {
+            print_message(lang, &Message::JsonLine(*line_num));
+            let message = match e {
+                JsonLineError::Syntax(column) => Message::JsonSyntax(*column),
+                JsonLineError::NotAnObject => Message::JsonNotAnObject,
+                JsonLineError::NestedValue(key) => Message::JsonNestedValue(key),
+                JsonLineError::UnknownKey(key) => Message::JsonUnknownKey(key),
+                JsonLineError::DuplicateKey(key) => Message::JsonDuplicateKey(key),
+                JsonLineError::MissingKey(key) => Message::JsonMissingKey(key),
+                JsonLineError::InvalidId(value) => Message::InvalidId(value),
+                JsonLineError::WrongType(column) => Message::JsonWrongType(column),
+                JsonLineError::StringTooLong(name, max) => Message::StringTooLong(name, *max),
+                JsonLineError::ControlCharacter(name, c) => Message::ControlCharacter(name, *c),
+            };
+            print_message(lang, &message);
+        }
+    }
+}
+
+fn handle_write_row_error(lang: Lang, error: &WriteRowError) {
+    match error {
+        WriteRowError::TableFull => print_message(lang, &Message::TableFull),
+        WriteRowError::DuplicateKey(_) => print_message(lang, &Message::DuplicateKey),
+        WriteRowError::PoisonedPager => print_message(lang, &Message::PoisonedPager),
+        WriteRowError::GetPage(e) => handle_get_page_error(lang, e),
+    }
+}
+
+fn handle_delete_row_error(lang: Lang, error: &DeleteRowError) {
+    match error {
+        DeleteRowError::GetPage(e) => handle_get_page_error(lang, e),
+    }
+}
+
+fn handle_get_page_error(lang: Lang, error: &GetPageError) {
+    match error {
+        GetPageError::MaxPageReached => print_message(lang, &Message::MaxPageReached),
         GetPageError::IoError(e) => println!("{e}"),
+        GetPageError::PageInUse => print_message(lang, &Message::PageInUse),
+        GetPageError::ReadOnly => print_message(lang, &Message::ReadOnly),
+        GetPageError::Corrupted(page_num) => {
+            print_message(lang, &Message::PageCorrupted(*page_num));
+        }
     }
 }
 
-fn handle_deserialize_error(error: &DeserializeError) {
+fn handle_deserialize_error(lang: Lang, error: &DeserializeError) {
     match error {
         DeserializeError::InvalidBytesSlice(_slice_len) => {
-            println!("Error while deserializing row.");
+            print_message(lang, &Message::DeserializeRow);
         }
         DeserializeError::FromUtf8Error(e) => println!("{e}"),
-        DeserializeError::TryFromSliceError { .. } => println!("Error while deserializing row."),
+        DeserializeError::Utf8Error(e) => println!("{e}"),
+        DeserializeError::TryFromSliceError { .. } => {
+            print_message(lang, &Message::DeserializeRow);
+        }
     }
 }
 
@@ -0,0 +1,793 @@
+use std::io;
+
+use crate::catalog::CatalogError;
+use crate::integrity::IntegrityProblem;
+
+/// Langue des messages affichés à l'utilisateur.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Clone, Copy)]
+pub enum Lang {
+    En,
+    Fr,
+}
+impl Lang {
+    /// Langue déduite de `LANG`, l'anglais si elle n'est pas prise en charge.
+    pub fn from_env() -> Self {
+        std::env::var("LANG")
+            .ok()
+            .and_then(|lang| Self::try_from(lang.as_str()).ok())
+            .unwrap_or(Self::En)
+    }
+}
+impl std::convert::TryFrom<&str> for Lang {
+    type Error = ();
+
+    /// Accepte `fr` comme `fr_FR.UTF-8`.
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let code = value.split(['_', '.', '-']).next().unwrap_or_default();
+        match code.to_lowercase().as_str() {
+            "en" | "c" | "posix" => Ok(Self::En),
+            "fr" => Ok(Self::Fr),
+            _ => Err(()),
+        }
+    }
+}
+impl std::fmt::Display for Lang {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::En => write!(f, "en"),
+            Self::Fr => write!(f, "fr"),
+        }
+    }
+}
+
+/*
+ * Catalogue des messages affichés à l'utilisateur. Chaque message est traduit
+ * dans toutes les langues de `Lang` : ajouter un message oblige à le traduire.
+ */
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum Message<'a> {
+    Executed,
+    Interrupted,
+    VirtualTableError(&'a str),
+    CsvLine(usize),
+    UnterminatedQuote,
+    /// Rowid de la ligne du fichier SQLite.
+    SqliteRow(i64),
+    UnsupportedValue(&'a str),
+    NotASqliteFile,
+    UnsupportedEncoding,
+    SqlitePageCorrupted(usize),
+    SqliteNoTable(Option<&'a str>),
+    InStatement(usize),
+    InvalidInput,
+    PoisonedTable,
+    PoisonedPager,
+    PageInUse,
+    UnrecognizedStatement(&'a str),
+    StringTooLong(&'a str, usize),
+    ControlCharacter(&'a str, char),
+    UnknownPragma(&'a str),
+    InvalidPragmaValue(&'a str, &'a str),
+    UnknownColumn(&'a str),
+    WrongNumberOfValues(usize),
+    InvalidId(&'a str),
+    UnsupportedFilter(&'a str),
+    UnknownCollation(&'a str),
+    UnknownFunction(&'a str),
+    WrongNumberOfArguments(&'a str, usize),
+    UnknownTable(&'a str),
+    TableExists(&'a str),
+    UnknownModule(&'a str),
+    CsvArguments,
+    SqliteArguments,
+    UnterminatedString(usize),
+    NumberTooLarge(usize),
+    UnexpectedToken(&'a str, &'a str),
+    UnexpectedEnd(&'a str),
+    /// Colonne où l'instruction dépasse la profondeur d'imbrication permise.
+    TooDeep(usize),
+    UnableToOpen(&'a str, &'a io::Error),
+    UnableToOpenCsv(&'a str, &'a io::Error),
+    UnableToOpenSqlite(&'a str, &'a io::Error),
+    UnableToRead(&'a str),
+    UnableToReadScript(&'a str, &'a io::Error),
+    UnrecognizedCommand(&'a str),
+    NoBackupFile,
+    NoRecoverFile,
+    NoPageNum,
+    SnapshotUsage,
+    NoSnapshotName,
+    InvalidSnapshotName(&'a str),
+    NoSnapshotOpen,
+    SnapshotCreated(&'a str),
+    SnapshotOpened(&'a str),
+    SnapshotClosed,
+    NothingToUndo,
+    /// Id de la ligne dont l'insertion est annulée.
+    UndoneInsert(u64),
+    /// Nombre de lignes remises.
+    UndoneDelete(usize),
+    /// Nombre de lignes supprimées, au-delà de `Table::UNDO_MAX_ROWS`.
+    UndoTooLarge(usize),
+    InvalidPageNum(&'a str),
+    /// Page demandée et nombre de pages de la base.
+    PageOutOfRange(usize, usize),
+    NoSaveFile,
+    UnknownMode(&'a str),
+    InvalidWidth(&'a str),
+    UnknownLang(&'a str),
+    InvalidSwitch(&'a str),
+    InvalidHistoryCount(&'a str),
+    /// `!!` ou `!N` sans ligne correspondante dans l'historique.
+    NoHistoryEntry(&'a str),
+    NoOutputFile,
+    UnableToOpenOutput(&'a str, &'a io::Error),
+    RunTime(f64),
+    FileIsCorrupted(usize),
+    NotADatabaseFile,
+    UnsupportedVersion(u32),
+    UnsupportedFlags(u32),
+    CatalogCorrupted,
+    UnknownColumnType(u8),
+    CatalogTooLarge,
+    SchemaMismatch,
+    WriteFailed(u64, &'a io::Error),
+    TableFull,
+    ReadOnly,
+    DuplicateKey,
+    MaxPageReached,
+    PageCorrupted(usize),
+    DeserializeRow,
+    CorruptedRowsSkipped(usize),
+    EndOfTable,
+    /// Clé de la cellule sous le curseur.
+    KeyChanged(u64),
+    /// Lignes récupérées, pages lues, lignes perdues et octets perdus.
+    Recovered(usize, usize, usize, usize),
+    /// Lignes importées et lignes ignorées, leur id étant déjà présent.
+    Imported(usize, usize),
+    ImportSqliteUsage,
+    ImportJsonUsage,
+    JsonLine(usize),
+    /// Colonne, à partir de 1.
+    JsonSyntax(usize),
+    JsonNotAnObject,
+    JsonNestedValue(&'a str),
+    JsonUnknownKey(&'a str),
+    JsonDuplicateKey(&'a str),
+    JsonMissingKey(&'a str),
+    JsonWrongType(&'a str),
+    UnableToOpenJson(&'a str, &'a io::Error),
+    SaveRecovered,
+    /// Pages en mémoire, leur taille en octets et la taille du cache en pages.
+    ResidentPages(usize, usize, usize),
+    CacheMetadata(usize),
+    MemoryTotal(usize),
+    UnableToReadSize(&'a io::Error),
+    FileSize(u64),
+    /// Pages en mémoire et leur taille en octets.
+    CacheSize(usize, usize),
+    /// Pages de la base et leur taille en octets.
+    DatabasePages(usize, usize),
+    DatabaseRows(usize),
+    /// Octets de pages par ligne, et taille fixe d'une ligne.
+    BytesPerRow(f64, usize),
+    BackupProgress(usize, usize),
+    BackupWritten(usize, &'a str),
+    IntegrityOk,
+    UnreadablePage(usize),
+    UnknownNodeType(usize, u8),
+    PageReferencedTwice(usize),
+    /// Page, parent stocké dans la page et parent attendu.
+    WrongParent(usize, usize, usize),
+    TooManyCells(usize, usize),
+    UnsortedKeys(usize, usize),
+    KeyOutOfBounds(usize, u64),
+    UnreadableRow(usize, usize),
+    KeyIdMismatch(usize, u64, u64),
+    BrokenLeafChain(usize, usize, usize),
+    OrphanPage(usize),
+    WrongRowCount(usize, usize),
+}
+impl Message<'_> {
+    pub fn text(&self, lang: Lang) -> String {
+        match lang {
+            Lang::En => self.text_en(),
+            Lang::Fr => self.text_fr(),
+        }
+    }
+
+    fn text_en(&self) -> String {
+        match self {
+            Self::Executed => "Executed.".to_owned(),
+            Self::Interrupted => "Interrupted.".to_owned(),
+            Self::VirtualTableError(e) => format!("Virtual table error: {e}"),
+            Self::CsvLine(line_num) => format!("Error on line {line_num} of the CSV file:"),
+            Self::UnterminatedQuote => "Unterminated quote.".to_owned(),
+            Self::SqliteRow(rowid) => format!("Error in row {rowid} of the SQLite file:"),
+            Self::UnsupportedValue(column) => {
+                format!("Unsupported value for column '{column}', expected an integer or text.")
+            }
+            Self::NotASqliteFile => "Not a SQLite database file.".to_owned(),
+            Self::UnsupportedEncoding => "Only UTF-8 SQLite files can be read.".to_owned(),
+            Self::SqlitePageCorrupted(page_num) => {
+                format!("Page {page_num} of the SQLite file is corrupted.")
+            }
+            Self::SqliteNoTable(None) => "The SQLite file contains no table.".to_owned(),
+            Self::SqliteNoTable(Some(name)) => format!("The SQLite file has no table '{name}'."),
+            Self::InStatement(statement_num) => format!("Error in statement {statement_num}:"),
+            Self::InvalidInput => "Invalid input.".to_owned(),
+            Self::PoisonedTable => "An error occured while loading the save file.".to_owned(),
+            Self::PoisonedPager => "An error occured while loading the pager.".to_owned(),
+            Self::PageInUse => "A page is already in use.".to_owned(),
+            Self::UnrecognizedStatement(buffer) => {
+                format!("Unrecognized keyword at start of '{buffer}'.")
+            }
+            Self::StringTooLong(name, max) => format!("'{name}' is too long, max: '{max}'."),
+            Self::ControlCharacter(name, c) => format!(
+                "'{name}' cannot contain the control character '{}'.",
+                c.escape_debug()
+            ),
+            Self::UnknownPragma(name) => format!("Unknown pragma '{name}'."),
+            Self::InvalidPragmaValue(name, value) => {
+                format!("Invalid value '{value}' for pragma '{name}'.")
+            }
+            Self::UnknownColumn(column) => format!("Unknown column '{column}'."),
+            Self::WrongNumberOfValues(nb_values) => {
+                format!("Expected 3 values (id, username, email), found {nb_values}.")
+            }
+            Self::InvalidId(value) => format!("Invalid id '{value}'."),
+            Self::UnsupportedFilter(filter) => {
+                format!("Filter '{filter}' is not supported here.")
+            }
+            Self::UnknownCollation(name) => {
+                format!("Unknown collation '{name}', expected binary, nocase or unicode.")
+            }
+            Self::UnknownFunction(name) => {
+                format!("Unknown function '{name}', expected length, lower or upper.")
+            }
+            Self::WrongNumberOfArguments(name, nb_arguments) => {
+                format!("Function '{name}' expects {nb_arguments} argument(s).")
+            }
+            Self::UnknownTable(name) => {
+                format!("Unknown table '{name}', expected users, my_db_master or a virtual table.")
+            }
+            Self::TableExists(name) => format!("Table '{name}' already exists."),
+            Self::UnknownModule(name) => {
+                format!("Unknown module '{name}', expected csv or sqlite.")
+            }
+            Self::CsvArguments => "The csv module expects a file path: csv('file.csv').".to_owned(),
+            Self::SqliteArguments => "The sqlite module expects a file path and an optional table name: sqlite('file.db', 'table').".to_owned(),
+            Self::UnterminatedString(column) => {
+                format!("Unterminated string starting at column {column}.")
+            }
+            Self::NumberTooLarge(column) => format!("Number too large at column {column}."),
+            Self::UnexpectedToken(expected, found) => {
+                format!("Expected {expected}, found '{found}'.")
+            }
+            Self::UnexpectedEnd(expected) => {
+                format!("Expected {expected}, found the end of the statement.")
+            }
+            Self::TooDeep(column) => format!("Statement nested too deeply at column {column}."),
+            Self::UnableToOpen(file_path, e) => {
+                format!("Unable to open the database file '{file_path}': {e}.")
+            }
+            Self::UnableToOpenCsv(file_path, e) => {
+                format!("Unable to read the CSV file '{file_path}': {e}.")
+            }
+            Self::UnableToOpenSqlite(file_path, e) => {
+                format!("Unable to read the SQLite file '{file_path}': {e}.")
+            }
+            Self::UnableToRead(file_path) => {
+                format!("Unable to read the database file '{file_path}'.")
+            }
+            Self::UnableToReadScript(script_path, e) => {
+                format!("Unable to read the script '{script_path}': {e}.")
+            }
+            Self::UnrecognizedCommand(buffer) => format!("Unrecognized command: '{buffer}'."),
+            Self::NoBackupFile => "No backup file provided.".to_owned(),
+            Self::NoRecoverFile => "No file to recover provided.".to_owned(),
+            Self::NoPageNum => "No page number provided.".to_owned(),
+            Self::SnapshotUsage => {
+                "Usage: '.snapshot create <name>', '.snapshot open <name>' or '.snapshot close'."
+                    .to_owned()
+            }
+            Self::NoSnapshotName => "No snapshot name provided.".to_owned(),
+            Self::InvalidSnapshotName(name) => {
+                format!("Invalid snapshot name '{name}', use only letters, digits, '-' and '_'.")
+            }
+            Self::NoSnapshotOpen => "No snapshot is open.".to_owned(),
+            Self::SnapshotCreated(file_path) => format!("Snapshot written to '{file_path}'."),
+            Self::SnapshotOpened(name) => format!(
+                "Snapshot '{name}' opened read-only, '.snapshot close' returns to the database."
+            ),
+            Self::SnapshotClosed => "Back to the database.".to_owned(),
+            Self::NothingToUndo => "Nothing to undo.".to_owned(),
+            Self::UndoneInsert(id) => format!("Insert of the row {id} undone."),
+            Self::UndoneDelete(nb_rows) => format!("Delete undone, {nb_rows} rows restored."),
+            Self::UndoTooLarge(nb_rows) => format!(
+                "The delete of {nb_rows} rows was too large to be kept and cannot be undone."
+            ),
+            Self::InvalidPageNum(value) => format!("Invalid page number '{value}'."),
+            Self::PageOutOfRange(page_num, nb_pages) => {
+                format!("Page {page_num} does not exist, the database has {nb_pages} pages.")
+            }
+            Self::NoSaveFile => "No file to save provided, use '.save <file>'.".to_owned(),
+            Self::UnknownMode(mode) => {
+                format!("Unknown mode '{mode}', expected 'list' or 'table'.")
+            }
+            Self::InvalidWidth(width) => format!("Invalid width '{width}'."),
+            Self::UnknownLang(lang) => format!("Unknown language '{lang}', expected 'en' or 'fr'."),
+            Self::InvalidSwitch(value) => {
+                format!("Invalid value '{value}', expected 'on' or 'off'.")
+            }
+            Self::InvalidHistoryCount(value) => format!("Invalid number of lines '{value}'."),
+            Self::NoHistoryEntry(event) => format!("{event}: no such line in the history."),
+            Self::NoOutputFile => "No output file provided.".to_owned(),
+            Self::UnableToOpenOutput(file_path, e) => {
+                format!("Unable to open the output file '{file_path}': {e}.")
+            }
+            Self::RunTime(seconds) => format!("Run Time: {seconds:.6}s"),
+            Self::FileIsCorrupted(lost_bytes) => {
+                format!("File is corrupted, {lost_bytes} bytes can't be read.")
+            }
+            Self::NotADatabaseFile => "Not a database file.".to_owned(),
+            Self::UnsupportedVersion(version) => {
+                format!("Unsupported file format version '{version}'.")
+            }
+            Self::UnsupportedFlags(flags) => {
+                format!("Unsupported file options '{flags:#x}', is the file compressed?")
+            }
+            Self::CatalogCorrupted => "The table catalog of the file is corrupted.".to_owned(),
+            Self::UnknownColumnType(tag) => format!("Unknown column type '{tag}' in the catalog."),
+            Self::CatalogTooLarge => "The table catalog does not fit in a page.".to_owned(),
+            Self::SchemaMismatch => {
+                "The tables of the file are not the ones this version can read.".to_owned()
+            }
+            Self::WriteFailed(offset, e) => format!(
+                "Unable to write the file at offset {offset}: {e}. The previous version is kept, the save can be retried."
+            ),
+            Self::TableFull => "Error: Table full.".to_owned(),
+            Self::ReadOnly => "Error: The database is read-only.".to_owned(),
+            Self::DuplicateKey => "Error: Duplicate key.".to_owned(),
+            Self::MaxPageReached => "Max page reached.".to_owned(),
+            Self::PageCorrupted(page_num) => format!("Page {page_num} is corrupted."),
+            Self::DeserializeRow => "Error while deserializing row.".to_owned(),
+            Self::CorruptedRowsSkipped(nb_rows) => {
+                format!("{nb_rows} corrupted rows were skipped.")
+            }
+            Self::EndOfTable => "No row left under the cursor.".to_owned(),
+            Self::KeyChanged(key) => format!("The row under the cursor must keep the id {key}."),
+            Self::Recovered(rows_recovered, pages_read, rows_lost, bytes_lost) => format!(
+                "Recovered {rows_recovered} rows from {pages_read} pages, {rows_lost} rows and {bytes_lost} bytes lost."
+            ),
+            Self::SaveRecovered => "Use '.save <file>' to keep the recovered data.".to_owned(),
+            Self::Imported(rows_imported, rows_skipped) => format!(
+                "Imported {rows_imported} rows, {rows_skipped} rows skipped because their id already exists."
+            ),
+            Self::ImportSqliteUsage => "Usage: '.import-sqlite <file.db> <table>'.".to_owned(),
+            Self::ImportJsonUsage => "Usage: '.import-json <file.jsonl>'.".to_owned(),
+            Self::JsonLine(line_num) => format!("Error on line {line_num} of the JSON file:"),
+            Self::JsonSyntax(column) => format!("Invalid JSON at column {column}."),
+            Self::JsonNotAnObject => "Each line must be a JSON object.".to_owned(),
+            Self::JsonNestedValue(key) => {
+                format!("The value of '{key}' is an object or an array, which is not supported.")
+            }
+            Self::JsonUnknownKey(key) => {
+                format!("Unknown key '{key}', expected id, username or email.")
+            }
+            Self::JsonDuplicateKey(key) => format!("Key '{key}' appears more than once."),
+            Self::JsonMissingKey(key) => format!("Missing key '{key}'."),
+            Self::JsonWrongType(column) => format!(
+                "Wrong type for '{column}', expected a number for id and a string otherwise."
+            ),
+            Self::UnableToOpenJson(file_path, e) => {
+                format!("Unable to read the JSON file '{file_path}': {e}.")
+            }
+            Self::ResidentPages(nb_pages, bytes, cache_size) => {
+                format!("Pages in memory: {nb_pages}/{cache_size} ({bytes} bytes).")
+            }
+            Self::CacheMetadata(bytes) => format!("Cache metadata: {bytes} bytes."),
+            Self::MemoryTotal(bytes) => format!("Total: {bytes} bytes."),
+            Self::UnableToReadSize(e) => format!("Unable to read the database size: {e}."),
+            Self::FileSize(bytes) => format!("File: {bytes} bytes."),
+            Self::CacheSize(nb_pages, bytes) => {
+                let pages = if *nb_pages == 1 { "page" } else { "pages" };
+                format!("Cache: {nb_pages} {pages} ({bytes} bytes).")
+            }
+            Self::DatabasePages(nb_pages, bytes) => {
+                format!("Pages: {nb_pages} ({bytes} bytes).")
+            }
+            Self::DatabaseRows(nb_rows) => format!("Rows: {nb_rows}."),
+            Self::BytesPerRow(average, row_size) => {
+                format!("Average: {average:.1} bytes per row, for rows of {row_size} bytes.")
+            }
+            Self::BackupProgress(done, total) => format!("Backup: {done}/{total} pages."),
+            Self::BackupWritten(pages_written, file_path) => {
+                format!("Backup of {pages_written} pages written to '{file_path}'.")
+            }
+            Self::IntegrityOk => "ok".to_owned(),
+            Self::UnreadablePage(page_num) => format!("Page {page_num} cannot be read."),
+            Self::UnknownNodeType(page_num, node_type) => {
+                format!("Page {page_num}: unknown node type {node_type}.")
+            }
+            Self::PageReferencedTwice(page_num) => {
+                format!("Page {page_num} is referenced more than once in the tree.")
+            }
+            Self::WrongParent(page_num, parent, expected) => {
+                format!("Page {page_num}: parent is {parent}, expected {expected}.")
+            }
+            Self::TooManyCells(page_num, nb_cells) => format!(
+                "Page {page_num}: header counts {nb_cells} cells, more than the page can hold."
+            ),
+            Self::UnsortedKeys(page_num, cell_num) => {
+                format!("Page {page_num}: key {cell_num} is not greater than the previous one.")
+            }
+            Self::KeyOutOfBounds(page_num, key) => {
+                format!("Page {page_num}: key {key} is outside the range of its parents.")
+            }
+            Self::UnreadableRow(page_num, cell_num) => {
+                format!("Page {page_num}: row {cell_num} cannot be deserialized.")
+            }
+            Self::KeyIdMismatch(page_num, key, id) => {
+                format!("Page {page_num}: key {key} holds the row with id {id}.")
+            }
+            Self::BrokenLeafChain(page_num, next_leaf, expected) => {
+                format!("Page {page_num}: next leaf is {next_leaf}, expected {expected}.")
+            }
+            Self::OrphanPage(page_num) => format!("Page {page_num} is not used by the tree."),
+            Self::WrongRowCount(expected, found) => {
+                format!("The file header counts {expected} rows, the tree holds {found}.")
+            }
+        }
+    }
+
+    fn text_fr(&self) -> String {
+        match self {
+            Self::Executed => "Exécuté.".to_owned(),
+            Self::Interrupted => "Interrompu.".to_owned(),
+            Self::VirtualTableError(e) => format!("Erreur de la table virtuelle : {e}"),
+            Self::CsvLine(line_num) => {
+                format!("Erreur à la ligne {line_num} du fichier CSV :")
+            }
+            Self::UnterminatedQuote => "Guillemet non refermé.".to_owned(),
+            Self::SqliteRow(rowid) => {
+                format!("Erreur dans la ligne {rowid} du fichier SQLite :")
+            }
+            Self::UnsupportedValue(column) => {
+                format!("Valeur non prise en charge pour la colonne '{column}', entier ou texte attendu.")
+            }
+            Self::NotASqliteFile => "Ce n'est pas un fichier de base SQLite.".to_owned(),
+            Self::UnsupportedEncoding => {
+                "Seuls les fichiers SQLite en UTF-8 peuvent être lus.".to_owned()
+            }
+            Self::SqlitePageCorrupted(page_num) => {
+                format!("La page {page_num} du fichier SQLite est corrompue.")
+            }
+            Self::SqliteNoTable(None) => "Le fichier SQLite ne contient aucune table.".to_owned(),
+            Self::SqliteNoTable(Some(name)) => {
+                format!("Le fichier SQLite n'a pas de table '{name}'.")
+            }
+            Self::InStatement(statement_num) => {
+                format!("Erreur dans l'instruction {statement_num} :")
+            }
+            Self::InvalidInput => "Saisie invalide.".to_owned(),
+            Self::PoisonedTable => {
+                "Une erreur est survenue au chargement du fichier de sauvegarde.".to_owned()
+            }
+            Self::PoisonedPager => "Une erreur est survenue au chargement du pager.".to_owned(),
+            Self::PageInUse => "Une page est déjà utilisée.".to_owned(),
+            Self::UnrecognizedStatement(buffer) => {
+                format!("Mot-clé non reconnu au début de '{buffer}'.")
+            }
+            Self::StringTooLong(name, max) => format!("'{name}' est trop long, max : '{max}'."),
+            Self::ControlCharacter(name, c) => format!(
+                "'{name}' ne peut pas contenir le caractère de contrôle '{}'.",
+                c.escape_debug()
+            ),
+            Self::UnknownPragma(name) => format!("Pragma inconnu '{name}'."),
+            Self::InvalidPragmaValue(name, value) => {
+                format!("Valeur '{value}' invalide pour le pragma '{name}'.")
+            }
+            Self::UnknownColumn(column) => format!("Colonne inconnue '{column}'."),
+            Self::WrongNumberOfValues(nb_values) => {
+                format!("3 valeurs attendues (id, username, email), {nb_values} trouvée(s).")
+            }
+            Self::InvalidId(value) => format!("Id invalide '{value}'."),
+            Self::UnsupportedFilter(filter) => {
+                format!("Le filtre '{filter}' n'est pas géré ici.")
+            }
+            Self::UnknownCollation(name) => {
+                format!("Collation inconnue '{name}', binary, nocase ou unicode attendu.")
+            }
+            Self::UnknownFunction(name) => {
+                format!("Fonction inconnue '{name}', length, lower ou upper attendu.")
+            }
+            Self::WrongNumberOfArguments(name, nb_arguments) => {
+                format!("La fonction '{name}' attend {nb_arguments} argument(s).")
+            }
+            Self::UnknownTable(name) => {
+                format!("Table inconnue '{name}', users, my_db_master ou une table virtuelle attendue.")
+            }
+            Self::TableExists(name) => format!("La table '{name}' existe déjà."),
+            Self::UnknownModule(name) => {
+                format!("Module inconnu '{name}', csv ou sqlite attendu.")
+            }
+            Self::CsvArguments => {
+                "Le module csv attend le chemin d'un fichier : csv('fichier.csv').".to_owned()
+            }
+            Self::SqliteArguments => "Le module sqlite attend le chemin d'un fichier, puis éventuellement le nom d'une table : sqlite('fichier.db', 'table').".to_owned(),
+            Self::UnterminatedString(column) => {
+                format!("Chaîne non terminée commençant à la colonne {column}.")
+            }
+            Self::NumberTooLarge(column) => format!("Nombre trop grand à la colonne {column}."),
+            Self::UnexpectedToken(expected, found) => {
+                format!("{expected} attendu, '{found}' trouvé.")
+            }
+            Self::UnexpectedEnd(expected) => {
+                format!("{expected} attendu, fin de l'instruction trouvée.")
+            }
+            Self::TooDeep(column) => {
+                format!("Instruction trop imbriquée à la colonne {column}.")
+            }
+            Self::UnableToOpen(file_path, e) => {
+                format!("Impossible d'ouvrir le fichier de base '{file_path}' : {e}.")
+            }
+            Self::UnableToOpenCsv(file_path, e) => {
+                format!("Impossible de lire le fichier CSV '{file_path}' : {e}.")
+            }
+            Self::UnableToOpenSqlite(file_path, e) => {
+                format!("Impossible de lire le fichier SQLite '{file_path}' : {e}.")
+            }
+            Self::UnableToRead(file_path) => {
+                format!("Impossible de lire le fichier de base '{file_path}'.")
+            }
+            Self::UnableToReadScript(script_path, e) => {
+                format!("Impossible de lire le script '{script_path}' : {e}.")
+            }
+            Self::UnrecognizedCommand(buffer) => format!("Commande non reconnue : '{buffer}'."),
+            Self::NoBackupFile => "Aucun fichier de copie fourni.".to_owned(),
+            Self::NoRecoverFile => "Aucun fichier à récupérer fourni.".to_owned(),
+            Self::NoPageNum => "Aucun numéro de page fourni.".to_owned(),
+            Self::SnapshotUsage => {
+                "Utilisation : '.snapshot create <nom>', '.snapshot open <nom>' ou '.snapshot close'."
+                    .to_owned()
+            }
+            Self::NoSnapshotName => "Aucun nom d'instantané fourni.".to_owned(),
+            Self::InvalidSnapshotName(name) => format!(
+                "Nom d'instantané '{name}' invalide, utilisez seulement des lettres, des chiffres, '-' et '_'."
+            ),
+            Self::NoSnapshotOpen => "Aucun instantané n'est ouvert.".to_owned(),
+            Self::SnapshotCreated(file_path) => format!("Instantané écrit dans '{file_path}'."),
+            Self::SnapshotOpened(name) => format!(
+                "Instantané '{name}' ouvert en lecture seule, '.snapshot close' revient à la base."
+            ),
+            Self::SnapshotClosed => "Retour à la base.".to_owned(),
+            Self::NothingToUndo => "Rien à annuler.".to_owned(),
+            Self::UndoneInsert(id) => format!("Insertion de la ligne {id} annulée."),
+            Self::UndoneDelete(nb_rows) => {
+                format!("Suppression annulée, {nb_rows} lignes restaurées.")
+            }
+            Self::UndoTooLarge(nb_rows) => format!(
+                "La suppression de {nb_rows} lignes, trop grande pour être gardée, ne peut pas être annulée."
+            ),
+            Self::InvalidPageNum(value) => format!("Numéro de page '{value}' invalide."),
+            Self::PageOutOfRange(page_num, nb_pages) => {
+                format!("La page {page_num} n'existe pas, la base a {nb_pages} pages.")
+            }
+            Self::NoSaveFile => {
+                "Aucun fichier de sauvegarde fourni, utilisez '.save <fichier>'.".to_owned()
+            }
+            Self::UnknownMode(mode) => {
+                format!("Mode '{mode}' inconnu, 'list' ou 'table' attendu.")
+            }
+            Self::InvalidWidth(width) => format!("Largeur '{width}' invalide."),
+            Self::UnknownLang(lang) => format!("Langue '{lang}' inconnue, 'en' ou 'fr' attendu."),
+            Self::InvalidSwitch(value) => {
+                format!("Valeur '{value}' invalide, 'on' ou 'off' attendu.")
+            }
+            Self::InvalidHistoryCount(value) => format!("Nombre de lignes '{value}' invalide."),
+            Self::NoHistoryEntry(event) => {
+                format!("{event} : aucune ligne correspondante dans l'historique.")
+            }
+            Self::NoOutputFile => "Aucun fichier de sortie fourni.".to_owned(),
+            Self::UnableToOpenOutput(file_path, e) => {
+                format!("Impossible d'ouvrir le fichier de sortie '{file_path}' : {e}.")
+            }
+            Self::RunTime(seconds) => format!("Durée : {seconds:.6}s"),
+            Self::FileIsCorrupted(lost_bytes) => {
+                format!("Le fichier est corrompu, {lost_bytes} octets sont illisibles.")
+            }
+            Self::NotADatabaseFile => "Ce n'est pas un fichier de base.".to_owned(),
+            Self::UnsupportedVersion(version) => {
+                format!("Version '{version}' du format de fichier non prise en charge.")
+            }
+            Self::UnsupportedFlags(flags) => format!(
+                "Options de fichier '{flags:#x}' non prises en charge, le fichier est-il compressé ?"
+            ),
+            Self::CatalogCorrupted => "Le catalogue des tables du fichier est corrompu.".to_owned(),
+            Self::UnknownColumnType(tag) => {
+                format!("Type de colonne '{tag}' inconnu dans le catalogue.")
+            }
+            Self::CatalogTooLarge => {
+                "Le catalogue des tables ne tient pas dans une page.".to_owned()
+            }
+            Self::SchemaMismatch => {
+                "Les tables du fichier ne sont pas celles que cette version sait lire.".to_owned()
+            }
+            Self::WriteFailed(offset, e) => format!(
+                "Impossible d'écrire le fichier à la position {offset} : {e}. La version précédente est gardée, la sauvegarde peut être relancée."
+            ),
+            Self::TableFull => "Erreur : table pleine.".to_owned(),
+            Self::ReadOnly => "Erreur : la base est en lecture seule.".to_owned(),
+            Self::DuplicateKey => "Erreur : clé en double.".to_owned(),
+            Self::MaxPageReached => "Nombre maximal de pages atteint.".to_owned(),
+            Self::PageCorrupted(page_num) => format!("La page {page_num} est corrompue."),
+            Self::DeserializeRow => "Erreur à la lecture d'une ligne.".to_owned(),
+            Self::CorruptedRowsSkipped(nb_rows) => {
+                format!("{nb_rows} lignes corrompues ont été passées.")
+            }
+            Self::EndOfTable => "Plus aucune ligne sous le curseur.".to_owned(),
+            Self::KeyChanged(key) => {
+                format!("La ligne sous le curseur doit garder l'id {key}.")
+            }
+            Self::Recovered(rows_recovered, pages_read, rows_lost, bytes_lost) => format!(
+                "{rows_recovered} lignes récupérées sur {pages_read} pages, {rows_lost} lignes et {bytes_lost} octets perdus."
+            ),
+            Self::SaveRecovered => {
+                "Utilisez '.save <fichier>' pour garder les données récupérées.".to_owned()
+            }
+            Self::Imported(rows_imported, rows_skipped) => format!(
+                "{rows_imported} lignes importées, {rows_skipped} lignes ignorées car leur id existe déjà."
+            ),
+            Self::ImportSqliteUsage => "Usage : '.import-sqlite <fichier.db> <table>'.".to_owned(),
+            Self::ImportJsonUsage => "Usage : '.import-json <fichier.jsonl>'.".to_owned(),
+            Self::JsonLine(line_num) => {
+                format!("Erreur à la ligne {line_num} du fichier JSON :")
+            }
+            Self::JsonSyntax(column) => format!("JSON invalide à la colonne {column}."),
+            Self::JsonNotAnObject => "Chaque ligne doit être un objet JSON.".to_owned(),
+            Self::JsonNestedValue(key) => format!(
+                "La valeur de '{key}' est un objet ou un tableau, ce qui n'est pas géré."
+            ),
+            Self::JsonUnknownKey(key) => {
+                format!("Clé inconnue '{key}', attendu id, username ou email.")
+            }
+            Self::JsonDuplicateKey(key) => format!("La clé '{key}' apparaît plusieurs fois."),
+            Self::JsonMissingKey(key) => format!("Clé manquante '{key}'."),
+            Self::JsonWrongType(column) => format!(
+                "Mauvais type pour '{column}', attendu un nombre pour id et une chaîne sinon."
+            ),
+            Self::UnableToOpenJson(file_path, e) => {
+                format!("Impossible de lire le fichier JSON '{file_path}' : {e}.")
+            }
+            Self::ResidentPages(nb_pages, bytes, cache_size) => {
+                format!("Pages en mémoire : {nb_pages}/{cache_size} ({bytes} octets).")
+            }
+            Self::CacheMetadata(bytes) => format!("Métadonnées du cache : {bytes} octets."),
+            Self::MemoryTotal(bytes) => format!("Total : {bytes} octets."),
+            Self::UnableToReadSize(e) => {
+                format!("Impossible de lire la taille de la base : {e}.")
+            }
+            Self::FileSize(bytes) => format!("Fichier : {bytes} octets."),
+            Self::CacheSize(nb_pages, bytes) => {
+                let pages = if *nb_pages > 1 { "pages" } else { "page" };
+                format!("Cache : {nb_pages} {pages} ({bytes} octets).")
+            }
+            Self::DatabasePages(nb_pages, bytes) => {
+                format!("Pages : {nb_pages} ({bytes} octets).")
+            }
+            Self::DatabaseRows(nb_rows) => format!("Lignes : {nb_rows}."),
+            Self::BytesPerRow(average, row_size) => {
+                format!("Moyenne : {average:.1} octets par ligne, pour des lignes de {row_size} octets.")
+            }
+            Self::BackupProgress(done, total) => format!("Copie : {done}/{total} pages."),
+            Self::BackupWritten(pages_written, file_path) => {
+                format!("Copie de {pages_written} pages écrite dans '{file_path}'.")
+            }
+            Self::IntegrityOk => "ok".to_owned(),
+            Self::UnreadablePage(page_num) => format!("La page {page_num} ne peut pas être lue."),
+            Self::UnknownNodeType(page_num, node_type) => {
+                format!("Page {page_num} : type de nœud inconnu {node_type}.")
+            }
+            Self::PageReferencedTwice(page_num) => {
+                format!("La page {page_num} est référencée plusieurs fois dans l'arbre.")
+            }
+            Self::WrongParent(page_num, parent, expected) => {
+                format!("Page {page_num} : le parent est {parent}, {expected} attendu.")
+            }
+            Self::TooManyCells(page_num, nb_cells) => format!(
+                "Page {page_num} : l'entête compte {nb_cells} cellules, plus que la page ne peut en contenir."
+            ),
+            Self::UnsortedKeys(page_num, cell_num) => {
+                format!("Page {page_num} : la clé {cell_num} n'est pas supérieure à la précédente.")
+            }
+            Self::KeyOutOfBounds(page_num, key) => {
+                format!("Page {page_num} : la clé {key} est hors des bornes de ses parents.")
+            }
+            Self::UnreadableRow(page_num, cell_num) => {
+                format!("Page {page_num} : la ligne {cell_num} ne peut pas être lue.")
+            }
+            Self::KeyIdMismatch(page_num, key, id) => {
+                format!("Page {page_num} : la clé {key} contient la ligne d'id {id}.")
+            }
+            Self::BrokenLeafChain(page_num, next_leaf, expected) => {
+                format!(
+                    "Page {page_num} : la feuille suivante est {next_leaf}, {expected} attendu."
+                )
+            }
+            Self::OrphanPage(page_num) => {
+                format!("La page {page_num} n'est pas utilisée par l'arbre.")
+            }
+            Self::WrongRowCount(expected, found) => {
+                format!(
+                    "L'entête du fichier compte {expected} lignes, l'arbre en contient {found}."
+                )
+            }
+        }
+    }
+}
+
+impl From<&CatalogError> for Message<'static> {
+    fn from(error: &CatalogError) -> Self {
+        match error {
+            CatalogError::InvalidMagic | CatalogError::Corrupted => Self::CatalogCorrupted,
+            CatalogError::UnknownColumnType(tag) => Self::UnknownColumnType(*tag),
+            CatalogError::TooLarge => Self::CatalogTooLarge,
+            CatalogError::SchemaMismatch => Self::SchemaMismatch,
+        }
+    }
+}
+impl From<&IntegrityProblem> for Message<'static> {
+    fn from(problem: &IntegrityProblem) -> Self {
+        match problem {
+            IntegrityProblem::Catalog(e) => Self::from(e),
+            IntegrityProblem::UnreadablePage(page_num) => Self::UnreadablePage(*page_num),
+            IntegrityProblem::UnknownNodeType(page_num, node_type) => {
+                Self::UnknownNodeType(*page_num, *node_type)
+            }
+            IntegrityProblem::PageReferencedTwice(page_num) => Self::PageReferencedTwice(*page_num),
+            IntegrityProblem::WrongParent(page_num, parent, expected) => {
+                Self::WrongParent(*page_num, *parent, *expected)
+            }
+            IntegrityProblem::TooManyCells(page_num, nb_cells) => {
+                Self::TooManyCells(*page_num, *nb_cells)
+            }
+            IntegrityProblem::UnsortedKeys(page_num, cell_num) => {
+                Self::UnsortedKeys(*page_num, *cell_num)
+            }
+            IntegrityProblem::KeyOutOfBounds(page_num, key) => {
+                Self::KeyOutOfBounds(*page_num, *key)
+            }
+            IntegrityProblem::UnreadableRow(page_num, cell_num) => {
+                Self::UnreadableRow(*page_num, *cell_num)
+            }
+            IntegrityProblem::KeyIdMismatch(page_num, key, id) => {
+                Self::KeyIdMismatch(*page_num, *key, *id)
+            }
+            IntegrityProblem::BrokenLeafChain(page_num, next_leaf, expected) => {
+                Self::BrokenLeafChain(*page_num, *next_leaf, *expected)
+            }
+            IntegrityProblem::OrphanPage(page_num) => Self::OrphanPage(*page_num),
+            IntegrityProblem::WrongRowCount(expected, found) => {
+                Self::WrongRowCount(*expected, *found)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod message_test {
+    use super::*;
+
+    #[test]
+    fn test_lang_from_locale() {
+        assert_eq!(Lang::try_from("fr_FR.UTF-8"), Ok(Lang::Fr));
+        assert_eq!(Lang::try_from("en"), Ok(Lang::En));
+        assert_eq!(Lang::try_from("C"), Ok(Lang::En));
+        assert_eq!(Lang::try_from("de_DE.UTF-8"), Err(()));
+        assert_eq!(Message::TableFull.text(Lang::En), "Error: Table full.");
+        assert_eq!(Message::TableFull.text(Lang::Fr), "Erreur : table pleine.");
+    }
+}
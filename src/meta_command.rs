@@ -1,12 +1,32 @@
+use std::io;
 use std::{cell::RefCell, rc::Rc};
 
 use crate::EXIT_SUCCESS;
-use crate::pager::SaveToDiskError;
-use crate::table::Table;
+use crate::btree::{Node, NodeType, NodeView};
+use crate::json::{JsonError, read_json_rows};
+use crate::message::{Lang, Message};
+use crate::open::{ReadDataError, read_data_from_file};
+use crate::output::{Output, OutputMode, Pagination};
+use crate::page_view::ReadBytes;
+use crate::pager::{GetPageError, Page, PagerOpenError, SaveToDiskError, SetOpenSaveFileError};
+use crate::provider::TableProvider;
+use crate::row::Row;
+use crate::sqlite::{SqliteError, SqliteTable};
+use crate::table::{DeleteRowError, GetRowError, Table, UndoEntry, WriteRowError};
 
 #[cfg_attr(debug_assertions, derive(Debug))]
+#[allow(clippy::enum_variant_names)]
 pub enum MetaCommandError {
     MetaCommandSave(MetaCommandSaveError),
+    MetaCommandRecover(MetaCommandRecoverError),
+    MetaCommandBackup(MetaCommandBackupError),
+    MetaCommandOutput(MetaCommandOutputError),
+    MetaCommandPageDump(MetaCommandPageDumpError),
+    MetaCommandSnapshot(MetaCommandSnapshotError),
+    MetaCommandImport(MetaCommandImportError),
+    MetaCommandUndo(MetaCommandUndoError),
+    /// Erreur en lisant la taille du fichier de sauvegarde.
+    MetaCommandSize(io::Error),
     UnknownMetaCommandError,
 }
 
@@ -16,17 +36,229 @@ pub enum MetaCommandSaveError {
     SaveToDisk(SaveToDiskError),
 }
 
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum MetaCommandBackupError {
+    NoFileProvided,
+    SaveToDisk(SaveToDiskError),
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum MetaCommandOutputError {
+    UnknownMode(String),
+    InvalidWidth(String),
+    UnknownLang(String),
+    /// La valeur n'est ni `on` ni `off`.
+    InvalidSwitch(String),
+    InvalidHistoryCount(String),
+    NoOutputFile,
+    /// Le fichier de sortie, et l'erreur rencontrée en le créant.
+    UnableToOpenOutput(String, io::Error),
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum MetaCommandPageDumpError {
+    NoPageProvided,
+    InvalidPageNum(String),
+    /// Page demandée et nombre de pages de la base.
+    PageOutOfRange(usize, usize),
+    GetPage(GetPageError),
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum MetaCommandSnapshotError {
+    /// Ni `create`, ni `open`, ni `close` : l'action reçue, s'il y en a une.
+    UnknownAction(Option<String>),
+    NoNameProvided,
+    InvalidName(String),
+    NoSnapshotOpen,
+    Create(SaveToDiskError),
+    Open(PagerOpenError),
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum MetaCommandImportError {
+    /// Il manque le fichier ou le nom de la table.
+    SqliteUsage,
+    JsonUsage,
+    Sqlite(SqliteError),
+    /// L'erreur d'ouverture du fichier, ou toutes ses lignes invalides.
+    Json(Vec<JsonError>),
+    Read(GetRowError),
+    WriteRow(WriteRowError),
+    /// Les lignes ont été importées mais la sauvegarde du mode write-through a échoué.
+    WriteThrough(SaveToDiskError),
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum MetaCommandUndoError {
+    NothingToUndo,
+    DeleteRow(DeleteRowError),
+    WriteRow(WriteRowError),
+    WriteThrough(SaveToDiskError),
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum MetaCommandRecoverError {
+    NoFileProvided,
+    ReadData(ReadDataError),
+    ClosePager(SetOpenSaveFileError),
+    WriteRow(WriteRowError),
+}
+
+/// Bilan d'une récupération : ce qui a pu être relu et ce qui a été perdu.
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct RecoveryReport {
+    pub pages_read: usize,
+    pub rows_recovered: usize,
+    pub rows_lost: usize,
+    pub bytes_lost: usize,
+}
+
+/// Bilan d'un import : les ids déjà présents dans la table sont ignorés.
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct ImportReport {
+    pub rows_imported: usize,
+    pub rows_skipped: usize,
+}
+
+/// Commandes reconnues par `do_meta_command`, pour la complétion du REPL.
+pub const META_COMMANDS: [&str; 21] = [
+    ".backup",
+    ".echo",
+    ".exit",
+    ".headers",
+    ".history",
+    ".import-json",
+    ".import-sqlite",
+    ".lang",
+    ".mode",
+    ".once",
+    ".output",
+    ".pagedump",
+    ".pager",
+    ".recover",
+    ".save",
+    ".size",
+    ".snapshot",
+    ".stats",
+    ".timer",
+    ".undo",
+    ".width",
+];
+
 pub fn is_meta_command(buffer: &str) -> bool {
     buffer.starts_with('.')
 }
 
-pub fn do_meta_command(table: Rc<RefCell<Table>>, buffer: &str) -> Result<(), MetaCommandError> {
+pub fn do_meta_command(
+    table: Rc<RefCell<Table>>,
+    output: &mut Output,
+    buffer: &str,
+) -> Result<(), MetaCommandError> {
     if buffer.to_lowercase() == ".exit" {
+        table
+            .borrow()
+            .flush_pending_write()
+            .map_err(|e| MetaCommandError::MetaCommandSave(MetaCommandSaveError::SaveToDisk(e)))?;
         std::process::exit(EXIT_SUCCESS)
     }
+    let lang = output.get_lang();
     if buffer.to_lowercase().starts_with(".save") {
         return meta_command_save(table, buffer).map_err(MetaCommandError::MetaCommandSave);
     }
+    if buffer.to_lowercase().starts_with(".backup") {
+        return meta_command_backup(table, lang, buffer)
+            .map_err(MetaCommandError::MetaCommandBackup);
+    }
+    // Avant `.pager`, dont le nom est un préfixe de `.pagedump`.
+    if buffer.to_lowercase().starts_with(".pagedump") {
+        return meta_command_pagedump(&table.borrow(), buffer)
+            .map_err(MetaCommandError::MetaCommandPageDump);
+    }
+    if buffer.to_lowercase().starts_with(".pager") {
+        meta_command_pager(output, buffer);
+        return Ok(());
+    }
+    if buffer.to_lowercase().starts_with(".mode") {
+        return meta_command_mode(output, buffer).map_err(MetaCommandError::MetaCommandOutput);
+    }
+    if buffer.to_lowercase().starts_with(".width") {
+        return meta_command_width(output, buffer).map_err(MetaCommandError::MetaCommandOutput);
+    }
+    if buffer.to_lowercase().starts_with(".headers") {
+        return meta_command_switch(buffer, output.get_headers())
+            .map(|headers| output.set_headers(headers))
+            .map_err(MetaCommandError::MetaCommandOutput);
+    }
+    if buffer.to_lowercase().starts_with(".timer") {
+        return meta_command_switch(buffer, output.get_timer())
+            .map(|timer| output.set_timer(timer))
+            .map_err(MetaCommandError::MetaCommandOutput);
+    }
+    if buffer.to_lowercase().starts_with(".echo") {
+        return meta_command_switch(buffer, output.get_echo())
+            .map(|echo| output.set_echo(echo))
+            .map_err(MetaCommandError::MetaCommandOutput);
+    }
+    if buffer.to_lowercase().starts_with(".history") {
+        return meta_command_history(output, buffer).map_err(MetaCommandError::MetaCommandOutput);
+    }
+    if buffer.to_lowercase().starts_with(".output") {
+        return meta_command_output(output, buffer).map_err(MetaCommandError::MetaCommandOutput);
+    }
+    if buffer.to_lowercase().starts_with(".once") {
+        return meta_command_once(output, buffer).map_err(MetaCommandError::MetaCommandOutput);
+    }
+    if buffer.to_lowercase().starts_with(".lang") {
+        return meta_command_lang(output, buffer).map_err(MetaCommandError::MetaCommandOutput);
+    }
+    if buffer.to_lowercase().starts_with(".snapshot") {
+        return meta_command_snapshot(&table, lang, buffer)
+            .map_err(MetaCommandError::MetaCommandSnapshot);
+    }
+    if buffer.to_lowercase().starts_with(".stats") {
+        meta_command_stats(&table.borrow(), lang);
+        return Ok(());
+    }
+    if buffer.to_lowercase().starts_with(".size") {
+        return meta_command_size(&table.borrow(), lang).map_err(MetaCommandError::MetaCommandSize);
+    }
+    if buffer.to_lowercase().starts_with(".import-") {
+        let report = if buffer.to_lowercase().starts_with(".import-json") {
+            meta_command_import_json(&table, buffer)
+        } else if buffer.to_lowercase().starts_with(".import-sqlite") {
+            meta_command_import_sqlite(&table, buffer)
+        } else {
+            return Err(MetaCommandError::UnknownMetaCommandError);
+        }
+        .map_err(MetaCommandError::MetaCommandImport)?;
+        let summary = Message::Imported(report.rows_imported, report.rows_skipped);
+        println!("{}", summary.text(lang));
+        return Ok(());
+    }
+    if buffer.to_lowercase().starts_with(".undo") {
+        let entry = meta_command_undo(&table).map_err(MetaCommandError::MetaCommandUndo)?;
+        let summary = match entry {
+            UndoEntry::Insert(row) => Message::UndoneInsert(**row.get_id()),
+            UndoEntry::DeleteAll(rows) => Message::UndoneDelete(rows.len()),
+            UndoEntry::LargeDelete(nb_rows) => Message::UndoTooLarge(nb_rows),
+        };
+        println!("{}", summary.text(lang));
+        return Ok(());
+    }
+    if buffer.to_lowercase().starts_with(".recover") {
+        let report =
+            meta_command_recover(table, buffer).map_err(MetaCommandError::MetaCommandRecover)?;
+        let summary = Message::Recovered(
+            report.rows_recovered,
+            report.pages_read,
+            report.rows_lost,
+            report.bytes_lost,
+        );
+        println!("{}", summary.text(lang));
+        println!("{}", Message::SaveRecovered.text(lang));
+        return Ok(());
+    }
 
     Err(MetaCommandError::UnknownMetaCommandError)
 }
@@ -37,9 +269,624 @@ pub fn meta_command_save(
 ) -> Result<(), MetaCommandSaveError> {
     let provided_file_path: Option<&str> = buffer.split_ascii_whitespace().nth(1);
     table
-        .borrow_mut()
-        .get_pager()
-        .borrow_mut()
+        .borrow()
         .save_to_disk(provided_file_path)
         .map_err(MetaCommandSaveError::SaveToDisk)
 }
+
+/// Copie la base dans un autre fichier en affichant l'avancement, sans changer
+/// le fichier ouvert par la session.
+pub fn meta_command_backup(
+    table: Rc<RefCell<Table>>,
+    lang: Lang,
+    buffer: &str,
+) -> Result<(), MetaCommandBackupError> {
+    let Some(file_path) = buffer.split_ascii_whitespace().nth(1) else {
+        return Err(MetaCommandBackupError::NoFileProvided);
+    };
+
+    let mut pages_written = 0;
+    table
+        .borrow()
+        .backup(file_path, &mut |done, total| {
+            pages_written = done;
+            // Un affichage tous les dix pourcents suffit pour les grosses bases.
+            if done % (total / 10).max(1) == 0 || done == total {
+                println!("{}", Message::BackupProgress(done, total).text(lang));
+            }
+        })
+        .map_err(MetaCommandBackupError::SaveToDisk)?;
+
+    println!(
+        "{}",
+        Message::BackupWritten(pages_written, file_path).text(lang)
+    );
+    Ok(())
+}
+
+/// Affiche la pagination des résultats, ou la change : `off`, `more`, ou une
+/// commande externe comme `less -S`.
+pub fn meta_command_pager(output: &mut Output, buffer: &str) {
+    let value = buffer[".pager".len()..].trim();
+    if value.is_empty() {
+        println!("{}", output.get_pagination());
+        return;
+    }
+    output.set_pagination(Pagination::from(value));
+}
+
+/// Affiche la présentation des résultats, ou la change : `list` ou `table`.
+pub fn meta_command_mode(output: &mut Output, buffer: &str) -> Result<(), MetaCommandOutputError> {
+    let Some(value) = buffer.split_ascii_whitespace().nth(1) else {
+        println!("{}", output.get_mode());
+        return Ok(());
+    };
+    let mode = OutputMode::try_from(value)
+        .map_err(|()| MetaCommandOutputError::UnknownMode(value.to_owned()))?;
+    output.set_mode(mode);
+    Ok(())
+}
+
+/// Impose la largeur des colonnes du mode `table`, dans l'ordre des colonnes.
+/// Sans argument, toutes les largeurs redeviennent automatiques.
+pub fn meta_command_width(output: &mut Output, buffer: &str) -> Result<(), MetaCommandOutputError> {
+    let widths = buffer
+        .split_ascii_whitespace()
+        .skip(1)
+        .map(|width| {
+            width
+                .parse::<usize>()
+                .map_err(|_| MetaCommandOutputError::InvalidWidth(width.to_owned()))
+        })
+        .collect::<Result<Vec<usize>, MetaCommandOutputError>>()?;
+    output.set_widths(&widths);
+    Ok(())
+}
+
+/// Lit la nouvelle valeur d'un réglage `on`/`off`. Sans argument, la valeur
+/// actuelle est affichée et renvoyée telle quelle.
+pub fn meta_command_switch(buffer: &str, current: bool) -> Result<bool, MetaCommandOutputError> {
+    let Some(value) = buffer.split_ascii_whitespace().nth(1) else {
+        println!("{}", if current { "on" } else { "off" });
+        return Ok(current);
+    };
+    match value.to_lowercase().as_str() {
+        "on" | "1" | "true" => Ok(true),
+        "off" | "0" | "false" => Ok(false),
+        _ => Err(MetaCommandOutputError::InvalidSwitch(value.to_owned())),
+    }
+}
+
+/// Affiche les `N` dernières lignes saisies pendant la session, ou toutes sans
+/// argument, avec le numéro à donner à `!N` pour en exécuter une de nouveau.
+pub fn meta_command_history(
+    output: &mut Output,
+    buffer: &str,
+) -> Result<(), MetaCommandOutputError> {
+    let history = output.get_history();
+    let nb_lines = match buffer.split_ascii_whitespace().nth(1) {
+        None => history.len(),
+        Some(value) => value
+            .parse::<usize>()
+            .map_err(|_| MetaCommandOutputError::InvalidHistoryCount(value.to_owned()))?,
+    };
+    let first = history.len().saturating_sub(nb_lines);
+    for (index, line) in history.iter().enumerate().skip(first) {
+        println!("{:>5}  {line}", index + 1);
+    }
+    Ok(())
+}
+
+/// Écrit les résultats suivants dans un fichier, dans le mode courant.
+/// `.output stdout`, ou `.output` seul, les affiche de nouveau.
+pub fn meta_command_output(
+    output: &mut Output,
+    buffer: &str,
+) -> Result<(), MetaCommandOutputError> {
+    match buffer.split_ascii_whitespace().nth(1) {
+        None | Some("stdout") => {
+            output.reset_redirection();
+            Ok(())
+        }
+        Some(file_path) => output
+            .redirect(file_path, false)
+            .map_err(|e| MetaCommandOutputError::UnableToOpenOutput(file_path.to_owned(), e)),
+    }
+}
+
+/// Écrit seulement le prochain résultat dans un fichier.
+pub fn meta_command_once(output: &mut Output, buffer: &str) -> Result<(), MetaCommandOutputError> {
+    let Some(file_path) = buffer.split_ascii_whitespace().nth(1) else {
+        return Err(MetaCommandOutputError::NoOutputFile);
+    };
+    output
+        .redirect(file_path, true)
+        .map_err(|e| MetaCommandOutputError::UnableToOpenOutput(file_path.to_owned(), e))
+}
+
+/// Affiche la langue des messages, ou la change : `en` ou `fr`.
+pub fn meta_command_lang(output: &mut Output, buffer: &str) -> Result<(), MetaCommandOutputError> {
+    let Some(value) = buffer.split_ascii_whitespace().nth(1) else {
+        println!("{}", output.get_lang());
+        return Ok(());
+    };
+    let lang = Lang::try_from(value)
+        .map_err(|()| MetaCommandOutputError::UnknownLang(value.to_owned()))?;
+    output.set_lang(lang);
+    Ok(())
+}
+
+/// `.snapshot create <nom>`, `.snapshot open <nom>` ou `.snapshot close`, voir
+/// `Table::open_snapshot`. Le nom fait partie d'un nom de fichier : seuls les
+/// lettres, chiffres, `-` et `_` sont acceptés.
+pub fn meta_command_snapshot(
+    table: &Rc<RefCell<Table>>,
+    lang: Lang,
+    buffer: &str,
+) -> Result<(), MetaCommandSnapshotError> {
+    let mut args = buffer.split_ascii_whitespace().skip(1);
+    let action = args.next().map(str::to_lowercase);
+    if action.as_deref() == Some("close") {
+        if !table.borrow_mut().close_snapshot() {
+            return Err(MetaCommandSnapshotError::NoSnapshotOpen);
+        }
+        println!("{}", Message::SnapshotClosed.text(lang));
+        return Ok(());
+    }
+    if !matches!(action.as_deref(), Some("create" | "open")) {
+        return Err(MetaCommandSnapshotError::UnknownAction(action));
+    }
+
+    let Some(name) = args.next() else {
+        return Err(MetaCommandSnapshotError::NoNameProvided);
+    };
+    let is_valid_name = name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !is_valid_name {
+        return Err(MetaCommandSnapshotError::InvalidName(name.to_owned()));
+    }
+
+    if action.as_deref() == Some("create") {
+        let file_path = table
+            .borrow()
+            .create_snapshot(name)
+            .map_err(MetaCommandSnapshotError::Create)?;
+        println!("{}", Message::SnapshotCreated(&file_path).text(lang));
+    } else {
+        table
+            .borrow_mut()
+            .open_snapshot(name)
+            .map_err(MetaCommandSnapshotError::Open)?;
+        println!("{}", Message::SnapshotOpened(name).text(lang));
+    }
+    Ok(())
+}
+
+/// Affiche la place occupée par la base, sur le disque et en mémoire. La
+/// moyenne par ligne montre le coût des lignes de taille fixe et des pages
+/// qui ne sont pas pleines.
+pub fn meta_command_size(table: &Table, lang: Lang) -> Result<(), io::Error> {
+    let pager = table.get_pager();
+    let nb_pages = pager.page_count()?;
+    let nb_cached_pages = pager.cached_pages();
+    let mut messages = vec![
+        Message::FileSize(pager.file_len()?),
+        Message::CacheSize(nb_cached_pages, nb_cached_pages * Page::SIZE),
+        Message::DatabasePages(nb_pages, nb_pages * Page::SIZE),
+        Message::DatabaseRows(table.len()),
+    ];
+    if !table.is_empty() {
+        // Le catalogue ne garde pas de lignes : seules les pages de l'arbre comptent.
+        let tree_pages = nb_pages.saturating_sub(Table::ROOT_PAGE_NUM);
+        let average = (tree_pages * Page::SIZE) as f64 / table.len() as f64;
+        messages.push(Message::BytesPerRow(average, Row::MAX_SIZE));
+    }
+    for message in messages {
+        println!("{}", message.text(lang));
+    }
+    Ok(())
+}
+
+/// Affiche la mémoire occupée par la base.
+pub fn meta_command_stats(table: &Table, lang: Lang) {
+    let usage = table.memory_usage();
+    let cache_size = table.get_pager().get_cache_size();
+    let messages = [
+        Message::ResidentPages(usage.nb_resident_pages, usage.page_bytes, cache_size),
+        Message::CacheMetadata(usage.cache_metadata_bytes),
+        Message::MemoryTotal(usage.get_total_bytes()),
+    ];
+    for message in messages {
+        println!("{}", message.text(lang));
+    }
+}
+
+/// Affiche le contenu d'une page de la base, voir `dump_page`. Seules les
+/// pages existantes peuvent être lues : en lire une autre l'ajouterait à la base.
+pub fn meta_command_pagedump(table: &Table, buffer: &str) -> Result<(), MetaCommandPageDumpError> {
+    let Some(value) = buffer.split_ascii_whitespace().nth(1) else {
+        return Err(MetaCommandPageDumpError::NoPageProvided);
+    };
+    let page_num = value
+        .parse::<usize>()
+        .map_err(|_| MetaCommandPageDumpError::InvalidPageNum(value.to_owned()))?;
+    let pager = table.get_pager();
+    let nb_pages = pager
+        .page_count()
+        .map_err(|e| MetaCommandPageDumpError::GetPage(GetPageError::IoError(e)))?;
+    if page_num >= nb_pages {
+        return Err(MetaCommandPageDumpError::PageOutOfRange(page_num, nb_pages));
+    }
+
+    let page = pager
+        .read_page(page_num)
+        .map_err(MetaCommandPageDumpError::GetPage)?;
+    for line in dump_page(page_num, &page[..]) {
+        println!("{line}");
+    }
+    Ok(())
+}
+
+/*
+ * Champs de l'entête du nœud, avec leur position dans la page, puis les
+ * octets de la page à la manière de `hexdump -C` : 16 octets par ligne, une
+ * suite de lignes identiques étant remplacée par `*`.
+ */
+pub fn dump_page(page_num: usize, page: &[u8]) -> Vec<String> {
+    let mut lines = Vec::new();
+    let field =
+        |offset: usize, name: &str, value: String| format!("{offset:04x}  {name:<11}  {value}");
+
+    if page_num == Table::CATALOG_PAGE_NUM {
+        lines.push(format!("page {page_num}: catalog"));
+    } else {
+        let root = if page_num == Table::ROOT_PAGE_NUM {
+            " (root)"
+        } else {
+            ""
+        };
+        match Node::new(page) {
+            Ok(node) => {
+                let node_type = node.get_node_type();
+                let type_name = match node_type {
+                    NodeType::Leaf => "leaf",
+                    NodeType::Internal => "internal",
+                };
+                lines.push(format!("page {page_num}: {type_name} node{root}"));
+                lines.push(field(
+                    NodeView::NODE_TYPE_OFFSET,
+                    "node type",
+                    format!("{} ({type_name})", node_type as u8),
+                ));
+                lines.push(field(
+                    NodeView::PARENT_POINTER_OFFSET,
+                    "parent",
+                    node.get_parent().to_string(),
+                ));
+                let fields = match node_type {
+                    NodeType::Leaf => [
+                        (NodeView::LEAF_NODE_NB_CELLS_OFFSET, "nb cells"),
+                        (NodeView::LEAF_NODE_NEXT_LEAF_OFFSET, "next leaf"),
+                    ],
+                    NodeType::Internal => [
+                        (NodeView::INTERNAL_NODE_NB_KEYS_OFFSET, "nb keys"),
+                        (NodeView::INTERNAL_NODE_RIGHT_CHILD_OFFSET, "right child"),
+                    ],
+                };
+                for (offset, name) in fields {
+                    lines.push(field(offset, name, page.read_u32_be(offset).to_string()));
+                }
+            }
+            Err(node_type) => {
+                lines.push(format!(
+                    "page {page_num}: unknown node type {node_type}{root}"
+                ));
+            }
+        }
+    }
+
+    const BYTES_PER_LINE: usize = 16;
+    let mut previous_chunk: Option<&[u8]> = None;
+    let mut is_repeating = false;
+    for (line_num, chunk) in page.chunks(BYTES_PER_LINE).enumerate() {
+        if previous_chunk == Some(chunk) {
+            if !is_repeating {
+                lines.push("*".to_owned());
+                is_repeating = true;
+            }
+            continue;
+        }
+        is_repeating = false;
+        previous_chunk = Some(chunk);
+
+        let hex: Vec<String> = chunk.iter().map(|byte| format!("{byte:02x}")).collect();
+        let (left, right) = hex.split_at(hex.len().min(BYTES_PER_LINE / 2));
+        let ascii: String = chunk
+            .iter()
+            .map(|byte| {
+                if byte.is_ascii_graphic() || *byte == b' ' {
+                    char::from(*byte)
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        lines.push(format!(
+            "{:04x}  {:<23}  {:<23}  |{ascii}|",
+            line_num * BYTES_PER_LINE,
+            left.join(" "),
+            right.join(" ")
+        ));
+    }
+    lines
+}
+
+/// Annule la dernière instruction du journal de la table et la renvoie. Les
+/// hooks reçoivent les lignes supprimées ou remises par l'annulation.
+///
+/// L'instruction ne quitte le journal qu'une fois annulée : après une erreur,
+/// la table est telle qu'avant et `.undo` peut être relancé. Une
+/// `UndoEntry::LargeDelete` est renvoyée sans rien changer, et reste.
+pub fn meta_command_undo(table: &Rc<RefCell<Table>>) -> Result<UndoEntry, MetaCommandUndoError> {
+    let Some(entry) = table.borrow().last_undo().cloned() else {
+        return Err(MetaCommandUndoError::NothingToUndo);
+    };
+    let hooks = table.borrow().get_hooks();
+    match &entry {
+        UndoEntry::Insert(row) => {
+            let deleted = table
+                .borrow_mut()
+                .delete_row(**row.get_id())
+                .map_err(MetaCommandUndoError::DeleteRow)?;
+            if deleted {
+                hooks.iter().for_each(|hooks| hooks.on_delete(row));
+            }
+        }
+        UndoEntry::DeleteAll(rows) => {
+            // Les lignes sont toutes remises, ou aucune.
+            for (nb_restored, row) in rows.iter().enumerate() {
+                let inserted = table.borrow_mut().insert(row.clone());
+                if let Err(e) = inserted {
+                    for row in &rows[..nb_restored] {
+                        let _ = table.borrow_mut().delete_row(**row.get_id());
+                    }
+                    return Err(MetaCommandUndoError::WriteRow(e));
+                }
+            }
+            for row in rows {
+                hooks.iter().for_each(|hooks| hooks.on_insert(row));
+            }
+        }
+        UndoEntry::LargeDelete(_) => return Ok(entry),
+    }
+    let _ = table.borrow_mut().pop_undo();
+    table
+        .borrow()
+        .on_modified()
+        .map_err(MetaCommandUndoError::WriteThrough)?;
+    Ok(entry)
+}
+
+/// Charge toutes les lignes lisibles d'un fichier endommagé dans une base en
+/// mémoire, sans fichier de sauvegarde associé.
+pub fn meta_command_recover(
+    table: Rc<RefCell<Table>>,
+    buffer: &str,
+) -> Result<RecoveryReport, MetaCommandRecoverError> {
+    let Some(file_path) = buffer.split_ascii_whitespace().nth(1) else {
+        return Err(MetaCommandRecoverError::NoFileProvided);
+    };
+
+    let pager = table.borrow().get_pager();
+    let (pages, bytes_lost) = match read_data_from_file(pager.get_vfs(), file_path) {
+        Ok(pages) => (pages, 0),
+        Err(ReadDataError::FileIsCorrupted { pages, lost_bytes }) => (pages, lost_bytes),
+        Err(e) => return Err(MetaCommandRecoverError::ReadData(e)),
+    };
+
+    let mut rows = Vec::<Row>::new();
+    let mut rows_lost = 0;
+    for page in &pages {
+        // Seules les feuilles contiennent des lignes, les autres pages sont ignorées.
+        let Ok(node @ Node::Leaf(_)) = Node::new(&page[..]) else {
+            continue;
+        };
+        for cell_num in 0..node.leaf_node_get_nb_cells() {
+            match Row::try_from(node.leaf_node_get_cell(cell_num).get_value()) {
+                Ok(row) => rows.push(row),
+                Err(_) => rows_lost += 1,
+            }
+        }
+    }
+    rows_lost += bytes_lost.div_ceil(Row::MAX_SIZE);
+
+    let mut table = table.borrow_mut();
+    table
+        .get_pager()
+        .close_save_file()
+        .map_err(MetaCommandRecoverError::ClosePager)?;
+    table.set_nb_rows(0);
+    table.clear_undo();
+
+    let mut rows_recovered = 0;
+    for row in rows {
+        match table.insert(row) {
+            Ok(()) => rows_recovered += 1,
+            // Seule la première ligne d'un id apparaissant plusieurs fois est gardée.
+            Err(WriteRowError::DuplicateKey(_)) => rows_lost += 1,
+            Err(e) => return Err(MetaCommandRecoverError::WriteRow(e)),
+        }
+    }
+
+    Ok(RecoveryReport {
+        pages_read: pages.len(),
+        rows_recovered,
+        rows_lost,
+        bytes_lost,
+    })
+}
+
+/// `.import-sqlite <fichier.db> <table>` : copie les lignes d'une table d'un
+/// fichier SQLite, lues comme par le module `sqlite` des tables virtuelles.
+pub fn meta_command_import_sqlite(
+    table: &Rc<RefCell<Table>>,
+    buffer: &str,
+) -> Result<ImportReport, MetaCommandImportError> {
+    let mut args = buffer.split_ascii_whitespace().skip(1);
+    let (Some(file_path), Some(sqlite_table)) = (args.next(), args.next()) else {
+        return Err(MetaCommandImportError::SqliteUsage);
+    };
+    let rows = SqliteTable::open(file_path, Some(sqlite_table))
+        .map_err(MetaCommandImportError::Sqlite)?
+        .scan()
+        .collect::<Result<Vec<Row>, GetRowError>>()
+        .map_err(MetaCommandImportError::Read)?;
+    import_rows(table, rows)
+}
+
+/// `.import-json <fichier.jsonl>` : un objet par ligne avec les clés `id`,
+/// `username` et `email`. Toutes les lignes invalides sont signalées.
+pub fn meta_command_import_json(
+    table: &Rc<RefCell<Table>>,
+    buffer: &str,
+) -> Result<ImportReport, MetaCommandImportError> {
+    let Some(file_path) = buffer.split_ascii_whitespace().nth(1) else {
+        return Err(MetaCommandImportError::JsonUsage);
+    };
+    let lines = read_json_rows(file_path).map_err(|e| MetaCommandImportError::Json(vec![e]))?;
+    let mut rows = Vec::with_capacity(lines.len());
+    let mut errors = Vec::new();
+    for line in lines {
+        match line {
+            Ok(row) => rows.push(row),
+            Err(e) => errors.push(e),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(MetaCommandImportError::Json(errors));
+    }
+    import_rows(table, rows)
+}
+
+/// Les lignes sont toutes lues avant d'être insérées : rien n'est importé si
+/// l'une d'elles est invalide. Une seule sauvegarde suit l'import.
+fn import_rows(
+    table: &Rc<RefCell<Table>>,
+    rows: Vec<Row>,
+) -> Result<ImportReport, MetaCommandImportError> {
+    let hooks = table.borrow().get_hooks();
+    let mut report = ImportReport {
+        rows_imported: 0,
+        rows_skipped: 0,
+    };
+    for row in rows {
+        match table.borrow_mut().insert(row.clone()) {
+            Ok(()) => report.rows_imported += 1,
+            Err(WriteRowError::DuplicateKey(_)) => {
+                report.rows_skipped += 1;
+                continue;
+            }
+            Err(e) => return Err(MetaCommandImportError::WriteRow(e)),
+        }
+        for hooks in &hooks {
+            hooks.on_insert(&row);
+        }
+    }
+    if report.rows_imported != 0 {
+        table.borrow_mut().clear_undo();
+        table
+            .borrow()
+            .on_modified()
+            .map_err(MetaCommandImportError::WriteThrough)?;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod meta_command_test {
+    use super::*;
+
+    use crate::pager::{Page, Pager};
+    use crate::statement::{execute_delete_all, execute_insert};
+    use crate::table::table_test::row;
+
+    #[test]
+    fn test_dump_page() {
+        let mut page = Page::default();
+        let mut leaf = Node::initialize_leaf(&mut page[..]);
+        leaf.set_parent(3);
+        leaf.leaf_node_set_nb_cells(2);
+        page[16..20].copy_from_slice(b"ab\x00c");
+
+        let lines = dump_page(5, &page[..]);
+        assert_eq!(
+            lines,
+            vec![
+                "page 5: leaf node",
+                "0000  node type    0 (leaf)",
+                "0001  parent       3",
+                "0005  nb cells     2",
+                "0009  next leaf    0",
+                "0000  00 00 00 00 03 00 00 00  02 00 00 00 00 00 00 00  |................|",
+                "0010  61 62 00 63 00 00 00 00  00 00 00 00 00 00 00 00  |ab.c............|",
+                "0020  00 00 00 00 00 00 00 00  00 00 00 00 00 00 00 00  |................|",
+                "*",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_import_rows_skips_existing_ids() {
+        let table = Rc::new(RefCell::new(Table::new(Rc::new(Pager::default()))));
+        table.borrow_mut().insert(row(2)).unwrap();
+
+        let report = import_rows(&table, vec![row(1), row(2), row(3)]).unwrap();
+        assert_eq!((report.rows_imported, report.rows_skipped), (2, 1));
+        assert_eq!(table.borrow().get_nb_rows(), 3);
+    }
+
+    #[test]
+    fn test_failed_undo_keeps_the_entry() {
+        let table = Rc::new(RefCell::new(Table::new(Rc::new(Pager::default()))));
+        for id in 1..=3 {
+            let _ = execute_insert(table.clone(), row(id)).unwrap();
+        }
+        let _ = execute_delete_all(table.clone()).unwrap();
+
+        // La ligne 2, remise hors du journal, bloque la restauration.
+        table.borrow_mut().insert(row(2)).unwrap();
+        assert!(matches!(
+            meta_command_undo(&table),
+            Err(MetaCommandUndoError::WriteRow(WriteRowError::DuplicateKey(
+                2
+            )))
+        ));
+        assert_eq!(table.borrow().len(), 1);
+
+        assert!(table.borrow_mut().delete_row(2).unwrap());
+        assert!(matches!(
+            meta_command_undo(&table),
+            Ok(UndoEntry::DeleteAll(rows)) if rows.len() == 3
+        ));
+        assert_eq!(table.borrow().len(), 3);
+    }
+
+    #[test]
+    fn test_large_delete_is_not_kept() {
+        let table = Rc::new(RefCell::new(Table::new(Rc::new(Pager::default()))));
+        let nb_rows = Table::UNDO_MAX_ROWS as u64 + 1;
+        for id in 1..=nb_rows {
+            let _ = execute_insert(table.clone(), row(id)).unwrap();
+        }
+        let _ = execute_delete_all(table.clone()).unwrap();
+
+        for _ in 0..2 {
+            assert!(matches!(
+                meta_command_undo(&table),
+                Ok(UndoEntry::LargeDelete(n)) if n as u64 == nb_rows
+            ));
+        }
+        assert!(table.borrow().is_empty());
+    }
+}
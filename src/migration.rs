@@ -0,0 +1,443 @@
+use std::io;
+use std::io::SeekFrom;
+use std::rc::Rc;
+
+use crate::backend::PagerBackend;
+use crate::btree::Node;
+use crate::catalog::Catalog;
+#[cfg(feature = "compression")]
+use crate::compression::{PageDirectory, decompress_page};
+use crate::header::{FileHeader, HeaderError};
+use crate::pager::{Page, Pager};
+use crate::row::Row;
+use crate::table::Table;
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum MigrationError {
+    IoError(io::Error),
+    Header(HeaderError),
+}
+
+type Migration = fn(Vec<u8>) -> Vec<u8>;
+
+/*
+ * `MIGRATIONS[n]` transforme un fichier de la version `n` en version `n + 1`.
+ *
+ * Version 0 : pas d'entête, les pages de lignes se suivent dès le début du fichier.
+ * Version 1 : entête `FileHeader` suivi des pages.
+ * Version 2 : l'entête contient le nombre de lignes de la table.
+ * Version 3 : les pages sont les nœuds d'un arbre trié par id, la racine en page 0.
+ * Version 4 : la page 0 contient le catalogue des tables, l'arbre commence en page 1.
+ */
+const MIGRATIONS: [Migration; FileHeader::CURRENT_VERSION as usize] = [
+    migrate_v0_to_v1,
+    migrate_v1_to_v2,
+    migrate_v2_to_v3,
+    migrate_v3_to_v4,
+];
+
+/// Jusqu'à la version 2, les lignes se suivent de page en page sans jamais
+/// être à cheval sur deux pages.
+const FLAT_ROWS_PER_PAGE: usize = Page::SIZE / Row::MAX_SIZE;
+
+fn migrate_v0_to_v1(pages: Vec<u8>) -> Vec<u8> {
+    let header = <[u8; FileHeader::SIZE]>::from(FileHeader::with_version(1));
+    let mut bytes = Vec::with_capacity(FileHeader::SIZE + pages.len());
+    bytes.extend_from_slice(&header);
+    bytes.extend_from_slice(&pages);
+    bytes
+}
+
+fn migrate_v1_to_v2(mut bytes: Vec<u8>) -> Vec<u8> {
+    let nb_rows = count_rows_v1(&bytes) as u64;
+    bytes[FileHeader::VERSION_RANGE].copy_from_slice(&2u32.to_be_bytes());
+    bytes[FileHeader::NB_ROWS_RANGE].copy_from_slice(&nb_rows.to_be_bytes());
+    bytes
+}
+
+/// Les fichiers en version 1 ne stockaient pas le nombre de lignes : les lignes
+/// étant contiguës, la dernière case non vide donne ce nombre.
+fn count_rows_v1(bytes: &[u8]) -> usize {
+    let pages: Vec<Page> = read_flat_pages(bytes);
+    pages
+        .iter()
+        .enumerate()
+        .flat_map(|(page_num, page)| {
+            page.chunks_exact(Row::MAX_SIZE)
+                .take(FLAT_ROWS_PER_PAGE)
+                .enumerate()
+                .map(move |(slot, row)| (page_num * FLAT_ROWS_PER_PAGE + slot, row))
+        })
+        .filter(|(_, row)| row.iter().any(|byte| *byte != 0))
+        .map(|(row_num, _)| row_num + 1)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Les lignes sont insérées une à une dans un arbre neuf, dont les pages
+/// remplacent les anciennes. Les pages ne sont plus compressées, la prochaine
+/// sauvegarde s'en charge si besoin.
+fn migrate_v2_to_v3(bytes: Vec<u8>) -> Vec<u8> {
+    let nb_rows = read_header(&bytes).map_or(0, |header| header.get_nb_rows() as usize);
+    let pages: Vec<Page> = read_flat_pages(&bytes);
+
+    let mut table = Table::new(Rc::new(Pager::default()));
+    let rows = pages
+        .iter()
+        .flat_map(|page| page.chunks_exact(Row::MAX_SIZE).take(FLAT_ROWS_PER_PAGE))
+        .take(nb_rows);
+    for row_bytes in rows {
+        // Une ligne illisible ou refusée par l'arbre est perdue, comme avec `.recover`.
+        if let Ok(row) = Row::try_from(row_bytes) {
+            let _ = table.insert(row);
+        }
+    }
+
+    // L'arbre est construit au format courant, sans le catalogue qui n'existait pas encore.
+    let pager = table.get_pager();
+    let nb_pages = pager.page_count().unwrap_or(0);
+    let mut pages: Vec<Page> = (Table::ROOT_PAGE_NUM..nb_pages)
+        // Le pager est en mémoire : ses pages ne peuvent ni manquer ni être en cours d'utilisation.
+        .filter_map(|page_num| pager.read_page(page_num).ok())
+        .map(|page| {
+            let mut copy = Page::default();
+            copy.copy_from_slice(&page[..]);
+            copy
+        })
+        .collect();
+    shift_page_nums(&mut pages, 0, |page_num| page_num - Table::ROOT_PAGE_NUM);
+
+    let header = FileHeader::with_version(3).with_nb_rows(table.get_nb_rows() as u64);
+    let mut bytes = Vec::from(<[u8; FileHeader::SIZE]>::from(header));
+    for page in &pages {
+        bytes.extend_from_slice(&page[..]);
+    }
+    bytes
+}
+
+/// Le catalogue prend la page 0 : toutes les pages sont décalées d'un rang.
+/// Les pages ne sont plus compressées, la prochaine sauvegarde s'en charge si besoin.
+fn migrate_v3_to_v4(bytes: Vec<u8>) -> Vec<u8> {
+    let nb_rows = read_header(&bytes).map_or(0, |header| header.get_nb_rows());
+    let mut pages: Vec<Page> = read_flat_pages(&bytes);
+    shift_page_nums(&mut pages, 0, |page_num| page_num + 1);
+
+    // Le catalogue compilé tient toujours dans une page.
+    let catalog = Page::try_from(&Catalog::compiled()).unwrap_or_default();
+
+    let header = FileHeader::with_version(4).with_nb_rows(nb_rows);
+    let mut bytes = Vec::from(<[u8; FileHeader::SIZE]>::from(header));
+    bytes.extend_from_slice(&catalog[..]);
+    for page in &pages {
+        bytes.extend_from_slice(&page[..]);
+    }
+    bytes
+}
+
+/// Renumérote les pages référencées par les nœuds : parents, enfants et
+/// feuilles suivantes. La racine, en `root_index`, n'a pas de parent et le 0
+/// qui termine la liste des feuilles n'est pas une page.
+fn shift_page_nums(pages: &mut [Page], root_index: usize, shift: impl Fn(usize) -> usize) {
+    for (index, page) in pages.iter_mut().enumerate() {
+        let Ok(mut node) = Node::new(&mut page[..]) else {
+            continue;
+        };
+        if index != root_index {
+            node.set_parent(shift(node.get_parent()));
+        }
+        match node {
+            Node::Leaf(_) => {
+                let next_leaf = node.leaf_node_get_next_leaf();
+                if next_leaf != 0 {
+                    node.leaf_node_set_next_leaf(shift(next_leaf));
+                }
+            }
+            Node::Internal(_) => {
+                for child_num in 0..node.internal_node_get_nb_keys() {
+                    let child = shift(node.internal_node_get_child(child_num));
+                    let key = node.internal_node_get_key(child_num);
+                    node.internal_node_set_cell(child_num, child, key);
+                }
+                let right_child = node.internal_node_get_right_child();
+                node.internal_node_set_right_child(shift(right_child));
+            }
+        }
+    }
+}
+
+fn read_flat_pages(bytes: &[u8]) -> Vec<Page> {
+    let body = &bytes[FileHeader::SIZE..];
+
+    // Sans la fonctionnalité, un fichier compressé est refusé avant la migration.
+    #[cfg(feature = "compression")]
+    if read_header(bytes).is_ok_and(|header| header.is_compressed()) {
+        let Ok(directory) = PageDirectory::try_from(body) else {
+            return Vec::new();
+        };
+        return directory
+            .iter()
+            .map(|location| {
+                let start = location.offset as usize;
+                let end = start.saturating_add(location.len as usize);
+                bytes
+                    .get(start..end)
+                    .and_then(|compressed| decompress_page(compressed).ok())
+                    .unwrap_or_default()
+            })
+            .collect();
+    }
+
+    body.chunks(Page::SIZE)
+        .map(|chunk| {
+            let mut page = Page::default();
+            page[..chunk.len()].copy_from_slice(chunk);
+            page
+        })
+        .collect()
+}
+
+/// Renvoie la version du format d'un fichier de sauvegarde non vide.
+pub fn detect_version(bytes: &[u8]) -> Result<u32, MigrationError> {
+    let Some(header_bytes) = bytes.first_chunk::<{ FileHeader::SIZE }>() else {
+        return headerless_version(bytes);
+    };
+
+    match FileHeader::try_from(header_bytes) {
+        Ok(header) => Ok(header.get_version()),
+        Err(HeaderError::InvalidMagic) => headerless_version(bytes),
+        Err(e) => Err(MigrationError::Header(e)),
+    }
+}
+
+/// Un fichier sans entête n'est pris pour l'ancien format que s'il en a la
+/// forme : des pages entières de lignes lisibles ou vides, sans rien après la
+/// dernière case de chaque page. Tout autre fichier est refusé, pas réécrit.
+fn headerless_version(bytes: &[u8]) -> Result<u32, MigrationError> {
+    let is_flat_rows = bytes.len().is_multiple_of(Page::SIZE)
+        && bytes.chunks_exact(Page::SIZE).all(|page| {
+            let (rows, padding) = page.split_at(FLAT_ROWS_PER_PAGE * Row::MAX_SIZE);
+            padding.iter().all(|byte| *byte == 0)
+                && rows
+                    .chunks_exact(Row::MAX_SIZE)
+                    .all(|row| row.iter().all(|byte| *byte == 0) || Row::try_from(row).is_ok())
+        });
+    if bytes.is_empty() || !is_flat_rows {
+        return Err(MigrationError::Header(HeaderError::InvalidMagic));
+    }
+    Ok(0)
+}
+
+/// Met à niveau un contenu de fichier vers la version courante du format.
+pub fn migrate_bytes(mut bytes: Vec<u8>) -> Result<Vec<u8>, MigrationError> {
+    let version = detect_version(&bytes)?;
+    for migration in &MIGRATIONS[version as usize..] {
+        bytes = migration(bytes);
+    }
+    Ok(bytes)
+}
+
+/// Lit le fichier de sauvegarde et renvoie son contenu au format courant, ou
+/// `None` s'il est vide ou déjà à jour. Le fichier n'est pas modifié.
+pub fn read_outdated(file: &mut dyn PagerBackend) -> Result<Option<Vec<u8>>, MigrationError> {
+    let mut bytes = Vec::new();
+    let _ = file
+        .seek(SeekFrom::Start(0))
+        .map_err(MigrationError::IoError)?;
+    let _ = file
+        .read_to_end(&mut bytes)
+        .map_err(MigrationError::IoError)?;
+
+    if bytes.is_empty() || detect_version(&bytes)? == FileHeader::CURRENT_VERSION {
+        return Ok(None);
+    }
+    migrate_bytes(bytes).map(Some)
+}
+
+/// Met à niveau le fichier de sauvegarde sur place si son format est ancien.
+/// Un fichier sur disque est plutôt remplacé par un renommage, voir `Pager`.
+///
+/// Renvoie l'entête du fichier une fois à jour, `None` si le fichier est vide.
+pub fn upgrade_file(file: &mut dyn PagerBackend) -> Result<Option<FileHeader>, MigrationError> {
+    if let Some(bytes) = read_outdated(file)? {
+        let () = file.set_len(0).map_err(MigrationError::IoError)?;
+        let _ = file
+            .seek(SeekFrom::Start(0))
+            .map_err(MigrationError::IoError)?;
+        let () = file.write_all(&bytes).map_err(MigrationError::IoError)?;
+    }
+
+    if file.get_len().map_err(MigrationError::IoError)? == 0 {
+        return Ok(None);
+    }
+    let mut header_bytes = [0; FileHeader::SIZE];
+    let _ = file
+        .seek(SeekFrom::Start(0))
+        .map_err(MigrationError::IoError)?;
+    file.read_exact(&mut header_bytes)
+        .map_err(MigrationError::IoError)?;
+    read_header(&header_bytes).map(Some)
+}
+
+/// Lit l'entête d'un contenu de fichier déjà au format courant.
+pub fn read_header(bytes: &[u8]) -> Result<FileHeader, MigrationError> {
+    let Some(header_bytes) = bytes.first_chunk::<{ FileHeader::SIZE }>() else {
+        return Err(MigrationError::Header(HeaderError::InvalidMagic));
+    };
+    FileHeader::try_from(header_bytes).map_err(MigrationError::Header)
+}
+
+#[cfg(test)]
+mod migration_test {
+    use super::*;
+
+    use std::cell::RefCell;
+
+    use crate::cursor::Cursor;
+    use crate::row::{Email, Id, Username};
+    use crate::table::table_test::row;
+
+    #[test]
+    fn test_migrate_headerless_file() {
+        let row = Row::new(
+            Id::new(7),
+            Username::new("user".to_owned()),
+            Email::new("user@mail.com".to_owned()),
+        );
+        let mut legacy = vec![0u8; Page::SIZE];
+        legacy[..Row::MAX_SIZE].copy_from_slice(&<[u8; Row::MAX_SIZE]>::from(row));
+        assert_eq!(detect_version(&legacy).unwrap(), 0);
+
+        let migrated = migrate_bytes(legacy.clone()).unwrap();
+        assert_eq!(
+            detect_version(&migrated).unwrap(),
+            FileHeader::CURRENT_VERSION
+        );
+        assert!(FileHeader::has_magic(&migrated));
+
+        let v1 = migrate_v0_to_v1(legacy.clone());
+        assert_eq!(&v1[FileHeader::SIZE..], &legacy[..]);
+
+        // À l'ouverture, le fichier est remplacé par sa version migrée.
+        let file_path =
+            std::env::temp_dir().join(format!("my_db_migration_test_v0_{}.db", std::process::id()));
+        let file_path = file_path.to_str().unwrap();
+        std::fs::write(file_path, &legacy).unwrap();
+        let pager = Pager::new(Some(file_path)).unwrap();
+        assert_eq!(std::fs::read(file_path).unwrap(), migrated);
+        assert!(!std::path::Path::new(&format!("{file_path}.tmp")).exists());
+        let table = Table::new(Rc::new(pager));
+        assert_eq!(table.get_nb_rows(), 1);
+        std::fs::remove_file(file_path).unwrap();
+    }
+
+    /// Un fichier qui n'est pas une base est refusé à l'ouverture, sans être réécrit.
+    #[test]
+    fn test_unknown_file_is_refused_and_kept() {
+        let text = b"Some notes that are not a database at all, but long enough for a header.\n";
+        assert!(text.len() >= FileHeader::SIZE);
+        let mut page = vec![7u8; Page::SIZE];
+        page[..text.len()].copy_from_slice(text);
+        for bytes in [&text[..], &page[..]] {
+            assert!(matches!(
+                detect_version(bytes),
+                Err(MigrationError::Header(HeaderError::InvalidMagic))
+            ));
+        }
+
+        let file_path = std::env::temp_dir().join(format!(
+            "my_db_migration_test_text_{}.txt",
+            std::process::id()
+        ));
+        let file_path = file_path.to_str().unwrap();
+        std::fs::write(file_path, text).unwrap();
+        assert!(Pager::new(Some(file_path)).is_err());
+        assert_eq!(std::fs::read(file_path).unwrap(), text);
+        std::fs::remove_file(file_path).unwrap();
+    }
+
+    #[test]
+    fn test_migrate_v1_counts_rows() {
+        let mut v1 = Vec::from(<[u8; FileHeader::SIZE]>::from(FileHeader::with_version(1)));
+        let mut page = Page::default();
+        let row_offset = (FLAT_ROWS_PER_PAGE - 1) * Row::MAX_SIZE;
+        page[row_offset] = 1;
+        v1.extend_from_slice(&page[..]);
+        v1.extend_from_slice(&[0, 0, 0, 1]);
+
+        let migrated = migrate_v1_to_v2(v1);
+        let header = read_header(&migrated).unwrap();
+        assert_eq!(header.get_version(), 2);
+        assert_eq!(header.get_nb_rows(), FLAT_ROWS_PER_PAGE as u64 + 1);
+    }
+
+    #[test]
+    fn test_migrate_v2_builds_tree() {
+        let header = FileHeader::with_version(2).with_nb_rows(2);
+        let mut v2 = Vec::from(<[u8; FileHeader::SIZE]>::from(header));
+        let mut page = Page::default();
+        for (slot, id) in [9u64, 4].into_iter().enumerate() {
+            let row = Row::new(
+                Id::new(id),
+                Username::new("user".to_owned()),
+                Email::new("user@mail.com".to_owned()),
+            );
+            let offset = slot * Row::MAX_SIZE;
+            page[offset..(offset + Row::MAX_SIZE)]
+                .copy_from_slice(&<[u8; Row::MAX_SIZE]>::from(row));
+        }
+        v2.extend_from_slice(&page[..]);
+
+        let migrated = migrate_bytes(v2).unwrap();
+        assert_eq!(read_header(&migrated).unwrap().get_nb_rows(), 2);
+        let catalog = &migrated[FileHeader::SIZE..(FileHeader::SIZE + Page::SIZE)];
+        assert_eq!(Catalog::try_from(catalog), Ok(Catalog::compiled()));
+        let root = Node::new(&migrated[(FileHeader::SIZE + Page::SIZE)..]).unwrap();
+        assert_eq!(root.leaf_node_get_nb_cells(), 2);
+        assert_eq!(root.leaf_node_get_cell(0).get_key(), 4);
+        assert_eq!(root.leaf_node_get_cell(1).get_key(), 9);
+    }
+
+    #[test]
+    fn test_migrate_v3_moves_deep_tree() {
+        let nb_rows = 3 * FLAT_ROWS_PER_PAGE;
+        let header = FileHeader::with_version(2).with_nb_rows(nb_rows as u64);
+        let mut v2 = Vec::from(<[u8; FileHeader::SIZE]>::from(header));
+        for page_ids in (0..nb_rows as u64)
+            .rev()
+            .collect::<Vec<u64>>()
+            .chunks(FLAT_ROWS_PER_PAGE)
+        {
+            let mut page = Page::default();
+            for (slot, id) in page_ids.iter().enumerate() {
+                let offset = slot * Row::MAX_SIZE;
+                page[offset..(offset + Row::MAX_SIZE)]
+                    .copy_from_slice(&<[u8; Row::MAX_SIZE]>::from(row(*id)));
+            }
+            v2.extend_from_slice(&page[..]);
+        }
+
+        let file_path =
+            std::env::temp_dir().join(format!("my_db_migration_test_v3_{}.db", std::process::id()));
+        let file_path = file_path.to_str().unwrap();
+        std::fs::write(file_path, migrate_bytes(v2).unwrap()).unwrap();
+        let pager = Pager::new(Some(file_path)).unwrap();
+        std::fs::remove_file(file_path).unwrap();
+
+        let table = Rc::new(RefCell::new(Table::new(Rc::new(pager))));
+        assert_eq!(table.borrow().check_catalog(), Ok(()));
+        let mut cursor = Cursor::at_start(table).unwrap();
+        let mut ids = Vec::new();
+        while !cursor.is_end_of_table() {
+            ids.push(**Row::try_from(&cursor.get().unwrap()[..]).unwrap().get_id());
+            cursor.advance().unwrap();
+        }
+        assert_eq!(ids, (0..nb_rows as u64).collect::<Vec<u64>>());
+    }
+
+    #[test]
+    fn test_current_file_is_untouched() {
+        let mut current = Vec::from(<[u8; FileHeader::SIZE]>::from(FileHeader::new()));
+        current.extend_from_slice(&[1, 2, 3]);
+        assert_eq!(migrate_bytes(current.clone()).unwrap(), current);
+    }
+}
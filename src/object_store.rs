@@ -0,0 +1,316 @@
+use std::io;
+use std::io::Read;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use hmac_sha256::{HMAC, Hash};
+
+use crate::backend::{MemoryVfs, OpenMode, PagerBackend, Vfs};
+
+/// Objets rangés par clé, comme dans un bucket S3.
+pub trait ObjectStore: std::fmt::Debug {
+    /// `None` si l'objet n'existe pas.
+    fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>>;
+
+    fn put(&self, key: &str, bytes: &[u8]) -> io::Result<()>;
+
+    /// Supprimer un objet absent n'est pas une erreur.
+    fn delete(&self, key: &str) -> io::Result<()>;
+}
+
+/*
+ * Bucket d'un service compatible S3 (AWS, MinIO, Garage...), adressé par
+ * chemin : `<endpoint>/<bucket>/<clé>`. Les requêtes sont signées en
+ * AWS Signature Version 4.
+ */
+#[derive(Debug)]
+pub struct S3Store {
+    /// Par exemple `https://s3.eu-west-3.amazonaws.com` ou `http://localhost:9000`.
+    endpoint: String,
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+    agent: ureq::Agent,
+}
+impl S3Store {
+    pub fn new(
+        endpoint: &str,
+        bucket: &str,
+        region: &str,
+        access_key: &str,
+        secret_key: &str,
+    ) -> Self {
+        Self {
+            endpoint: endpoint.trim_end_matches('/').to_owned(),
+            bucket: bucket.to_owned(),
+            region: region.to_owned(),
+            access_key: access_key.to_owned(),
+            secret_key: secret_key.to_owned(),
+            agent: ureq::Agent::new(),
+        }
+    }
+
+    /// Lit la configuration dans les variables d'environnement des outils
+    /// AWS : `AWS_ENDPOINT_URL`, `AWS_REGION`, `AWS_ACCESS_KEY_ID` et
+    /// `AWS_SECRET_ACCESS_KEY`. `None` s'il en manque une.
+    pub fn from_env(bucket: &str) -> Option<Self> {
+        let var = |name| std::env::var(name).ok();
+        Some(Self::new(
+            &var("AWS_ENDPOINT_URL")?,
+            bucket,
+            &var("AWS_REGION")?,
+            &var("AWS_ACCESS_KEY_ID")?,
+            &var("AWS_SECRET_ACCESS_KEY")?,
+        ))
+    }
+
+    /// `None` si le service répond 404.
+    fn request(&self, method: &str, key: &str, body: &[u8]) -> io::Result<Option<ureq::Response>> {
+        let path = format!("/{}/{}", uri_encode(&self.bucket), uri_encode(key));
+        let host = self
+            .endpoint
+            .split_once("://")
+            .map_or(self.endpoint.as_str(), |(_, host)| host);
+        let timestamp = amz_timestamp(SystemTime::now());
+        let day = &timestamp[..8];
+        let payload_hash = hex(&Hash::hash(body));
+
+        let canonical_request = format!(
+            "{method}\n{path}\n\nhost:{host}\nx-amz-content-sha256:{payload_hash}\n\
+             x-amz-date:{timestamp}\n\n{SIGNED_HEADERS}\n{payload_hash}"
+        );
+        let scope = format!("{day}/{}/s3/aws4_request", self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{timestamp}\n{scope}\n{}",
+            hex(&Hash::hash(canonical_request.as_bytes()))
+        );
+        let key = signing_key(&self.secret_key, day, &self.region, "s3");
+        let signature = hex(&HMAC::mac(string_to_sign, key));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={SIGNED_HEADERS}, \
+             Signature={signature}",
+            self.access_key
+        );
+
+        let response = self
+            .agent
+            .request(method, &format!("{}{path}", self.endpoint))
+            .set("x-amz-content-sha256", &payload_hash)
+            .set("x-amz-date", &timestamp)
+            .set("authorization", &authorization)
+            .send_bytes(body);
+        match response {
+            Ok(response) => Ok(Some(response)),
+            Err(ureq::Error::Status(404, _)) => Ok(None),
+            Err(e) => Err(io::Error::other(e.to_string())),
+        }
+    }
+}
+impl ObjectStore for S3Store {
+    fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        let Some(response) = self.request("GET", key, &[])? else {
+            return Ok(None);
+        };
+        let mut bytes = Vec::new();
+        let _ = response.into_reader().read_to_end(&mut bytes)?;
+        Ok(Some(bytes))
+    }
+
+    /// Un 404 signifie que le bucket n'existe pas.
+    fn put(&self, key: &str, bytes: &[u8]) -> io::Result<()> {
+        match self.request("PUT", key, bytes)? {
+            Some(_) => Ok(()),
+            None => Err(io::ErrorKind::NotFound.into()),
+        }
+    }
+
+    fn delete(&self, key: &str) -> io::Result<()> {
+        let _ = self.request("DELETE", key, &[])?;
+        Ok(())
+    }
+}
+
+/// Seuls ces en-têtes sont signés, `host` compris.
+const SIGNED_HEADERS: &str = "host;x-amz-content-sha256;x-amz-date";
+
+/// Clé de signature du jour `day` (`AAAAMMJJ`) pour `service` dans `region`.
+fn signing_key(secret_key: &str, day: &str, region: &str, service: &str) -> [u8; 32] {
+    let key = HMAC::mac(day, format!("AWS4{secret_key}"));
+    let key = HMAC::mac(region, key);
+    let key = HMAC::mac(service, key);
+    HMAC::mac("aws4_request", key)
+}
+
+/// Encodage des chemins attendu par la signature : tout sauf les caractères
+/// non réservés et `/` est écrit `%XX`.
+fn uri_encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(char::from(byte));
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Date UTC au format `AAAAMMJJTHHMMSSZ` des en-têtes `x-amz-date`.
+fn amz_timestamp(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+    // Conversion d'un nombre de jours en date du calendrier grégorien, d'après
+    // `civil_from_days` de Howard Hinnant.
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    format!(
+        "{year:04}{month:02}{day:02}T{:02}{:02}{:02}Z",
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60
+    )
+}
+
+/*
+ * Fichiers de sauvegarde stockés dans un `ObjectStore`, un objet par fichier.
+ * Un objet est téléchargé à sa première ouverture puis lu dans le cache en
+ * mémoire. Il n'est envoyé que lorsqu'une sauvegarde renomme son fichier
+ * temporaire sur son chemin : les fichiers temporaires restent locaux, et
+ * chaque sauvegarde remplace l'objet en entier. Rien n'empêche deux machines
+ * de sauvegarder la même base, la dernière sauvegarde l'emporte.
+ */
+#[derive(Debug)]
+pub struct ObjectStoreVfs {
+    store: Rc<dyn ObjectStore>,
+    cache: MemoryVfs,
+}
+impl ObjectStoreVfs {
+    pub fn new(store: Rc<dyn ObjectStore>) -> Self {
+        Self {
+            store,
+            cache: MemoryVfs::default(),
+        }
+    }
+
+    /// Télécharge l'objet `path` dans le cache s'il n'y est pas déjà. Renvoie
+    /// `false` s'il n'existe nulle part.
+    fn fetch(&self, path: &str) -> io::Result<bool> {
+        if self.cache.exists(path) {
+            return Ok(true);
+        }
+        match self.store.get(path)? {
+            Some(bytes) => {
+                self.cache.set_bytes(path, bytes);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+impl Vfs for ObjectStoreVfs {
+    fn open(&self, path: &str, mode: OpenMode) -> io::Result<Box<dyn PagerBackend>> {
+        if mode != OpenMode::Create {
+            let _ = self.fetch(path)?;
+        }
+        self.cache.open(path, mode)
+    }
+
+    /// Une erreur du service est traitée comme un objet absent.
+    fn exists(&self, path: &str) -> bool {
+        self.fetch(path).unwrap_or(false)
+    }
+
+    fn rename(&self, from: &str, to: &str) -> io::Result<()> {
+        let bytes = self.cache.get_bytes(from).ok_or(io::ErrorKind::NotFound)?;
+        self.store.put(to, &bytes)?;
+        self.cache.rename(from, to)
+    }
+
+    fn remove(&self, path: &str) -> io::Result<()> {
+        let _ = self.cache.remove(path);
+        self.store.delete(path)
+    }
+
+    fn sync_dir(&self, _path: &str) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod object_store_test {
+    use super::*;
+
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    use crate::pager::Pager;
+
+    #[derive(Debug, Default)]
+    struct MapStore(RefCell<HashMap<String, Vec<u8>>>);
+    impl ObjectStore for MapStore {
+        fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+            Ok(self.0.borrow().get(key).cloned())
+        }
+
+        fn put(&self, key: &str, bytes: &[u8]) -> io::Result<()> {
+            let _ = self.0.borrow_mut().insert(key.to_owned(), bytes.to_vec());
+            Ok(())
+        }
+
+        fn delete(&self, key: &str) -> io::Result<()> {
+            let _ = self.0.borrow_mut().remove(key);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_signature_helpers() {
+        // Exemple de la documentation AWS sur la clé de signature.
+        let key = signing_key(
+            "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+            "20120215",
+            "us-east-1",
+            "iam",
+        );
+        assert_eq!(
+            hex(&key),
+            "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+        );
+        assert_eq!(uri_encode("my db/a+b.db"), "my%20db/a%2Bb.db");
+        let time = UNIX_EPOCH + std::time::Duration::from_secs(1_709_251_199);
+        assert_eq!(amz_timestamp(time), "20240229T235959Z");
+    }
+
+    #[test]
+    fn test_save_uploads_only_the_database() {
+        let store = Rc::new(MapStore::default());
+        let pager = Pager::with_vfs(
+            Box::new(ObjectStoreVfs::new(store.clone())),
+            Some("demo.db"),
+        )
+        .unwrap();
+        assert!(store.0.borrow().is_empty());
+        pager.write_at(0, 10, b"abc").unwrap();
+        pager.save_to_disk(None, 0).unwrap();
+        let keys: Vec<String> = store.0.borrow().keys().cloned().collect();
+        assert_eq!(keys, vec!["demo.db".to_owned()]);
+
+        // Une autre machine lit la base depuis le service.
+        let pager = Pager::with_vfs(Box::new(ObjectStoreVfs::new(store)), Some("demo.db")).unwrap();
+        assert_eq!(pager.read_at(0, 10, 3).unwrap(), b"abc");
+    }
+}
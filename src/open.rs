@@ -0,0 +1,94 @@
+use std::io;
+use std::io::Read;
+
+use crate::backend::{OpenMode, Vfs};
+#[cfg(feature = "compression")]
+use crate::compression::{PageDirectory, decompress_page};
+use crate::header::FileHeader;
+#[cfg(feature = "compression")]
+use crate::migration::read_header;
+use crate::migration::{MigrationError, migrate_bytes};
+use crate::pager::Page;
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum ReadDataError {
+    IoError(io::Error),
+    Migration(MigrationError),
+    /// Le fichier ne contient pas un nombre entier de pages : les pages
+    /// complètes sont renvoyées, ainsi que le nombre d'octets ignorés.
+    FileIsCorrupted {
+        pages: Vec<Page>,
+        lost_bytes: usize,
+    },
+}
+
+/// Lit toutes les pages d'un fichier de sauvegarde, quelle que soit la version de son format.
+pub fn read_data_from_file(vfs: &dyn Vfs, file_path: &str) -> Result<Vec<Page>, ReadDataError> {
+    let mut bytes = Vec::new();
+    let _ = vfs
+        .open(file_path, OpenMode::ReadOnly)
+        .and_then(|mut file| file.read_to_end(&mut bytes))
+        .map_err(ReadDataError::IoError)?;
+    read_data_from_bytes(bytes)
+}
+
+/// Comme `read_data_from_file`, sur le contenu d'un fichier déjà lu.
+pub fn read_data_from_bytes(bytes: Vec<u8>) -> Result<Vec<Page>, ReadDataError> {
+    if bytes.is_empty() {
+        return Ok(Vec::new());
+    }
+    let bytes = migrate_bytes(bytes).map_err(ReadDataError::Migration)?;
+
+    #[cfg(feature = "compression")]
+    if read_header(&bytes)
+        .map_err(ReadDataError::Migration)?
+        .is_compressed()
+    {
+        return read_compressed_pages(&bytes);
+    }
+
+    let chunks = bytes[FileHeader::SIZE..].chunks_exact(Page::SIZE);
+    let lost_bytes = chunks.remainder().len();
+    let pages: Vec<Page> = chunks
+        .map(|chunk| {
+            let mut page = Page::default();
+            page.copy_from_slice(chunk);
+            page
+        })
+        .collect();
+
+    if lost_bytes != 0 {
+        return Err(ReadDataError::FileIsCorrupted { pages, lost_bytes });
+    }
+
+    Ok(pages)
+}
+
+/// Décompresse toutes les pages lisibles d'un fichier compressé.
+#[cfg(feature = "compression")]
+fn read_compressed_pages(bytes: &[u8]) -> Result<Vec<Page>, ReadDataError> {
+    let body = &bytes[FileHeader::SIZE..];
+    let Ok(directory) = PageDirectory::try_from(body) else {
+        return Err(ReadDataError::FileIsCorrupted {
+            pages: Vec::new(),
+            lost_bytes: body.len(),
+        });
+    };
+
+    let mut pages = Vec::with_capacity(directory.len());
+    let mut lost_bytes = 0;
+    for location in directory.iter() {
+        let start = location.offset as usize;
+        let end = start.saturating_add(location.len as usize);
+        match bytes.get(start..end).map(decompress_page) {
+            Some(Ok(page)) => pages.push(page),
+            _ => lost_bytes += location.len as usize,
+        }
+    }
+
+    if lost_bytes != 0 {
+        return Err(ReadDataError::FileIsCorrupted { pages, lost_bytes });
+    }
+
+    Ok(pages)
+}
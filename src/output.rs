@@ -0,0 +1,575 @@
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufWriter, IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+use crate::message::Lang;
+use crate::row::{ColumnType, Row};
+use crate::statement::{ResultColumn, ResultSet};
+use crate::table::GetRowError;
+
+/// Hauteur utilisée quand celle du terminal est inconnue.
+const DEFAULT_HEIGHT: usize = 24;
+const MORE_PROMPT: &str = "--More--";
+
+/// Largeur maximale d'une colonne calculée automatiquement.
+const MAX_AUTO_WIDTH: usize = 40;
+/// Nombre de lignes lues avant l'affichage pour calculer la largeur des colonnes.
+const WIDTH_SAMPLE_SIZE: usize = 100;
+
+/// Présentation des lignes d'un `select`.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Clone, Copy)]
+pub enum OutputMode {
+    /// `(id, username, email)`, une ligne par résultat.
+    List,
+    /// Colonnes alignées sous un entête, les valeurs trop longues sont tronquées.
+    Table,
+}
+impl std::convert::TryFrom<&str> for OutputMode {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "list" => Ok(Self::List),
+            "table" => Ok(Self::Table),
+            _ => Err(()),
+        }
+    }
+}
+impl std::fmt::Display for OutputMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::List => write!(f, "list"),
+            Self::Table => write!(f, "table"),
+        }
+    }
+}
+
+/// Découpage en pages des résultats trop longs pour le terminal.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Clone)]
+pub enum Pagination {
+    Off,
+    /// Invite `--More--` après chaque écran : Entrée continue, `q` arrête.
+    More,
+    /// Les résultats sont envoyés à une commande externe, comme `less`.
+    Pager(String),
+}
+impl std::convert::From<&str> for Pagination {
+    fn from(value: &str) -> Self {
+        match value.to_lowercase().as_str() {
+            "off" | "0" => Self::Off,
+            "more" | "on" | "1" => Self::More,
+            _ => Self::Pager(value.to_owned()),
+        }
+    }
+}
+impl std::fmt::Display for Pagination {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Off => write!(f, "off"),
+            Self::More => write!(f, "more"),
+            Self::Pager(command) => write!(f, "{command}"),
+        }
+    }
+}
+
+/// Colonne d'un résultat : son nom dans l'entête et l'alignement de ses valeurs.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Clone, Copy)]
+pub struct ColumnHeader {
+    pub name: &'static str,
+    pub is_numeric: bool,
+}
+impl std::convert::From<&ResultColumn> for ColumnHeader {
+    fn from(column: &ResultColumn) -> Self {
+        Self {
+            name: column.name,
+            is_numeric: column.column_type == ColumnType::Integer,
+        }
+    }
+}
+
+/// Fichier qui reçoit les résultats à la place de la sortie standard.
+#[cfg_attr(debug_assertions, derive(Debug))]
+struct Redirection {
+    file: File,
+    /// Seul le prochain résultat y est écrit, voir `.once`.
+    once: bool,
+}
+
+/*
+ * Couche d'affichage des résultats de la session. Les réglages ne dépendent
+ * pas de la base ouverte.
+ */
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct Output {
+    pagination: Pagination,
+    height: usize,
+    mode: OutputMode,
+    /// Largeur imposée de chaque colonne en mode `table`, `None` pour une largeur automatique.
+    widths: [Option<usize>; Row::COLUMN_NAMES.len()],
+    /// Affiche l'entête des colonnes. `None` suit le mode : un entête en
+    /// mode `table`, aucun en mode `list`.
+    headers: Option<bool>,
+    /// Affiche la durée de chaque requête.
+    timer: bool,
+    /// Affiche chaque ligne avant de l'exécuter, pour suivre un script.
+    echo: bool,
+    lang: Lang,
+    redirection: Option<Redirection>,
+    /// Lignes saisies dans le REPL pendant la session, voir `.history`.
+    history: Vec<String>,
+}
+impl Output {
+    pub fn new(pagination: Pagination, height: usize) -> Self {
+        Self {
+            pagination,
+            height,
+            mode: OutputMode::List,
+            widths: [None; Row::COLUMN_NAMES.len()],
+            headers: None,
+            timer: false,
+            echo: false,
+            lang: Lang::En,
+            redirection: None,
+            history: Vec::new(),
+        }
+    }
+
+    pub fn get_mode(&self) -> OutputMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: OutputMode) {
+        self.mode = mode;
+    }
+
+    /// Largeurs des colonnes dans l'ordre `id`, `username`, `email`. Une largeur
+    /// de 0, ou une colonne absente, redevient automatique.
+    pub fn set_widths(&mut self, widths: &[usize]) {
+        for (column, width) in self.widths.iter_mut().enumerate() {
+            *width = widths.get(column).copied().filter(|width| *width != 0);
+        }
+    }
+
+    pub fn get_headers(&self) -> bool {
+        self.headers.unwrap_or(self.mode == OutputMode::Table)
+    }
+
+    /// Le choix vaut pour tous les modes, même après un changement de mode.
+    pub fn set_headers(&mut self, headers: bool) {
+        self.headers = Some(headers);
+    }
+
+    pub fn get_timer(&self) -> bool {
+        self.timer
+    }
+
+    pub fn set_timer(&mut self, timer: bool) {
+        self.timer = timer;
+    }
+
+    pub fn get_echo(&self) -> bool {
+        self.echo
+    }
+
+    pub fn set_echo(&mut self, echo: bool) {
+        self.echo = echo;
+    }
+
+    pub fn get_lang(&self) -> Lang {
+        self.lang
+    }
+
+    pub fn set_lang(&mut self, lang: Lang) {
+        self.lang = lang;
+    }
+
+    pub fn get_history(&self) -> &[String] {
+        &self.history
+    }
+
+    /// Comme dans l'historique de rustyline, une ligne vide ou identique à la
+    /// précédente n'est pas ajoutée.
+    pub fn add_history(&mut self, line: &str) {
+        if line.trim().is_empty() || self.history.last().is_some_and(|last| last == line) {
+            return;
+        }
+        self.history.push(line.to_owned());
+    }
+
+    pub fn get_pagination(&self) -> &Pagination {
+        &self.pagination
+    }
+
+    pub fn set_pagination(&mut self, pagination: Pagination) {
+        self.pagination = pagination;
+    }
+
+    /// Les résultats suivants sont écrits, sans pagination, dans `file_path`,
+    /// vidé s'il existe. Avec `once`, seul le prochain résultat y est écrit.
+    pub fn redirect(&mut self, file_path: &str, once: bool) -> io::Result<()> {
+        let file = File::create(file_path)?;
+        self.redirection = Some(Redirection { file, once });
+        Ok(())
+    }
+
+    /// Les résultats sont de nouveau affichés sur la sortie standard.
+    pub fn reset_redirection(&mut self) {
+        self.redirection = None;
+    }
+
+    /// Affiche le résultat sur la sortie standard en respectant la pagination.
+    /// Renvoie le nombre de lignes corrompues passées, ou l'erreur qui a
+    /// interrompu la lecture des lignes.
+    pub fn print_result(&mut self, result: ResultSet) -> Result<usize, GetRowError> {
+        let (headers, forced_widths) = self.result_headers(&result);
+        let skipped_rows = result.get_skipped_rows();
+        self.print_lines(headers, forced_widths, result_records(result))?;
+        Ok(skipped_rows.get())
+    }
+
+    /// Entête des colonnes du résultat et largeurs imposées par `set_widths`.
+    fn result_headers(&self, result: &ResultSet) -> (Vec<ColumnHeader>, Vec<Option<usize>>) {
+        let headers = result
+            .get_columns()
+            .iter()
+            .map(ColumnHeader::from)
+            .collect();
+        let forced_widths = result
+            .get_positions()
+            .iter()
+            .map(|column| self.widths[*column])
+            .collect();
+        (headers, forced_widths)
+    }
+
+    /// Affiche des enregistrements qui ne sont pas des `Row`, comme ceux de
+    /// `my_db_master`. Les largeurs imposées ne s'y appliquent pas.
+    pub fn print_records<I>(
+        &mut self,
+        headers: &[ColumnHeader],
+        records: I,
+    ) -> Result<(), GetRowError>
+    where
+        I: Iterator<Item = Result<Vec<String>, GetRowError>>,
+    {
+        self.print_lines(headers.to_vec(), vec![None; headers.len()], records)
+    }
+
+    fn print_lines<I>(
+        &mut self,
+        headers: Vec<ColumnHeader>,
+        forced_widths: Vec<Option<usize>>,
+        records: I,
+    ) -> Result<(), GetRowError>
+    where
+        I: Iterator<Item = Result<Vec<String>, GetRowError>>,
+    {
+        let redirection = self.redirection.take();
+        let lines: Box<dyn Iterator<Item = Result<String, GetRowError>>> = match self.mode {
+            OutputMode::List => {
+                let names: Vec<&str> = headers.iter().map(|header| header.name).collect();
+                let header_lines = if self.get_headers() {
+                    vec![Ok(format!("({})", names.join(", ")))]
+                } else {
+                    Vec::new()
+                };
+                Box::new(header_lines.into_iter().chain(
+                    records.map(|values| values.map(|values| format!("({})", values.join(", ")))),
+                ))
+            }
+            OutputMode::Table => Box::new(self.table_lines(headers, forced_widths, records)),
+        };
+        if let Some(mut redirection) = redirection {
+            let mut out = BufWriter::new(&mut redirection.file);
+            let result = write_lines(lines, &mut out);
+            let _ = out.flush();
+            drop(out);
+            if !redirection.once {
+                self.redirection = Some(redirection);
+            }
+            return result;
+        }
+        match &self.pagination {
+            Pagination::Off => write_lines(lines, &mut io::stdout().lock()),
+            Pagination::More => write_lines_with_more(
+                lines,
+                &mut io::stdout().lock(),
+                &mut io::stdin().lock(),
+                self.height,
+            ),
+            Pagination::Pager(command) => write_lines_to_pager(lines, command, self.height),
+        }
+    }
+
+    /// Les largeurs automatiques sont calculées sur les premières lignes
+    /// seulement, pour ne pas lire tout le résultat avant de l'afficher.
+    fn table_lines<I>(
+        &self,
+        headers: Vec<ColumnHeader>,
+        forced_widths: Vec<Option<usize>>,
+        mut records: I,
+    ) -> impl Iterator<Item = Result<String, GetRowError>>
+    where
+        I: Iterator<Item = Result<Vec<String>, GetRowError>>,
+    {
+        let sample: Vec<Result<Vec<String>, GetRowError>> =
+            records.by_ref().take(WIDTH_SAMPLE_SIZE).collect();
+
+        let mut widths: Vec<usize> = headers
+            .iter()
+            .map(|header| header.name.chars().count())
+            .collect();
+        for values in sample.iter().flatten() {
+            for (width, value) in widths.iter_mut().zip(values) {
+                *width = (*width).max(value.chars().count()).min(MAX_AUTO_WIDTH);
+            }
+        }
+        for (width, forced_width) in widths.iter_mut().zip(forced_widths) {
+            *width = forced_width.unwrap_or(*width);
+        }
+
+        let names: Vec<String> = headers
+            .iter()
+            .map(|header| header.name.to_owned())
+            .collect();
+        let header = format_cells(&headers, &names, &widths);
+        let separator = widths
+            .iter()
+            .map(|width| "-".repeat(*width))
+            .collect::<Vec<String>>()
+            .join("  ");
+        let header_lines = if self.get_headers() {
+            vec![Ok(header), Ok(separator)]
+        } else {
+            Vec::new()
+        };
+        header_lines.into_iter().chain(
+            sample
+                .into_iter()
+                .chain(records)
+                .map(move |values| values.map(|values| format_cells(&headers, &values, &widths))),
+        )
+    }
+}
+
+impl Default for Output {
+    /// La langue des messages suit `LANG`. La pagination n'est active par défaut que dans un terminal interactif,
+    /// jamais quand la sortie est redirigée.
+    fn default() -> Self {
+        let is_interactive = io::stdout().is_terminal() && io::stdin().is_terminal();
+        let pagination = if is_interactive {
+            Pagination::More
+        } else {
+            Pagination::Off
+        };
+        let height = std::env::var("LINES")
+            .ok()
+            .and_then(|lines| lines.parse::<usize>().ok())
+            .filter(|lines| *lines > 1)
+            .unwrap_or(DEFAULT_HEIGHT);
+
+        let mut output = Self::new(pagination, height);
+        output.set_lang(Lang::from_env());
+        output
+    }
+}
+
+fn result_records(result: ResultSet) -> impl Iterator<Item = Result<Vec<String>, GetRowError>> {
+    result
+        .into_records()
+        .map(|values| values.map(|values| values.iter().map(ToString::to_string).collect()))
+}
+
+/// Les nombres sont alignés à droite, le texte à gauche.
+fn format_cells(headers: &[ColumnHeader], values: &[String], widths: &[usize]) -> String {
+    headers
+        .iter()
+        .zip(values)
+        .zip(widths)
+        .map(|((header, value), width)| {
+            let value = truncate(value, *width);
+            if header.is_numeric {
+                format!("{value:>width$}")
+            } else {
+                format!("{value:<width$}")
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("  ")
+        .trim_end()
+        .to_owned()
+}
+
+/// Tronque la valeur à `width` caractères, le dernier étant remplacé par `…`.
+fn truncate(value: &str, width: usize) -> String {
+    if value.chars().count() <= width {
+        return value.to_owned();
+    }
+    let mut truncated: String = value.chars().take(width.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+/*
+ * Les erreurs d'écriture sur la sortie sont ignorées, comme avec `println!`,
+ * mais arrêtent l'affichage : inutile de lire la suite des lignes.
+ */
+
+fn write_lines<I, W>(lines: I, out: &mut W) -> Result<(), GetRowError>
+where
+    I: Iterator<Item = Result<String, GetRowError>>,
+    W: Write,
+{
+    for line in lines {
+        if writeln!(out, "{}", line?).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn write_lines_with_more<I, W, R>(
+    lines: I,
+    out: &mut W,
+    input: &mut R,
+    height: usize,
+) -> Result<(), GetRowError>
+where
+    I: Iterator<Item = Result<String, GetRowError>>,
+    W: Write,
+    R: BufRead,
+{
+    // La dernière ligne de l'écran est réservée à l'invite.
+    let page_height = height.saturating_sub(1).max(1);
+    for (line_num, line) in lines.enumerate() {
+        let line = line?;
+        if line_num != 0 && line_num % page_height == 0 {
+            let _ = write!(out, "{MORE_PROMPT}");
+            let _ = out.flush();
+            let mut answer = String::new();
+            let stop = match input.read_line(&mut answer) {
+                Ok(0) | Err(_) => true,
+                Ok(_) => answer.trim().eq_ignore_ascii_case("q"),
+            };
+            if stop {
+                let _ = writeln!(out);
+                return Ok(());
+            }
+        }
+        if writeln!(out, "{line}").is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Les premières lignes sont gardées en mémoire : la commande n'est lancée
+/// que si elles ne tiennent pas dans un écran.
+fn write_lines_to_pager<I>(mut lines: I, command: &str, height: usize) -> Result<(), GetRowError>
+where
+    I: Iterator<Item = Result<String, GetRowError>>,
+{
+    let mut first_lines = Vec::<String>::new();
+    for line in lines.by_ref() {
+        first_lines.push(line?);
+        if first_lines.len() >= height {
+            break;
+        }
+    }
+    let first_lines = first_lines.into_iter().map(Ok);
+    if first_lines.len() < height {
+        return write_lines(first_lines, &mut io::stdout().lock());
+    }
+
+    let mut words = command.split_whitespace();
+    let child = words.next().and_then(|program| {
+        Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .spawn()
+            .ok()
+    });
+    let Some(mut child) = child else {
+        // Commande introuvable : les lignes sont affichées sans pagination.
+        return write_lines(first_lines.chain(lines), &mut io::stdout().lock());
+    };
+
+    let result = match child.stdin.take() {
+        Some(mut stdin) => write_lines(first_lines.chain(lines), &mut stdin),
+        None => Ok(()),
+    };
+    let _ = child.wait();
+    result
+}
+
+#[cfg(test)]
+mod output_test {
+    use super::*;
+
+    use crate::row::{Email, Id, Username};
+    use crate::statement::Rows;
+
+    #[test]
+    fn test_table_lines_align_and_truncate() {
+        let mut output = Output::new(Pagination::Off, DEFAULT_HEIGHT);
+        output.set_mode(OutputMode::Table);
+        output.set_widths(&[0, 0, 8]);
+
+        let result = |columns| {
+            let rows = [(7, "bob", "bob@mail.com"), (42, "alice", "a@b.c")].map(
+                |(id, username, email)| {
+                    Row::new(
+                        Id::new(id),
+                        Username::new(username.to_owned()),
+                        Email::new(email.to_owned()),
+                    )
+                },
+            );
+            ResultSet::from(Rows::from_rows(rows.to_vec(), columns))
+        };
+        let lines = |result: ResultSet| -> Vec<String> {
+            let (headers, forced_widths) = output.result_headers(&result);
+            output
+                .table_lines(headers, forced_widths, result_records(result))
+                .map(Result::unwrap)
+                .collect()
+        };
+        let lines_all = lines(result(vec![0, 1, 2]));
+        assert_eq!(
+            lines_all,
+            vec![
+                "id  username  email",
+                "--  --------  --------",
+                " 7  bob       bob@mai…",
+                "42  alice     a@b.c",
+            ]
+        );
+
+        assert_eq!(
+            lines(result(vec![2, 0])),
+            vec![
+                "email     id",
+                "--------  --",
+                "bob@mai…   7",
+                "a@b.c     42"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_more_prompt_stops_on_q() {
+        let lines = (0..10).map(|line_num| Ok(line_num.to_string()));
+        let mut out = Vec::<u8>::new();
+        let mut input: &[u8] = b"\nq\n";
+
+        write_lines_with_more(lines, &mut out, &mut input, 4).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "0\n1\n2\n--More--3\n4\n5\n--More--\n"
+        );
+    }
+}
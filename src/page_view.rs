@@ -0,0 +1,188 @@
+use std::cell::{Ref, RefMut};
+use std::ops::Range;
+
+/*
+ * Vues sur la mémoire d'une page du `Pager`.
+ *
+ * Une vue garde l'emprunt du `RefCell` qui contient le pager : elle ne peut
+ * donc pas lui survivre, et une vue mutable ne peut pas coexister avec une
+ * autre vue sur le même pager (vérifié à l'exécution par le `RefCell`).
+ */
+
+/// Lectures typées, avec vérification des bornes, sur une suite d'octets.
+pub trait ReadBytes {
+    fn read_array<const N: usize>(&self, offset: usize) -> [u8; N];
+
+    fn read_u32_be(&self, offset: usize) -> u32 {
+        u32::from_be_bytes(self.read_array(offset))
+    }
+
+    fn read_u64_be(&self, offset: usize) -> u64 {
+        u64::from_be_bytes(self.read_array(offset))
+    }
+}
+impl ReadBytes for [u8] {
+    fn read_array<const N: usize>(&self, offset: usize) -> [u8; N] {
+        let end = offset.checked_add(N);
+        assert!(
+            end.is_some_and(|end| end <= self.len()),
+            "Page view out of bounds."
+        );
+
+        let mut arr = [0; N];
+        arr.copy_from_slice(&self[offset..(offset + N)]);
+        arr
+    }
+}
+
+/// Écritures typées, avec vérification des bornes, sur une suite d'octets.
+pub trait WriteBytes {
+    fn write_bytes(&mut self, offset: usize, src: &[u8]);
+
+    fn write_u32_be(&mut self, offset: usize, value: u32) {
+        self.write_bytes(offset, &value.to_be_bytes());
+    }
+
+    fn write_u64_be(&mut self, offset: usize, value: u64) {
+        self.write_bytes(offset, &value.to_be_bytes());
+    }
+}
+impl WriteBytes for [u8] {
+    fn write_bytes(&mut self, offset: usize, src: &[u8]) {
+        let end = offset.checked_add(src.len());
+        assert!(
+            end.is_some_and(|end| end <= self.len()),
+            "Page view out of bounds."
+        );
+
+        self[offset..(offset + src.len())].copy_from_slice(src);
+    }
+}
+
+pub struct PageView<'a>(Ref<'a, [u8]>);
+impl<'a> PageView<'a> {
+    pub fn subslice(self, range: Range<usize>) -> Self {
+        assert!(
+            range.start <= range.end && range.end <= self.0.len(),
+            "Page view out of bounds."
+        );
+        Self(Ref::map(self.0, |bytes| &bytes[range]))
+    }
+
+    pub fn split_at(self, idx: usize) -> (Self, Self) {
+        assert!(idx <= self.0.len(), "Page view out of bounds.");
+        let (left, right) = Ref::map_split(self.0, |bytes| bytes.split_at(idx));
+        (Self(left), Self(right))
+    }
+}
+impl<'a> std::convert::From<Ref<'a, [u8]>> for PageView<'a> {
+    fn from(bytes: Ref<'a, [u8]>) -> Self {
+        Self(bytes)
+    }
+}
+impl Clone for PageView<'_> {
+    fn clone(&self) -> Self {
+        Self(Ref::clone(&self.0))
+    }
+}
+impl std::ops::Deref for PageView<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+pub struct PageViewMut<'a>(RefMut<'a, [u8]>);
+impl<'a> PageViewMut<'a> {
+    pub fn subslice(self, range: Range<usize>) -> Self {
+        assert!(
+            range.start <= range.end && range.end <= self.0.len(),
+            "Page view out of bounds."
+        );
+        Self(RefMut::map(self.0, |bytes| &mut bytes[range]))
+    }
+
+    pub fn split_at(self, idx: usize) -> (Self, Self) {
+        assert!(idx <= self.0.len(), "Page view out of bounds.");
+        let (left, right) = RefMut::map_split(self.0, |bytes| bytes.split_at_mut(idx));
+        (Self(left), Self(right))
+    }
+
+    /// Copie `src` dans la vue, qui doit être exactement de la même taille.
+    pub fn copy_from(&mut self, src: &[u8]) {
+        assert_eq!(self.0.len(), src.len(), "Page view size mismatch.");
+        self.0.copy_from_slice(src);
+    }
+}
+impl<'a> std::convert::From<RefMut<'a, [u8]>> for PageViewMut<'a> {
+    fn from(bytes: RefMut<'a, [u8]>) -> Self {
+        Self(bytes)
+    }
+}
+impl std::ops::Deref for PageViewMut<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl std::ops::DerefMut for PageViewMut<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+#[cfg(test)]
+mod page_view_test {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    #[test]
+    fn test_read_write_bytes() {
+        let mut bytes = [0u8; 8];
+        bytes.write_u32_be(4, 0xdead_beef);
+        assert_eq!(bytes, [0, 0, 0, 0, 0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(bytes.read_u32_be(4), 0xdead_beef);
+        assert_eq!(bytes.read_array::<2>(3), [0, 0xde]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Page view out of bounds.")]
+    fn test_read_out_of_bounds() {
+        let bytes = [0u8; 8];
+        let _ = bytes.read_u32_be(5);
+    }
+
+    #[test]
+    #[should_panic(expected = "Page view out of bounds.")]
+    fn test_write_offset_overflow() {
+        let mut bytes = [0u8; 8];
+        bytes.write_u32_be(usize::MAX - 1, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Page view out of bounds.")]
+    fn test_split_out_of_bounds() {
+        let cell = RefCell::new(vec![0u8; 4]);
+        let view_mut = PageViewMut::from(RefMut::map(cell.borrow_mut(), |bytes| &mut bytes[..]));
+        let _ = view_mut.split_at(5);
+    }
+
+    #[test]
+    fn test_split_and_subslice() {
+        let cell = RefCell::new(vec![1u8, 2, 3, 4, 5]);
+        let view = PageView::from(Ref::map(cell.borrow(), |bytes| &bytes[..]));
+        let (left, right) = view.split_at(2);
+        assert_eq!(&left[..], &[1, 2]);
+        assert_eq!(&right.subslice(1..3)[..], &[4, 5]);
+        drop(left);
+
+        let mut view_mut =
+            PageViewMut::from(RefMut::map(cell.borrow_mut(), |bytes| &mut bytes[..]));
+        view_mut.copy_from(&[5, 4, 3, 2, 1]);
+        drop(view_mut);
+        assert_eq!(&cell.borrow()[..], &[5, 4, 3, 2, 1]);
+    }
+}
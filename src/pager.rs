@@ -1,8 +1,17 @@
-use std::fs::{File, OpenOptions};
+use std::cell::{Cell, Ref, RefCell, RefMut};
 use std::io;
-use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::time::{Duration, Instant};
 
-use crate::slice_pointer::{SlicePointer, SlicePointerMut};
+use tracing::{debug, info, trace};
+
+use crate::backend::{MemoryBackend, OpenMode, PagerBackend, StdVfs, Vfs};
+use crate::catalog::CatalogError;
+#[cfg(feature = "compression")]
+use crate::compression::{PageDirectory, compress_page, decompress_page};
+use crate::header::FileHeader;
+use crate::migration::{MigrationError, read_outdated, upgrade_file};
+use crate::page_view::{PageView, PageViewMut, ReadBytes, WriteBytes};
 
 type PageType = Box<[u8; Page::SIZE]>;
 
@@ -29,16 +38,31 @@ impl Default for Page {
     }
 }
 
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum PagerOpenError {
+    IoError(String, io::Error),
+    Migration(String, MigrationError),
+    Catalog(String, CatalogError),
+}
+
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub enum SetOpenSaveFileError {
     IoError(io::Error),
+    Migration(MigrationError),
+    Catalog(CatalogError),
     PoisonedTable,
+    PageInUse,
 }
 
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub enum GetPageError {
     MaxPageReached,
     IoError(io::Error),
+    PageInUse,
+    /// La page ne contient pas un nœud de l'arbre valide.
+    Corrupted(usize),
+    /// La base a été ouverte en lecture seule.
+    ReadOnly,
 }
 
 #[cfg_attr(debug_assertions, derive(Debug))]
@@ -46,146 +70,901 @@ pub enum SaveToDiskError {
     NoFileToWriteProvided,
     PoisonedTable,
     IoError(io::Error),
-    NotAllBytesWritten,
+    /// Position dans le fichier de l'écriture qui a échoué, et son erreur.
+    WriteFailed(u64, io::Error),
+    PageInUse,
+    /// Une page jamais chargée n'a pas pu être relue dans l'ancien fichier.
+    GetPage(GetPageError),
+    Reopen(PagerOpenError),
+    /// La base a été ouverte en lecture seule, son fichier ne peut pas être remplacé.
+    ReadOnly,
+    Catalog(CatalogError),
+}
+
+/// Niveau de durabilité des sauvegardes, à la manière de `PRAGMA synchronous`.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Clone, Copy)]
+pub enum Synchronous {
+    /// Les données sont confiées au système sans attendre qu'elles soient sur le disque.
+    Off,
+    /// Chaque sauvegarde attend que les données soient sur le disque (`fsync`).
+    Full,
+}
+impl std::convert::TryFrom<&str> for Synchronous {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "off" | "0" => Ok(Self::Off),
+            "full" | "on" | "1" => Ok(Self::Full),
+            _ => Err(()),
+        }
+    }
+}
+impl std::fmt::Display for Synchronous {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Off => write!(f, "off"),
+            Self::Full => write!(f, "full"),
+        }
+    }
+}
+
+/// Moment où les modifications sont écrites dans le fichier ouvert.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Clone, Copy)]
+pub enum WriteMode {
+    /// Les pages restent en mémoire jusqu'à `.save` ou la fermeture.
+    WriteBack,
+    /// La base est sauvegardée après chaque instruction qui la modifie.
+    WriteThrough,
+}
+impl std::convert::TryFrom<&str> for WriteMode {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "write_back" | "back" | "off" | "0" => Ok(Self::WriteBack),
+            "write_through" | "through" | "on" | "1" => Ok(Self::WriteThrough),
+            _ => Err(()),
+        }
+    }
+}
+impl std::fmt::Display for WriteMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::WriteBack => write!(f, "write_back"),
+            Self::WriteThrough => write!(f, "write_through"),
+        }
+    }
+}
+
+/// Mémoire occupée par le pager, en octets.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Clone, Copy)]
+pub struct MemoryUsage {
+    pub nb_resident_pages: usize,
+    /// Contenu des pages présentes en mémoire.
+    pub page_bytes: usize,
+    /// Emplacements et état des pages, répertoire des pages compressées.
+    pub cache_metadata_bytes: usize,
+}
+impl MemoryUsage {
+    pub fn get_total_bytes(&self) -> usize {
+        self.page_bytes + self.cache_metadata_bytes
+    }
+}
+
+/// Accès en lecture à une page, qui reste épinglée tant que le garde existe.
+pub struct PageReadGuard<'a> {
+    page_num: usize,
+    page: Ref<'a, Page>,
+}
+impl PageReadGuard<'_> {
+    pub fn page_num(&self) -> usize {
+        self.page_num
+    }
+}
+impl std::ops::Deref for PageReadGuard<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.page[..]
+    }
+}
+impl<'a> std::convert::From<PageReadGuard<'a>> for PageView<'a> {
+    fn from(guard: PageReadGuard<'a>) -> Self {
+        PageView::from(Ref::map(guard.page, |page| &page[..]))
+    }
+}
+
+/// Accès exclusif en écriture à une page, qui reste épinglée tant que le garde existe.
+pub struct PageWriteGuard<'a> {
+    page_num: usize,
+    page: RefMut<'a, Page>,
+}
+impl PageWriteGuard<'_> {
+    pub fn page_num(&self) -> usize {
+        self.page_num
+    }
+}
+impl std::ops::Deref for PageWriteGuard<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.page[..]
+    }
+}
+impl std::ops::DerefMut for PageWriteGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.page[..]
+    }
+}
+impl<'a> std::convert::From<PageWriteGuard<'a>> for PageViewMut<'a> {
+    fn from(guard: PageWriteGuard<'a>) -> Self {
+        PageViewMut::from(RefMut::map(guard.page, |page| &mut page[..]))
+    }
+}
+
+/*
+ * Chaque page est dans son propre `RefCell` : plusieurs lectures d'une même
+ * page peuvent coexister, mais une écriture exige qu'aucun autre garde ne soit
+ * ouvert sur cette page. Les conflits sont signalés par `PageInUse` au lieu de
+ * produire des vues qui se chevauchent.
+ */
+#[cfg_attr(debug_assertions, derive(Debug))]
+struct SaveFile {
+    /// `None` pour un contenu en mémoire, qui ne peut pas être remplacé par une sauvegarde.
+    path: Option<String>,
+    file: Box<dyn PagerBackend>,
+    /// Nombre de lignes enregistré dans l'entête, 0 pour un nouveau fichier.
+    nb_rows: usize,
+    /// Présent uniquement si les pages du fichier sont compressées.
+    #[cfg(feature = "compression")]
+    directory: Option<PageDirectory>,
+}
+impl SaveFile {
+    /// Ouvre (ou crée) le fichier, le met au format courant et lit son répertoire de pages.
+    fn open(vfs: &dyn Vfs, file_path: &str) -> Result<Self, PagerOpenError> {
+        let io_error = |e| PagerOpenError::IoError(file_path.to_owned(), e);
+        let mut file = Pager::open_or_create(vfs, file_path).map_err(io_error)?;
+
+        // Comme `save_to_disk`, la version migrée est écrite à côté puis renommée :
+        // une panne pendant la migration laisse le fichier d'origine intact.
+        let migrated = read_outdated(file.as_mut())
+            .map_err(|e| PagerOpenError::Migration(file_path.to_owned(), e))?;
+        if let Some(bytes) = migrated {
+            info!(path = %file_path, "migrate file format");
+            let tmp_path = format!("{file_path}.tmp");
+            let written = vfs
+                .open(&tmp_path, OpenMode::Create)
+                .and_then(|mut tmp_file| {
+                    tmp_file.write_all(&bytes)?;
+                    tmp_file.sync()?;
+                    vfs.rename(&tmp_path, file_path)
+                });
+            if let Err(e) = written {
+                let _ = vfs.remove(&tmp_path);
+                return Err(io_error(e));
+            }
+            vfs.sync_dir(file_path).map_err(io_error)?;
+            file = vfs.open(file_path, OpenMode::ReadWrite).map_err(io_error)?;
+        }
+        Self::from_backend(Some(file_path), file)
+    }
+
+    /// Ouvre un fichier existant sans jamais y écrire : un fichier d'un ancien
+    /// format ne peut pas être mis à niveau et est refusé.
+    fn open_read_only(vfs: &dyn Vfs, file_path: &str) -> Result<Self, PagerOpenError> {
+        let file = vfs
+            .open(file_path, OpenMode::ReadOnly)
+            .map_err(|e| PagerOpenError::IoError(file_path.to_owned(), e))?;
+        Self::from_backend(Some(file_path), file)
+    }
+
+    fn from_backend(
+        file_path: Option<&str>,
+        mut file: Box<dyn PagerBackend>,
+    ) -> Result<Self, PagerOpenError> {
+        let name = file_path.unwrap_or(MemoryBackend::NAME);
+        let header = upgrade_file(file.as_mut())
+            .map_err(|e| PagerOpenError::Migration(name.to_owned(), e))?;
+
+        let nb_rows = header
+            .as_ref()
+            .map_or(0, |header| header.get_nb_rows() as usize);
+
+        #[cfg(feature = "compression")]
+        let directory = match header {
+            Some(header) if header.is_compressed() => Some(
+                PageDirectory::read_from(file.as_mut())
+                    .map_err(|e| PagerOpenError::IoError(name.to_owned(), e))?,
+            ),
+            _ => None,
+        };
+
+        Ok(Self {
+            path: file_path.map(str::to_owned),
+            file,
+            nb_rows,
+            #[cfg(feature = "compression")]
+            directory,
+        })
+    }
 }
 
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub struct Pager {
-    save_file: Option<File>,
-    pages: [Option<Page>; Self::MAX_PAGES],
+    /// Ouvre le fichier de sauvegarde et les fichiers écrits par les sauvegardes.
+    vfs: Box<dyn Vfs>,
+    save_file: RefCell<Option<SaveFile>>,
+    pages: [RefCell<Option<Page>>; Self::MAX_PAGES],
+    /// La page a été modifiée depuis sa lecture dans le fichier ouvert.
+    dirty: [Cell<bool>; Self::MAX_PAGES],
+    /// Valeur de `clock` lors du dernier accès à la page.
+    last_used: [Cell<u64>; Self::MAX_PAGES],
+    clock: Cell<u64>,
+    /// Nombre de pages gardées en mémoire, voir `evict_pages`.
+    cache_size: Cell<usize>,
+    synchronous: Cell<Synchronous>,
+    write_mode: Cell<WriteMode>,
+    /// Délai maximal avant la sauvegarde d'une modification en mode write-through.
+    commit_delay: Cell<Duration>,
+    /// Intervalle des sauvegardes périodiques en mode write-back, nul sans.
+    flush_interval: Cell<Duration>,
+    /// Première modification pas encore sauvegardée, voir `mark_write_pending`.
+    write_pending_since: Cell<Option<Instant>>,
+    /// Les pages du fichier ouvert ont été vidées et ne doivent plus être relues.
+    truncated: Cell<bool>,
+    /// Aucune page ne peut être modifiée et le fichier ouvert n'est jamais réécrit.
+    read_only: bool,
 }
 impl Pager {
     pub const MAX_PAGES: usize = 100;
 
-    pub fn new(file_path: Option<&str>) -> Self {
+    pub fn new(file_path: Option<&str>) -> Result<Self, PagerOpenError> {
+        Self::with_vfs(Box::new(StdVfs), file_path)
+    }
+
+    /// Comme `new`, les fichiers étant ouverts, renommés et supprimés par `vfs`.
+    pub fn with_vfs(vfs: Box<dyn Vfs>, file_path: Option<&str>) -> Result<Self, PagerOpenError> {
         let save_file = if let Some(file_path) = file_path {
-            Some(
-                OpenOptions::new()
-                    .read(true)
-                    .write(true)
-                    .open(file_path)
-                    .unwrap(),
-            )
+            Some(SaveFile::open(vfs.as_ref(), file_path)?)
         } else {
             None
         };
 
-        Self {
-            save_file,
-            pages: [const { None }; Self::MAX_PAGES],
+        Ok(Self {
+            vfs,
+            save_file: RefCell::new(save_file),
+            ..Self::default()
+        })
+    }
+
+    /// Lit les pages dans `backend` au lieu d'un fichier, par exemple un
+    /// `MemoryBackend` rempli par `write_to`.
+    pub fn with_backend(backend: Box<dyn PagerBackend>) -> Result<Self, PagerOpenError> {
+        Ok(Self {
+            save_file: RefCell::new(Some(SaveFile::from_backend(None, backend)?)),
+            ..Self::default()
+        })
+    }
+
+    /// Base en mémoire dont les premières pages sont `pages`, comme celles
+    /// renvoyées par `read_data_from_bytes`. Les pages au-delà de `MAX_PAGES`
+    /// sont ignorées.
+    pub fn from_pages(pages: Vec<Page>) -> Self {
+        let pager = Self::default();
+        for (slot, page) in pager.pages.iter().zip(pages) {
+            *slot.borrow_mut() = Some(page);
+        }
+        pager
+    }
+
+    /// Ouvre la base en lecture seule, voir [`Pager::is_read_only`].
+    pub fn new_read_only(file_path: Option<&str>) -> Result<Self, PagerOpenError> {
+        let save_file = if let Some(file_path) = file_path {
+            Some(SaveFile::open_read_only(&StdVfs, file_path)?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            save_file: RefCell::new(save_file),
+            read_only: true,
+            ..Self::default()
+        })
+    }
+
+    /// Garde au plus `cache_size` pages en mémoire, voir `evict_pages`.
+    pub fn with_cache_size(self, cache_size: usize) -> Self {
+        self.set_cache_size(cache_size);
+        self
+    }
+
+    pub fn get_cache_size(&self) -> usize {
+        self.cache_size.get()
+    }
+
+    /// Une taille nulle est ramenée à une page.
+    pub fn set_cache_size(&self, cache_size: usize) {
+        self.cache_size.set(cache_size.max(1));
+        self.evict_pages(None);
+    }
+
+    /// Nombre de pages présentes en mémoire.
+    pub fn cached_pages(&self) -> usize {
+        self.pages
+            .iter()
+            .filter(|page| !matches!(page.try_borrow().as_deref(), Ok(None)))
+            .count()
+    }
+
+    /// Les pages vides des emplacements non chargés ne comptent pas : elles ne
+    /// sont pas allouées.
+    pub fn memory_usage(&self) -> MemoryUsage {
+        let nb_resident_pages = self.cached_pages();
+        #[allow(unused_mut)]
+        let mut cache_metadata_bytes = std::mem::size_of_val(&self.pages)
+            + std::mem::size_of_val(&self.dirty)
+            + std::mem::size_of_val(&self.last_used);
+        #[cfg(feature = "compression")]
+        if let Some(directory) = self
+            .save_file
+            .borrow()
+            .as_ref()
+            .and_then(|save_file| save_file.directory.as_ref())
+        {
+            cache_metadata_bytes +=
+                directory.len() * std::mem::size_of::<crate::compression::PageLocation>();
+        }
+
+        MemoryUsage {
+            nb_resident_pages,
+            page_bytes: nb_resident_pages * Page::SIZE,
+            cache_metadata_bytes,
+        }
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Nombre de lignes enregistré dans le fichier ouvert, 0 sans fichier.
+    pub fn nb_rows_in_file(&self) -> usize {
+        self.save_file
+            .borrow()
+            .as_ref()
+            .map_or(0, |save_file| save_file.nb_rows)
+    }
+
+    pub fn has_save_file(&self) -> bool {
+        self.save_file.borrow().is_some()
+    }
+
+    pub fn get_synchronous(&self) -> Synchronous {
+        self.synchronous.get()
+    }
+
+    pub fn set_synchronous(&self, synchronous: Synchronous) {
+        self.synchronous.set(synchronous);
+    }
+
+    pub fn get_write_mode(&self) -> WriteMode {
+        self.write_mode.get()
+    }
+
+    pub fn set_write_mode(&self, write_mode: WriteMode) {
+        self.write_mode.set(write_mode);
+    }
+
+    pub fn get_commit_delay(&self) -> Duration {
+        self.commit_delay.get()
+    }
+
+    pub fn set_commit_delay(&self, commit_delay: Duration) {
+        self.commit_delay.set(commit_delay);
+    }
+
+    pub fn get_flush_interval(&self) -> Duration {
+        self.flush_interval.get()
+    }
+
+    pub fn set_flush_interval(&self, flush_interval: Duration) {
+        self.flush_interval.set(flush_interval);
+    }
+
+    /// Délai avant la sauvegarde d'une modification, selon le mode d'écriture.
+    /// `None` si les modifications ne sont sauvegardées que par `.save`.
+    pub fn get_write_delay(&self) -> Option<Duration> {
+        match self.write_mode.get() {
+            WriteMode::WriteThrough => Some(self.commit_delay.get()),
+            WriteMode::WriteBack if self.flush_interval.get().is_zero() => None,
+            WriteMode::WriteBack => Some(self.flush_interval.get()),
+        }
+    }
+
+    /// Note qu'une modification attend d'être sauvegardée. Seule la première
+    /// compte : les suivantes sont sauvegardées avec elle.
+    pub fn mark_write_pending(&self) {
+        if self.write_pending_since.get().is_none() {
+            self.write_pending_since.set(Some(Instant::now()));
+        }
+    }
+
+    /// Moment où les modifications en attente doivent être sauvegardées, `None`
+    /// s'il n'y en a aucune.
+    pub fn get_write_deadline(&self) -> Option<Instant> {
+        let write_delay = self.get_write_delay()?;
+        self.write_pending_since
+            .get()
+            .map(|since| since + write_delay)
+    }
+
+    /// Oublie les modifications en attente et indique s'il y en avait.
+    pub fn take_pending_write(&self) -> bool {
+        self.write_pending_since.take().is_some()
+    }
+
+    /// Chemin du fichier ouvert, `None` pour une base en mémoire.
+    pub fn get_save_path(&self) -> Option<String> {
+        self.save_file
+            .borrow()
+            .as_ref()
+            .and_then(|save_file| save_file.path.clone())
+    }
+
+    /// Indique si le fichier ouvert a un chemin où `save_to_disk` peut écrire,
+    /// contrairement à une base en mémoire ou lue depuis un `PagerBackend`.
+    pub fn has_save_path(&self) -> bool {
+        self.save_file
+            .borrow()
+            .as_ref()
+            .is_some_and(|save_file| save_file.path.is_some())
+    }
+
+    pub fn get_vfs(&self) -> &dyn Vfs {
+        self.vfs.as_ref()
+    }
+
+    /// Ouvre le fichier de sauvegarde en lecture et écriture, en le créant s'il n'existe pas.
+    fn open_or_create(vfs: &dyn Vfs, file_path: &str) -> io::Result<Box<dyn PagerBackend>> {
+        let is_new_file = !vfs.exists(file_path);
+        let file = vfs.open(file_path, OpenMode::ReadWrite)?;
+
+        if is_new_file {
+            vfs.sync_dir(file_path)?;
         }
+        Ok(file)
     }
 
-    pub fn set_open_save_file(&mut self, file_path: &str) -> Result<(), SetOpenSaveFileError> {
-        // TODO: sauvegarder le chemin même si le fichier n'existe pas.
-        let file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .open(file_path)
-            .map_err(SetOpenSaveFileError::IoError)?;
+    pub fn set_open_save_file(&self, file_path: &str) -> Result<(), SetOpenSaveFileError> {
+        let save_file = SaveFile::open(self.vfs.as_ref(), file_path).map_err(|e| match e {
+            PagerOpenError::IoError(_, e) => SetOpenSaveFileError::IoError(e),
+            PagerOpenError::Migration(_, e) => SetOpenSaveFileError::Migration(e),
+            PagerOpenError::Catalog(_, e) => SetOpenSaveFileError::Catalog(e),
+        })?;
 
-        self.save_file = Some(file);
+        self.clear_pages()?;
+        *self.save_file.borrow_mut() = Some(save_file);
+        self.truncated.set(false);
+        Ok(())
+    }
 
-        self.pages = [const { None }; Self::MAX_PAGES];
+    /// Détache le fichier de sauvegarde et vide le cache : le pager repart d'une base vide en mémoire.
+    pub fn close_save_file(&self) -> Result<(), SetOpenSaveFileError> {
+        self.clear_pages()?;
+        *self.save_file.borrow_mut() = None;
+        self.truncated.set(false);
         Ok(())
     }
 
-    fn load_or_create_page(&mut self, page_num: usize) -> Page {
-        if let Some(save_file) = self.save_file.as_mut() {
-            let offset = Page::SIZE * page_num;
-            let seek_from = SeekFrom::Start(offset as u64);
-            let _ = save_file.seek(seek_from).unwrap();
-            let mut page = Page::default();
-            save_file.read_exact(&mut page[..]).unwrap();
-            page
-        } else {
-            Page::default()
+    fn clear_pages(&self) -> Result<(), SetOpenSaveFileError> {
+        for (page, dirty) in self.pages.iter().zip(&self.dirty) {
+            let mut page = page
+                .try_borrow_mut()
+                .map_err(|_| SetOpenSaveFileError::PageInUse)?;
+            *page = None;
+            dirty.set(false);
         }
+        Ok(())
     }
 
-    pub fn get(&mut self, page_num: usize) -> SlicePointer {
-        assert!(page_num < Self::MAX_PAGES, "Max page reached.");
+    /*
+     * Tant que plus de `cache_size` pages sont en mémoire, libère la page la
+     * moins récemment utilisée qui peut être relue telle quelle dans le fichier
+     * ouvert : non modifiée, présente dans le fichier et non empruntée. Les
+     * pages modifiées restent en mémoire jusqu'à la sauvegarde, le cache peut
+     * donc dépasser sa taille. `keep` est la page en cours de chargement.
+     */
+    fn evict_pages(&self, keep: Option<usize>) {
+        let Ok(nb_pages_in_file) = self.nb_pages_in_file() else {
+            return;
+        };
+        let mut nb_resident_pages = self.cached_pages();
+        while nb_resident_pages > self.cache_size.get() {
+            let victim = (0..nb_pages_in_file.min(Self::MAX_PAGES))
+                .filter(|page_num| Some(*page_num) != keep && !self.dirty[*page_num].get())
+                .filter(|page_num| {
+                    matches!(
+                        self.pages[*page_num].try_borrow_mut().as_deref(),
+                        Ok(Some(_))
+                    )
+                })
+                .min_by_key(|page_num| self.last_used[*page_num].get());
+            let Some(page_num) = victim else {
+                return;
+            };
+            trace!(page_num, "page eviction");
+            *self.pages[page_num].borrow_mut() = None;
+            nb_resident_pages -= 1;
+        }
+    }
+
+    /// Libère toutes les pages, en mémoire comme dans le fichier ouvert, sans les
+    /// relire ni les parcourir. Le fichier n'est réécrit qu'à la prochaine sauvegarde.
+    pub fn truncate(&self) -> Result<(), GetPageError> {
+        if self.read_only {
+            return Err(GetPageError::ReadOnly);
+        }
+        for (page, dirty) in self.pages.iter().zip(&self.dirty) {
+            let mut page = page.try_borrow_mut().map_err(|_| GetPageError::PageInUse)?;
+            *page = None;
+            dirty.set(false);
+        }
+        self.truncated.set(true);
+        Ok(())
+    }
+
+    /// Nombre de pages, complètes ou non, présentes dans le fichier de sauvegarde.
+    fn nb_pages_in_file(&self) -> Result<usize, io::Error> {
+        if self.truncated.get() {
+            return Ok(0);
+        }
 
-        if self.pages[page_num].is_some() {
-            let page = self.pages[page_num].as_mut().unwrap();
-            return SlicePointer::from(&page[..]);
+        #[cfg(feature = "compression")]
+        if let Some(directory) = self
+            .save_file
+            .borrow()
+            .as_ref()
+            .and_then(|save_file| save_file.directory.as_ref())
+        {
+            return Ok(directory.len());
         }
 
-        let page = self.load_or_create_page(page_num);
+        let file_len = self.file_len()? as usize;
+        Ok(file_len
+            .saturating_sub(FileHeader::SIZE)
+            .div_ceil(Page::SIZE))
+    }
 
-        self.pages[page_num] = Some(page);
-        let page = self.pages[page_num].as_mut().unwrap();
-        SlicePointer::from(&page[..])
+    /// Taille en octets du fichier de sauvegarde ouvert, entête compris. 0 pour
+    /// une base en mémoire.
+    pub fn file_len(&self) -> io::Result<u64> {
+        self.save_file
+            .borrow()
+            .as_ref()
+            .map_or(Ok(0), |save_file| save_file.file.get_len())
     }
 
-    pub fn get_mut(&mut self, page_num: usize) -> SlicePointerMut {
-        assert!(page_num < Self::MAX_PAGES, "Max page reached.");
+    /// Nombre de pages de la base, dans le fichier ou seulement en mémoire. Une
+    /// nouvelle page prend le numéro suivant.
+    pub fn page_count(&self) -> Result<usize, io::Error> {
+        let nb_cached_pages = self
+            .pages
+            .iter()
+            .rposition(|page| !matches!(page.try_borrow().as_deref(), Ok(None)))
+            .map_or(0, |page_num| page_num + 1);
+        Ok(self.nb_pages_in_file()?.max(nb_cached_pages))
+    }
 
-        if self.pages[page_num].is_some() {
-            let page = self.pages[page_num].as_mut().unwrap();
-            return SlicePointerMut::from(&mut page[..]);
+    fn load_or_create_page(&self, page_num: usize) -> Result<Page, GetPageError> {
+        if self.truncated.get() {
+            return Ok(Page::default());
         }
 
-        let page = self.load_or_create_page(page_num);
+        let mut save_file = self.save_file.borrow_mut();
+        let Some(save_file) = save_file.as_mut() else {
+            return Ok(Page::default());
+        };
+
+        #[cfg(feature = "compression")]
+        if let Some(directory) = save_file.directory.as_ref() {
+            let Some(location) = directory.get(page_num) else {
+                return Ok(Page::default());
+            };
+            let _ = save_file
+                .file
+                .seek(SeekFrom::Start(location.offset))
+                .map_err(GetPageError::IoError)?;
+            let mut compressed_page = vec![0; location.len as usize];
+            // Une page compressée coupée par la fin du fichier ne peut pas être lue.
+            save_file
+                .file
+                .read_exact(&mut compressed_page)
+                .map_err(|e| match e.kind() {
+                    io::ErrorKind::UnexpectedEof => GetPageError::Corrupted(page_num),
+                    _ => GetPageError::IoError(e),
+                })?;
+            return decompress_page(&compressed_page)
+                .map_err(|_| GetPageError::Corrupted(page_num));
+        }
 
-        self.pages[page_num] = Some(page);
-        let page = self.pages[page_num].as_mut().unwrap();
-        SlicePointerMut::from(&mut page[..])
+        let save_file = &mut save_file.file;
+        let offset = (FileHeader::SIZE + Page::SIZE * page_num) as u64;
+        // Les pages au-delà de la fin du fichier n'ont jamais été sauvegardées.
+        if offset >= save_file.get_len().map_err(GetPageError::IoError)? {
+            return Ok(Page::default());
+        }
+        let _ = save_file
+            .seek(SeekFrom::Start(offset))
+            .map_err(GetPageError::IoError)?;
+        // Une dernière page incomplète est complétée par des zéros.
+        let mut bytes = Vec::with_capacity(Page::SIZE);
+        let _ = Read::take(save_file.as_mut(), Page::SIZE as u64)
+            .read_to_end(&mut bytes)
+            .map_err(GetPageError::IoError)?;
+        let mut page = Page::default();
+        page[..bytes.len()].copy_from_slice(&bytes);
+        Ok(page)
     }
 
-    pub fn get_page(&mut self, page_num: usize) -> Result<&mut Page, GetPageError> {
-        if page_num >= Self::MAX_PAGES {
+    fn load(&self, page_num: usize) -> Result<&RefCell<Option<Page>>, GetPageError> {
+        let Some(slot) = self.pages.get(page_num) else {
             return Err(GetPageError::MaxPageReached);
+        };
+
+        let is_loaded = slot
+            .try_borrow()
+            .map_err(|_| GetPageError::PageInUse)?
+            .is_some();
+        if is_loaded {
+            trace!(page_num, "page cache hit");
+        } else {
+            debug!(page_num, "page load");
+            let page = self.load_or_create_page(page_num)?;
+            *slot.try_borrow_mut().map_err(|_| GetPageError::PageInUse)? = Some(page);
+            self.evict_pages(Some(page_num));
         }
+        self.clock.set(self.clock.get() + 1);
+        self.last_used[page_num].set(self.clock.get());
 
-        if self.pages[page_num].is_some() {
-            // Je ne peux pas utiliser le modèle `if let` sinon j'ai une ref.
-            #[allow(clippy::unwrap_used)]
-            return Ok(self.pages[page_num].as_mut().unwrap());
+        Ok(slot)
+    }
+
+    pub fn read_page(&self, page_num: usize) -> Result<PageReadGuard<'_>, GetPageError> {
+        let slot = self.load(page_num)?;
+        let page = slot.try_borrow().map_err(|_| GetPageError::PageInUse)?;
+        // La page vient d'être chargée, l'option est nécessairement `Some`.
+        let page = Ref::filter_map(page, Option::as_ref).map_err(|_| GetPageError::PageInUse)?;
+
+        Ok(PageReadGuard { page_num, page })
+    }
+
+    pub fn write_page(&self, page_num: usize) -> Result<PageWriteGuard<'_>, GetPageError> {
+        if self.read_only {
+            return Err(GetPageError::ReadOnly);
         }
+        let slot = self.load(page_num)?;
+        let page = slot.try_borrow_mut().map_err(|_| GetPageError::PageInUse)?;
+        // La page vient d'être chargée, l'option est nécessairement `Some`.
+        let page = RefMut::filter_map(page, Option::as_mut).map_err(|_| GetPageError::PageInUse)?;
+        self.dirty[page_num].set(true);
 
-        let page = if let Some(save_file) = self.save_file.as_mut() {
-            let offset = 8 + Page::SIZE * page_num;
-            let seek_from = SeekFrom::Start(offset as u64);
-            let _ = save_file.seek(seek_from).map_err(GetPageError::IoError)?;
-            let mut page = Page::default();
-            if let Err(io_error) = save_file.read_exact(&mut page[..]) {
-                if io_error.kind() != ErrorKind::UnexpectedEof {
-                    return Err(GetPageError::IoError(io_error));
-                }
-            }
-            page
-        } else {
-            Page::default()
-        };
+        Ok(PageWriteGuard { page_num, page })
+    }
+
+    /*
+     * Accès par position dans une page : les octets sont copiés, aucun emprunt
+     * de la page ne survit à l'appel. Comme pour les vues, une position en
+     * dehors de la page est une erreur de programmation et panique.
+     */
 
-        self.pages[page_num] = Some(page);
-        // L'option ici est nécessairement `Some`.
-        #[allow(clippy::unwrap_used)]
-        Ok(self.pages[page_num].as_mut().unwrap())
+    pub fn read_at(
+        &self,
+        page_num: usize,
+        offset: usize,
+        len: usize,
+    ) -> Result<Vec<u8>, GetPageError> {
+        let page = self.read_page(page_num)?;
+        let end = offset.checked_add(len);
+        assert!(
+            end.is_some_and(|end| end <= Page::SIZE),
+            "Page view out of bounds."
+        );
+        Ok(page[offset..(offset + len)].to_vec())
     }
 
+    pub fn write_at(&self, page_num: usize, offset: usize, src: &[u8]) -> Result<(), GetPageError> {
+        self.write_page(page_num)?.write_bytes(offset, src);
+        Ok(())
+    }
+
+    pub fn read_u32(&self, page_num: usize, offset: usize) -> Result<u32, GetPageError> {
+        Ok(self.read_page(page_num)?.read_u32_be(offset))
+    }
+
+    pub fn write_u32(
+        &self,
+        page_num: usize,
+        offset: usize,
+        value: u32,
+    ) -> Result<(), GetPageError> {
+        self.write_page(page_num)?.write_u32_be(offset, value);
+        Ok(())
+    }
+
+    /// Sauvegarde la base dans `file_path`, ou dans le fichier ouvert si aucun n'est donné.
+    ///
+    /// Les données sont d'abord écrites dans `<fichier>.tmp` qui remplace ensuite
+    /// le fichier d'origine par un renommage atomique : une sauvegarde interrompue
+    /// laisse l'ancienne version intacte.
     pub fn save_to_disk(
-        &mut self,
+        &self,
         file_path: Option<&str>,
+        nb_rows: usize,
     ) -> Result<(), SaveToDiskError> {
-        let save_file = if let Some(path) = file_path {
-            &mut File::create(path).map_err(SaveToDiskError::IoError)?
-        } else if let Some(file) = self.save_file.as_mut() {
-            let () = file.set_len(0).map_err(SaveToDiskError::IoError)?;
-            let seek_from = SeekFrom::Start(0);
-            let _ = file.seek(seek_from).map_err(SaveToDiskError::IoError)?;
-            file
-        } else {
+        let primary_path = self
+            .save_file
+            .borrow()
+            .as_ref()
+            .and_then(|save_file| save_file.path.clone());
+        let Some(target_path) = file_path.map(str::to_owned).or(primary_path.clone()) else {
             return Err(SaveToDiskError::NoFileToWriteProvided);
         };
+        // Une sauvegarde dans un autre fichier reste possible, comme `.backup`.
+        if self.read_only && primary_path.as_deref() == Some(target_path.as_str()) {
+            return Err(SaveToDiskError::ReadOnly);
+        }
+
+        let tmp_path = format!("{target_path}.tmp");
+        info!(path = %target_path, nb_rows, "flush to disk");
+        let written = self
+            .write_database_file(&tmp_path, nb_rows, &mut |_, _| {})
+            .and_then(|()| {
+                self.vfs
+                    .rename(&tmp_path, &target_path)
+                    .map_err(SaveToDiskError::IoError)
+            });
+        if let Err(e) = written {
+            let _ = self.vfs.remove(&tmp_path);
+            return Err(e);
+        }
+
+        if self.synchronous.get() == Synchronous::Full {
+            let () = self
+                .vfs
+                .sync_dir(&target_path)
+                .map_err(SaveToDiskError::IoError)?;
+        }
 
-        for page_bytes in self.pages.iter().flatten() {
-            let table_page_bytes_written = save_file
-                .write(&page_bytes[..])
+        // Le fichier ouvert désigne toujours l'ancienne version, remplacée par le renommage.
+        if primary_path.as_deref() == Some(target_path.as_str()) {
+            let save_file =
+                SaveFile::open(self.vfs.as_ref(), &target_path).map_err(SaveToDiskError::Reopen)?;
+            *self.save_file.borrow_mut() = Some(save_file);
+            self.truncated.set(false);
+            // Les pages en mémoire sont désormais celles du fichier.
+            for dirty in &self.dirty {
+                dirty.set(false);
+            }
+            self.write_pending_since.set(None);
+            self.evict_pages(None);
+        }
+
+        Ok(())
+    }
+
+    /// Copie la base, pages modifiées en mémoire comprises, dans un fichier distinct
+    /// sans changer le fichier ouvert. `progress` reçoit le nombre de pages écrites
+    /// et le nombre total de pages.
+    pub fn backup(
+        &self,
+        file_path: &str,
+        nb_rows: usize,
+        progress: &mut dyn FnMut(usize, usize),
+    ) -> Result<(), SaveToDiskError> {
+        let tmp_path = format!("{file_path}.tmp");
+        info!(path = %file_path, nb_rows, "backup");
+        let written = self
+            .write_database_file(&tmp_path, nb_rows, progress)
+            .and_then(|()| {
+                self.vfs
+                    .rename(&tmp_path, file_path)
+                    .map_err(SaveToDiskError::IoError)
+            });
+        if let Err(e) = written {
+            let _ = self.vfs.remove(&tmp_path);
+            return Err(e);
+        }
+
+        if self.synchronous.get() == Synchronous::Full {
+            let () = self
+                .vfs
+                .sync_dir(file_path)
                 .map_err(SaveToDiskError::IoError)?;
-            if page_bytes.len() != table_page_bytes_written {
-                return Err(SaveToDiskError::NotAllBytesWritten);
+        }
+
+        Ok(())
+    }
+
+    fn write_database_file(
+        &self,
+        file_path: &str,
+        nb_rows: usize,
+        progress: &mut dyn FnMut(usize, usize),
+    ) -> Result<(), SaveToDiskError> {
+        let mut save_file = self
+            .vfs
+            .open(file_path, OpenMode::Create)
+            .map_err(SaveToDiskError::IoError)?;
+        self.write_database(save_file.as_mut(), nb_rows, progress)?;
+        if self.synchronous.get() == Synchronous::Full {
+            let () = save_file.sync().map_err(SaveToDiskError::IoError)?;
+        }
+        Ok(())
+    }
+
+    /// Écrit la base au format d'un fichier de sauvegarde, sans toucher au
+    /// fichier ouvert.
+    pub fn write_to(&self, out: &mut dyn Write, nb_rows: usize) -> Result<(), SaveToDiskError> {
+        self.write_database(out, nb_rows, &mut |_, _| {})
+    }
+
+    fn write_database(
+        &self,
+        save_file: &mut dyn Write,
+        nb_rows: usize,
+        progress: &mut dyn FnMut(usize, usize),
+    ) -> Result<(), SaveToDiskError> {
+        let header = FileHeader::new()
+            .with_compression(cfg!(feature = "compression"))
+            .with_nb_rows(nb_rows as u64);
+        let mut offset = 0;
+        write_all_at(
+            save_file,
+            &<[u8; FileHeader::SIZE]>::from(header),
+            &mut offset,
+        )?;
+
+        let nb_pages = self.page_count().map_err(SaveToDiskError::IoError)?;
+        debug!(nb_pages, "writing pages");
+
+        #[cfg(feature = "compression")]
+        let mut compressed_pages = Vec::<Vec<u8>>::with_capacity(nb_pages);
+
+        for page_num in 0..nb_pages {
+            let page = self.pages[page_num]
+                .try_borrow()
+                .map_err(|_| SaveToDiskError::PageInUse)?;
+            // Les pages jamais chargées sont recopiées depuis l'ancien fichier.
+            let loaded_page;
+            let page_bytes: &[u8] = match page.as_ref() {
+                Some(page) => &page[..],
+                None => {
+                    loaded_page = self
+                        .load_or_create_page(page_num)
+                        .map_err(SaveToDiskError::GetPage)?;
+                    &loaded_page[..]
+                }
+            };
+
+            // Les pages compressées sont écrites après le répertoire, qui doit
+            // connaître toutes leurs tailles.
+            #[cfg(feature = "compression")]
+            compressed_pages.push(compress_page(page_bytes));
+
+            #[cfg(not(feature = "compression"))]
+            write_all_at(save_file, page_bytes, &mut offset)?;
+            progress(page_num + 1, nb_pages);
+        }
+
+        #[cfg(feature = "compression")]
+        {
+            let directory = PageDirectory::for_pages(&compressed_pages);
+            let directory_bytes = Vec::<u8>::from(&directory);
+            for bytes in std::iter::once(&directory_bytes).chain(&compressed_pages) {
+                write_all_at(save_file, bytes, &mut offset)?;
             }
         }
 
@@ -195,8 +974,272 @@ impl Pager {
 impl Default for Pager {
     fn default() -> Self {
         Self {
-            save_file: None,
-            pages: [const { None }; Self::MAX_PAGES],
+            vfs: Box::new(StdVfs),
+            save_file: RefCell::new(None),
+            pages: [const { RefCell::new(None) }; Self::MAX_PAGES],
+            dirty: [const { Cell::new(false) }; Self::MAX_PAGES],
+            last_used: [const { Cell::new(0) }; Self::MAX_PAGES],
+            clock: Cell::new(0),
+            cache_size: Cell::new(Self::MAX_PAGES),
+            truncated: Cell::new(false),
+            synchronous: Cell::new(Synchronous::Full),
+            write_mode: Cell::new(WriteMode::WriteBack),
+            commit_delay: Cell::new(Duration::ZERO),
+            flush_interval: Cell::new(Duration::ZERO),
+            write_pending_since: Cell::new(None),
+            read_only: false,
+        }
+    }
+}
+
+/// `write_all` reprend les écritures partielles ou interrompues. `offset`
+/// suit la position dans le fichier, pour dire où une écriture a échoué.
+fn write_all_at(
+    out: &mut dyn Write,
+    bytes: &[u8],
+    offset: &mut u64,
+) -> Result<(), SaveToDiskError> {
+    out.write_all(bytes)
+        .map_err(|e| SaveToDiskError::WriteFailed(*offset, e))?;
+    *offset += bytes.len() as u64;
+    Ok(())
+}
+
+#[cfg(test)]
+mod pager_test {
+    use super::*;
+
+    use crate::backend::MemoryVfs;
+
+    #[test]
+    fn test_cache_size_evicts_clean_pages() {
+        let pages = (0..6)
+            .map(|page_num| {
+                let mut page = Page::default();
+                page[0] = page_num;
+                page
+            })
+            .collect();
+        let mut bytes = Vec::new();
+        Pager::from_pages(pages).write_to(&mut bytes, 0).unwrap();
+        let pager = Pager::with_backend(Box::new(MemoryBackend::new(bytes)))
+            .unwrap()
+            .with_cache_size(2);
+
+        pager.write_page(1).unwrap()[1] = 1;
+        for page_num in 2..6 {
+            assert_eq!(pager.read_page(page_num).unwrap()[0], page_num as u8);
+        }
+        // La page modifiée reste en mémoire, en plus de la dernière lue.
+        assert_eq!(pager.cached_pages(), 2);
+        assert_eq!(pager.memory_usage().page_bytes, 2 * Page::SIZE);
+        assert_eq!(pager.read_page(1).unwrap()[1], 1);
+        assert_eq!(pager.read_page(2).unwrap()[0], 2);
+        assert_eq!(pager.page_count().unwrap(), 6);
+    }
+
+    /// N'accepte que 1000 octets par écriture, jusqu'à `capacity`.
+    struct ShortWriter {
+        bytes: Vec<u8>,
+        capacity: usize,
+    }
+    impl Write for ShortWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            let room = self.capacity - self.bytes.len();
+            if room == 0 {
+                return Err(io::Error::other("disk full"));
+            }
+            let len = buf.len().min(room).min(1000);
+            self.bytes.extend_from_slice(&buf[..len]);
+            Ok(len)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_write_to_short_writes() {
+        let pager = Pager::from_pages((0..3).map(|_| Page::default()).collect());
+        let mut expected = Vec::new();
+        pager.write_to(&mut expected, 0).unwrap();
+
+        let mut out = ShortWriter {
+            bytes: Vec::new(),
+            capacity: usize::MAX,
+        };
+        pager.write_to(&mut out, 0).unwrap();
+        assert_eq!(out.bytes, expected);
+
+        // L'en-tête est écrit, l'écriture suivante échoue juste après.
+        let mut out = ShortWriter {
+            bytes: Vec::new(),
+            capacity: FileHeader::SIZE + 10,
+        };
+        let result = pager.write_to(&mut out, 0);
+        assert!(matches!(
+            result,
+            Err(SaveToDiskError::WriteFailed(offset, _)) if offset == FileHeader::SIZE as u64
+        ));
+    }
+
+    #[test]
+    fn test_load_truncated_file() {
+        let pages = (1..=3)
+            .map(|page_num| {
+                let mut page = Page::default();
+                page.fill(page_num);
+                page
+            })
+            .collect();
+        let mut bytes = Vec::new();
+        Pager::from_pages(pages).write_to(&mut bytes, 0).unwrap();
+        // Les pages compressées tiennent en quelques octets.
+        let nb_bytes_lost = if cfg!(feature = "compression") {
+            1
+        } else {
+            100
+        };
+        bytes.truncate(bytes.len() - nb_bytes_lost);
+        let pager = Pager::with_backend(Box::new(MemoryBackend::new(bytes))).unwrap();
+
+        assert!(pager.read_page(0).unwrap().iter().all(|byte| *byte == 1));
+        #[cfg(not(feature = "compression"))]
+        {
+            let page = pager.read_page(2).unwrap();
+            assert!(page[..Page::SIZE - 100].iter().all(|byte| *byte == 3));
+            assert!(page[Page::SIZE - 100..].iter().all(|byte| *byte == 0));
         }
+        // La dernière page compressée est coupée : elle est signalée, sans panique.
+        #[cfg(feature = "compression")]
+        assert!(matches!(
+            pager.read_page(2),
+            Err(GetPageError::Corrupted(2))
+        ));
+    }
+
+    #[test]
+    fn test_read_write_at() {
+        let pager = Pager::default();
+        pager.write_at(2, 10, b"abc").unwrap();
+        pager.write_u32(2, 20, 0xdead_beef).unwrap();
+
+        assert_eq!(pager.read_at(2, 9, 5).unwrap(), b"\0abc\0");
+        assert_eq!(pager.read_u32(2, 20).unwrap(), 0xdead_beef);
+        // Aucun emprunt ne survit aux accès.
+        assert!(pager.write_page(2).is_ok());
+    }
+
+    #[test]
+    fn test_save_through_memory_vfs() {
+        let vfs = MemoryVfs::default();
+        let pager = Pager::with_vfs(Box::new(vfs.clone()), Some("a.db")).unwrap();
+        assert_eq!(vfs.get_bytes("a.db"), Some(Vec::new()));
+        pager.write_at(1, 10, b"abc").unwrap();
+        pager.save_to_disk(None, 0).unwrap();
+        assert!(!vfs.exists("a.db.tmp"));
+
+        let pager = Pager::with_vfs(Box::new(vfs.clone()), Some("a.db")).unwrap();
+        let file_len = vfs.get_bytes("a.db").unwrap().len() as u64;
+        assert_eq!(pager.file_len().unwrap(), file_len);
+        assert_eq!(pager.page_count().unwrap(), 2);
+        assert_eq!(pager.cached_pages(), 0);
+        assert_eq!(pager.read_at(1, 10, 3).unwrap(), b"abc");
+        assert_eq!(pager.cached_pages(), 1);
+        assert!(!std::path::Path::new("a.db").exists());
+    }
+
+    #[test]
+    fn test_page_guards_are_exclusive() {
+        let pager = Pager::default();
+
+        let read_guard = pager.read_page(0).unwrap();
+        let other_read_guard = pager.read_page(0).unwrap();
+        assert!(matches!(pager.write_page(0), Err(GetPageError::PageInUse)));
+        assert!(pager.write_page(1).is_ok());
+        drop(read_guard);
+        drop(other_read_guard);
+
+        let mut write_guard = pager.write_page(0).unwrap();
+        write_guard[0] = 42;
+        assert!(matches!(pager.read_page(0), Err(GetPageError::PageInUse)));
+        drop(write_guard);
+
+        assert_eq!(pager.read_page(0).unwrap()[0], 42);
+        assert!(matches!(
+            pager.read_page(Pager::MAX_PAGES),
+            Err(GetPageError::MaxPageReached)
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn test_compressed_file_round_trip() {
+        let file_path = std::env::temp_dir().join(format!(
+            "my_db_pager_test_compressed_{}.db",
+            std::process::id()
+        ));
+        let file_path = file_path.to_str().unwrap();
+
+        let pager = Pager::default();
+        pager.write_page(0).unwrap()[..4].copy_from_slice(&[0xca, 0xfe, 0xba, 0xbe]);
+        pager.write_page(2).unwrap()[Page::SIZE - 1] = 42;
+        pager.save_to_disk(Some(file_path), 0).unwrap();
+
+        let file_len = std::fs::metadata(file_path).unwrap().len() as usize;
+        assert!(file_len < FileHeader::SIZE + Page::SIZE);
+
+        let pager = Pager::new(Some(file_path)).unwrap();
+        assert_eq!(&pager.read_page(0).unwrap()[..4], &[0xca, 0xfe, 0xba, 0xbe]);
+        assert!(pager.read_page(1).unwrap().iter().all(|byte| *byte == 0));
+        assert_eq!(pager.read_page(2).unwrap()[Page::SIZE - 1], 42);
+        std::fs::remove_file(file_path).unwrap();
+    }
+
+    #[test]
+    fn test_truncate_drops_file_pages() {
+        let file_path = std::env::temp_dir().join(format!(
+            "my_db_pager_test_truncate_{}.db",
+            std::process::id()
+        ));
+        let file_path = file_path.to_str().unwrap();
+
+        let pager = Pager::default();
+        pager.write_page(3).unwrap()[0] = 42;
+        pager.save_to_disk(Some(file_path), 0).unwrap();
+
+        let pager = Pager::new(Some(file_path)).unwrap();
+        pager.truncate().unwrap();
+        assert_eq!(pager.nb_pages_in_file().unwrap(), 0);
+        pager.save_to_disk(None, 0).unwrap();
+
+        let pager = Pager::new(Some(file_path)).unwrap();
+        assert_eq!(pager.nb_pages_in_file().unwrap(), 0);
+        assert_eq!(pager.read_page(3).unwrap()[0], 0);
+        std::fs::remove_file(file_path).unwrap();
+    }
+
+    #[test]
+    fn test_read_only_pager_refuses_writes() {
+        let file_path = std::env::temp_dir().join(format!(
+            "my_db_pager_test_read_only_{}.db",
+            std::process::id()
+        ));
+        let file_path = file_path.to_str().unwrap();
+
+        let pager = Pager::default();
+        pager.write_page(0).unwrap()[0] = 42;
+        pager.save_to_disk(Some(file_path), 0).unwrap();
+
+        let pager = Pager::new_read_only(Some(file_path)).unwrap();
+        assert_eq!(pager.read_page(0).unwrap()[0], 42);
+        assert!(matches!(pager.write_page(0), Err(GetPageError::ReadOnly)));
+        assert!(matches!(pager.truncate(), Err(GetPageError::ReadOnly)));
+        assert!(matches!(
+            pager.save_to_disk(None, 0),
+            Err(SaveToDiskError::ReadOnly)
+        ));
+        std::fs::remove_file(file_path).unwrap();
     }
 }
@@ -0,0 +1,737 @@
+use std::iter::Peekable;
+use std::slice::Iter;
+
+use crate::expr::{Arithmetic, Comparison, Expr, Value};
+use crate::lexer::{Keyword, LexError, Token, TokenKind, tokenize};
+
+/*
+ * Arbre syntaxique des instructions. Il décrit seulement ce qui a été écrit :
+ * les colonnes, les tables et les valeurs ne sont vérifiées qu'en préparant
+ * l'exécution, dans `statement.rs`.
+ */
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Clone)]
+pub enum Stmt {
+    Select {
+        projection: Projection,
+        /// `None` sans `from` : la table des lignes.
+        table: Option<String>,
+        filter: Option<Expr>,
+        order: Option<Order>,
+        limit: Option<u64>,
+    },
+    Insert {
+        values: Vec<Value>,
+        returning: Option<Projection>,
+    },
+    /// `delete` ou `truncate`, sans condition.
+    Delete {
+        returning: Option<Projection>,
+    },
+    Pragma {
+        name: String,
+        value: Option<String>,
+    },
+    Explain(Box<Stmt>),
+    /// `create virtual table name using module(arguments)`.
+    CreateVirtualTable {
+        name: String,
+        module: String,
+        arguments: Vec<Value>,
+    },
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Clone)]
+pub enum Projection {
+    /// `select` ou `select *`.
+    All,
+    Columns(Vec<String>),
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Clone)]
+pub struct Order {
+    pub column: String,
+    /// Nom donné après `collate`, vérifié à la préparation.
+    pub collation: Option<String>,
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq)]
+pub enum ParseError {
+    Lex(LexError),
+    /// L'instruction ne commence pas par un mot-clé connu.
+    UnrecognizedStatement,
+    /// Position du token trouvé, ou longueur de l'instruction en fin
+    /// d'instruction, ce qui était attendu, et le token trouvé à la place,
+    /// `None` en fin d'instruction.
+    UnexpectedToken(usize, &'static str, Option<String>),
+    /// Position du token qui dépasse `Parser::MAX_DEPTH` niveaux d'imbrication.
+    TooDeep(usize),
+}
+impl ParseError {
+    /// Position en octets dans l'instruction de ce qui l'a rendue invalide.
+    pub fn get_offset(&self) -> Option<usize> {
+        match self {
+            Self::Lex(LexError::UnterminatedString(offset) | LexError::NumberTooLarge(offset))
+            | Self::UnexpectedToken(offset, _, _)
+            | Self::TooDeep(offset) => Some(*offset),
+            Self::UnrecognizedStatement => None,
+        }
+    }
+}
+
+pub fn parse(input: &str) -> Result<Stmt, ParseError> {
+    let tokens = tokenize(input).map_err(ParseError::Lex)?;
+    let mut parser = Parser {
+        tokens: tokens.iter().peekable(),
+        end: input.len(),
+        depth: 0,
+    };
+    let stmt = parser.parse_stmt()?;
+    parser.expect_end()?;
+    Ok(stmt)
+}
+
+struct Parser<'a> {
+    tokens: Peekable<Iter<'a, Token>>,
+    /// Position de la fin de l'instruction.
+    end: usize,
+    /// Niveaux d'imbrication ouverts, voir `nested`.
+    depth: usize,
+}
+impl Parser<'_> {
+    /// L'analyse, puis l'évaluation et la libération des expressions, sont
+    /// récursives : au-delà, la pile déborderait et arrêterait le processus.
+    const MAX_DEPTH: usize = 128;
+
+    /// Ouvre un niveau d'imbrication : parenthèses, appel de fonction,
+    /// `explain`, ou opérateur enchaîné qui ajoute un nœud au-dessus des autres.
+    /// Celui qui l'ouvre remet `depth` à sa valeur en terminant.
+    fn enter(&mut self) -> Result<(), ParseError> {
+        if self.depth == Self::MAX_DEPTH {
+            let offset = self.tokens.peek().map_or(self.end, |token| token.offset);
+            return Err(ParseError::TooDeep(offset));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// Analyse un élément un niveau plus bas.
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Self) -> Result<T, ParseError>,
+    ) -> Result<T, ParseError> {
+        let depth = self.depth;
+        self.enter()?;
+        let result = parse(self);
+        self.depth = depth;
+        result
+    }
+
+    fn unexpected(&mut self, expected: &'static str) -> ParseError {
+        match self.tokens.peek() {
+            Some(token) => {
+                ParseError::UnexpectedToken(token.offset, expected, Some(token.to_string()))
+            }
+            None => ParseError::UnexpectedToken(self.end, expected, None),
+        }
+    }
+
+    fn next_is_keyword(&mut self, keyword: Keyword) -> bool {
+        self.tokens
+            .next_if(|token| token.is_keyword(keyword))
+            .is_some()
+    }
+
+    fn next_is(&mut self, kind: &TokenKind) -> bool {
+        self.tokens.next_if(|token| token.kind == *kind).is_some()
+    }
+
+    fn expect_keyword(&mut self, keyword: Keyword) -> Result<(), ParseError> {
+        if self.next_is_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.unexpected(keyword.as_str()))
+        }
+    }
+
+    fn expect_end(&mut self) -> Result<(), ParseError> {
+        match self.tokens.peek() {
+            None => Ok(()),
+            Some(_) => Err(self.unexpected("end of statement")),
+        }
+    }
+
+    fn parse_stmt(&mut self) -> Result<Stmt, ParseError> {
+        let Some(keyword) = self.tokens.next().and_then(Token::get_keyword) else {
+            return Err(ParseError::UnrecognizedStatement);
+        };
+        match keyword {
+            Keyword::Select => self.parse_select(),
+            Keyword::Insert => self.parse_insert(),
+            Keyword::Delete | Keyword::Truncate => Ok(Stmt::Delete {
+                returning: self.parse_returning()?,
+            }),
+            Keyword::Pragma => self.parse_pragma(),
+            Keyword::Explain => Ok(Stmt::Explain(Box::new(self.nested(Self::parse_stmt)?))),
+            Keyword::Create => self.parse_create_virtual_table(),
+            _ => Err(ParseError::UnrecognizedStatement),
+        }
+    }
+
+    /// `select [* | colonne, ...] [from table] [where expr]
+    /// [order by colonne [collate nom] [asc]] [limit n]`.
+    ///
+    /// `select n` reste un raccourci pour `select where id = n`.
+    fn parse_select(&mut self) -> Result<Stmt, ParseError> {
+        if let Some(Token {
+            kind: TokenKind::Number(id),
+            ..
+        }) = self
+            .tokens
+            .next_if(|token| matches!(token.kind, TokenKind::Number(_)))
+        {
+            return Ok(Stmt::Select {
+                projection: Projection::All,
+                table: None,
+                filter: Some(Expr::Compare(
+                    Box::new(Expr::Column("id".to_owned())),
+                    Comparison::Eq,
+                    Box::new(Expr::Value(Value::Number(*id))),
+                )),
+                order: None,
+                limit: self.parse_limit()?,
+            });
+        }
+
+        let projection = self.parse_projection()?;
+        let table = if self.next_is_keyword(Keyword::From) {
+            Some(
+                self.parse_column()
+                    .map_err(|_| self.unexpected("table name"))?,
+            )
+        } else {
+            None
+        };
+        let filter = if self.next_is_keyword(Keyword::Where) {
+            Some(self.parse_expr()?)
+        } else {
+            None
+        };
+        let order = if self.next_is_keyword(Keyword::Order) {
+            self.expect_keyword(Keyword::By)?;
+            let column = self.parse_column()?;
+            let collation = if self.next_is_keyword(Keyword::Collate) {
+                Some(
+                    self.parse_column()
+                        .map_err(|_| self.unexpected("collation"))?,
+                )
+            } else {
+                None
+            };
+            let _ = self.next_is_keyword(Keyword::Asc);
+            Some(Order { column, collation })
+        } else {
+            None
+        };
+        let limit = self.parse_limit()?;
+
+        Ok(Stmt::Select {
+            projection,
+            table,
+            filter,
+            order,
+            limit,
+        })
+    }
+
+    fn parse_projection(&mut self) -> Result<Projection, ParseError> {
+        if self.next_is(&TokenKind::Star) {
+            return Ok(Projection::All);
+        }
+        let starts_columns = self.tokens.peek().is_some_and(|token| {
+            matches!(token.kind, TokenKind::Word(_)) && token.get_keyword().is_none()
+        });
+        if !starts_columns {
+            return Ok(Projection::All);
+        }
+
+        self.parse_columns().map(Projection::Columns)
+    }
+
+    fn parse_columns(&mut self) -> Result<Vec<String>, ParseError> {
+        let mut columns = vec![self.parse_column()?];
+        while self.next_is(&TokenKind::Comma) {
+            columns.push(self.parse_column()?);
+        }
+        Ok(columns)
+    }
+
+    fn parse_limit(&mut self) -> Result<Option<u64>, ParseError> {
+        if !self.next_is_keyword(Keyword::Limit) {
+            return Ok(None);
+        }
+        match self.tokens.peek().map(|token| &token.kind) {
+            Some(TokenKind::Number(limit)) => {
+                let limit = *limit;
+                let _ = self.tokens.next();
+                Ok(Some(limit))
+            }
+            _ => Err(self.unexpected("limit")),
+        }
+    }
+
+    /// Un nom de colonne ne peut pas être un mot-clé.
+    fn parse_column(&mut self) -> Result<String, ParseError> {
+        match self.tokens.peek() {
+            Some(token) if token.get_keyword().is_none() => match &token.kind {
+                TokenKind::Word(column) => {
+                    let column = column.clone();
+                    let _ = self.tokens.next();
+                    Ok(column)
+                }
+                _ => Err(self.unexpected("column")),
+            },
+            _ => Err(self.unexpected("column")),
+        }
+    }
+
+    /// `or` lie moins fort que `and`, qui lie moins fort que les comparaisons
+    /// et `between`. Les parenthèses regroupent des conditions.
+    fn parse_expr(&mut self) -> Result<Expr, ParseError> {
+        let depth = self.depth;
+        let mut expr = self.parse_and()?;
+        while self.next_is_keyword(Keyword::Or) {
+            self.enter()?;
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        self.depth = depth;
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, ParseError> {
+        let depth = self.depth;
+        let mut expr = self.parse_comparison()?;
+        while self.next_is_keyword(Keyword::And) {
+            self.enter()?;
+            expr = Expr::And(Box::new(expr), Box::new(self.parse_comparison()?));
+        }
+        self.depth = depth;
+        Ok(expr)
+    }
+
+    /// Une expression seule, sans comparaison, est aussi une condition.
+    fn parse_comparison(&mut self) -> Result<Expr, ParseError> {
+        let left = self.parse_sum()?;
+        let comparison = self.tokens.peek().and_then(|token| match token.kind {
+            TokenKind::Equals => Some(Comparison::Eq),
+            TokenKind::NotEquals => Some(Comparison::Ne),
+            TokenKind::Less => Some(Comparison::Lt),
+            TokenKind::LessOrEquals => Some(Comparison::Le),
+            TokenKind::Greater => Some(Comparison::Gt),
+            TokenKind::GreaterOrEquals => Some(Comparison::Ge),
+            _ => None,
+        });
+        if let Some(comparison) = comparison {
+            let _ = self.tokens.next();
+            let right = self.parse_sum()?;
+            return Ok(Expr::Compare(Box::new(left), comparison, Box::new(right)));
+        }
+        if self.next_is_keyword(Keyword::Between) {
+            let low = self.parse_sum()?;
+            self.expect_keyword(Keyword::And)?;
+            let high = self.parse_sum()?;
+            return Ok(Expr::Between {
+                expr: Box::new(left),
+                low: Box::new(low),
+                high: Box::new(high),
+            });
+        }
+        Ok(left)
+    }
+
+    /// `+` et `-` lient moins fort que `*` et `/`.
+    fn parse_sum(&mut self) -> Result<Expr, ParseError> {
+        let depth = self.depth;
+        let mut expr = self.parse_product()?;
+        loop {
+            let arithmetic = match self.tokens.peek().map(|token| &token.kind) {
+                Some(TokenKind::Plus) => Arithmetic::Add,
+                Some(TokenKind::Minus) => Arithmetic::Sub,
+                _ => {
+                    self.depth = depth;
+                    return Ok(expr);
+                }
+            };
+            let _ = self.tokens.next();
+            self.enter()?;
+            let right = self.parse_product()?;
+            expr = Expr::Arithmetic(Box::new(expr), arithmetic, Box::new(right));
+        }
+    }
+
+    fn parse_product(&mut self) -> Result<Expr, ParseError> {
+        let depth = self.depth;
+        let mut expr = self.parse_operand()?;
+        loop {
+            let arithmetic = match self.tokens.peek().map(|token| &token.kind) {
+                Some(TokenKind::Star) => Arithmetic::Mul,
+                Some(TokenKind::Slash) => Arithmetic::Div,
+                _ => {
+                    self.depth = depth;
+                    return Ok(expr);
+                }
+            };
+            let _ = self.tokens.next();
+            self.enter()?;
+            let right = self.parse_operand()?;
+            expr = Expr::Arithmetic(Box::new(expr), arithmetic, Box::new(right));
+        }
+    }
+
+    /// Colonne, valeur, appel de fonction `nom(expr, ...)` ou expression entre
+    /// parenthèses.
+    fn parse_operand(&mut self) -> Result<Expr, ParseError> {
+        if self.next_is(&TokenKind::LeftParen) {
+            let expr = self.nested(Self::parse_expr)?;
+            if !self.next_is(&TokenKind::RightParen) {
+                return Err(self.unexpected("')'"));
+            }
+            return Ok(expr);
+        }
+
+        let operand = match self.tokens.peek() {
+            Some(token) if token.get_keyword().is_some() => None,
+            Some(token) => match &token.kind {
+                TokenKind::Word(column) => Some(Expr::Column(column.clone())),
+                TokenKind::Number(number) => Some(Expr::Value(Value::Number(*number))),
+                TokenKind::String(text) => Some(Expr::Value(Value::Text(text.clone()))),
+                _ => None,
+            },
+            None => None,
+        };
+        let Some(operand) = operand else {
+            return Err(self.unexpected("value"));
+        };
+        let _ = self.tokens.next();
+
+        match operand {
+            Expr::Column(function) if self.next_is(&TokenKind::LeftParen) => {
+                let mut arguments = Vec::new();
+                if !self.next_is(&TokenKind::RightParen) {
+                    arguments.push(self.nested(Self::parse_expr)?);
+                    while self.next_is(&TokenKind::Comma) {
+                        arguments.push(self.nested(Self::parse_expr)?);
+                    }
+                    if !self.next_is(&TokenKind::RightParen) {
+                        return Err(self.unexpected("')'"));
+                    }
+                }
+                Ok(Expr::Call {
+                    function,
+                    arguments,
+                })
+            }
+            operand => Ok(operand),
+        }
+    }
+
+    /// `insert valeur valeur ... [returning ...]`, les valeurs séparées par des
+    /// espaces. Une valeur `returning` doit être écrite entre guillemets.
+    fn parse_insert(&mut self) -> Result<Stmt, ParseError> {
+        let mut values = Vec::new();
+        while let Some(token) = self.tokens.next_if(|token| {
+            matches!(
+                token.kind,
+                TokenKind::Word(_) | TokenKind::Number(_) | TokenKind::String(_)
+            ) && !token.is_keyword(Keyword::Returning)
+        }) {
+            values.push(match &token.kind {
+                TokenKind::Number(number) => Value::Number(*number),
+                TokenKind::Word(text) | TokenKind::String(text) => Value::Text(text.clone()),
+                _ => unreachable!("filtered by next_if"),
+            });
+        }
+        Ok(Stmt::Insert {
+            values,
+            returning: self.parse_returning()?,
+        })
+    }
+
+    /// `returning * | colonne, ...` après une instruction qui modifie des lignes.
+    fn parse_returning(&mut self) -> Result<Option<Projection>, ParseError> {
+        if !self.next_is_keyword(Keyword::Returning) {
+            return Ok(None);
+        }
+        if self.next_is(&TokenKind::Star) {
+            return Ok(Some(Projection::All));
+        }
+        self.parse_columns()
+            .map(|columns| Some(Projection::Columns(columns)))
+    }
+
+    /// `create virtual table nom using module([valeur, ...])`.
+    fn parse_create_virtual_table(&mut self) -> Result<Stmt, ParseError> {
+        self.expect_keyword(Keyword::Virtual)?;
+        self.expect_keyword(Keyword::Table)?;
+        let name = self
+            .parse_column()
+            .map_err(|_| self.unexpected("table name"))?;
+        self.expect_keyword(Keyword::Using)?;
+        let module = self
+            .parse_column()
+            .map_err(|_| self.unexpected("module name"))?;
+        if !self.next_is(&TokenKind::LeftParen) {
+            return Err(self.unexpected("'('"));
+        }
+        let mut arguments = Vec::new();
+        if !self.next_is(&TokenKind::RightParen) {
+            loop {
+                arguments.push(match self.tokens.peek().map(|token| &token.kind) {
+                    Some(TokenKind::Number(number)) => Value::Number(*number),
+                    Some(TokenKind::Word(text) | TokenKind::String(text)) => {
+                        Value::Text(text.clone())
+                    }
+                    _ => return Err(self.unexpected("value")),
+                });
+                let _ = self.tokens.next();
+                if self.next_is(&TokenKind::RightParen) {
+                    break;
+                }
+                if !self.next_is(&TokenKind::Comma) {
+                    return Err(self.unexpected("',' or ')'"));
+                }
+            }
+        }
+        Ok(Stmt::CreateVirtualTable {
+            name,
+            module,
+            arguments,
+        })
+    }
+
+    /// `pragma nom [= valeur]`.
+    fn parse_pragma(&mut self) -> Result<Stmt, ParseError> {
+        let name = self
+            .parse_column()
+            .map_err(|_| self.unexpected("pragma name"))?;
+        let value = if self.next_is(&TokenKind::Equals) {
+            let value = match self.tokens.peek().map(|token| &token.kind) {
+                Some(TokenKind::Word(value) | TokenKind::String(value)) => value.clone(),
+                Some(TokenKind::Number(value)) => value.to_string(),
+                _ => return Err(self.unexpected("pragma value")),
+            };
+            let _ = self.tokens.next();
+            Some(value)
+        } else {
+            None
+        };
+        Ok(Stmt::Pragma { name, value })
+    }
+}
+
+#[cfg(test)]
+mod parser_test {
+    use super::*;
+
+    fn id_eq(id: u64) -> Expr {
+        Expr::Compare(
+            Box::new(Expr::Column("id".to_owned())),
+            Comparison::Eq,
+            Box::new(Expr::Value(Value::Number(id))),
+        )
+    }
+
+    #[test]
+    fn test_parse_select() {
+        assert_eq!(
+            parse("select 7"),
+            Ok(Stmt::Select {
+                projection: Projection::All,
+                table: None,
+                filter: Some(id_eq(7)),
+                order: None,
+                limit: None,
+            })
+        );
+        assert_eq!(
+            parse("SELECT id, email WHERE id BETWEEN 1 AND 9 AND id = 7 ORDER BY id LIMIT 2"),
+            Ok(Stmt::Select {
+                projection: Projection::Columns(vec!["id".to_owned(), "email".to_owned()]),
+                table: None,
+                filter: Some(Expr::And(
+                    Box::new(Expr::Between {
+                        expr: Box::new(Expr::Column("id".to_owned())),
+                        low: Box::new(Expr::Value(Value::Number(1))),
+                        high: Box::new(Expr::Value(Value::Number(9))),
+                    }),
+                    Box::new(id_eq(7)),
+                )),
+                order: Some(Order {
+                    column: "id".to_owned(),
+                    collation: None,
+                }),
+                limit: Some(2),
+            })
+        );
+        assert_eq!(
+            parse("select from my_db_master order by name collate nocase asc"),
+            Ok(Stmt::Select {
+                projection: Projection::All,
+                table: Some("my_db_master".to_owned()),
+                filter: None,
+                order: Some(Order {
+                    column: "name".to_owned(),
+                    collation: Some("nocase".to_owned()),
+                }),
+                limit: None,
+            })
+        );
+        assert_eq!(
+            parse("select where id ="),
+            Err(ParseError::UnexpectedToken(17, "value", None))
+        );
+        assert_eq!(
+            parse("select limit 3 where"),
+            Err(ParseError::UnexpectedToken(
+                15,
+                "end of statement",
+                Some("where".to_owned())
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_where_precedence() {
+        let compare = |column: &str, comparison, value| {
+            Box::new(Expr::Compare(
+                Box::new(Expr::Column(column.to_owned())),
+                comparison,
+                Box::new(Expr::Value(value)),
+            ))
+        };
+        let filter = |statement| match parse(statement) {
+            Ok(Stmt::Select { filter, .. }) => filter,
+            _ => panic!("Not a select."),
+        };
+        let (id_gt, is_a, is_b) = (
+            compare("id", Comparison::Gt, Value::Number(5)),
+            compare("username", Comparison::Eq, Value::Text("a".to_owned())),
+            compare("username", Comparison::Ne, Value::Text("b".to_owned())),
+        );
+
+        let grouped = Expr::And(
+            id_gt.clone(),
+            Box::new(Expr::Or(is_a.clone(), is_b.clone())),
+        );
+        assert_eq!(
+            filter("select where id > 5 and (username = 'a' or username <> 'b')"),
+            Some(grouped.clone())
+        );
+        assert_eq!(
+            grouped.to_string(),
+            "id > 5 and (username = 'a' or username != 'b')"
+        );
+        assert_eq!(
+            filter("select where id > 5 and username = 'a' or username != 'b'"),
+            Some(Expr::Or(Box::new(Expr::And(id_gt, is_a)), is_b))
+        );
+        assert_eq!(
+            parse("select where (id > 5"),
+            Err(ParseError::UnexpectedToken(20, "')'", None))
+        );
+    }
+
+    #[test]
+    fn test_parse_other_statements() {
+        assert_eq!(
+            parse("insert 1 bob 'bob@mail.com'"),
+            Ok(Stmt::Insert {
+                values: vec![
+                    Value::Number(1),
+                    Value::Text("bob".to_owned()),
+                    Value::Text("bob@mail.com".to_owned()),
+                ],
+                returning: None,
+            })
+        );
+        assert_eq!(
+            parse("insert 1 'returning' bob@mail.com returning id, email"),
+            Ok(Stmt::Insert {
+                values: vec![
+                    Value::Number(1),
+                    Value::Text("returning".to_owned()),
+                    Value::Text("bob@mail.com".to_owned()),
+                ],
+                returning: Some(Projection::Columns(vec![
+                    "id".to_owned(),
+                    "email".to_owned()
+                ])),
+            })
+        );
+        assert_eq!(
+            parse("delete returning *"),
+            Ok(Stmt::Delete {
+                returning: Some(Projection::All)
+            })
+        );
+        assert_eq!(
+            parse("delete returning"),
+            Err(ParseError::UnexpectedToken(16, "column", None))
+        );
+        assert_eq!(
+            parse("pragma synchronous = off"),
+            Ok(Stmt::Pragma {
+                name: "synchronous".to_owned(),
+                value: Some("off".to_owned()),
+            })
+        );
+        assert_eq!(
+            parse("explain truncate"),
+            Ok(Stmt::Explain(Box::new(Stmt::Delete { returning: None })))
+        );
+        assert_eq!(
+            parse("create virtual table people using csv('people.csv')"),
+            Ok(Stmt::CreateVirtualTable {
+                name: "people".to_owned(),
+                module: "csv".to_owned(),
+                arguments: vec![Value::Text("people.csv".to_owned())],
+            })
+        );
+        assert_eq!(
+            parse("create virtual table people using csv('people.csv'"),
+            Err(ParseError::UnexpectedToken(50, "',' or ')'", None))
+        );
+        assert_eq!(
+            parse("pragma synchronous = ,"),
+            Err(ParseError::UnexpectedToken(
+                21,
+                "pragma value",
+                Some(",".to_owned())
+            ))
+        );
+        assert_eq!(parse("update"), Err(ParseError::UnrecognizedStatement));
+    }
+
+    /// L'imbrication est bornée : l'analyse récursive ne doit jamais faire
+    /// déborder la pile, quelle que soit l'entrée.
+    #[test]
+    fn test_parse_rejects_deep_nesting() {
+        let parens =
+            |depth: usize| format!("select where {}id{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(parse(&parens(Parser::MAX_DEPTH)).is_ok());
+        assert_eq!(
+            parse(&parens(20_000)),
+            Err(ParseError::TooDeep(13 + Parser::MAX_DEPTH + 1))
+        );
+
+        let explain = format!("{}select", "explain ".repeat(20_000));
+        assert!(matches!(parse(&explain), Err(ParseError::TooDeep(_))));
+        let or = format!("select where {}", vec!["id = 1"; 20_000].join(" or "));
+        assert!(matches!(parse(&or), Err(ParseError::TooDeep(_))));
+        let sum = format!("select where id = {}", vec!["1"; 20_000].join(" + "));
+        assert!(matches!(parse(&sum), Err(ParseError::TooDeep(_))));
+    }
+}
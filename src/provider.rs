@@ -0,0 +1,17 @@
+use crate::row::Row;
+use crate::table::GetRowError;
+
+/// Lignes rendues par une table virtuelle, dans un ordre quelconque.
+pub type ProviderRows<'a> = Box<dyn Iterator<Item = Result<Row, GetRowError>> + 'a>;
+
+/*
+ * Source de lignes qui ne sont pas stockées dans les pages : une table
+ * virtuelle enregistrée sous un nom avec `Database::register_provider` est
+ * lue par `select from <nom>`, avec les mêmes colonnes, filtres, tris et
+ * limites que la table des `Row`.
+ */
+pub trait TableProvider: std::fmt::Debug {
+    /// Relit toutes les lignes de la source à chaque appel. Le filtre sur l'id
+    /// est appliqué ensuite : aucune ligne n'est ignorée par la source.
+    fn scan(&self) -> ProviderRows<'_>;
+}
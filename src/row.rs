@@ -1,10 +1,13 @@
 use std::ops::Range;
 
+use crate::page_view::ReadBytes;
+
 #[cfg_attr(debug_assertions, derive(Debug))]
 #[derive(PartialEq)]
 pub enum DeserializeError {
     InvalidBytesSlice(usize),
     FromUtf8Error(std::string::FromUtf8Error),
+    Utf8Error(std::str::Utf8Error),
     TryFromSliceError {
         name: String,
         expected_size: usize,
@@ -12,13 +15,94 @@ pub enum DeserializeError {
     },
 }
 
+/// Type des valeurs d'une colonne.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "lowercase")
+)]
+#[derive(PartialEq, Clone, Copy)]
+pub enum ColumnType {
+    Integer,
+    Text,
+}
+
+/// Raison pour laquelle un texte ne peut pas être stocké dans une colonne.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq)]
+pub enum TextError {
+    /// Taille maximale en octets.
+    TooLong(usize),
+    ControlCharacter(char),
+}
+
+/// Les textes sont complétés par des `\0` sur le disque : un `\0` les
+/// couperait à la relecture, et un retour à la ligne casserait l'affichage.
+/// Tous les caractères de contrôle sont donc refusés.
+pub fn check_text(text: &str, max_size: usize) -> Result<(), TextError> {
+    if text.len() > max_size {
+        return Err(TextError::TooLong(max_size));
+    }
+    if let Some(c) = text.chars().find(|c| c.is_control()) {
+        return Err(TextError::ControlCharacter(c));
+    }
+    Ok(())
+}
+
+/*
+ * Retire les `\0` qui complètent un texte jusqu'à sa taille fixe, avant de
+ * valider l'UTF-8 : seuls les octets utiles sont validés et copiés. Un `\0`
+ * ne fait jamais partie d'un caractère sur plusieurs octets, le résultat est
+ * donc celui d'un `trim_matches` sur le texte entier.
+ */
+fn trim_padding(bytes: &[u8]) -> &[u8] {
+    let start = bytes
+        .iter()
+        .position(|byte| *byte != 0)
+        .unwrap_or(bytes.len());
+    let end = bytes
+        .iter()
+        .rposition(|byte| *byte != 0)
+        .map_or(start, |end| end + 1);
+    &bytes[start..end]
+}
+
+/// Texte vérifié par `check_text` à la désérialisation, comme à l'insertion.
+#[cfg(feature = "serde")]
+fn deserialize_text<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+    max_size: usize,
+) -> Result<String, D::Error> {
+    use serde::de::{Error, Unexpected};
+
+    let text = <String as serde::Deserialize>::deserialize(deserializer)?;
+    match check_text(&text, max_size) {
+        Ok(()) => Ok(text),
+        Err(TextError::TooLong(max_size)) => {
+            let expected = format!("at most {max_size} bytes");
+            Err(D::Error::invalid_length(text.len(), &expected.as_str()))
+        }
+        Err(TextError::ControlCharacter(c)) => Err(D::Error::invalid_value(
+            Unexpected::Char(c),
+            &"a text without control characters",
+        )),
+    }
+}
+
 #[cfg_attr(debug_assertions, derive(Debug))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 #[derive(PartialEq, Clone)]
-pub struct Id(usize);
+pub struct Id(u64);
 impl Id {
-    pub const MAX_SIZE: usize = 8;
+    // Taille fixe sur le disque, quelle que soit la taille de `usize` sur la machine.
+    pub const MAX_SIZE: usize = std::mem::size_of::<u64>();
 
-    pub fn new(id: usize) -> Self {
+    pub fn new(id: u64) -> Self {
         Self(id)
     }
 }
@@ -29,11 +113,11 @@ impl std::convert::From<Id> for [u8; Id::MAX_SIZE] {
 }
 impl std::convert::From<[u8; Self::MAX_SIZE]> for Id {
     fn from(arr: [u8; Self::MAX_SIZE]) -> Self {
-        Self(usize::from_be_bytes(arr))
+        Self(u64::from_be_bytes(arr))
     }
 }
 impl std::ops::Deref for Id {
-    type Target = usize;
+    type Target = u64;
 
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -41,14 +125,21 @@ impl std::ops::Deref for Id {
 }
 
 #[cfg_attr(debug_assertions, derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(transparent))]
 #[derive(PartialEq, Clone)]
 pub struct Username(String);
 impl Username {
     pub const MAX_SIZE: usize = 32;
 
+    /// Sans vérification : voir `try_new` pour un texte venu de l'extérieur.
     pub fn new(username: String) -> Self {
         Self(username)
     }
+
+    pub fn try_new(username: String) -> Result<Self, TextError> {
+        check_text(&username, Self::MAX_SIZE)?;
+        Ok(Self(username))
+    }
 }
 impl std::convert::From<Username> for [u8; Username::MAX_SIZE] {
     fn from(username: Username) -> [u8; Username::MAX_SIZE] {
@@ -63,10 +154,8 @@ impl std::convert::TryFrom<[u8; Self::MAX_SIZE]> for Username {
     type Error = DeserializeError;
 
     fn try_from(arr: [u8; Self::MAX_SIZE]) -> Result<Self, Self::Error> {
-        let username = String::from_utf8(Vec::<u8>::from(arr))
-            .map_err(DeserializeError::FromUtf8Error)?
-            .trim_matches(char::from(0))
-            .to_string();
+        let username = String::from_utf8(trim_padding(&arr).to_vec())
+            .map_err(DeserializeError::FromUtf8Error)?;
 
         Ok(Username(username))
     }
@@ -78,16 +167,29 @@ impl std::ops::Deref for Username {
         &self.0
     }
 }
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Username {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_text(deserializer, Self::MAX_SIZE).map(Self)
+    }
+}
 
 #[cfg_attr(debug_assertions, derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(transparent))]
 #[derive(PartialEq, Clone)]
 pub struct Email(String);
 impl Email {
     pub const MAX_SIZE: usize = 255;
 
+    /// Sans vérification : voir `try_new` pour un texte venu de l'extérieur.
     pub fn new(email: String) -> Self {
         Self(email)
     }
+
+    pub fn try_new(email: String) -> Result<Self, TextError> {
+        check_text(&email, Self::MAX_SIZE)?;
+        Ok(Self(email))
+    }
 }
 impl std::convert::From<Email> for [u8; Email::MAX_SIZE] {
     fn from(email: Email) -> [u8; Email::MAX_SIZE] {
@@ -102,10 +204,8 @@ impl std::convert::TryFrom<[u8; Self::MAX_SIZE]> for Email {
     type Error = DeserializeError;
 
     fn try_from(arr: [u8; Self::MAX_SIZE]) -> Result<Self, Self::Error> {
-        let email = String::from_utf8(Vec::<u8>::from(arr))
-            .map_err(DeserializeError::FromUtf8Error)?
-            .trim_matches(char::from(0))
-            .to_string();
+        let email = String::from_utf8(trim_padding(&arr).to_vec())
+            .map_err(DeserializeError::FromUtf8Error)?;
 
         Ok(Email(email))
     }
@@ -117,8 +217,15 @@ impl std::ops::Deref for Email {
         &self.0
     }
 }
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Email {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize_text(deserializer, Self::MAX_SIZE).map(Self)
+    }
+}
 
 #[cfg_attr(debug_assertions, derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(PartialEq, Clone)]
 pub struct Row {
     id: Id,
@@ -138,6 +245,12 @@ impl Row {
 
     pub const MAX_SIZE: usize = Id::MAX_SIZE + Username::MAX_SIZE + Email::MAX_SIZE;
 
+    /// Noms des colonnes, dans l'ordre de stockage.
+    pub const COLUMN_NAMES: [&str; 3] = ["id", "username", "email"];
+    /// Types des colonnes, dans le même ordre que `COLUMN_NAMES`.
+    pub const COLUMN_TYPES: [ColumnType; 3] =
+        [ColumnType::Integer, ColumnType::Text, ColumnType::Text];
+
     pub fn new(id: Id, username: Username, email: Email) -> Self {
         Self {
             id,
@@ -145,6 +258,26 @@ impl Row {
             email,
         }
     }
+
+    pub fn get_id(&self) -> &Id {
+        &self.id
+    }
+
+    pub fn get_username(&self) -> &Username {
+        &self.username
+    }
+
+    pub fn get_email(&self) -> &Email {
+        &self.email
+    }
+
+    pub fn as_row_ref(&self) -> RowRef<'_> {
+        RowRef {
+            id: *self.id,
+            username: &self.username,
+            email: &self.email,
+        }
+    }
 }
 impl std::convert::From<Row> for [u8; Row::MAX_SIZE] {
     fn from(row: Row) -> [u8; Row::MAX_SIZE] {
@@ -213,23 +346,82 @@ impl std::fmt::Display for Row {
     }
 }
 
+/*
+ * Ligne lue sur place dans les octets d'une page : les textes sont des
+ * tranches de la page, aucune `String` n'est allouée. `to_row` en fait une
+ * `Row` quand la ligne doit survivre à l'emprunt de la page.
+ */
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Clone, Copy)]
+pub struct RowRef<'a> {
+    id: u64,
+    username: &'a str,
+    email: &'a str,
+}
+impl<'a> RowRef<'a> {
+    pub fn get_id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn get_username(&self) -> &'a str {
+        self.username
+    }
+
+    pub fn get_email(&self) -> &'a str {
+        self.email
+    }
+
+    pub fn to_row(&self) -> Row {
+        Row::new(
+            Id::new(self.id),
+            Username::new(self.username.to_owned()),
+            Email::new(self.email.to_owned()),
+        )
+    }
+}
+impl<'a> std::convert::TryFrom<&'a [u8]> for RowRef<'a> {
+    type Error = DeserializeError;
+
+    fn try_from(arr: &'a [u8]) -> Result<Self, Self::Error> {
+        if arr.len() < Row::MAX_SIZE {
+            return Err(DeserializeError::InvalidBytesSlice(arr.len()));
+        }
+        let text = |range: Range<usize>| {
+            std::str::from_utf8(trim_padding(&arr[range])).map_err(DeserializeError::Utf8Error)
+        };
+
+        Ok(Self {
+            id: u64::from_be_bytes(arr.read_array(Row::ID_OFFSET)),
+            username: text(Row::USERNAME_RANGE)?,
+            email: text(Row::EMAIL_RANGE)?,
+        })
+    }
+}
+impl std::fmt::Display for RowRef<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {}, {})", self.id, self.username, self.email)
+    }
+}
+
 #[cfg(test)]
 mod row_test {
     use super::*;
 
+    use proptest::prelude::*;
+
     #[test]
     fn test_id_from_into_u8_array() {
         let id_arr = <[u8; Id::MAX_SIZE]>::from(Id(42));
         assert_eq!(id_arr, [0, 0, 0, 0, 0, 0, 0, 42]);
         assert_eq!(Id::from(id_arr), Id(42));
 
-        let id_arr = <[u8; Id::MAX_SIZE]>::from(Id(usize::MIN));
+        let id_arr = <[u8; Id::MAX_SIZE]>::from(Id(u64::MIN));
         assert_eq!(id_arr, [0, 0, 0, 0, 0, 0, 0, 0]);
-        assert_eq!(Id::from(id_arr), Id(usize::MIN));
+        assert_eq!(Id::from(id_arr), Id(u64::MIN));
 
-        let id_arr = <[u8; Id::MAX_SIZE]>::from(Id(usize::MAX));
+        let id_arr = <[u8; Id::MAX_SIZE]>::from(Id(u64::MAX));
         assert_eq!(id_arr, [255, 255, 255, 255, 255, 255, 255, 255]);
-        assert_eq!(Id::from(id_arr), Id(usize::MAX));
+        assert_eq!(Id::from(id_arr), Id(u64::MAX));
     }
 
     #[test]
@@ -241,9 +433,7 @@ mod row_test {
             [97, 98, 105, 103, 97, 195, 171, 108]
         );
 
-        let username_deser =
-            Username::try_from(<[u8; Username::MAX_SIZE]>::try_from(username_array).unwrap())
-                .unwrap();
+        let username_deser = Username::try_from(username_array).unwrap();
         assert_eq!(username_deser, username);
     }
 
@@ -258,8 +448,7 @@ mod row_test {
             ]
         );
 
-        let email_deser =
-            Email::try_from(<[u8; Email::MAX_SIZE]>::try_from(email_bytes).unwrap()).unwrap();
+        let email_deser = Email::try_from(email_bytes).unwrap();
         assert_eq!(email_deser, email);
     }
 
@@ -297,4 +486,104 @@ mod row_test {
         assert_eq!(username_deser, username);
         assert_eq!(email_deser, email);
     }
+
+    /*
+     * Générateurs de valeurs valides : les textes ne commencent ni ne finissent
+     * par `\0`, retiré à la lecture, et tiennent dans leur taille maximale en
+     * octets. Les caractères sur plusieurs octets amènent souvent les textes
+     * au plus près de la limite.
+     */
+
+    fn text_strategy(max_size: usize) -> impl Strategy<Value = String> {
+        proptest::collection::vec(any::<char>(), 0..=max_size).prop_map(move |chars| {
+            let mut text = String::new();
+            for c in chars {
+                if text.len() + c.len_utf8() > max_size {
+                    break;
+                }
+                text.push(c);
+            }
+            text.trim_matches(char::from(0)).to_owned()
+        })
+    }
+
+    fn id_strategy() -> impl Strategy<Value = Id> {
+        any::<u64>().prop_map(Id::new)
+    }
+
+    fn username_strategy() -> impl Strategy<Value = Username> {
+        text_strategy(Username::MAX_SIZE).prop_map(Username::new)
+    }
+
+    fn email_strategy() -> impl Strategy<Value = Email> {
+        text_strategy(Email::MAX_SIZE).prop_map(Email::new)
+    }
+
+    fn row_strategy() -> impl Strategy<Value = Row> {
+        (id_strategy(), username_strategy(), email_strategy())
+            .prop_map(|(id, username, email)| Row::new(id, username, email))
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_row_serde() {
+        let row = Row::new(
+            Id(1),
+            Username::new("bob".to_owned()),
+            Email::new("bob@mail.com".to_owned()),
+        );
+        let json = serde_json::to_string(&row).unwrap();
+        assert_eq!(json, r#"{"id":1,"username":"bob","email":"bob@mail.com"}"#);
+        assert_eq!(serde_json::from_str::<Row>(&json).unwrap(), row);
+
+        let too_long = format!(r#"{{"id":1,"username":"{}","email":""}}"#, "a".repeat(33));
+        let error = serde_json::from_str::<Row>(&too_long).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with("invalid length 33, expected at most 32 bytes")
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn prop_row_round_trip(row in row_strategy()) {
+            let arr = <[u8; Row::MAX_SIZE]>::from(row.clone());
+            prop_assert_eq!(Row::try_from(&arr[..]), Ok(row));
+        }
+
+        /// Tout tableau de la taille d'une ligne est lu, ou refusé parce qu'un
+        /// texte n'est pas de l'UTF-8. Une ligne lue se réécrit à l'identique.
+        #[test]
+        fn prop_any_bytes_parse_or_fail(
+            // La moitié des tableaux ne contient que de l'ASCII, pour que certains soient lus.
+            bytes in prop_oneof![
+                proptest::collection::vec(any::<u8>(), Row::MAX_SIZE),
+                proptest::collection::vec(0u8..0x80, Row::MAX_SIZE),
+            ]
+        ) {
+            match Row::try_from(&bytes[..]) {
+                Ok(row) => {
+                    let arr = <[u8; Row::MAX_SIZE]>::from(row.clone());
+                    prop_assert_eq!(Row::try_from(&arr[..]), Ok(row));
+                }
+                Err(e) => prop_assert!(matches!(e, DeserializeError::FromUtf8Error(_))),
+            }
+        }
+
+        /// Une ligne lue sur place est celle lue par `Row::try_from`.
+        #[test]
+        fn prop_row_ref_matches_row(
+            bytes in prop_oneof![
+                proptest::collection::vec(any::<u8>(), Row::MAX_SIZE),
+                proptest::collection::vec(0u8..0x80, Row::MAX_SIZE),
+            ]
+        ) {
+            let row_ref = RowRef::try_from(&bytes[..]);
+            prop_assert_eq!(row_ref.map(|row_ref| row_ref.to_row()).ok(), Row::try_from(&bytes[..]).ok());
+            if let Ok(row) = Row::try_from(&bytes[..]) {
+                prop_assert_eq!(row.as_row_ref(), RowRef::try_from(&bytes[..]).unwrap());
+            }
+        }
+    }
 }
@@ -0,0 +1,538 @@
+use std::fs;
+use std::io;
+
+use crate::provider::{ProviderRows, TableProvider};
+use crate::row::{Email, Id, Row, TextError, Username, check_text};
+use crate::table::GetRowError;
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum SqliteError {
+    /// Le fichier, et l'erreur rencontrée en le lisant.
+    Open(String, io::Error),
+    NotASqliteFile,
+    /// Encodage du texte lu dans l'entête : seul l'UTF-8, 1, est lu.
+    UnsupportedEncoding(u32),
+    /// Numéro de la page, à partir de 1, qui ne peut pas être lue.
+    CorruptedPage(usize),
+    /// Aucune table dans le fichier, ou pas celle demandée.
+    NoTable(Option<String>),
+    /// Rowid de la ligne, et ce qui l'empêche d'être lue.
+    Record(i64, SqliteRecordError),
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum SqliteRecordError {
+    /// Nombre de valeurs trouvées.
+    WrongNumberOfValues(usize),
+    InvalidId(String),
+    /// Colonne et taille maximale.
+    StringTooLong(&'static str, usize),
+    /// Colonne et caractère de contrôle trouvé dans sa valeur.
+    ControlCharacter(&'static str, char),
+    /// Colonne dont la valeur est un réel ou un blob.
+    UnsupportedValue(&'static str),
+}
+
+/// Valeur d'un enregistrement SQLite.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq)]
+enum SqliteValue {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+const MAGIC: &[u8; 16] = b"SQLite format 3\0";
+const HEADER_SIZE: usize = 100;
+const INTERIOR_TABLE_PAGE: u8 = 0x05;
+const LEAF_TABLE_PAGE: u8 = 0x0D;
+/// Page de `sqlite_master`, qui décrit les tables du fichier.
+const MASTER_ROOT_PAGE: usize = 1;
+
+/*
+ * Table virtuelle lisant, en lecture seule, une table d'un fichier SQLite :
+ * l'entête, les pages des arbres de tables et le format des enregistrements.
+ * Chaque enregistrement doit avoir trois valeurs, un id puis deux textes ; un
+ * id `NULL` est celui d'une colonne `INTEGER PRIMARY KEY`, qui vaut le rowid.
+ *
+ * Le fichier est relu en entier à chaque `select`, comme un fichier CSV.
+ */
+#[derive(Debug)]
+pub struct SqliteTable {
+    file_path: String,
+    /// `None` pour la première table du fichier.
+    table: Option<String>,
+}
+impl SqliteTable {
+    /// Vérifie que le fichier est une base SQLite qui contient la table.
+    pub fn open(file_path: &str, table: Option<&str>) -> Result<Self, SqliteError> {
+        let sqlite_table = Self {
+            file_path: file_path.to_owned(),
+            table: table.map(str::to_owned),
+        };
+        let file = SqliteFile::read(file_path)?;
+        let _ = file.find_table(sqlite_table.table.as_deref())?;
+        Ok(sqlite_table)
+    }
+
+    fn read_rows(&self) -> Result<Vec<Result<Row, SqliteError>>, SqliteError> {
+        let file = SqliteFile::read(&self.file_path)?;
+        let root_page = file.find_table(self.table.as_deref())?;
+        let rows = file
+            .table_records(root_page)?
+            .into_iter()
+            .map(|(rowid, values)| {
+                record_to_row(rowid, values).map_err(|e| SqliteError::Record(rowid, e))
+            })
+            .collect();
+        Ok(rows)
+    }
+}
+impl TableProvider for SqliteTable {
+    fn scan(&self) -> ProviderRows<'_> {
+        match self.read_rows() {
+            Ok(rows) => Box::new(rows.into_iter().map(|row| row.map_err(GetRowError::Sqlite))),
+            Err(e) => Box::new(std::iter::once(Err(GetRowError::Sqlite(e)))),
+        }
+    }
+}
+
+/// Contenu d'un fichier SQLite et taille de ses pages.
+struct SqliteFile {
+    bytes: Vec<u8>,
+    page_size: usize,
+    /// Taille de page sans l'espace réservé en fin de page.
+    usable_size: usize,
+}
+impl SqliteFile {
+    fn read(file_path: &str) -> Result<Self, SqliteError> {
+        let bytes = fs::read(file_path).map_err(|e| SqliteError::Open(file_path.to_owned(), e))?;
+        if bytes.len() < HEADER_SIZE || !bytes.starts_with(MAGIC) {
+            return Err(SqliteError::NotASqliteFile);
+        }
+        // Une taille de 1 désigne des pages de 65536 octets.
+        let page_size = match read_u16(&bytes, 16) {
+            Some(1) => 65536,
+            Some(page_size) => usize::from(page_size),
+            None => return Err(SqliteError::NotASqliteFile),
+        };
+        if !page_size.is_power_of_two() || page_size < 512 {
+            return Err(SqliteError::NotASqliteFile);
+        }
+        let usable_size = page_size - usize::from(bytes[20]);
+        let encoding = read_u32(&bytes, 56).unwrap_or(0);
+        // 0 : base vide, dont l'encodage n'est pas encore choisi.
+        if encoding > 1 {
+            return Err(SqliteError::UnsupportedEncoding(encoding));
+        }
+        Ok(Self {
+            bytes,
+            page_size,
+            usable_size,
+        })
+    }
+
+    fn get_nb_pages(&self) -> usize {
+        self.bytes.len() / self.page_size
+    }
+
+    fn page(&self, page_num: usize) -> Result<&[u8], SqliteError> {
+        if page_num == 0 {
+            return Err(SqliteError::CorruptedPage(page_num));
+        }
+        let start = (page_num - 1) * self.page_size;
+        self.bytes
+            .get(start..start + self.page_size)
+            .ok_or(SqliteError::CorruptedPage(page_num))
+    }
+
+    /// Page racine de la table `name`, ou de la première table du fichier.
+    /// Les tables internes, dont le nom commence par `sqlite_`, sont ignorées.
+    fn find_table(&self, name: Option<&str>) -> Result<usize, SqliteError> {
+        let tables = self.table_records(MASTER_ROOT_PAGE)?;
+        let root_page = tables.into_iter().find_map(|(_, values)| {
+            let [
+                SqliteValue::Text(kind),
+                SqliteValue::Text(table),
+                _,
+                root_page,
+                ..,
+            ] = &values[..]
+            else {
+                return None;
+            };
+            let is_wanted = match name {
+                Some(name) => table.eq_ignore_ascii_case(name),
+                None => !table.starts_with("sqlite_"),
+            };
+            match root_page {
+                SqliteValue::Integer(root_page) if kind == "table" && is_wanted => {
+                    usize::try_from(*root_page).ok()
+                }
+                _ => None,
+            }
+        });
+        root_page.ok_or_else(|| SqliteError::NoTable(name.map(str::to_owned)))
+    }
+
+    /// Enregistrements de l'arbre de la table, dans l'ordre des rowids.
+    fn table_records(&self, root_page: usize) -> Result<Vec<(i64, Vec<SqliteValue>)>, SqliteError> {
+        let mut records = Vec::new();
+        let mut pages_to_visit = vec![root_page];
+        let mut nb_pages_visited = 0;
+        while let Some(page_num) = pages_to_visit.pop() {
+            // Un arbre qui boucle sur lui-même visiterait des pages sans fin.
+            nb_pages_visited += 1;
+            if nb_pages_visited > self.get_nb_pages() {
+                return Err(SqliteError::CorruptedPage(page_num));
+            }
+            let page = self.page(page_num)?;
+            let corrupted = || SqliteError::CorruptedPage(page_num);
+            let header_offset = if page_num == 1 { HEADER_SIZE } else { 0 };
+            let page_type = *page.get(header_offset).ok_or_else(corrupted)?;
+            let nb_cells = usize::from(read_u16(page, header_offset + 3).ok_or_else(corrupted)?);
+            let cell_pointers_offset = match page_type {
+                LEAF_TABLE_PAGE => header_offset + 8,
+                INTERIOR_TABLE_PAGE => header_offset + 12,
+                _ => return Err(corrupted()),
+            };
+            let cell_offsets = (0..nb_cells)
+                .map(|cell_num| {
+                    read_u16(page, cell_pointers_offset + 2 * cell_num)
+                        .map(usize::from)
+                        .ok_or_else(corrupted)
+                })
+                .collect::<Result<Vec<usize>, SqliteError>>()?;
+
+            if page_type == INTERIOR_TABLE_PAGE {
+                // Les enfants sont empilés à l'envers pour être visités dans l'ordre.
+                let right_child = read_u32(page, header_offset + 8).ok_or_else(corrupted)?;
+                pages_to_visit.push(right_child as usize);
+                for cell_offset in cell_offsets.into_iter().rev() {
+                    let left_child = read_u32(page, cell_offset).ok_or_else(corrupted)?;
+                    pages_to_visit.push(left_child as usize);
+                }
+                continue;
+            }
+            for cell_offset in cell_offsets {
+                let (rowid, payload) = self.read_leaf_cell(page_num, page, cell_offset)?;
+                let values = parse_record(&payload).ok_or_else(corrupted)?;
+                records.push((rowid, values));
+            }
+        }
+        Ok(records)
+    }
+
+    /// Rowid et contenu d'une cellule de feuille, suivi dans les pages de
+    /// débordement quand il ne tient pas dans la page.
+    fn read_leaf_cell(
+        &self,
+        page_num: usize,
+        page: &[u8],
+        cell_offset: usize,
+    ) -> Result<(i64, Vec<u8>), SqliteError> {
+        let corrupted = || SqliteError::CorruptedPage(page_num);
+        let (payload_size, size_len) = read_varint(page, cell_offset).ok_or_else(corrupted)?;
+        let (rowid, rowid_len) = read_varint(page, cell_offset + size_len).ok_or_else(corrupted)?;
+        let payload_size = usize::try_from(payload_size).map_err(|_| corrupted())?;
+        let payload_offset = cell_offset + size_len + rowid_len;
+
+        let max_local = self.usable_size - 35;
+        let local_size = if payload_size <= max_local {
+            payload_size
+        } else {
+            let min_local = (self.usable_size - 12) * 32 / 255 - 23;
+            let local_size = min_local + (payload_size - min_local) % (self.usable_size - 4);
+            if local_size <= max_local {
+                local_size
+            } else {
+                min_local
+            }
+        };
+        let mut payload = page
+            .get(payload_offset..payload_offset + local_size)
+            .ok_or_else(corrupted)?
+            .to_vec();
+        if local_size == payload_size {
+            return Ok((rowid as i64, payload));
+        }
+
+        let mut overflow_page =
+            read_u32(page, payload_offset + local_size).ok_or_else(corrupted)? as usize;
+        let mut nb_overflow_pages = 0;
+        while payload.len() < payload_size {
+            nb_overflow_pages += 1;
+            if overflow_page == 0 || nb_overflow_pages > self.get_nb_pages() {
+                return Err(SqliteError::CorruptedPage(overflow_page));
+            }
+            let page = self.page(overflow_page)?;
+            let content_size = (payload_size - payload.len()).min(self.usable_size - 4);
+            payload.extend_from_slice(&page[4..4 + content_size]);
+            overflow_page = read_u32(page, 0).unwrap_or(0) as usize;
+        }
+        Ok((rowid as i64, payload))
+    }
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    let bytes = bytes.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    let bytes = bytes.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// Entier de 1 à 9 octets : les 7 bits de poids faible de chaque octet, tant
+/// que son bit de poids fort est à 1, puis les 8 bits du neuvième octet.
+/// Renvoie la valeur et le nombre d'octets lus.
+fn read_varint(bytes: &[u8], offset: usize) -> Option<(u64, usize)> {
+    let mut value = 0u64;
+    for len in 0..9 {
+        let byte = *bytes.get(offset + len)?;
+        if len == 8 {
+            return Some(((value << 8) | u64::from(byte), 9));
+        }
+        value = (value << 7) | u64::from(byte & 0x7F);
+        if byte & 0x80 == 0 {
+            return Some((value, len + 1));
+        }
+    }
+    None
+}
+
+/// Un entête, sa taille puis le type de chaque valeur, suivi des valeurs.
+/// Tailles et types viennent du fichier : tout ce qui dépasse l'enregistrement
+/// le rend invalide.
+fn parse_record(payload: &[u8]) -> Option<Vec<SqliteValue>> {
+    let (header_size, mut header_offset) = read_varint(payload, 0)?;
+    let header_size = usize::try_from(header_size)
+        .ok()
+        .filter(|&header_size| header_size <= payload.len())?;
+    let mut value_offset = header_size;
+    let mut values = Vec::new();
+    while header_offset < header_size {
+        let (serial_type, len) = read_varint(payload, header_offset)?;
+        header_offset += len;
+        let size = match serial_type {
+            0 | 8 | 9 => 0,
+            1..=4 => serial_type as usize,
+            5 => 6,
+            6 | 7 => 8,
+            10 | 11 => return None,
+            _ => usize::try_from((serial_type - 12) / 2).ok()?,
+        };
+        let end = value_offset
+            .checked_add(size)
+            .filter(|&end| end <= payload.len())?;
+        let bytes = &payload[value_offset..end];
+        value_offset = end;
+        let value = match serial_type {
+            0 => SqliteValue::Null,
+            1..=6 => {
+                // Entier signé en gros-boutiste, étendu à 8 octets.
+                let fill = if bytes[0] & 0x80 == 0 { 0 } else { 0xFF };
+                let mut be_bytes = [fill; 8];
+                be_bytes[8 - size..].copy_from_slice(bytes);
+                SqliteValue::Integer(i64::from_be_bytes(be_bytes))
+            }
+            7 => SqliteValue::Real(f64::from_be_bytes(bytes.try_into().ok()?)),
+            8 => SqliteValue::Integer(0),
+            9 => SqliteValue::Integer(1),
+            _ if serial_type % 2 == 0 => SqliteValue::Blob(bytes.to_vec()),
+            _ => SqliteValue::Text(String::from_utf8(bytes.to_vec()).ok()?),
+        };
+        values.push(value);
+    }
+    Some(values)
+}
+
+/// Les mêmes vérifications qu'une insertion.
+fn record_to_row(rowid: i64, values: Vec<SqliteValue>) -> Result<Row, SqliteRecordError> {
+    let nb_values = values.len();
+    let Ok([id, username, email]) = <[SqliteValue; 3]>::try_from(values) else {
+        return Err(SqliteRecordError::WrongNumberOfValues(nb_values));
+    };
+    let id = match id {
+        SqliteValue::Null => rowid,
+        SqliteValue::Integer(id) => id,
+        SqliteValue::Text(text) => text
+            .trim()
+            .parse::<i64>()
+            .map_err(|_| SqliteRecordError::InvalidId(text))?,
+        _ => return Err(SqliteRecordError::UnsupportedValue("id")),
+    };
+    let id = u64::try_from(id).map_err(|_| SqliteRecordError::InvalidId(id.to_string()))?;
+    let username = record_text("username", username, Username::MAX_SIZE)?;
+    let email = record_text("email", email, Email::MAX_SIZE)?;
+    Ok(Row::new(
+        Id::new(id),
+        Username::new(username),
+        Email::new(email),
+    ))
+}
+
+/// Un texte, un entier écrit en décimal, ou un texte vide pour `NULL`.
+fn record_text(
+    column: &'static str,
+    value: SqliteValue,
+    max_size: usize,
+) -> Result<String, SqliteRecordError> {
+    let text = match value {
+        SqliteValue::Null => String::new(),
+        SqliteValue::Integer(integer) => integer.to_string(),
+        SqliteValue::Text(text) => text,
+        SqliteValue::Real(_) | SqliteValue::Blob(_) => {
+            return Err(SqliteRecordError::UnsupportedValue(column));
+        }
+    };
+    match check_text(&text, max_size) {
+        Ok(()) => Ok(text),
+        Err(TextError::TooLong(max_size)) => {
+            Err(SqliteRecordError::StringTooLong(column, max_size))
+        }
+        Err(TextError::ControlCharacter(c)) => Err(SqliteRecordError::ControlCharacter(column, c)),
+    }
+}
+
+#[cfg(test)]
+mod sqlite_test {
+    use super::*;
+
+    const PAGE_SIZE: usize = 512;
+
+    /// Enregistrement dont les valeurs sont des entiers d'un octet, `NULL`
+    /// pour `None`, ou des textes courts.
+    fn record(values: &[Result<Option<i8>, &str>]) -> Vec<u8> {
+        let mut header = Vec::new();
+        let mut body = Vec::new();
+        for value in values {
+            match value {
+                Ok(None) => header.push(0),
+                Ok(Some(integer)) => {
+                    header.push(1);
+                    body.extend_from_slice(&integer.to_be_bytes());
+                }
+                Err(text) => {
+                    header.push((text.len() * 2 + 13) as u8);
+                    body.extend_from_slice(text.as_bytes());
+                }
+            }
+        }
+        let mut record = vec![(header.len() + 1) as u8];
+        record.extend(header);
+        record.extend(body);
+        record
+    }
+
+    /// Feuille dont les cellules sont écrites à la suite, après leurs pointeurs.
+    fn leaf_page(header_offset: usize, cells: &[(u8, Vec<u8>)]) -> Vec<u8> {
+        let mut page = vec![0; PAGE_SIZE];
+        page[header_offset] = LEAF_TABLE_PAGE;
+        page[header_offset + 3..header_offset + 5]
+            .copy_from_slice(&(cells.len() as u16).to_be_bytes());
+        let mut cell_offset = header_offset + 8 + 2 * cells.len();
+        for (cell_num, (rowid, payload)) in cells.iter().enumerate() {
+            let pointer = header_offset + 8 + 2 * cell_num;
+            page[pointer..pointer + 2].copy_from_slice(&(cell_offset as u16).to_be_bytes());
+            let cell = [&[payload.len() as u8, *rowid][..], payload].concat();
+            page[cell_offset..cell_offset + cell.len()].copy_from_slice(&cell);
+            cell_offset += cell.len();
+        }
+        page
+    }
+
+    #[test]
+    fn test_reads_the_first_table() {
+        let mut file = leaf_page(
+            HEADER_SIZE,
+            &[(
+                1,
+                record(&[
+                    Err("table"),
+                    Err("people"),
+                    Err("people"),
+                    Ok(Some(2)),
+                    Err("CREATE TABLE people(id, username, email)"),
+                ]),
+            )],
+        );
+        file[..16].copy_from_slice(MAGIC);
+        file[16..18].copy_from_slice(&(PAGE_SIZE as u16).to_be_bytes());
+        file[56..60].copy_from_slice(&1u32.to_be_bytes());
+
+        // Nœud interne : la page 3 pour les rowids jusqu'à 1, la page 4 ensuite.
+        let mut interior = vec![0; PAGE_SIZE];
+        interior[0] = INTERIOR_TABLE_PAGE;
+        interior[3..5].copy_from_slice(&1u16.to_be_bytes());
+        interior[8..12].copy_from_slice(&4u32.to_be_bytes());
+        interior[12..14].copy_from_slice(&14u16.to_be_bytes());
+        interior[14..18].copy_from_slice(&3u32.to_be_bytes());
+        interior[18] = 1;
+        file.extend(interior);
+        file.extend(leaf_page(
+            0,
+            &[(1, record(&[Ok(None), Err("alice"), Err("alice@mail.com")]))],
+        ));
+        file.extend(leaf_page(
+            0,
+            &[
+                (2, record(&[Ok(Some(7)), Err("bob"), Ok(None)])),
+                (3, record(&[Ok(Some(-1)), Err("carol"), Err("c@mail.com")])),
+            ],
+        ));
+
+        let file_path =
+            std::env::temp_dir().join(format!("my_db_sqlite_{}.db", std::process::id()));
+        fs::write(&file_path, file).unwrap();
+        let file_path = file_path.to_str().unwrap();
+
+        let ids: Vec<Result<u64, String>> = SqliteTable::open(file_path, None)
+            .unwrap()
+            .scan()
+            .map(|row| match row {
+                Ok(row) => Ok(**row.get_id()),
+                Err(GetRowError::Sqlite(SqliteError::Record(
+                    rowid,
+                    SqliteRecordError::InvalidId(id),
+                ))) => Err(format!("{rowid}: {id}")),
+                Err(_) => Err("other error".to_owned()),
+            })
+            .collect();
+        assert_eq!(ids, vec![Ok(1), Ok(7), Err("3: -1".to_owned())]);
+        assert!(matches!(
+            SqliteTable::open(file_path, Some("users")),
+            Err(SqliteError::NoTable(Some(_)))
+        ));
+        fs::remove_file(file_path).unwrap();
+    }
+
+    #[test]
+    fn test_read_varint() {
+        assert_eq!(read_varint(&[0x05], 0), Some((5, 1)));
+        assert_eq!(read_varint(&[0x81, 0x00], 0), Some((128, 2)));
+        assert_eq!(read_varint(&[0xFF; 9], 0), Some((u64::MAX, 9)));
+        assert_eq!(read_varint(&[0x81], 0), None);
+    }
+
+    #[test]
+    fn test_parse_record_rejects_sizes_past_the_payload() {
+        assert_eq!(
+            parse_record(&record(&[Ok(Some(7)), Err("bob")])),
+            Some(vec![
+                SqliteValue::Integer(7),
+                SqliteValue::Text("bob".to_owned())
+            ])
+        );
+        // Taille d'entête plus grande que l'enregistrement.
+        assert_eq!(parse_record(&[0x7F, 0x01, 0x07]), None);
+        // Type de texte de taille maximale : la fin déborderait.
+        let huge_text = [&[10][..], &[0xFF; 9]].concat();
+        assert_eq!(parse_record(&huge_text), None);
+        // Texte plus long que ce qui suit l'entête.
+        assert_eq!(parse_record(&[0x02, 0x17, b'a']), None);
+    }
+}
@@ -1,18 +1,32 @@
-use std::sync::LazyLock;
-use std::{cell::RefCell, rc::Rc};
-
-use regex::Regex;
+use std::ops::RangeInclusive;
+use std::time::Duration;
+use std::{cell::Cell, cell::RefCell, rc::Rc};
 
+use crate::catalog::{Catalog, CatalogError};
+use crate::collation::Collation;
+use crate::csv::{CsvError, CsvTable};
 use crate::cursor::Cursor;
-use crate::row::{Email, Id, Row, Username};
-use crate::table::{GetRowError, Table, WriteRowError};
+use crate::expr::{Comparison, Expr, Function, Value};
+use crate::integrity::{IntegrityProblem, check_integrity};
+use crate::interrupt;
+use crate::message::{Lang, Message};
+use crate::pager::{GetPageError, SaveToDiskError, Synchronous, WriteMode};
+use crate::parser::{Order, ParseError, Projection, Stmt, parse};
+use crate::provider::TableProvider;
+use crate::row::{ColumnType, Email, Id, Row, RowRef, TextError, Username};
+use crate::sqlite::{SqliteError, SqliteTable};
+use crate::table::{GetRowError, Table, UndoEntry, WriteRowError};
 
-const INSERT_REGEX_STR: &str = r"insert (?<id>\b\d+\b) (?<username>\w+) (?<email>.+)";
-static INSERT_REGEX: LazyLock<Regex> = LazyLock::new(|| {
-    // Si le regex est invalide le programme ne peut pas fonctionner.
-    #[allow(clippy::expect_used)]
-    Regex::new(INSERT_REGEX_STR).expect("Unable to parse regex.")
-});
+/// Réglages reconnus par `pragma`, pour la complétion du REPL.
+pub const PRAGMA_NAMES: [&str; 7] = [
+    "bloom_filter",
+    "cache_size",
+    "commit_delay",
+    "flush_interval",
+    "integrity_check",
+    "synchronous",
+    "write_mode",
+];
 
 trait MapOkErr<T, E> {
     type Output<U, F>;
@@ -34,111 +48,1854 @@ impl<T, E> MapOkErr<T, E> for Result<T, E> {
     }
 }
 
+/// Lignes lues par un `select`, toujours dans l'ordre des ids.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq)]
+pub enum IdFilter {
+    /// Parcours de toute la table.
+    All,
+    /// Recherche d'un seul id dans l'arbre.
+    Id(u64),
+    /// Parcours des seules feuilles contenant les ids de l'intervalle.
+    Between(RangeInclusive<u64>),
+}
+impl IdFilter {
+    pub fn get_range(&self) -> RangeInclusive<u64> {
+        match self {
+            Self::All => 0..=u64::MAX,
+            Self::Id(id) => *id..=*id,
+            Self::Between(range) => range.clone(),
+        }
+    }
+}
+
+/// Colonnes de texte, les seules sur lesquelles une collation s'applique.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq, Clone, Copy)]
+pub enum TextColumn {
+    Username,
+    Email,
+}
+impl TextColumn {
+    pub fn get(self, row: &Row) -> &str {
+        match self {
+            Self::Username => row.get_username(),
+            Self::Email => row.get_email(),
+        }
+    }
+}
+impl std::fmt::Display for TextColumn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Username => write!(f, "username"),
+            Self::Email => write!(f, "email"),
+        }
+    }
+}
+
+/// Tri sur une colonne de texte : contrairement à l'ordre des ids, il faut lire
+/// toutes les lignes sélectionnées avant de rendre la première.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq)]
+pub struct SortOrder {
+    pub column: TextColumn,
+    pub collation: Collation,
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq)]
+pub struct SelectQuery {
+    /// Nom de la table virtuelle lue, `None` pour la table des `Row`.
+    pub table: Option<String>,
+    pub filter: IdFilter,
+    /// Reste du `where`, quand l'intervalle d'ids parcouru ne suffit pas.
+    pub predicate: Option<Expr>,
+    /// Positions dans `Row::COLUMN_NAMES` des colonnes affichées.
+    pub columns: Vec<usize>,
+    /// `None` pour l'ordre des ids, celui de l'arbre.
+    pub order: Option<SortOrder>,
+    pub limit: Option<usize>,
+}
+
+/// `select` sur `my_db_master`. Le catalogue tient dans une page : il est lu
+/// en entier puis trié.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq)]
+pub struct MasterQuery {
+    /// Positions dans `Catalog::MASTER_COLUMN_NAMES` des colonnes affichées.
+    pub columns: Vec<usize>,
+    /// Position de la colonne de tri et collation de ses valeurs.
+    pub order: Option<(usize, Collation)>,
+    pub limit: Option<usize>,
+}
+
 #[cfg_attr(debug_assertions, derive(Debug))]
 #[derive(PartialEq)]
 pub enum StatementType {
-    Select,
-    Insert(Row),
+    Select(SelectQuery),
+    SelectMaster(MasterQuery),
+    /// `returning` donne les colonnes de la ligne insérée à renvoyer.
+    Insert {
+        row: Row,
+        returning: Option<Vec<usize>>,
+    },
+    /// `delete` sans condition ou `truncate` : supprime toutes les lignes.
+    DeleteAll {
+        returning: Option<Vec<usize>>,
+    },
+    Pragma(Pragma),
+    /// Décrit comment l'instruction serait exécutée, sans l'exécuter.
+    Explain(Box<StatementType>),
+    /// La table virtuelle n'est gardée que jusqu'à la fermeture de la base.
+    CreateVirtualTable {
+        name: String,
+        module: VirtualTableModule,
+    },
+}
+
+/// Sources utilisables par `create virtual table ... using`.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq)]
+pub enum VirtualTableModule {
+    /// Chemin du fichier lu.
+    Csv(String),
+    /// Chemin du fichier SQLite et table lue, `None` pour la première.
+    Sqlite {
+        file_path: String,
+        table: Option<String>,
+    },
+}
+impl std::fmt::Display for VirtualTableModule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Csv(_) => write!(f, "csv"),
+            Self::Sqlite { .. } => write!(f, "sqlite"),
+        }
+    }
+}
+impl std::fmt::Display for StatementType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Select(SelectQuery {
+                table: Some(table),
+                filter,
+                predicate,
+                columns: _,
+                order,
+                limit,
+            }) => {
+                write!(f, "SCAN VIRTUAL TABLE {table}")?;
+                match filter {
+                    IdFilter::All => {}
+                    IdFilter::Id(id) => write!(f, " FILTER (id = {id})")?,
+                    IdFilter::Between(range) => write!(
+                        f,
+                        " FILTER (id BETWEEN {} AND {})",
+                        range.start(),
+                        range.end()
+                    )?,
+                }
+                if let Some(predicate) = predicate {
+                    write!(f, " FILTER ({predicate})")?;
+                }
+                if let Some(SortOrder { column, collation }) = order {
+                    write!(f, " SORT ({column} COLLATE {collation})")?;
+                }
+                match limit {
+                    Some(limit) => write!(f, " LIMIT {limit}"),
+                    None => Ok(()),
+                }
+            }
+            Self::Select(SelectQuery {
+                table: None,
+                filter,
+                predicate,
+                columns: _,
+                order,
+                limit,
+            }) => {
+                match filter {
+                    IdFilter::All => write!(f, "SCAN TABLE")?,
+                    IdFilter::Id(id) => write!(f, "INDEX SEEK (id = {id})")?,
+                    IdFilter::Between(range) => write!(
+                        f,
+                        "INDEX RANGE SCAN (id BETWEEN {} AND {})",
+                        range.start(),
+                        range.end()
+                    )?,
+                }
+                if let Some(predicate) = predicate {
+                    write!(f, " FILTER ({predicate})")?;
+                }
+                if let Some(SortOrder { column, collation }) = order {
+                    write!(f, " SORT ({column} COLLATE {collation})")?;
+                }
+                match limit {
+                    Some(limit) => write!(f, " LIMIT {limit}"),
+                    None => Ok(()),
+                }
+            }
+            Self::SelectMaster(MasterQuery {
+                columns: _,
+                order,
+                limit,
+            }) => {
+                write!(f, "SCAN {}", Catalog::MASTER_TABLE_NAME)?;
+                if let Some((column, collation)) = order {
+                    let column = Catalog::MASTER_COLUMN_NAMES[*column];
+                    write!(f, " SORT ({column} COLLATE {collation})")?;
+                }
+                match limit {
+                    Some(limit) => write!(f, " LIMIT {limit}"),
+                    None => Ok(()),
+                }
+            }
+            Self::Insert { row, returning } => {
+                write!(f, "INSERT (id = {})", **row.get_id())?;
+                write_returning(f, returning.as_deref())
+            }
+            Self::DeleteAll { returning } => {
+                write!(f, "TRUNCATE TABLE")?;
+                write_returning(f, returning.as_deref())
+            }
+            Self::Pragma(_) => write!(f, "PRAGMA"),
+            Self::Explain(statement) => write!(f, "EXPLAIN {statement}"),
+            Self::CreateVirtualTable { name, module } => {
+                write!(f, "CREATE VIRTUAL TABLE {name} USING {module}")
+            }
+        }
+    }
+}
+
+fn write_returning(
+    f: &mut std::fmt::Formatter<'_>,
+    returning: Option<&[usize]>,
+) -> std::fmt::Result {
+    match returning {
+        Some(columns) => {
+            let names: Vec<&str> = columns.iter().map(|c| Row::COLUMN_NAMES[*c]).collect();
+            write!(f, " RETURNING {}", names.join(", "))
+        }
+        None => Ok(()),
+    }
+}
+
+/// Réglage lu (`None`) ou modifié (`Some`) par une instruction `pragma`.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(PartialEq)]
+pub enum Pragma {
+    Synchronous(Option<Synchronous>),
+    WriteMode(Option<WriteMode>),
+    /// Nombre de pages gardées en mémoire, au moins une.
+    CacheSize(Option<usize>),
+    /// Délai maximal, en millisecondes, avant la sauvegarde d'une modification
+    /// en mode write-through. 0 sauvegarde après chaque instruction.
+    CommitDelay(Option<u64>),
+    /// Intervalle, en millisecondes, des sauvegardes périodiques en mode
+    /// write-back. 0 les désactive.
+    FlushInterval(Option<u64>),
+    /// Filtre de Bloom sur les ids, voir `Table::set_bloom_filter`.
+    BloomFilter(Option<bool>),
+    /// Vérifie l'arbre et les lignes de la table, sans rien modifier.
+    IntegrityCheck,
 }
 
 #[cfg_attr(debug_assertions, derive(Debug))]
 #[derive(PartialEq)]
 pub enum PrepareStatementError {
-    UnrecognizedStatement,
-    InvalidInsert,
+    Parse(ParseError),
     StringTooLong(String, usize),
+    /// Colonne et caractère de contrôle trouvé dans sa valeur.
+    ControlCharacter(String, char),
+    UnknownPragma(String),
+    InvalidPragmaValue(String, String),
+    UnknownColumn(String),
+    /// Une insertion donne une valeur pour chaque colonne, ici le nombre de valeurs reçues.
+    WrongNumberOfValues(usize),
+    InvalidId(String),
+    /// Filtre impossible, comme un `where` sur `my_db_master`, ici le filtre demandé.
+    UnsupportedFilter(String),
+    UnknownCollation(String),
+    UnknownFunction(String),
+    /// Nom de la fonction et nombre d'arguments attendus.
+    WrongNumberOfArguments(String, usize),
+    /// Le nom d'une table virtuelle est déjà celui de la table des `Row` ou du catalogue.
+    TableExists(String),
+    UnknownModule(String),
+    /// `csv` attend un seul argument, le chemin du fichier.
+    CsvArguments,
+    /// `sqlite` attend le chemin du fichier, puis le nom d'une table.
+    SqliteArguments,
 }
 
+/*
+ * Lignes d'un `select`, lues une à une depuis l'arbre au fil de l'itération :
+ * la mémoire utilisée ne dépend pas du nombre de lignes sélectionnées.
+ *
+ * Après une erreur, l'itération s'arrête.
+ *
+ * Seul un tri sur une colonne de texte oblige à garder toutes les lignes en
+ * mémoire, voir `Rows::sort`.
+ */
 #[cfg_attr(debug_assertions, derive(Debug))]
-#[derive(PartialEq)]
+pub struct Rows {
+    cursor: Option<Cursor>,
+    /// Lignes déjà lues, rendues avant celles du curseur.
+    buffered: std::vec::IntoIter<Row>,
+    columns: Vec<usize>,
+    remaining: usize,
+    pending_error: Option<GetRowError>,
+    /// Condition du `where` vérifiée sur les lignes du curseur.
+    predicate: Option<Expr>,
+    /// Une ligne illisible est passée au lieu d'arrêter la lecture, voir
+    /// `skipping_corrupted`.
+    skip_corrupted: bool,
+    /// Lignes illisibles passées sans interrompre la lecture.
+    skipped: Rc<Cell<usize>>,
+}
+impl Rows {
+    pub fn new(cursor: Cursor, columns: Vec<usize>, limit: Option<usize>) -> Self {
+        Self {
+            cursor: Some(cursor),
+            buffered: Vec::new().into_iter(),
+            columns,
+            remaining: limit.unwrap_or(usize::MAX),
+            pending_error: None,
+            predicate: None,
+            skip_corrupted: false,
+            skipped: Rc::default(),
+        }
+    }
+
+    /// Seules les lignes du curseur qui vérifient `predicate` sont rendues.
+    pub fn with_predicate(mut self, predicate: Option<Expr>) -> Self {
+        self.predicate = predicate;
+        self
+    }
+
+    /// Les lignes illisibles sont comptées et passées, pour qu'un `select`
+    /// rende les autres. Sans cela, la première arrête la lecture avec
+    /// `GetRowError::Deserialize`.
+    pub fn skipping_corrupted(mut self) -> Self {
+        self.skip_corrupted = true;
+        self
+    }
+
+    /// Lignes déjà en mémoire, comme celles renvoyées par `returning`.
+    pub fn from_rows(rows: Vec<Row>, columns: Vec<usize>) -> Self {
+        Self {
+            cursor: None,
+            buffered: rows.into_iter(),
+            columns,
+            remaining: usize::MAX,
+            pending_error: None,
+            predicate: None,
+            skip_corrupted: false,
+            skipped: Rc::default(),
+        }
+    }
+
+    /// Positions dans `Row::COLUMN_NAMES` des colonnes à afficher.
+    pub fn get_columns(&self) -> &[usize] {
+        &self.columns
+    }
+
+    /// Nombre de lignes passées car leurs octets ne forment pas un `Row`. Le
+    /// compteur est partagé : il augmente à mesure que les lignes sont lues.
+    pub fn get_skipped_rows(&self) -> Rc<Cell<usize>> {
+        self.skipped.clone()
+    }
+
+    /// Lit toutes les lignes restantes, puis les rend dans l'ordre de `order`
+    /// jusqu'à la limite. La première erreur de lecture interrompt le tri.
+    pub fn sort(mut self, order: &SortOrder) -> Result<Self, GetRowError> {
+        let limit = std::mem::replace(&mut self.remaining, usize::MAX);
+        let mut rows = self.by_ref().collect::<Result<Vec<Row>, GetRowError>>()?;
+        rows.sort_by(|a, b| {
+            order
+                .collation
+                .compare(order.column.get(a), order.column.get(b))
+        });
+        rows.truncate(limit);
+        self.buffered = rows.into_iter();
+        Ok(self)
+    }
+}
+impl Iterator for Rows {
+    type Item = Result<Row, GetRowError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(row) = self.buffered.next() {
+            return Some(Ok(row));
+        }
+        loop {
+            if let Some(e) = self.pending_error.take() {
+                self.remaining = 0;
+                return Some(Err(e));
+            }
+            let cursor = self.cursor.as_mut()?;
+            if self.remaining == 0 || cursor.is_end_of_table() {
+                return None;
+            }
+            if interrupt::is_interrupted() {
+                self.remaining = 0;
+                return Some(Err(GetRowError::Interrupted));
+            }
+
+            let row_bytes = match cursor.get() {
+                Ok(row_bytes) => row_bytes,
+                Err(e) => {
+                    self.remaining = 0;
+                    return Some(Err(GetRowError::from(e)));
+                }
+            };
+            // L'erreur du déplacement est rendue à l'appel suivant, après la ligne lue.
+            if let Err(e) = cursor.advance() {
+                self.pending_error = Some(GetRowError::GetPage(e));
+            }
+            let row = match RowRef::try_from(&row_bytes[..]) {
+                Ok(row) => row,
+                // Une ligne corrompue est comptée puis passée : les autres restent lisibles.
+                Err(_) if self.skip_corrupted => {
+                    self.skipped.set(self.skipped.get() + 1);
+                    continue;
+                }
+                Err(e) => {
+                    self.remaining = 0;
+                    return Some(Err(GetRowError::Deserialize(e)));
+                }
+            };
+            // Une ligne écartée par le `where` ne compte pas dans la limite, et
+            // n'est jamais copiée.
+            if self
+                .predicate
+                .as_ref()
+                .is_some_and(|predicate| !predicate.eval(&row).is_true())
+            {
+                continue;
+            }
+            self.remaining -= 1;
+            return Some(Ok(row.to_row()));
+        }
+    }
+}
+
+/// Colonne d'un résultat : son nom et le type de ses valeurs.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(PartialEq, Clone, Copy)]
+pub struct ResultColumn {
+    pub name: &'static str,
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
+    pub column_type: ColumnType,
+}
+
+/*
+ * Résultat d'un `select` ou d'un `returning` : la description des colonnes,
+ * puis les lignes lues au fil de l'itération. Les colonnes suffisent pour
+ * présenter le résultat sans connaître la table lue.
+ *
+ * L'affichage et la sérialisation lisent les lignes : elles ne sont rendues
+ * qu'une fois, un second affichage ne montre que les colonnes.
+ */
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct ResultSet {
+    columns: Vec<ResultColumn>,
+    rows: RefCell<Rows>,
+}
+impl ResultSet {
+    pub fn get_columns(&self) -> &[ResultColumn] {
+        &self.columns
+    }
+
+    /// Positions dans `Row::COLUMN_NAMES` des colonnes du résultat.
+    pub fn get_positions(&self) -> Vec<usize> {
+        self.rows.borrow().get_columns().to_vec()
+    }
+
+    /// Voir `Rows::get_skipped_rows`.
+    pub fn get_skipped_rows(&self) -> Rc<Cell<usize>> {
+        self.rows.borrow().get_skipped_rows()
+    }
+
+    pub fn into_rows(self) -> Rows {
+        self.rows.into_inner()
+    }
+
+    /// Valeurs de chaque ligne, dans l'ordre de `get_columns`.
+    pub fn into_records(self) -> impl Iterator<Item = Result<Vec<Value>, GetRowError>> {
+        let positions = self.get_positions();
+        self.into_rows()
+            .map(move |row| row.map(|row| row_record(&positions, &row)))
+    }
+}
+impl From<Rows> for ResultSet {
+    fn from(rows: Rows) -> Self {
+        let columns = rows
+            .get_columns()
+            .iter()
+            .map(|position| ResultColumn {
+                name: Row::COLUMN_NAMES[*position],
+                column_type: Row::COLUMN_TYPES[*position],
+            })
+            .collect();
+        Self {
+            columns,
+            rows: RefCell::new(rows),
+        }
+    }
+}
+impl std::fmt::Display for ResultSet {
+    /// Une ligne `(…)` par résultat, comme le mode `list`. Une erreur de
+    /// lecture interrompt l'affichage avec `fmt::Error` : `into_records` la
+    /// rend, elle.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let positions = self.get_positions();
+        for (row_num, row) in self.rows.borrow_mut().by_ref().enumerate() {
+            let row = row.map_err(|_| std::fmt::Error)?;
+            let values: Vec<String> = row_record(&positions, &row)
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+            if row_num != 0 {
+                writeln!(f)?;
+            }
+            write!(f, "({})", values.join(", "))?;
+        }
+        Ok(())
+    }
+}
+/// `{"columns": [{"name": "id", "type": "integer"}, …], "rows": [[1, "bob", …], …]}`,
+/// les lignes étant sérialisées au fil de leur lecture.
+#[cfg(feature = "serde")]
+impl serde::Serialize for ResultSet {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("ResultSet", 2)?;
+        state.serialize_field("columns", &self.columns)?;
+        state.serialize_field("rows", &SerializedRows(self))?;
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+struct SerializedRows<'a>(&'a ResultSet);
+#[cfg(feature = "serde")]
+impl serde::Serialize for SerializedRows<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::{Error, SerializeSeq};
+
+        let positions = self.0.get_positions();
+        let mut seq = serializer.serialize_seq(None)?;
+        for row in self.0.rows.borrow_mut().by_ref() {
+            let row = row.map_err(|_| S::Error::custom("unable to read a row"))?;
+            seq.serialize_element(&row_record(&positions, &row))?;
+        }
+        seq.end()
+    }
+}
+
+/// Valeurs des colonnes `positions` de la ligne, dans cet ordre.
+fn row_record(positions: &[usize], row: &Row) -> Vec<Value> {
+    let row = row.as_row_ref();
+    positions
+        .iter()
+        .map(|position| match position {
+            0 => Value::Number(row.get_id()),
+            1 => Value::Text(row.get_username().to_owned()),
+            _ => Value::Text(row.get_email().to_owned()),
+        })
+        .collect()
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum StatementOutput {
-    Select(Vec<Row>),
+    /// Gardé dans une boîte : le curseur des lignes est bien plus grand que les
+    /// autres variantes.
+    Select(Box<ResultSet>),
     InsertSuccessfull,
+    DeleteSuccessfull,
+    PragmaValue(String),
+    PragmaSet,
+    TableCreated,
+    Plan(String),
+    /// Problèmes trouvés par `pragma integrity_check`, aucun si la base est saine.
+    IntegrityReport(Vec<IntegrityProblem>),
+    /// Lignes de `my_db_master`, réduites aux `columns` demandées.
+    Master {
+        columns: Vec<usize>,
+        records: Vec<Vec<String>>,
+    },
+}
+impl std::fmt::Display for StatementOutput {
+    /// Ce qu'affiche la console avant `Executed.`, les messages étant en anglais.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Select(result) => write!(f, "{result}"),
+            Self::InsertSuccessfull
+            | Self::DeleteSuccessfull
+            | Self::PragmaSet
+            | Self::TableCreated => Ok(()),
+            Self::PragmaValue(value) => write!(f, "{value}"),
+            Self::Plan(plan) => write!(f, "{plan}"),
+            Self::IntegrityReport(problems) if problems.is_empty() => {
+                write!(f, "{}", Message::IntegrityOk.text(Lang::En))
+            }
+            Self::IntegrityReport(problems) => {
+                let lines: Vec<String> = problems
+                    .iter()
+                    .map(|problem| Message::from(problem).text(Lang::En))
+                    .collect();
+                write!(f, "{}", lines.join("\n"))
+            }
+            Self::Master { records, .. } => {
+                let lines: Vec<String> = records
+                    .iter()
+                    .map(|values| format!("({})", values.join(", ")))
+                    .collect();
+                write!(f, "{}", lines.join("\n"))
+            }
+        }
+    }
 }
 
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub enum StatementOutputError {
     PoisonedTable,
-    Select(Vec<Row>, GetRowError),
+    Select(GetRowError),
     Insert(WriteRowError),
+    Delete(GetPageError),
+    Catalog(CatalogError),
+    /// La modification a été faite mais la sauvegarde du mode write-through a échoué.
+    WriteThrough(SaveToDiskError),
+    /// Ni la table des `Row`, ni le catalogue, ni une table virtuelle enregistrée.
+    UnknownTable(String),
+    Csv(CsvError),
+    Sqlite(SqliteError),
+}
+
+/// Découpe une ligne en instructions séparées par `;`. Un `;` entre guillemets,
+/// simples ou doubles, fait partie de l'instruction. Les instructions vides
+/// sont ignorées.
+pub fn split_statements(buffer: &str) -> Vec<&str> {
+    let mut statements = Vec::new();
+    let mut quote: Option<char> = None;
+    let mut statement_start = 0;
+    for (index, c) in buffer.char_indices() {
+        match (quote, c) {
+            (None, '\'' | '"') => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            (None, ';') => {
+                statements.push(&buffer[statement_start..index]);
+                statement_start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    statements.push(&buffer[statement_start..]);
+
+    statements
+        .into_iter()
+        .map(str::trim)
+        .filter(|statement| !statement.is_empty())
+        .collect()
 }
 
 pub fn prepare_statement(buffer: &str) -> Result<StatementType, PrepareStatementError> {
-    let lowercase: String = buffer.to_lowercase();
-    if lowercase.starts_with("select") {
-        return Ok(StatementType::Select);
+    let stmt = parse(buffer).map_err(PrepareStatementError::Parse)?;
+    plan_statement(stmt)
+}
+
+/// Choisit comment exécuter une instruction analysée, en vérifiant ses colonnes
+/// et ses valeurs.
+pub fn plan_statement(stmt: Stmt) -> Result<StatementType, PrepareStatementError> {
+    match stmt {
+        Stmt::Select {
+            projection,
+            table,
+            filter,
+            order,
+            limit,
+        } => {
+            let limit = limit.map(|limit| usize::try_from(limit).unwrap_or(usize::MAX));
+            match table {
+                Some(table) if table.eq_ignore_ascii_case(Catalog::MASTER_TABLE_NAME) => {
+                    plan_select_master(projection, filter, order, limit)
+                }
+                // Une table virtuelle n'est cherchée qu'à l'exécution.
+                table => {
+                    let table =
+                        table.filter(|table| !table.eq_ignore_ascii_case(Catalog::TABLE_NAME));
+                    plan_select(table, projection, filter, order, limit)
+                }
+            }
+        }
+        Stmt::Insert { values, returning } => Ok(StatementType::Insert {
+            row: plan_insert(values)?,
+            returning: returning.map(plan_projection).transpose()?,
+        }),
+        Stmt::Delete { returning } => Ok(StatementType::DeleteAll {
+            returning: returning.map(plan_projection).transpose()?,
+        }),
+        Stmt::Pragma { name, value } => {
+            plan_pragma(&name, value.as_deref()).map(StatementType::Pragma)
+        }
+        Stmt::Explain(stmt) => Ok(StatementType::Explain(Box::new(plan_statement(*stmt)?))),
+        Stmt::CreateVirtualTable {
+            name,
+            module,
+            arguments,
+        } => plan_create_virtual_table(name, &module, arguments),
     }
-    if lowercase.starts_with("insert") {
-        let Some(caps) = INSERT_REGEX.captures(buffer) else {
-            return Err(PrepareStatementError::InvalidInsert);
-        };
+}
 
-        let Ok(id) = caps["id"].parse::<usize>() else {
-            return Err(PrepareStatementError::InvalidInsert);
-        };
+fn plan_select(
+    table: Option<String>,
+    projection: Projection,
+    filter: Option<Expr>,
+    order: Option<Order>,
+    limit: Option<usize>,
+) -> Result<StatementType, PrepareStatementError> {
+    let columns = plan_projection(projection)?;
+    let order = order.map(plan_order).transpose()?.flatten();
+    if let Some(filter) = &filter {
+        plan_predicate(filter)?;
+    }
+    let range = filter.as_ref().map_or(0..=u64::MAX, get_id_range);
+    let predicate = filter.filter(|filter| !is_id_range(filter));
+    let filter = match (*range.start(), *range.end()) {
+        (0, u64::MAX) => IdFilter::All,
+        (start, end) if start == end => IdFilter::Id(start),
+        _ => IdFilter::Between(range),
+    };
+    Ok(StatementType::Select(SelectQuery {
+        table,
+        filter,
+        predicate,
+        columns,
+        order,
+        limit,
+    }))
+}
+
+/// `my_db_master` n'a pas d'id : aucun filtre n'est possible.
+fn plan_select_master(
+    projection: Projection,
+    filter: Option<Expr>,
+    order: Option<Order>,
+    limit: Option<usize>,
+) -> Result<StatementType, PrepareStatementError> {
+    if let Some(filter) = filter {
+        return Err(PrepareStatementError::UnsupportedFilter(filter.to_string()));
+    }
+    let get_position = |column: &str| {
+        Catalog::MASTER_COLUMN_NAMES
+            .iter()
+            .position(|name| name.eq_ignore_ascii_case(column))
+            .ok_or_else(|| PrepareStatementError::UnknownColumn(column.to_owned()))
+    };
+    let columns = match projection {
+        Projection::All => (0..Catalog::MASTER_COLUMN_NAMES.len()).collect(),
+        Projection::Columns(columns) => columns
+            .iter()
+            .map(|column| get_position(column))
+            .collect::<Result<Vec<usize>, PrepareStatementError>>()?,
+    };
+    let order = order
+        .map(|order| {
+            let collation = match order.collation {
+                Some(name) => Collation::try_from(name.as_str())
+                    .map_err(|()| PrepareStatementError::UnknownCollation(name))?,
+                None => Collation::default(),
+            };
+            Ok((get_position(&order.column)?, collation))
+        })
+        .transpose()?;
+    Ok(StatementType::SelectMaster(MasterQuery {
+        columns,
+        order,
+        limit,
+    }))
+}
+
+/// Positions dans `Row::COLUMN_NAMES` des colonnes demandées, dans l'ordre
+/// demandé.
+fn plan_projection(projection: Projection) -> Result<Vec<usize>, PrepareStatementError> {
+    match projection {
+        Projection::All => Ok((0..Row::COLUMN_NAMES.len()).collect()),
+        Projection::Columns(columns) => columns
+            .iter()
+            .map(|column| get_column_position(column))
+            .collect(),
+    }
+}
+
+fn get_column_position(column: &str) -> Result<usize, PrepareStatementError> {
+    Row::COLUMN_NAMES
+        .iter()
+        .position(|name| name.eq_ignore_ascii_case(column))
+        .ok_or_else(|| PrepareStatementError::UnknownColumn(column.to_owned()))
+}
+
+/// Renvoie le nom de la colonne tel qu'il est déclaré dans la table.
+fn check_column(column: &str) -> Result<&'static str, PrepareStatementError> {
+    get_column_position(column).map(|position| Row::COLUMN_NAMES[position])
+}
+
+/// L'ordre des ids est celui du parcours de l'arbre : il n'y a rien à trier, et
+/// une collation ne change rien à l'ordre des nombres.
+fn plan_order(order: Order) -> Result<Option<SortOrder>, PrepareStatementError> {
+    let collation = match order.collation {
+        Some(name) => Collation::try_from(name.as_str())
+            .map_err(|()| PrepareStatementError::UnknownCollation(name))?,
+        None => Collation::default(),
+    };
+    let column = match check_column(&order.column)? {
+        "username" => TextColumn::Username,
+        "email" => TextColumn::Email,
+        _ => return Ok(None),
+    };
+    Ok(Some(SortOrder { column, collation }))
+}
+
+/// Vérifie les colonnes et les fonctions d'un `where`. Un id ne peut être
+/// comparé qu'à un nombre : `id = 'a'` est refusé.
+fn plan_predicate(filter: &Expr) -> Result<(), PrepareStatementError> {
+    match filter {
+        Expr::Column(_) | Expr::Value(_) => {
+            Err(PrepareStatementError::UnsupportedFilter(filter.to_string()))
+        }
+        _ => check_expr(filter),
+    }
+}
+
+fn check_expr(expr: &Expr) -> Result<(), PrepareStatementError> {
+    let check_id_operands = |operands: &[&Expr]| {
+        let is_id = |expr: &&Expr| matches!(expr, Expr::Column(column) if column.eq_ignore_ascii_case("id"));
+        if !operands.iter().any(is_id) {
+            return Ok(());
+        }
+        match operands.iter().find_map(|operand| match operand {
+            Expr::Value(Value::Text(text)) => Some(text),
+            _ => None,
+        }) {
+            Some(text) => Err(PrepareStatementError::InvalidId(text.clone())),
+            None => Ok(()),
+        }
+    };
+
+    match expr {
+        Expr::Column(column) => check_column(column).map(|_| ()),
+        Expr::Value(_) => Ok(()),
+        Expr::Compare(left, _, right) => {
+            check_expr(left)?;
+            check_expr(right)?;
+            check_id_operands(&[left, right])
+        }
+        Expr::Between {
+            expr: operand,
+            low,
+            high,
+        } => {
+            check_expr(operand)?;
+            check_expr(low)?;
+            check_expr(high)?;
+            check_id_operands(&[operand, low])?;
+            check_id_operands(&[operand, high])
+        }
+        Expr::And(left, right) | Expr::Or(left, right) | Expr::Arithmetic(left, _, right) => {
+            check_expr(left)?;
+            check_expr(right)
+        }
+        Expr::Call {
+            function,
+            arguments,
+        } => {
+            let Ok(known_function) = Function::try_from(function.as_str()) else {
+                return Err(PrepareStatementError::UnknownFunction(function.clone()));
+            };
+            if arguments.len() != known_function.get_nb_arguments() {
+                return Err(PrepareStatementError::WrongNumberOfArguments(
+                    function.clone(),
+                    known_function.get_nb_arguments(),
+                ));
+            }
+            arguments.iter().try_for_each(check_expr)
+        }
+    }
+}
+
+/// Id comparé à un nombre, comme `id > 5` ou `5 < id`.
+fn get_id_comparison(expr: &Expr) -> Option<(Comparison, u64)> {
+    let Expr::Compare(left, comparison, right) = expr else {
+        return None;
+    };
+    match (left.as_ref(), right.as_ref()) {
+        (Expr::Column(column), Expr::Value(Value::Number(id)))
+            if column.eq_ignore_ascii_case("id") =>
+        {
+            Some((*comparison, *id))
+        }
+        (Expr::Value(Value::Number(id)), Expr::Column(column))
+            if column.eq_ignore_ascii_case("id") =>
+        {
+            Some((comparison.flip(), *id))
+        }
+        _ => None,
+    }
+}
+
+/// `id between` deux nombres.
+fn get_id_between(expr: &Expr) -> Option<RangeInclusive<u64>> {
+    match expr {
+        Expr::Between { expr, low, high } => match (expr.as_ref(), low.as_ref(), high.as_ref()) {
+            (
+                Expr::Column(column),
+                Expr::Value(Value::Number(low)),
+                Expr::Value(Value::Number(high)),
+            ) if column.eq_ignore_ascii_case("id") => Some(*low..=*high),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Intervalle contenant les ids de toutes les lignes qui vérifient `filter`,
+/// vide s'il n'y en a aucune.
+fn get_id_range(filter: &Expr) -> RangeInclusive<u64> {
+    if let Some((comparison, id)) = get_id_comparison(filter) {
+        return id_comparison_range(comparison, id);
+    }
+    if let Some(range) = get_id_between(filter) {
+        return range;
+    }
+    match filter {
+        Expr::And(left, right) => {
+            let (left, right) = (get_id_range(left), get_id_range(right));
+            *left.start().max(right.start())..=*left.end().min(right.end())
+        }
+        Expr::Or(left, right) => {
+            let (left, right) = (get_id_range(left), get_id_range(right));
+            match (left.is_empty(), right.is_empty()) {
+                (true, _) => right,
+                (_, true) => left,
+                _ => *left.start().min(right.start())..=*left.end().max(right.end()),
+            }
+        }
+        _ => 0..=u64::MAX,
+    }
+}
 
-        let username = caps["username"].to_owned();
-        if username.len() > Username::MAX_SIZE {
-            return Err(PrepareStatementError::StringTooLong(
-                "username".to_string(),
-                Username::MAX_SIZE,
-            ));
+/// Vrai si l'intervalle de `get_id_range` suffit à choisir les lignes.
+fn is_id_range(filter: &Expr) -> bool {
+    match filter {
+        Expr::And(left, right) => is_id_range(left) && is_id_range(right),
+        _ => {
+            get_id_comparison(filter).is_some_and(|(comparison, _)| comparison != Comparison::Ne)
+                || get_id_between(filter).is_some()
         }
+    }
+}
 
-        let email = caps["email"].to_owned();
-        if email.len() > Email::MAX_SIZE {
-            return Err(PrepareStatementError::StringTooLong(
-                "email".to_string(),
-                Email::MAX_SIZE,
-            ));
+fn id_comparison_range(comparison: Comparison, id: u64) -> RangeInclusive<u64> {
+    let empty = || RangeInclusive::new(1, 0);
+    match comparison {
+        Comparison::Eq => id..=id,
+        Comparison::Ne => 0..=u64::MAX,
+        Comparison::Lt => id.checked_sub(1).map_or_else(empty, |end| 0..=end),
+        Comparison::Le => 0..=id,
+        Comparison::Gt => id
+            .checked_add(1)
+            .map_or_else(empty, |start| start..=u64::MAX),
+        Comparison::Ge => id..=u64::MAX,
+    }
+}
+
+/// Les valeurs sont données dans l'ordre des colonnes : id, username, email.
+fn plan_insert(values: Vec<Value>) -> Result<Row, PrepareStatementError> {
+    let nb_values = values.len();
+    let Ok([id, username, email]) = <[Value; 3]>::try_from(values) else {
+        return Err(PrepareStatementError::WrongNumberOfValues(nb_values));
+    };
+    let Value::Number(id) = id else {
+        return Err(PrepareStatementError::InvalidId(id.to_string()));
+    };
+    let text_error = |column: &str, e| match e {
+        TextError::TooLong(max_size) => {
+            PrepareStatementError::StringTooLong(column.to_string(), max_size)
+        }
+        TextError::ControlCharacter(c) => {
+            PrepareStatementError::ControlCharacter(column.to_string(), c)
         }
+    };
+    let username =
+        Username::try_new(username.to_string()).map_err(|e| text_error("username", e))?;
+    let email = Email::try_new(email.to_string()).map_err(|e| text_error("email", e))?;
 
-        let row = Row::new(Id::new(id), Username::new(username), Email::new(email));
+    Ok(Row::new(Id::new(id), username, email))
+}
 
-        return Ok(StatementType::Insert(row));
+fn plan_create_virtual_table(
+    name: String,
+    module: &str,
+    arguments: Vec<Value>,
+) -> Result<StatementType, PrepareStatementError> {
+    if [Catalog::TABLE_NAME, Catalog::MASTER_TABLE_NAME]
+        .iter()
+        .any(|table| table.eq_ignore_ascii_case(&name))
+    {
+        return Err(PrepareStatementError::TableExists(name));
     }
+    let module = match module.to_lowercase().as_str() {
+        "csv" => {
+            let Ok([Value::Text(file_path)]) = <[Value; 1]>::try_from(arguments) else {
+                return Err(PrepareStatementError::CsvArguments);
+            };
+            VirtualTableModule::Csv(file_path)
+        }
+        "sqlite" => match <[Value; 2]>::try_from(arguments) {
+            Ok([Value::Text(file_path), Value::Text(table)]) => VirtualTableModule::Sqlite {
+                file_path,
+                table: Some(table),
+            },
+            Err(arguments) => match <[Value; 1]>::try_from(arguments) {
+                Ok([Value::Text(file_path)]) => VirtualTableModule::Sqlite {
+                    file_path,
+                    table: None,
+                },
+                _ => return Err(PrepareStatementError::SqliteArguments),
+            },
+            Ok(_) => return Err(PrepareStatementError::SqliteArguments),
+        },
+        _ => return Err(PrepareStatementError::UnknownModule(module.to_owned())),
+    };
+    Ok(StatementType::CreateVirtualTable { name, module })
+}
+
+fn plan_pragma(name: &str, value: Option<&str>) -> Result<Pragma, PrepareStatementError> {
+    let name = name.to_lowercase();
+    let invalid_value =
+        |value: &str| PrepareStatementError::InvalidPragmaValue(name.clone(), value.to_owned());
 
-    Err(PrepareStatementError::UnrecognizedStatement)
+    match name.as_str() {
+        "synchronous" => {
+            let synchronous = value
+                .map(|value| Synchronous::try_from(value).map_err(|()| invalid_value(value)))
+                .transpose()?;
+            Ok(Pragma::Synchronous(synchronous))
+        }
+        "write_mode" => {
+            let write_mode = value
+                .map(|value| WriteMode::try_from(value).map_err(|()| invalid_value(value)))
+                .transpose()?;
+            Ok(Pragma::WriteMode(write_mode))
+        }
+        "cache_size" => {
+            let cache_size = value
+                .map(|value| match value.parse::<usize>() {
+                    Ok(cache_size) if cache_size > 0 => Ok(cache_size),
+                    _ => Err(invalid_value(value)),
+                })
+                .transpose()?;
+            Ok(Pragma::CacheSize(cache_size))
+        }
+        "commit_delay" => {
+            let commit_delay = value
+                .map(|value| value.parse::<u64>().map_err(|_| invalid_value(value)))
+                .transpose()?;
+            Ok(Pragma::CommitDelay(commit_delay))
+        }
+        "flush_interval" => {
+            let flush_interval = value
+                .map(|value| value.parse::<u64>().map_err(|_| invalid_value(value)))
+                .transpose()?;
+            Ok(Pragma::FlushInterval(flush_interval))
+        }
+        "bloom_filter" => {
+            let enabled = value
+                .map(|value| match value.to_lowercase().as_str() {
+                    "on" | "1" => Ok(true),
+                    "off" | "0" => Ok(false),
+                    _ => Err(invalid_value(value)),
+                })
+                .transpose()?;
+            Ok(Pragma::BloomFilter(enabled))
+        }
+        "integrity_check" => match value {
+            Some(value) => Err(invalid_value(value)),
+            None => Ok(Pragma::IntegrityCheck),
+        },
+        _ => Err(PrepareStatementError::UnknownPragma(name)),
+    }
 }
 
+#[tracing::instrument(skip_all, fields(statement = %statement))]
 pub fn execute_statement(
     table: Rc<RefCell<Table>>,
     statement: StatementType,
 ) -> Result<StatementOutput, StatementOutputError> {
     match statement {
-        StatementType::Select => Ok(execute_select(table)),
-        StatementType::Insert(row) => execute_insert(table, row),
+        StatementType::Select(query) => execute_select(table, &query),
+        StatementType::SelectMaster(query) => execute_select_master(&table, &query),
+        StatementType::Insert { row, returning } => match returning {
+            Some(columns) => execute_insert_returning(table, row, columns),
+            None => execute_insert(table, row),
+        },
+        StatementType::DeleteAll { returning } => match returning {
+            Some(columns) => execute_delete_all_returning(table, columns),
+            None => execute_delete_all(table),
+        },
+        StatementType::Pragma(pragma) => execute_pragma(table, pragma),
+        StatementType::Explain(statement) => Ok(StatementOutput::Plan(statement.to_string())),
+        StatementType::CreateVirtualTable { name, module } => {
+            let provider: Rc<dyn TableProvider> = match module {
+                VirtualTableModule::Csv(file_path) => {
+                    Rc::new(CsvTable::open(&file_path).map_err(StatementOutputError::Csv)?)
+                }
+                VirtualTableModule::Sqlite {
+                    file_path,
+                    table: sqlite_table,
+                } => Rc::new(
+                    SqliteTable::open(&file_path, sqlite_table.as_deref())
+                        .map_err(StatementOutputError::Sqlite)?,
+                ),
+            };
+            table.borrow_mut().register_provider(&name, provider);
+            Ok(StatementOutput::TableCreated)
+        }
     }
 }
 
-pub fn execute_select(table: Rc<RefCell<Table>>) -> StatementOutput {
-    let mut cursor = Cursor::at_start(table.clone());
+pub fn execute_pragma(
+    table: Rc<RefCell<Table>>,
+    pragma: Pragma,
+) -> Result<StatementOutput, StatementOutputError> {
+    let pager = table.borrow().get_pager();
+    let output = match pragma {
+        Pragma::Synchronous(None) => {
+            StatementOutput::PragmaValue(pager.get_synchronous().to_string())
+        }
+        Pragma::Synchronous(Some(synchronous)) => {
+            pager.set_synchronous(synchronous);
+            StatementOutput::PragmaSet
+        }
+        Pragma::WriteMode(None) => StatementOutput::PragmaValue(pager.get_write_mode().to_string()),
+        Pragma::WriteMode(Some(write_mode)) => {
+            pager.set_write_mode(write_mode);
+            StatementOutput::PragmaSet
+        }
+        Pragma::CacheSize(None) => StatementOutput::PragmaValue(pager.get_cache_size().to_string()),
+        Pragma::CacheSize(Some(cache_size)) => {
+            pager.set_cache_size(cache_size);
+            StatementOutput::PragmaSet
+        }
+        Pragma::CommitDelay(None) => {
+            StatementOutput::PragmaValue(pager.get_commit_delay().as_millis().to_string())
+        }
+        Pragma::CommitDelay(Some(commit_delay)) => {
+            pager.set_commit_delay(Duration::from_millis(commit_delay));
+            StatementOutput::PragmaSet
+        }
+        Pragma::FlushInterval(None) => {
+            StatementOutput::PragmaValue(pager.get_flush_interval().as_millis().to_string())
+        }
+        Pragma::FlushInterval(Some(flush_interval)) => {
+            pager.set_flush_interval(Duration::from_millis(flush_interval));
+            StatementOutput::PragmaSet
+        }
+        Pragma::BloomFilter(None) => {
+            let enabled = table.borrow().has_bloom_filter();
+            StatementOutput::PragmaValue(if enabled { "on" } else { "off" }.to_owned())
+        }
+        Pragma::BloomFilter(Some(enabled)) => {
+            table
+                .borrow_mut()
+                .set_bloom_filter(enabled)
+                .map_err(|e| StatementOutputError::Select(GetRowError::GetPage(e)))?;
+            StatementOutput::PragmaSet
+        }
+        Pragma::IntegrityCheck => {
+            StatementOutput::IntegrityReport(check_integrity(&table.borrow()))
+        }
+    };
+    Ok(output)
+}
+
+/// Seules les feuilles qui contiennent l'intervalle demandé sont lues, à mesure
+/// que les lignes sont consommées, et la lecture s'arrête à la limite.
+pub fn execute_select(
+    table: Rc<RefCell<Table>>,
+    query: &SelectQuery,
+) -> Result<StatementOutput, StatementOutputError> {
+    if let Some(name) = &query.table {
+        let provider = table
+            .borrow()
+            .get_provider(name)
+            .ok_or_else(|| StatementOutputError::UnknownTable(name.clone()))?;
+        return execute_select_provider(provider.as_ref(), query);
+    }
+    // Un id absent du filtre de Bloom n'est pas cherché dans l'arbre.
+    if let IdFilter::Id(id) = query.filter
+        && !table.borrow().may_contain(id)
+    {
+        return Ok(StatementOutput::Select(Box::new(
+            Rows::from_rows(Vec::new(), query.columns.clone()).into(),
+        )));
+    }
+    let cursor = Cursor::range(table, query.filter.get_range())
+        .map_err(|e| StatementOutputError::Select(GetRowError::GetPage(e)))?;
+    let rows = Rows::new(cursor, query.columns.clone(), query.limit)
+        .with_predicate(query.predicate.clone())
+        .skipping_corrupted();
+    match &query.order {
+        Some(order) => rows.sort(order).map_ok_err(
+            |rows| StatementOutput::Select(Box::new(rows.into())),
+            StatementOutputError::Select,
+        ),
+        None => Ok(StatementOutput::Select(Box::new(rows.into()))),
+    }
+}
 
-    let mut result = Vec::<Row>::new();
-    while !cursor.is_end_of_table() {
-        let bytes = cursor.get();
-        let row = Row::try_from(bytes).unwrap();
-        result.push(row);
-        cursor.advance();
+/// La source est lue en entier à chaque `select`. Sans `order by`, les lignes
+/// sont rendues dans l'ordre des ids, comme celles de l'arbre.
+fn execute_select_provider(
+    provider: &dyn TableProvider,
+    query: &SelectQuery,
+) -> Result<StatementOutput, StatementOutputError> {
+    let range = query.filter.get_range();
+    let mut rows = provider
+        .scan()
+        .filter(|row| match row {
+            Ok(row) => {
+                range.contains(&**row.get_id())
+                    && query
+                        .predicate
+                        .as_ref()
+                        .is_none_or(|predicate| predicate.eval(&row.as_row_ref()).is_true())
+            }
+            Err(_) => true,
+        })
+        .collect::<Result<Vec<Row>, GetRowError>>()
+        .map_err(StatementOutputError::Select)?;
+    match &query.order {
+        Some(order) => rows.sort_by(|a, b| {
+            order
+                .collation
+                .compare(order.column.get(a), order.column.get(b))
+        }),
+        None => rows.sort_by_key(|row| **row.get_id()),
     }
+    rows.truncate(query.limit.unwrap_or(usize::MAX));
+    Ok(StatementOutput::Select(Box::new(
+        Rows::from_rows(rows, query.columns.clone()).into(),
+    )))
+}
 
-    StatementOutput::Select(result)
+/// La page racine est triée comme un nombre, les autres colonnes comme du texte.
+pub fn execute_select_master(
+    table: &Rc<RefCell<Table>>,
+    query: &MasterQuery,
+) -> Result<StatementOutput, StatementOutputError> {
+    let catalog = table
+        .borrow()
+        .get_catalog()
+        .map_err(StatementOutputError::Catalog)?;
+    let mut records = catalog.get_master_records();
+    if let Some((column, collation)) = query.order {
+        let root_page_column = Catalog::MASTER_COLUMN_NAMES
+            .iter()
+            .position(|name| *name == "rootpage");
+        records.sort_by(|a, b| {
+            let (a, b) = (&a[column], &b[column]);
+            if Some(column) == root_page_column {
+                a.parse::<usize>().ok().cmp(&b.parse::<usize>().ok())
+            } else {
+                collation.compare(a, b)
+            }
+        });
+    }
+    records.truncate(query.limit.unwrap_or(usize::MAX));
+    let records = records
+        .iter()
+        .map(|record| {
+            query
+                .columns
+                .iter()
+                .map(|column| record[*column].clone())
+                .collect()
+        })
+        .collect();
+    Ok(StatementOutput::Master {
+        columns: query.columns.clone(),
+        records,
+    })
 }
 
 pub fn execute_insert(
     table: Rc<RefCell<Table>>,
     row: Row,
 ) -> Result<StatementOutput, StatementOutputError> {
-    let mut cursor = Cursor::at_end(table.clone());
-    let row_bytes = <[u8; Row::MAX_SIZE]>::from(row);
-    cursor.get_mut().copy_from_slice(&row_bytes[..]);
-    {
-        let mut table_mut = table.borrow_mut();
-        let nb_rows = table_mut.get_nb_rows();
-        table_mut.set_nb_rows(nb_rows + 1);
+    table
+        .borrow_mut()
+        .insert(row.clone())
+        .map_err(StatementOutputError::Insert)?;
+    table.borrow_mut().push_undo(UndoEntry::Insert(row.clone()));
+    let hooks = table.borrow().get_hooks();
+    for hooks in hooks {
+        hooks.on_insert(&row);
+    }
+    table.borrow().on_modified().map_ok_err(
+        |()| StatementOutput::InsertSuccessfull,
+        StatementOutputError::WriteThrough,
+    )
+}
+
+/// La ligne est gardée telle qu'elle a été insérée.
+pub fn execute_insert_returning(
+    table: Rc<RefCell<Table>>,
+    row: Row,
+    columns: Vec<usize>,
+) -> Result<StatementOutput, StatementOutputError> {
+    let _ = execute_insert(table, row.clone())?;
+    Ok(StatementOutput::Select(Box::new(
+        Rows::from_rows(vec![row], columns).into(),
+    )))
+}
+
+/// Les lignes sont lues avant d'être supprimées : rien n'est supprimé si l'une
+/// d'elles ne peut pas être lue.
+pub fn execute_delete_all_returning(
+    table: Rc<RefCell<Table>>,
+    columns: Vec<usize>,
+) -> Result<StatementOutput, StatementOutputError> {
+    let rows = read_all_rows(table.clone())?;
+    let _ = delete_all(table, &rows)?;
+    Ok(StatementOutput::Select(Box::new(
+        Rows::from_rows(rows, columns).into(),
+    )))
+}
+
+/// Les lignes ne sont lues que si des hooks doivent les recevoir, ou si `.undo`
+/// peut les garder, voir `Table::UNDO_MAX_ROWS`.
+pub fn execute_delete_all(
+    table: Rc<RefCell<Table>>,
+) -> Result<StatementOutput, StatementOutputError> {
+    let is_read = {
+        let table = table.borrow();
+        table.has_hooks() || table.get_nb_rows() <= Table::UNDO_MAX_ROWS
+    };
+    let rows = if is_read {
+        read_all_rows(table.clone())?
+    } else {
+        Vec::new()
+    };
+    delete_all(table, &rows)
+}
+
+fn read_all_rows(table: Rc<RefCell<Table>>) -> Result<Vec<Row>, StatementOutputError> {
+    let cursor = Cursor::range(table, IdFilter::All.get_range())
+        .map_err(|e| StatementOutputError::Select(GetRowError::GetPage(e)))?;
+    Rows::new(cursor, Vec::new(), None)
+        .collect::<Result<Vec<Row>, GetRowError>>()
+        .map_err(StatementOutputError::Select)
+}
+
+/// `rows` sont les lignes supprimées, passées aux hooks et gardées pour `.undo`
+/// s'il y en a au plus `Table::UNDO_MAX_ROWS`.
+fn delete_all(
+    table: Rc<RefCell<Table>>,
+    rows: &[Row],
+) -> Result<StatementOutput, StatementOutputError> {
+    let nb_rows = table.borrow().get_nb_rows();
+    table
+        .borrow_mut()
+        .truncate()
+        .map_err(StatementOutputError::Delete)?;
+    if nb_rows <= Table::UNDO_MAX_ROWS {
+        table
+            .borrow_mut()
+            .push_undo(UndoEntry::DeleteAll(rows.to_vec()));
+    } else {
+        // Les instructions précédentes ne s'appliqueraient plus à la table vidée.
+        table.borrow_mut().clear_undo();
+        table
+            .borrow_mut()
+            .push_undo(UndoEntry::LargeDelete(nb_rows));
+    }
+    let hooks = table.borrow().get_hooks();
+    for hooks in hooks {
+        rows.iter().for_each(|row| hooks.on_delete(row));
     }
-    Ok(StatementOutput::InsertSuccessfull)
+    table.borrow().on_modified().map_ok_err(
+        |()| StatementOutput::DeleteSuccessfull,
+        StatementOutputError::WriteThrough,
+    )
 }
 
 #[cfg(test)]
-mod statement_test {}
+mod statement_test {
+    use super::*;
+
+    use crate::btree::NodeView;
+    use crate::hooks::Hooks;
+    use crate::pager::Pager;
+    use crate::provider::ProviderRows;
+    use crate::table::table_test::row;
+
+    #[test]
+    fn test_split_statements() {
+        assert_eq!(
+            split_statements("insert 1 a a@x; select;"),
+            vec!["insert 1 a a@x", "select"]
+        );
+        assert_eq!(
+            split_statements("insert 1 a 'a;b' ; ;select"),
+            vec!["insert 1 a 'a;b'", "select"]
+        );
+        assert_eq!(split_statements(" ; "), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_prepare_insert() {
+        let insert = |id, username: &str, email: &str| {
+            Ok(StatementType::Insert {
+                row: Row::new(
+                    Id::new(id),
+                    Username::new(username.to_owned()),
+                    Email::new(email.to_owned()),
+                ),
+                returning: None,
+            })
+        };
+        assert_eq!(
+            prepare_statement("insert 1 bob bob@mail.com"),
+            insert(1, "bob", "bob@mail.com")
+        );
+        assert_eq!(
+            prepare_statement("INSERT  2 'Bob Dylan' \"bob;d@mail.com\""),
+            insert(2, "Bob Dylan", "bob;d@mail.com")
+        );
+        assert_eq!(
+            prepare_statement("insert bob 1 bob@mail.com"),
+            Err(PrepareStatementError::InvalidId("bob".to_owned()))
+        );
+        assert_eq!(
+            prepare_statement("insert 1 bob"),
+            Err(PrepareStatementError::WrongNumberOfValues(2))
+        );
+        assert_eq!(
+            prepare_statement("insert 3 'bo\tb' bob@mail.com"),
+            Err(PrepareStatementError::ControlCharacter(
+                "username".to_owned(),
+                '\t'
+            ))
+        );
+    }
+
+    #[test]
+    fn test_prepare_select() {
+        let select = |filter, limit| {
+            Ok(StatementType::Select(SelectQuery {
+                table: None,
+                filter,
+                predicate: None,
+                columns: vec![0, 1, 2],
+                order: None,
+                limit,
+            }))
+        };
+        assert_eq!(prepare_statement("select"), select(IdFilter::All, None));
+        assert_eq!(
+            prepare_statement("select where id = 7"),
+            select(IdFilter::Id(7), None)
+        );
+        assert_eq!(
+            prepare_statement("select where id between 2 and 5 order by id limit 3"),
+            select(IdFilter::Between(2..=5), Some(3))
+        );
+        assert_eq!(
+            prepare_statement("explain select 7").map(|statement| statement.to_string()),
+            Ok("EXPLAIN INDEX SEEK (id = 7)".to_owned())
+        );
+        assert_eq!(
+            prepare_statement("select where 5 = id and id between 1 and 9"),
+            select(IdFilter::Id(5), None)
+        );
+        assert_eq!(
+            prepare_statement("select name"),
+            Err(PrepareStatementError::UnknownColumn("name".to_owned()))
+        );
+        assert_eq!(
+            prepare_statement("select where username = bob"),
+            Err(PrepareStatementError::UnknownColumn("bob".to_owned()))
+        );
+        assert_eq!(
+            prepare_statement("select where id > 'bob'"),
+            Err(PrepareStatementError::InvalidId("bob".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_select_skips_corrupted_rows() {
+        let table = Rc::new(RefCell::new(Table::new(Rc::new(Pager::default()))));
+        for id in 1..=3 {
+            let _ = execute_insert(table.clone(), row(id)).unwrap();
+        }
+        // Un username qui n'est plus de l'UTF-8 valide.
+        let offset = NodeView::leaf_node_value_range(1).start + Row::USERNAME_OFFSET;
+        table
+            .borrow()
+            .get_pager()
+            .write_at(Table::ROOT_PAGE_NUM, offset, &[0xFF])
+            .unwrap();
+
+        let query = SelectQuery {
+            table: None,
+            filter: IdFilter::All,
+            predicate: None,
+            columns: vec![0],
+            order: None,
+            limit: None,
+        };
+        let Ok(StatementOutput::Select(result)) = execute_select(table.clone(), &query) else {
+            panic!("The select should succeed.");
+        };
+        let skipped_rows = result.get_skipped_rows();
+        let ids: Vec<u64> = result
+            .into_rows()
+            .map(|row| **row.unwrap().get_id())
+            .collect();
+        assert_eq!(ids, vec![1, 3]);
+        assert_eq!(skipped_rows.get(), 1);
+
+        // Seul le `select` passe la ligne : rien n'est supprimé sans elle.
+        assert!(matches!(
+            execute_delete_all_returning(table.clone(), vec![0]),
+            Err(StatementOutputError::Select(GetRowError::Deserialize(_)))
+        ));
+        assert_eq!(table.borrow().get_nb_rows(), 3);
+    }
+
+    #[test]
+    fn test_select_filters_across_leaves() {
+        let table = Rc::new(RefCell::new(Table::new(Rc::new(Pager::default()))));
+        for id in (0..100).rev() {
+            let _ = execute_insert(table.clone(), row(id)).unwrap();
+        }
+
+        let select_ids = |filter, limit| {
+            let query = SelectQuery {
+                table: None,
+                filter,
+                predicate: None,
+                columns: vec![0, 1, 2],
+                order: None,
+                limit,
+            };
+            let Ok(StatementOutput::Select(result)) = execute_select(table.clone(), &query) else {
+                panic!("Select failed.");
+            };
+            result
+                .into_rows()
+                .map(|row| **row.unwrap().get_id())
+                .collect::<Vec<u64>>()
+        };
+
+        assert_eq!(
+            select_ids(IdFilter::All, None),
+            (0..100).collect::<Vec<u64>>()
+        );
+        assert_eq!(select_ids(IdFilter::Id(42), None), vec![42]);
+        assert!(select_ids(IdFilter::Id(100), None).is_empty());
+        assert_eq!(
+            select_ids(IdFilter::Between(20..=60), Some(3)),
+            vec![20, 21, 22]
+        );
+        assert_eq!(
+            select_ids(IdFilter::Between(95..=200), None),
+            vec![95, 96, 97, 98, 99]
+        );
+    }
+
+    #[test]
+    fn test_select_compound_predicates() {
+        let table = Rc::new(RefCell::new(Table::new(Rc::new(Pager::default()))));
+        for (id, username) in [(1, "a"), (2, "b"), (6, "a"), (7, "c"), (8, "b")] {
+            let row = Row::new(
+                Id::new(id),
+                Username::new(username.to_owned()),
+                Email::new(format!("{username}@mail.com")),
+            );
+            let _ = execute_insert(table.clone(), row).unwrap();
+        }
+        let select_ids = |statement| {
+            let statement = prepare_statement(statement).unwrap();
+            let Ok(StatementOutput::Select(result)) = execute_statement(table.clone(), statement)
+            else {
+                panic!("Select failed.");
+            };
+            result
+                .into_rows()
+                .map(|row| **row.unwrap().get_id())
+                .collect::<Vec<u64>>()
+        };
+
+        assert_eq!(
+            select_ids("select where id > 5 and (username = 'a' or username = 'b')"),
+            vec![6, 8]
+        );
+        assert_eq!(
+            select_ids("select where id > 5 and username = 'a' or username = 'b'"),
+            vec![2, 6, 8]
+        );
+        assert_eq!(
+            select_ids("select where id != 6 and email >= 'b' limit 2"),
+            vec![2, 7]
+        );
+        assert_eq!(
+            select_ids("select where id < 0 or 9 <= id"),
+            Vec::<u64>::new()
+        );
+        assert_eq!(
+            select_ids("select where id * 2 - 1 > 12 or upper(email) = 'A@MAIL.COM'"),
+            vec![1, 6, 7, 8]
+        );
+        assert_eq!(
+            prepare_statement("select where trim(username) = 'a'"),
+            Err(PrepareStatementError::UnknownFunction("trim".to_owned()))
+        );
+        assert_eq!(
+            prepare_statement("select where length() = 1"),
+            Err(PrepareStatementError::WrongNumberOfArguments(
+                "length".to_owned(),
+                1
+            ))
+        );
+        assert_eq!(
+            prepare_statement("explain select where id >= 2 and id < 7 and username != 'a'")
+                .map(|statement| statement.to_string()),
+            Ok("EXPLAIN INDEX RANGE SCAN (id BETWEEN 2 AND 6) FILTER (id >= 2 and id < 7 and username != 'a')".to_owned())
+        );
+        assert_eq!(
+            prepare_statement("explain select where id > 5 and id <= 6")
+                .map(|statement| statement.to_string()),
+            Ok("EXPLAIN INDEX SEEK (id = 6)".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_select_order_by_collation() {
+        let table = Rc::new(RefCell::new(Table::new(Rc::new(Pager::default()))));
+        for (id, username) in [(1, "bob"), (2, "Alice"), (3, "carol"), (4, "Bob")] {
+            let row = Row::new(
+                Id::new(id),
+                Username::new(username.to_owned()),
+                Email::new(format!("{username}@mail.com")),
+            );
+            let _ = execute_insert(table.clone(), row).unwrap();
+        }
+
+        let select_ids = |statement| {
+            let Ok(StatementType::Select(query)) = prepare_statement(statement) else {
+                panic!("Prepare failed.");
+            };
+            let Ok(StatementOutput::Select(result)) = execute_select(table.clone(), &query) else {
+                panic!("Select failed.");
+            };
+            result
+                .into_rows()
+                .map(|row| **row.unwrap().get_id())
+                .collect::<Vec<u64>>()
+        };
+
+        assert_eq!(select_ids("select order by username"), vec![2, 4, 1, 3]);
+        assert_eq!(
+            select_ids("select order by username collate nocase limit 3"),
+            vec![2, 4, 1]
+        );
+        assert_eq!(
+            select_ids("select where id between 2 and 4 order by username collate nocase"),
+            vec![2, 4, 3]
+        );
+        assert_eq!(
+            prepare_statement("select order by username collate french"),
+            Err(PrepareStatementError::UnknownCollation("french".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_returning() {
+        let table = Rc::new(RefCell::new(Table::new(Rc::new(Pager::default()))));
+        let returned = |statement| {
+            let statement = prepare_statement(statement).unwrap();
+            let Ok(StatementOutput::Select(result)) = execute_statement(table.clone(), statement)
+            else {
+                panic!("Nothing returned.");
+            };
+            let names: Vec<&str> = result.get_columns().iter().map(|c| c.name).collect();
+            let records = result.into_records().map(Result::unwrap).collect();
+            (names, records)
+        };
+
+        assert_eq!(
+            returned("insert 2 bob bob@mail.com returning email, id"),
+            (
+                vec!["email", "id"],
+                vec![vec![
+                    Value::Text("bob@mail.com".to_owned()),
+                    Value::Number(2)
+                ]]
+            )
+        );
+        let _ = returned("insert 1 alice alice@mail.com returning *");
+        let (names, records) = returned("delete returning *");
+        assert_eq!(names, vec!["id", "username", "email"]);
+        assert_eq!(
+            records
+                .iter()
+                .map(|values| values[0].clone())
+                .collect::<Vec<Value>>(),
+            vec![Value::Number(1), Value::Number(2)]
+        );
+        assert_eq!(returned("delete returning id"), (vec!["id"], Vec::new()));
+    }
+
+    #[test]
+    fn test_display_output() {
+        let table = Rc::new(RefCell::new(Table::new(Rc::new(Pager::default()))));
+        let output = |statement| {
+            execute_statement(table.clone(), prepare_statement(statement).unwrap()).unwrap()
+        };
+        let _ = output("insert 1 alice alice@mail.com");
+        let _ = output("insert 2 bob bob@mail.com");
+
+        assert_eq!(
+            output("select username, id").to_string(),
+            "(alice, 1)\n(bob, 2)"
+        );
+        assert_eq!(output("pragma integrity_check").to_string(), "ok");
+        assert_eq!(output("insert 3 carol carol@mail.com").to_string(), "");
+
+        #[cfg(feature = "serde")]
+        assert_eq!(
+            serde_json::to_string(&output("select email where id = 2")).unwrap(),
+            r#"{"select":{"columns":[{"name":"email","type":"text"}],"rows":[["bob@mail.com"]]}}"#
+        );
+    }
+
+    #[test]
+    fn test_hooks() {
+        #[derive(Debug, Default)]
+        struct Log(RefCell<Vec<String>>);
+        impl Hooks for Log {
+            fn on_insert(&self, row: &Row) {
+                self.0
+                    .borrow_mut()
+                    .push(format!("insert {}", **row.get_id()));
+            }
+
+            fn on_delete(&self, row: &Row) {
+                self.0
+                    .borrow_mut()
+                    .push(format!("delete {}", **row.get_id()));
+            }
+        }
+
+        let table = Rc::new(RefCell::new(Table::new(Rc::new(Pager::default()))));
+        let log = Rc::new(Log::default());
+        table.borrow_mut().add_hooks(log.clone());
+        for statement in [
+            "insert 2 bob bob@mail.com",
+            "insert 2 bob bob@mail.com",
+            "insert 1 alice alice@mail.com returning id",
+            "delete",
+        ] {
+            let _ = execute_statement(table.clone(), prepare_statement(statement).unwrap());
+        }
+        assert_eq!(
+            *log.0.borrow(),
+            ["insert 2", "insert 1", "delete 1", "delete 2"]
+        );
+    }
+
+    #[test]
+    fn test_select_provider() {
+        #[derive(Debug)]
+        struct Ids(Vec<u64>);
+        impl TableProvider for Ids {
+            fn scan(&self) -> ProviderRows<'_> {
+                Box::new(self.0.iter().map(|id| Ok(row(*id))))
+            }
+        }
+
+        let table = Rc::new(RefCell::new(Table::new(Rc::new(Pager::default()))));
+        table
+            .borrow_mut()
+            .register_provider("Ids", Rc::new(Ids(vec![5, 1, 4, 2, 3])));
+        let select_ids = |statement| {
+            let statement = prepare_statement(statement).unwrap();
+            let Ok(StatementOutput::Select(result)) = execute_statement(table.clone(), statement)
+            else {
+                panic!("Select failed.");
+            };
+            result
+                .into_rows()
+                .map(|row| **row.unwrap().get_id())
+                .collect::<Vec<u64>>()
+        };
+        assert_eq!(select_ids("select from ids"), vec![1, 2, 3, 4, 5]);
+        assert_eq!(
+            select_ids("select from IDS where id between 2 and 4 limit 2"),
+            vec![2, 3]
+        );
+        assert_eq!(
+            select_ids("select from ids order by username limit 3"),
+            vec![1, 2, 3]
+        );
+        assert_eq!(
+            prepare_statement("select from ids where id between 3 and 4 limit 1")
+                .map(|statement| statement.to_string()),
+            Ok("SCAN VIRTUAL TABLE ids FILTER (id BETWEEN 3 AND 4) LIMIT 1".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_select_master() {
+        let table = Rc::new(RefCell::new(Table::new(Rc::new(Pager::default()))));
+        let statement = prepare_statement("select name, rootpage from MY_DB_MASTER").unwrap();
+        let Ok(StatementOutput::Master { columns, records }) =
+            execute_statement(table.clone(), statement)
+        else {
+            panic!("Nothing selected.");
+        };
+        assert_eq!(columns, vec![1, 3]);
+        assert_eq!(records, vec![vec!["users".to_owned(), "1".to_owned()]]);
+
+        assert_eq!(
+            prepare_statement("select from my_db_master order by sql collate nocase limit 1")
+                .map(|statement| statement.to_string()),
+            Ok("SCAN my_db_master SORT (sql COLLATE nocase) LIMIT 1".to_owned())
+        );
+        assert_eq!(
+            prepare_statement("select from my_db_master where id = 1"),
+            Err(PrepareStatementError::UnsupportedFilter(
+                "id = 1".to_owned()
+            ))
+        );
+        let statement = prepare_statement("select from accounts").unwrap();
+        assert!(matches!(
+            execute_statement(table, statement),
+            Err(StatementOutputError::UnknownTable(name)) if name == "accounts"
+        ));
+        assert!(matches!(
+            prepare_statement("select from users where id = 1"),
+            Ok(StatementType::Select(_))
+        ));
+    }
+}
@@ -1,42 +1,427 @@
-use std::{cell::RefCell, rc::Rc};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Instant;
 
-use crate::pager::{GetPageError, Page, Pager};
+use tracing::debug;
+
+use crate::bloom::BloomFilter;
+use crate::btree::{Node, NodeView, read_node, write_node};
+use crate::catalog::{Catalog, CatalogError};
+use crate::csv::CsvError;
+use crate::cursor::{CursorError, RowIter};
+use crate::hooks::Hooks;
+use crate::page_view::PageViewMut;
+use crate::pager::{
+    GetPageError, MemoryUsage, Page, Pager, PagerOpenError, SaveToDiskError, SetOpenSaveFileError,
+    WriteMode,
+};
+use crate::provider::TableProvider;
 use crate::row::{DeserializeError, Row};
-use crate::slice_pointer::{SlicePointer, SlicePointerMut};
+use crate::sqlite::SqliteError;
 
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub enum GetRowError {
     PoisonedPager,
     GetPage(GetPageError),
     Deserialize(DeserializeError),
+    /// La lecture a été abandonnée par Ctrl-C.
+    Interrupted,
+    /// Erreur propre à la source d'une table virtuelle, déjà mise en forme.
+    Provider(String),
+    Csv(CsvError),
+    Sqlite(SqliteError),
+    Cursor(CursorError),
+}
+impl From<CursorError> for GetRowError {
+    fn from(e: CursorError) -> Self {
+        match e {
+            CursorError::GetPage(e) => Self::GetPage(e),
+            e => Self::Cursor(e),
+        }
+    }
 }
 
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub enum WriteRowError {
     TableFull,
+    /// Une ligne avec le même id existe déjà.
+    DuplicateKey(u64),
     PoisonedPager,
     GetPage(GetPageError),
 }
 
+/// Ce qu'il faut pour annuler une instruction, voir `.undo`.
+#[cfg_attr(debug_assertions, derive(Debug))]
+#[derive(Clone)]
+pub enum UndoEntry {
+    /// Ligne insérée.
+    Insert(Row),
+    /// Lignes supprimées, dans l'ordre des clés.
+    DeleteAll(Vec<Row>),
+    /// Nombre de lignes d'une suppression trop grande pour être gardée, voir
+    /// `Table::UNDO_MAX_ROWS`. Ni elle ni les instructions précédentes ne
+    /// peuvent être annulées.
+    LargeDelete(usize),
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum UpdateRowError {
+    /// Aucune ligne n'a cet id.
+    NotFound(u64),
+    /// Id de la ligne remplacée et id de la nouvelle ligne, qui doivent être égaux.
+    KeyMismatch(u64, u64),
+    GetPage(GetPageError),
+}
+
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub enum DeleteRowError {
+    GetPage(GetPageError),
+}
+
 #[cfg_attr(debug_assertions, derive(Debug))]
 pub struct Table {
-    pager: Rc<RefCell<Pager>>,
+    pager: Rc<Pager>,
     nb_rows: usize,
+    /// Clés de la table, seulement si le filtre a été activé, voir `set_bloom_filter`.
+    bloom_filter: Option<BloomFilter>,
+    /// Table de la base, mise de côté pendant qu'un instantané est ouvert.
+    main: Option<Box<Table>>,
+    hooks: Vec<Rc<dyn Hooks>>,
+    /// Tables virtuelles, sous leur nom en minuscules.
+    providers: Vec<(String, Rc<dyn TableProvider>)>,
+    /// Dernières instructions de la session, la plus récente à la fin.
+    undo_log: Vec<UndoEntry>,
 }
 impl Table {
-    pub const ROWS_PER_PAGE: usize = Page::SIZE / Row::MAX_SIZE;
-    pub const MAX_ROWS: usize = Self::ROWS_PER_PAGE * Pager::MAX_PAGES;
+    /// Le catalogue occupe toujours la première page, voir `catalog.rs`.
+    pub const CATALOG_PAGE_NUM: usize = 0;
+
+    /// La racine de l'arbre ne change jamais de page.
+    pub const ROOT_PAGE_NUM: usize = 1;
+
+    /// Nombre d'instructions que `.undo` peut annuler à la suite.
+    pub const UNDO_LOG_SIZE: usize = 16;
+
+    /// Au-delà, les lignes d'un `delete` ne sont pas gardées pour `.undo` : le
+    /// journal resterait en mémoire avec plusieurs copies de la table.
+    pub const UNDO_MAX_ROWS: usize = 256;
+
+    /// Le nombre de lignes est relu dans l'entête du fichier ouvert par le pager.
+    pub fn new(pager: Rc<Pager>) -> Self {
+        let nb_rows = pager.nb_rows_in_file();
+        Self {
+            pager,
+            nb_rows,
+            bloom_filter: None,
+            main: None,
+            hooks: Vec::new(),
+            providers: Vec::new(),
+            undo_log: Vec::new(),
+        }
+    }
+
+    /// Remplace le fichier ouvert et recharge son nombre de lignes.
+    pub fn set_open_save_file(&mut self, file_path: &str) -> Result<(), SetOpenSaveFileError> {
+        self.pager.set_open_save_file(file_path)?;
+        self.nb_rows = self.pager.nb_rows_in_file();
+        self.clear_undo();
+        self.check_catalog()
+            .map_err(SetOpenSaveFileError::Catalog)?;
+        if self.has_bloom_filter() {
+            self.set_bloom_filter(true).map_err(|e| match e {
+                GetPageError::IoError(e) => SetOpenSaveFileError::IoError(e),
+                _ => SetOpenSaveFileError::PageInUse,
+            })?;
+        }
+        Ok(())
+    }
+
+    /*
+     * Instantanés : copies figées de la base, écrites comme par `.backup` dans
+     * `<fichier>.<nom>.snapshot`, ou `<nom>.snapshot` pour une base en mémoire.
+     * Un instantané ouvert remplace la table en lecture seule ; la table de la
+     * base est gardée telle quelle, modifications non sauvegardées comprises,
+     * et reprend sa place à la fermeture de l'instantané.
+     */
+
+    pub fn get_snapshot_path(&self, name: &str) -> String {
+        let main = self.main.as_deref().unwrap_or(self);
+        match main.pager.get_save_path() {
+            Some(file_path) => format!("{file_path}.{name}.snapshot"),
+            None => format!("{name}.snapshot"),
+        }
+    }
+
+    /// Écrit l'instantané `name` et renvoie son chemin.
+    pub fn create_snapshot(&self, name: &str) -> Result<String, SaveToDiskError> {
+        let file_path = self.get_snapshot_path(name);
+        self.backup(&file_path, &mut |_, _| {})?;
+        Ok(file_path)
+    }
+
+    /// Un instantané déjà ouvert est remplacé.
+    pub fn open_snapshot(&mut self, name: &str) -> Result<(), PagerOpenError> {
+        let file_path = self.get_snapshot_path(name);
+        let snapshot = Table::new(Rc::new(Pager::new_read_only(Some(&file_path))?));
+        snapshot
+            .check_catalog()
+            .map_err(|e| PagerOpenError::Catalog(file_path, e))?;
+
+        let main = match self.main.take() {
+            Some(main) => {
+                *self = snapshot;
+                *main
+            }
+            None => std::mem::replace(self, snapshot),
+        };
+        self.main = Some(Box::new(main));
+        Ok(())
+    }
+
+    /// Rend sa place à la table de la base. Renvoie `false` si aucun
+    /// instantané n'était ouvert.
+    pub fn close_snapshot(&mut self) -> bool {
+        let Some(main) = self.main.take() else {
+            return false;
+        };
+        *self = *main;
+        true
+    }
+
+    pub fn is_snapshot_open(&self) -> bool {
+        self.main.is_some()
+    }
+
+    /*
+     * Journal d'annulation : l'image d'avant de chaque instruction qui modifie
+     * la table, gardé en mémoire pour corriger vite une erreur de saisie. Il ne
+     * dépend pas de la sauvegarde et disparaît avec la session. Un instantané
+     * ouvert a son propre journal, vide.
+     */
+
+    /// La plus ancienne instruction est oubliée au-delà de `UNDO_LOG_SIZE`.
+    pub fn push_undo(&mut self, entry: UndoEntry) {
+        if self.undo_log.len() == Self::UNDO_LOG_SIZE {
+            let _ = self.undo_log.remove(0);
+        }
+        self.undo_log.push(entry);
+    }
+
+    /// Instruction que `.undo` annulerait, laissée dans le journal.
+    pub fn last_undo(&self) -> Option<&UndoEntry> {
+        self.undo_log.last()
+    }
+
+    pub fn pop_undo(&mut self) -> Option<UndoEntry> {
+        self.undo_log.pop()
+    }
+
+    /// Après une modification qui n'est pas journalisée, les images gardées
+    /// ne permettent plus de revenir en arrière.
+    pub fn clear_undo(&mut self) {
+        self.undo_log.clear();
+    }
+
+    /// Les hooks restent sur la table de la base pendant qu'un instantané,
+    /// en lecture seule, est ouvert.
+    pub fn add_hooks(&mut self, hooks: Rc<dyn Hooks>) {
+        match &mut self.main {
+            Some(main) => main.add_hooks(hooks),
+            None => self.hooks.push(hooks),
+        }
+    }
+
+    /// Copie des hooks, pour les appeler sans garder la table empruntée.
+    pub fn get_hooks(&self) -> Vec<Rc<dyn Hooks>> {
+        self.hooks.clone()
+    }
+
+    pub fn has_hooks(&self) -> bool {
+        !self.hooks.is_empty()
+    }
+
+    /// Une table virtuelle du même nom est remplacée. Comme les hooks, elles
+    /// restent sur la table de la base pendant qu'un instantané est ouvert.
+    pub fn register_provider(&mut self, name: &str, provider: Rc<dyn TableProvider>) {
+        if let Some(main) = &mut self.main {
+            return main.register_provider(name, provider);
+        }
+        let name = name.to_lowercase();
+        self.providers.retain(|(registered, _)| *registered != name);
+        self.providers.push((name, provider));
+    }
+
+    /// Le nom est comparé sans tenir compte de la casse.
+    pub fn get_provider(&self, name: &str) -> Option<Rc<dyn TableProvider>> {
+        let providers = match &self.main {
+            Some(main) => &main.providers,
+            None => &self.providers,
+        };
+        providers
+            .iter()
+            .find(|(registered, _)| registered.eq_ignore_ascii_case(name))
+            .map(|(_, provider)| provider.clone())
+    }
+
+    pub fn has_bloom_filter(&self) -> bool {
+        self.bloom_filter.is_some()
+    }
+
+    /// Active le filtre en y ajoutant toutes les clés de l'arbre, parcouru de
+    /// feuille en feuille, ou le retire.
+    pub fn set_bloom_filter(&mut self, enabled: bool) -> Result<(), GetPageError> {
+        if !enabled {
+            self.bloom_filter = None;
+            return Ok(());
+        }
+
+        let mut bloom_filter = BloomFilter::default();
+        let (mut page_num, _) = self.find(0)?;
+        for _ in 0..Pager::MAX_PAGES {
+            let node = read_node(&self.pager, page_num)?;
+            if !matches!(node, Node::Leaf(_)) {
+                return Err(GetPageError::Corrupted(page_num));
+            }
+            for cell_num in 0..node.leaf_node_get_nb_cells() {
+                bloom_filter.insert(node.leaf_node_get_cell(cell_num).get_key());
+            }
+            page_num = node.leaf_node_get_next_leaf();
+            if page_num == 0 {
+                self.bloom_filter = Some(bloom_filter);
+                return Ok(());
+            }
+        }
+        Err(GetPageError::Corrupted(page_num))
+    }
+
+    /// `false` seulement si la clé est sûrement absente. Sans filtre, toute
+    /// clé peut être présente.
+    pub fn may_contain(&self, key: u64) -> bool {
+        self.bloom_filter
+            .as_ref()
+            .is_none_or(|bloom_filter| bloom_filter.may_contain(key))
+    }
+
+    /// Vérifie que le catalogue du fichier ouvert décrit bien la table des
+    /// `Row`.
+    pub fn check_catalog(&self) -> Result<(), CatalogError> {
+        if self.get_catalog()? != Catalog::compiled() {
+            return Err(CatalogError::SchemaMismatch);
+        }
+        Ok(())
+    }
+
+    /// Catalogue lu dans le fichier. Une base qui n'a jamais été sauvegardée
+    /// n'a pas encore de catalogue : c'est celui que la sauvegarde écrira.
+    pub fn get_catalog(&self) -> Result<Catalog, CatalogError> {
+        let page = self
+            .pager
+            .read_at(Self::CATALOG_PAGE_NUM, 0, Page::SIZE)
+            .map_err(|_| CatalogError::Corrupted)?;
+        if page.iter().all(|byte| *byte == 0) {
+            return Ok(Catalog::compiled());
+        }
+        Catalog::try_from(&page[..])
+    }
+
+    /// Le catalogue est écrit avant chaque sauvegarde, les autres pages ne
+    /// sont jamais allouées à sa place.
+    fn write_catalog(&self) -> Result<(), SaveToDiskError> {
+        if self.pager.is_read_only() {
+            return Ok(());
+        }
+        let catalog = Page::try_from(&Catalog::compiled()).map_err(SaveToDiskError::Catalog)?;
+        self.pager
+            .write_at(Self::CATALOG_PAGE_NUM, 0, &catalog[..])
+            .map_err(|e| match e {
+                GetPageError::IoError(e) => SaveToDiskError::IoError(e),
+                _ => SaveToDiskError::PageInUse,
+            })
+    }
+
+    /// Sauvegarde les pages et le nombre de lignes, voir `Pager::save_to_disk`.
+    pub fn save_to_disk(&self, file_path: Option<&str>) -> Result<(), SaveToDiskError> {
+        self.write_catalog()?;
+        self.pager.save_to_disk(file_path, self.nb_rows)
+    }
+
+    /// La table ne garde aucune ligne hors des pages du pager.
+    pub fn memory_usage(&self) -> MemoryUsage {
+        self.pager.memory_usage()
+    }
+
+    /*
+     * Appelé après chaque instruction qui modifie la table, selon le mode
+     * d'écriture du pager :
+     * - en write-through, la base est sauvegardée aussitôt, ou avec les
+     *   modifications suivantes quand un `commit_delay` est donné ;
+     * - en write-back avec un `flush_interval`, la modification est seulement
+     *   notée : le REPL la sauvegarde quand il attend une entrée, jamais
+     *   pendant une instruction.
+     * Une base sans fichier n'a rien à écrire.
+     */
+    pub fn on_modified(&self) -> Result<(), SaveToDiskError> {
+        if self.pager.get_write_delay().is_none()
+            || !self.pager.has_save_path()
+            || self.pager.is_read_only()
+        {
+            return Ok(());
+        }
+        self.pager.mark_write_pending();
+        if self.pager.get_write_mode() == WriteMode::WriteThrough
+            && self
+                .pager
+                .get_write_deadline()
+                .is_some_and(|deadline| deadline <= Instant::now())
+        {
+            return self.flush_pending_write();
+        }
+        Ok(())
+    }
 
-    pub fn new(pager: Rc<RefCell<Pager>>) -> Self {
-        let nb_rows = 0;
-        Self { pager, nb_rows }
+    /// Sauvegarde les modifications en attente, voir `on_modified`. Après un
+    /// échec, elles ne sont plus en attente : elles restent en mémoire jusqu'à
+    /// la prochaine modification ou `.save`.
+    pub fn flush_pending_write(&self) -> Result<(), SaveToDiskError> {
+        if let Some(main) = self.main.as_deref() {
+            main.flush_pending_write()?;
+        }
+        if self.pager.take_pending_write() {
+            self.save_to_disk(None)?;
+        }
+        Ok(())
+    }
+
+    /// Copie la base dans un autre fichier, voir `Pager::backup`.
+    pub fn backup(
+        &self,
+        file_path: &str,
+        progress: &mut dyn FnMut(usize, usize),
+    ) -> Result<(), SaveToDiskError> {
+        self.write_catalog()?;
+        self.pager.backup(file_path, self.nb_rows, progress)
     }
 
     pub fn get_nb_rows(&self) -> usize {
         self.nb_rows
     }
 
-    pub fn get_pager(&self) -> Rc<RefCell<Pager>> {
+    /// Nombre de lignes, comme `get_nb_rows`.
+    pub fn len(&self) -> usize {
+        self.nb_rows
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nb_rows == 0
+    }
+
+    /// Toutes les lignes dans l'ordre des ids. Le parcours emprunte la table à
+    /// chaque ligne : elle doit être partagée, d'où `Table::iter(&table)`.
+    pub fn iter(table: &Rc<RefCell<Self>>) -> Result<RowIter, GetPageError> {
+        RowIter::new(table.clone())
+    }
+
+    pub fn get_pager(&self) -> Rc<Pager> {
         self.pager.clone()
     }
 
@@ -44,71 +429,473 @@ impl Table {
         self.nb_rows = nb_rows;
     }
 
-    pub fn get(&self, row_number: usize) -> SlicePointer {
-        assert!(row_number < self.nb_rows, "Max row reached.");
+    /// Vide la table en libérant toutes ses pages, sans supprimer les lignes une à une.
+    pub fn truncate(&mut self) -> Result<(), GetPageError> {
+        self.pager.truncate()?;
+        self.nb_rows = 0;
+        if let Some(bloom_filter) = self.bloom_filter.as_mut() {
+            bloom_filter.clear();
+        }
+        Ok(())
+    }
 
-        let page_num = row_number / Self::ROWS_PER_PAGE;
-        let mut page: SlicePointer = self.pager.borrow_mut().get(page_num);
+    /// Cherche la feuille où se trouve, ou devrait se trouver, `key`. Renvoie la
+    /// page de la feuille et la position de la clé dans cette feuille.
+    pub fn find(&self, key: u64) -> Result<(usize, usize), GetPageError> {
+        let mut page_num = Self::ROOT_PAGE_NUM;
+        // Un arbre valide ne peut pas être plus profond que son nombre de pages.
+        for _ in 0..Pager::MAX_PAGES {
+            match read_node(&self.pager, page_num)? {
+                node @ Node::Leaf(_) => return Ok((page_num, node.leaf_node_find(key))),
+                node @ Node::Internal(_) => {
+                    page_num = node.internal_node_get_child(node.internal_node_find_child(key));
+                }
+            }
+        }
+        Err(GetPageError::Corrupted(page_num))
+    }
 
-        let row_offset: usize = (row_number % Self::ROWS_PER_PAGE) * Row::MAX_SIZE;
-        page += row_offset;
-        page.set_len(Row::MAX_SIZE);
-        page
+    /// Comme `find`, mais `None` si aucune ligne n'a l'id `key`.
+    fn find_row(&self, key: u64) -> Result<Option<(usize, usize)>, GetPageError> {
+        let (page_num, cell_num) = self.find(key)?;
+        let leaf = read_node(&self.pager, page_num)?;
+        let is_found = cell_num < leaf.leaf_node_get_nb_cells()
+            && leaf.leaf_node_get_cell(cell_num).get_key() == key;
+        Ok(is_found.then_some((page_num, cell_num)))
     }
 
-    pub fn get_mut(&mut self, row_number: usize) -> SlicePointerMut {
-        assert!(row_number >= self.nb_rows, "Max row reached.");
+    /// Le filtre de Bloom, s'il est activé, évite de parcourir l'arbre pour
+    /// une clé absente.
+    pub fn contains_key(&self, key: u64) -> Result<bool, GetPageError> {
+        if !self.may_contain(key) {
+            return Ok(false);
+        }
+        self.find_row(key).map(|position| position.is_some())
+    }
 
-        let page_num = row_number / Self::ROWS_PER_PAGE;
-        let mut page: SlicePointerMut = self.pager.borrow_mut().get_mut(page_num);
+    /// Plus grande clé stockée sous le nœud, 0 pour une feuille vide.
+    fn get_max_key(&self, page_num: usize) -> Result<u64, GetPageError> {
+        let mut page_num = page_num;
+        for _ in 0..Pager::MAX_PAGES {
+            match read_node(&self.pager, page_num)? {
+                node @ Node::Leaf(_) => {
+                    let nb_cells = node.leaf_node_get_nb_cells();
+                    if nb_cells == 0 {
+                        return Ok(0);
+                    }
+                    return Ok(node.leaf_node_get_cell(nb_cells - 1).get_key());
+                }
+                node @ Node::Internal(_) => page_num = node.internal_node_get_right_child(),
+            }
+        }
+        Err(GetPageError::Corrupted(page_num))
+    }
 
-        let row_offset: usize = (row_number % Self::ROWS_PER_PAGE) * Row::MAX_SIZE;
-        page += row_offset;
-        page.set_len(Row::MAX_SIZE);
-        page
+    /// Numéro de la prochaine page libre.
+    fn get_unused_page_num(&self) -> Result<usize, WriteRowError> {
+        let page_num = self
+            .pager
+            .page_count()
+            .map_err(|e| WriteRowError::GetPage(GetPageError::IoError(e)))?;
+        if page_num >= Pager::MAX_PAGES {
+            return Err(WriteRowError::TableFull);
+        }
+        Ok(page_num)
     }
 
-    pub fn get_row(&self, _row_number: usize) -> Option<Result<Row, GetRowError>> {
-        unimplemented!()
-        // if row_number >= self.nb_rows {
-        //     return None;
-        // }
-        //
-        // let Ok(mut pager) = PAGER.lock() else {
-        //     return Some(Err(GetRowError::PoisonedPager));
-        // };
-        //
-        // let page_num = row_number / Self::ROWS_PER_PAGE;
-        // let get_page_result = pager.get_page(page_num);
-        // let page: &mut Page = match get_page_result {
-        //     Ok(page) => page,
-        //     Err(e) => return Some(Err(GetRowError::GetPage(e))),
-        // };
-        //
-        // let row_offset = (row_number % Self::ROWS_PER_PAGE) * Row::MAX_SIZE;
-        // let row_range = row_offset..(row_offset + Row::MAX_SIZE);
-        // Some(Row::try_from(&page[row_range]).map_err(GetRowError::Deserialize))
+    /// Insère la ligne à sa place dans l'arbre, triée par id. Les cellules
+    /// suivantes de la feuille sont décalées, un id déjà présent est refusé.
+    pub fn insert(&mut self, row: Row) -> Result<(), WriteRowError> {
+        let key = **row.get_id();
+        self.insert_cell(key, &<[u8; Row::MAX_SIZE]>::from(row))
     }
 
-    pub fn write_row(&mut self, row: Row) -> Result<(), WriteRowError> {
-        if self.nb_rows == Self::MAX_ROWS {
+    /// Insère une cellule dont la valeur n'est pas forcément un `Row`, voir
+    /// `TypedTable`.
+    pub(crate) fn insert_cell(
+        &mut self,
+        key: u64,
+        value: &[u8; Row::MAX_SIZE],
+    ) -> Result<(), WriteRowError> {
+        let (page_num, cell_num) = self.find(key).map_err(WriteRowError::from)?;
+
+        let nb_cells = {
+            let leaf = read_node(&self.pager, page_num).map_err(WriteRowError::from)?;
+            let nb_cells = leaf.leaf_node_get_nb_cells();
+            if cell_num < nb_cells && leaf.leaf_node_get_cell(cell_num).get_key() == key {
+                return Err(WriteRowError::DuplicateKey(key));
+            }
+            nb_cells
+        };
+        if nb_cells < NodeView::LEAF_NODE_MAX_CELLS {
+            write_node(&self.pager, page_num)
+                .map_err(WriteRowError::from)?
+                .leaf_node_insert_cell(cell_num, key, value);
+        } else {
+            self.leaf_node_split_and_insert(page_num, cell_num, key, value)?;
+        }
+
+        self.nb_rows += 1;
+        if let Some(bloom_filter) = self.bloom_filter.as_mut() {
+            bloom_filter.insert(key);
+        }
+        Ok(())
+    }
+
+    /// Remplace la ligne d'id `key` sur place : l'arbre ne change pas de forme.
+    /// La nouvelle ligne doit garder le même id.
+    pub fn update_row(&mut self, key: u64, row: Row) -> Result<(), UpdateRowError> {
+        let row_key = **row.get_id();
+        if row_key != key {
+            return Err(UpdateRowError::KeyMismatch(key, row_key));
+        }
+        let Some((page_num, cell_num)) = self.find_row(key).map_err(UpdateRowError::GetPage)?
+        else {
+            return Err(UpdateRowError::NotFound(key));
+        };
+        let mut leaf = write_node(&self.pager, page_num).map_err(UpdateRowError::GetPage)?;
+        leaf.leaf_node_get_mut_cell(cell_num)
+            .get_mut_value()
+            .copy_from_slice(&<[u8; Row::MAX_SIZE]>::from(row));
+        Ok(())
+    }
+
+    /*
+     * Retire la ligne d'id `key` de sa feuille. Renvoie `false` si aucune ligne
+     * n'a cet id.
+     *
+     * Les feuilles ne sont jamais fusionnées : une feuille peut devenir vide et
+     * la clé de son parent rester plus grande que sa plus grande clé. La
+     * recherche et la coupure d'une feuille ne lisent que les clés du parent,
+     * pas le contenu de la feuille. Le filtre de Bloom garde la clé, un faux
+     * positif de plus.
+     */
+    pub fn delete_row(&mut self, key: u64) -> Result<bool, DeleteRowError> {
+        let Some((page_num, cell_num)) = self.find_row(key).map_err(DeleteRowError::GetPage)?
+        else {
+            return Ok(false);
+        };
+        write_node(&self.pager, page_num)
+            .map_err(DeleteRowError::GetPage)?
+            .leaf_node_remove_cell(cell_num);
+        self.nb_rows -= 1;
+        Ok(true)
+    }
+
+    /*
+     * Une feuille pleine est coupée en deux : la moitié haute des cellules,
+     * nouvelle cellule comprise, part dans une nouvelle feuille que le parent
+     * doit ensuite référencer. Si la feuille était la racine, une nouvelle
+     * racine est créée au-dessus des deux feuilles.
+     *
+     * Les nœuds internes ne sont pas divisés : un parent plein rend la table pleine.
+     */
+    fn leaf_node_split_and_insert(
+        &mut self,
+        old_page_num: usize,
+        cell_num: usize,
+        key: u64,
+        value: &[u8],
+    ) -> Result<(), WriteRowError> {
+        let is_root = old_page_num == Self::ROOT_PAGE_NUM;
+        let parent_page_num = read_node(&self.pager, old_page_num)
+            .map_err(WriteRowError::from)?
+            .get_parent();
+        // Tout est vérifié avant de modifier l'arbre, qui reste cohérent en cas d'erreur.
+        let mut child_num = 0;
+        if !is_root {
+            let parent = read_node(&self.pager, parent_page_num).map_err(WriteRowError::from)?;
+            if parent.internal_node_get_nb_keys() >= NodeView::INTERNAL_NODE_MAX_KEYS {
+                return Err(WriteRowError::TableFull);
+            }
+            child_num = parent.internal_node_find_child(key);
+        }
+        let new_page_num = self.get_unused_page_num()?;
+        if is_root && new_page_num + 1 >= Pager::MAX_PAGES {
             return Err(WriteRowError::TableFull);
         }
+        debug!(old_page_num, new_page_num, key, "leaf split");
 
-        let page_num = self.nb_rows / Self::ROWS_PER_PAGE;
-        let mut binding = self.pager.borrow_mut();
-        let page: &mut Page = binding.get_page(page_num).map_err(WriteRowError::GetPage)?;
+        let left_max_key;
+        {
+            let mut old_node =
+                write_node(&self.pager, old_page_num).map_err(WriteRowError::from)?;
+            let page = PageViewMut::from(
+                self.pager
+                    .write_page(new_page_num)
+                    .map_err(WriteRowError::from)?,
+            );
+            let mut new_node = Node::initialize_leaf(page);
 
-        let row_offset = (self.nb_rows % Self::ROWS_PER_PAGE) * Row::MAX_SIZE;
-        let row_range = row_offset..(row_offset + Row::MAX_SIZE);
+            let mut cells: Vec<(u64, Vec<u8>)> = (0..old_node.leaf_node_get_nb_cells())
+                .map(|cell_num| {
+                    let cell = old_node.leaf_node_get_cell(cell_num);
+                    (cell.get_key(), cell.get_value().to_vec())
+                })
+                .collect();
+            cells.insert(cell_num, (key, value.to_vec()));
+            let right_cells = cells.split_off(NodeView::LEAF_NODE_LEFT_SPLIT_COUNT);
+            left_max_key = cells[cells.len() - 1].0;
 
-        let serialized_row = <[u8; Row::MAX_SIZE]>::from(row);
-        page[row_range].copy_from_slice(&serialized_row);
-        self.nb_rows += 1;
+            new_node.set_parent(parent_page_num);
+            new_node.leaf_node_set_next_leaf(old_node.leaf_node_get_next_leaf());
+            old_node.leaf_node_set_next_leaf(new_page_num);
+
+            old_node.leaf_node_set_nb_cells(0);
+            for (cell_num, (key, value)) in cells.iter().enumerate() {
+                old_node.leaf_node_insert_cell(cell_num, *key, value);
+            }
+            for (cell_num, (key, value)) in right_cells.iter().enumerate() {
+                new_node.leaf_node_insert_cell(cell_num, *key, value);
+            }
+        }
+
+        if is_root {
+            return self.create_new_root(new_page_num);
+        }
+
+        self.internal_node_insert(parent_page_num, child_num, left_max_key, new_page_num)
+    }
 
+    /// La racine est recopiée dans une nouvelle page et devient un nœud interne
+    /// dont les enfants sont cette copie et `right_child_page_num`.
+    fn create_new_root(&mut self, right_child_page_num: usize) -> Result<(), WriteRowError> {
+        let left_child_page_num = self.get_unused_page_num()?;
+        debug!(left_child_page_num, right_child_page_num, "new root");
+        let root = self
+            .pager
+            .read_at(Self::ROOT_PAGE_NUM, 0, Page::SIZE)
+            .map_err(WriteRowError::from)?;
+        self.pager
+            .write_at(left_child_page_num, 0, &root)
+            .map_err(WriteRowError::from)?;
+        for child_page_num in [left_child_page_num, right_child_page_num] {
+            self.pager
+                .write_u32(
+                    child_page_num,
+                    NodeView::PARENT_POINTER_OFFSET,
+                    Self::ROOT_PAGE_NUM as u32,
+                )
+                .map_err(WriteRowError::from)?;
+        }
+
+        let left_child_max_key = self
+            .get_max_key(left_child_page_num)
+            .map_err(WriteRowError::from)?;
+        let page = PageViewMut::from(
+            self.pager
+                .write_page(Self::ROOT_PAGE_NUM)
+                .map_err(WriteRowError::from)?,
+        );
+        let mut root = Node::initialize_internal(page);
+        root.internal_node_insert_cell(0, left_child_page_num, left_child_max_key);
+        root.internal_node_set_right_child(right_child_page_num);
+        Ok(())
+    }
+
+    /*
+     * L'enfant `child_num` du parent vient d'être coupé : il garde sa place avec
+     * la plus grande clé de sa moitié gauche, et la nouvelle feuille `new_page_num`
+     * le suit avec la clé que le parent avait pour lui. Cette clé n'est pas lue
+     * dans la feuille, que `delete_row` a pu vider.
+     */
+    fn internal_node_insert(
+        &mut self,
+        parent_page_num: usize,
+        child_num: usize,
+        left_max_key: u64,
+        new_page_num: usize,
+    ) -> Result<(), WriteRowError> {
+        let mut parent = write_node(&self.pager, parent_page_num).map_err(WriteRowError::from)?;
+        let nb_keys = parent.internal_node_get_nb_keys();
+        let old_page_num = parent.internal_node_get_child(child_num);
+        if child_num == nb_keys {
+            parent.internal_node_insert_cell(nb_keys, old_page_num, left_max_key);
+            parent.internal_node_set_right_child(new_page_num);
+        } else {
+            let old_key = parent.internal_node_get_key(child_num);
+            parent.internal_node_set_cell(child_num, old_page_num, left_max_key);
+            parent.internal_node_insert_cell(child_num + 1, new_page_num, old_key);
+        }
         Ok(())
     }
 }
+impl std::convert::From<GetPageError> for WriteRowError {
+    fn from(error: GetPageError) -> Self {
+        match error {
+            GetPageError::MaxPageReached => Self::TableFull,
+            e => Self::GetPage(e),
+        }
+    }
+}
 
 #[cfg(test)]
-mod table_test {}
+pub(crate) mod table_test {
+    use super::*;
+
+    use crate::backend::MemoryBackend;
+    use crate::integrity::check_integrity;
+    use crate::row::{Email, Id, Username};
+
+    /// Ligne `id` des tests, de username `user{id}`.
+    pub(crate) fn row(id: u64) -> Row {
+        Row::new(
+            Id::new(id),
+            Username::new(format!("user{id}")),
+            Email::new(format!("user{id}@mail.com")),
+        )
+    }
+
+    /// Table en mémoire avec une ligne par id, insérées dans l'ordre donné.
+    pub(crate) fn table_with_ids(ids: impl IntoIterator<Item = u64>) -> Rc<RefCell<Table>> {
+        let table = Rc::new(RefCell::new(Table::new(Rc::new(Pager::default()))));
+        for id in ids {
+            table.borrow_mut().insert(row(id)).unwrap();
+        }
+        table
+    }
+
+    #[test]
+    fn test_nb_rows_survives_reopen() {
+        let mut table = Table::new(Rc::new(Pager::default()));
+        for id in 0..3 {
+            let row = Row::new(
+                Id::new(id),
+                Username::new("user".to_owned()),
+                Email::new("user@mail.com".to_owned()),
+            );
+            table.insert(row).unwrap();
+        }
+        let mut backend = MemoryBackend::default();
+        table.get_pager().write_to(&mut backend, 3).unwrap();
+
+        let table = Table::new(Rc::new(Pager::with_backend(Box::new(backend)).unwrap()));
+        assert_eq!(table.get_nb_rows(), 3);
+        assert!(table.check_catalog().is_ok());
+    }
+
+    #[test]
+    fn test_bloom_filter_covers_every_leaf() {
+        let table = table_with_ids((2..=100).step_by(2));
+        let mut table = table.borrow_mut();
+        table.set_bloom_filter(true).unwrap();
+        table.insert(row(101)).unwrap();
+
+        assert!((2..=100).step_by(2).all(|id| table.may_contain(id)));
+        assert!(table.may_contain(101));
+        assert!((1..100).step_by(2).any(|id| !table.may_contain(id)));
+
+        table.truncate().unwrap();
+        assert!(!table.may_contain(101));
+    }
+
+    #[test]
+    fn test_insert_rejects_duplicate_key() {
+        let mut table = Table::new(Rc::new(Pager::default()));
+        let row = |username: &str| {
+            Row::new(
+                Id::new(1),
+                Username::new(username.to_owned()),
+                Email::new("user@mail.com".to_owned()),
+            )
+        };
+        table.insert(row("first")).unwrap();
+        assert!(matches!(
+            table.insert(row("second")),
+            Err(WriteRowError::DuplicateKey(1))
+        ));
+        assert_eq!(table.get_nb_rows(), 1);
+    }
+
+    #[test]
+    fn test_update_row_overwrites_in_place() {
+        let mut table = Table::new(Rc::new(Pager::default()));
+        let row = |id: u64, username: &str| {
+            Row::new(
+                Id::new(id),
+                Username::new(username.to_owned()),
+                Email::new("user@mail.com".to_owned()),
+            )
+        };
+        for id in 1..=30 {
+            table.insert(row(id, "before")).unwrap();
+        }
+        let nb_pages = table.get_pager().page_count().unwrap();
+        table.update_row(17, row(17, "after")).unwrap();
+
+        let (page_num, cell_num) = table.find(17).unwrap();
+        {
+            let leaf = read_node(&table.pager, page_num).unwrap();
+            let updated = Row::try_from(leaf.leaf_node_get_cell(cell_num).get_value()).unwrap();
+            assert!(updated == row(17, "after"));
+        }
+        assert_eq!(table.get_nb_rows(), 30);
+        assert_eq!(table.get_pager().page_count().unwrap(), nb_pages);
+
+        assert!(matches!(
+            table.update_row(31, row(31, "after")),
+            Err(UpdateRowError::NotFound(31))
+        ));
+        assert!(matches!(
+            table.update_row(1, row(2, "after")),
+            Err(UpdateRowError::KeyMismatch(1, 2))
+        ));
+    }
+
+    #[test]
+    fn test_delete_row_keeps_the_tree_valid() {
+        let table = table_with_ids(1..=40);
+        // Une feuille entière est vidée, dont la plus grande clé de son parent.
+        for id in (1..=40).filter(|id| id % 3 == 0).chain(1..=14) {
+            let _ = table.borrow_mut().delete_row(id).unwrap();
+        }
+        assert!(!table.borrow_mut().delete_row(3).unwrap());
+        assert!(check_integrity(&table.borrow()).is_empty());
+
+        assert!(!table.borrow().contains_key(3).unwrap());
+        assert!(table.borrow().contains_key(16).unwrap());
+
+        let expected: Vec<u64> = (15..=40).filter(|id| id % 3 != 0).collect();
+        assert_eq!(table.borrow().len(), expected.len());
+        let ids: Vec<u64> = Table::iter(&table)
+            .unwrap()
+            .map(|row| **row.unwrap().get_id())
+            .collect();
+        assert_eq!(ids, expected);
+
+        // Plusieurs feuilles vides se suivent à droite de l'arbre.
+        for id in 21..=40 {
+            let _ = table.borrow_mut().delete_row(id).unwrap();
+        }
+        assert!(check_integrity(&table.borrow()).is_empty());
+        let expected: Vec<u64> = (15..=20).rev().filter(|id| id % 3 != 0).collect();
+        let ids: Vec<u64> = Table::iter(&table)
+            .unwrap()
+            .rev()
+            .map(|row| **row.unwrap().get_id())
+            .collect();
+        assert_eq!(ids, expected);
+    }
+
+    #[test]
+    fn test_insert_after_delete_row_keeps_the_tree_valid() {
+        let table = table_with_ids((10..=140).step_by(10));
+        // La feuille de droite est vidée, puis celle de gauche est coupée.
+        for id in (80..=140).step_by(10) {
+            assert!(table.borrow_mut().delete_row(id).unwrap());
+        }
+        for id in 11..=17 {
+            table.borrow_mut().insert(row(id)).unwrap();
+        }
+        assert!(check_integrity(&table.borrow()).is_empty());
+
+        let mut expected: Vec<u64> = (10..=70).step_by(10).chain(11..=17).collect();
+        expected.sort_unstable();
+        let ids: Vec<u64> = Table::iter(&table)
+            .unwrap()
+            .map(|row| **row.unwrap().get_id())
+            .collect();
+        assert_eq!(ids, expected);
+        assert!(table.borrow().contains_key(15).unwrap());
+    }
+}
@@ -0,0 +1,177 @@
+use std::marker::PhantomData;
+use std::ops::RangeInclusive;
+use std::{cell::RefCell, rc::Rc};
+
+use crate::cursor::Cursor;
+use crate::row::{DeserializeError, Row};
+use crate::table::{GetRowError, Table, WriteRowError};
+
+/*
+ * Enregistrement stocké par un `TypedTable` à la place d'un `Row`. Ses octets
+ * occupent la place d'une ligne dans les cellules de l'arbre : `SIZE` ne peut
+ * pas dépasser `Row::MAX_SIZE`, ce qui est vérifié à la compilation.
+ */
+pub trait Record: Sized {
+    /// Nombre d'octets écrits par `write_bytes` et lus par `read_bytes`.
+    const SIZE: usize;
+
+    /// Clé de l'enregistrement dans l'arbre, unique dans la table.
+    fn get_key(&self) -> u64;
+
+    /// `bytes` fait exactement `SIZE` octets.
+    fn write_bytes(&self, bytes: &mut [u8]);
+
+    /// `bytes` fait exactement `SIZE` octets.
+    fn read_bytes(bytes: &[u8]) -> Result<Self, DeserializeError>;
+}
+impl Record for Row {
+    const SIZE: usize = Row::MAX_SIZE;
+
+    fn get_key(&self) -> u64 {
+        **self.get_id()
+    }
+
+    fn write_bytes(&self, bytes: &mut [u8]) {
+        bytes.copy_from_slice(&<[u8; Row::MAX_SIZE]>::from(self.clone()));
+    }
+
+    fn read_bytes(bytes: &[u8]) -> Result<Self, DeserializeError> {
+        Row::try_from(bytes)
+    }
+}
+
+/*
+ * Table d'enregistrements `T` triés par clé, dans le même arbre que les
+ * `Row`. Une table ne doit contenir qu'un seul type : ses cellules ne sont
+ * plus lisibles par `select` ni par `pragma integrity_check` si `T` n'est
+ * pas `Row`. Les hooks ne sont pas appelés et rien n'est sauvegardé avant
+ * `Table::save_to_disk`.
+ */
+#[cfg_attr(debug_assertions, derive(Debug))]
+pub struct TypedTable<T: Record> {
+    table: Rc<RefCell<Table>>,
+    record: PhantomData<T>,
+}
+impl<T: Record> TypedTable<T> {
+    pub fn new(table: Rc<RefCell<Table>>) -> Self {
+        const {
+            assert!(
+                T::SIZE <= Row::MAX_SIZE,
+                "The record does not fit in a cell."
+            );
+        }
+        Self {
+            table,
+            record: PhantomData,
+        }
+    }
+
+    pub fn get_table(&self) -> Rc<RefCell<Table>> {
+        self.table.clone()
+    }
+
+    /// Une clé déjà présente est refusée, comme par `Table::insert`.
+    pub fn insert(&self, record: &T) -> Result<(), WriteRowError> {
+        let mut value = [0; Row::MAX_SIZE];
+        record.write_bytes(&mut value[..T::SIZE]);
+        self.table
+            .borrow_mut()
+            .insert_cell(record.get_key(), &value)
+    }
+
+    pub fn get(&self, key: u64) -> Result<Option<T>, GetRowError> {
+        self.range(key..=key).next().transpose()
+    }
+
+    /// Enregistrements dont la clé est dans `keys`, dans l'ordre des clés.
+    pub fn range(&self, keys: RangeInclusive<u64>) -> impl Iterator<Item = Result<T, GetRowError>> {
+        let mut cursor = Some(Cursor::range(self.table.clone(), keys));
+        std::iter::from_fn(move || {
+            let mut current = match cursor.take()? {
+                Ok(current) => current,
+                Err(e) => return Some(Err(GetRowError::GetPage(e))),
+            };
+            if current.is_end_of_table() {
+                return None;
+            }
+            let record = current.get().map_err(GetRowError::from).and_then(|bytes| {
+                T::read_bytes(&bytes[..T::SIZE]).map_err(GetRowError::Deserialize)
+            });
+            cursor = Some(current.advance().map(|()| current));
+            Some(record)
+        })
+    }
+}
+
+#[cfg(test)]
+mod typed_table_test {
+    use super::*;
+
+    use crate::page_view::ReadBytes;
+    use crate::pager::Pager;
+
+    #[cfg_attr(debug_assertions, derive(Debug))]
+    #[derive(PartialEq)]
+    struct Point {
+        id: u64,
+        x: i32,
+        y: i32,
+    }
+    impl Record for Point {
+        const SIZE: usize = 16;
+
+        fn get_key(&self) -> u64 {
+            self.id
+        }
+
+        fn write_bytes(&self, bytes: &mut [u8]) {
+            bytes[0..8].copy_from_slice(&self.id.to_be_bytes());
+            bytes[8..12].copy_from_slice(&self.x.to_be_bytes());
+            bytes[12..16].copy_from_slice(&self.y.to_be_bytes());
+        }
+
+        fn read_bytes(bytes: &[u8]) -> Result<Self, DeserializeError> {
+            Ok(Self {
+                id: bytes.read_u64_be(0),
+                x: i32::from_be_bytes(bytes.read_array(8)),
+                y: i32::from_be_bytes(bytes.read_array(12)),
+            })
+        }
+    }
+
+    #[test]
+    fn test_typed_table() {
+        let table = Rc::new(RefCell::new(Table::new(Rc::new(Pager::default()))));
+        let points = TypedTable::<Point>::new(table.clone());
+        for id in (1..=40).rev() {
+            let id_i32 = i32::try_from(id).unwrap();
+            points
+                .insert(&Point {
+                    id,
+                    x: -id_i32,
+                    y: id_i32 * 2,
+                })
+                .unwrap();
+        }
+        assert!(matches!(
+            points.insert(&Point { id: 3, x: 0, y: 0 }),
+            Err(WriteRowError::DuplicateKey(3))
+        ));
+
+        assert_eq!(
+            points.get(7).unwrap(),
+            Some(Point {
+                id: 7,
+                x: -7,
+                y: 14
+            })
+        );
+        assert_eq!(points.get(41).unwrap(), None);
+        let ids: Vec<u64> = points
+            .range(10..=39)
+            .map(|point| point.unwrap().id)
+            .collect();
+        assert_eq!(ids, (10..=39).collect::<Vec<u64>>());
+        assert_eq!(table.borrow().get_nb_rows(), 40);
+    }
+}
@@ -0,0 +1,104 @@
+/*
+ * Format sur le disque, octet par octet : une ligne, un entête et un fichier
+ * de sauvegarde complet sont comparés aux fichiers hexadécimaux de
+ * `tests/golden`. Un décalage, un boutisme ou une taille qui change fait
+ * échouer ces tests avant de rendre illisibles les fichiers des utilisateurs.
+ *
+ * Après un changement de format voulu (et une migration), les fichiers sont
+ * réécrits par `UPDATE_GOLDEN=1 cargo test --test golden`.
+ */
+
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use my_db::header::FileHeader;
+use my_db::row::{Email, Id, Row, Username};
+
+fn row(id: u64) -> Row {
+    Row::new(
+        Id::new(id),
+        Username::new(format!("user{id}")),
+        Email::new(format!("user{id}@example.com")),
+    )
+}
+
+/// Seize octets par ligne précédés de leur position, comme `hexdump -C` sans
+/// le texte. Une ligne identique à la précédente est remplacée par `*`, et la
+/// dernière ligne donne la taille totale.
+fn hex_dump(bytes: &[u8]) -> String {
+    let mut dump = String::new();
+    let mut previous: Option<&[u8]> = None;
+    let mut skipping = false;
+    for (line_num, line) in bytes.chunks(16).enumerate() {
+        if previous == Some(line) {
+            if !skipping {
+                dump.push_str("*\n");
+                skipping = true;
+            }
+            continue;
+        }
+        skipping = false;
+        previous = Some(line);
+        let _ = write!(dump, "{:08x}", line_num * 16);
+        for byte in line {
+            let _ = write!(dump, " {byte:02x}");
+        }
+        dump.push('\n');
+    }
+    let _ = writeln!(dump, "{:08x}", bytes.len());
+    dump
+}
+
+/// Compare `bytes` au fichier `tests/golden/<name>.hex`, ou le réécrit si
+/// `UPDATE_GOLDEN` est définie.
+fn assert_golden(name: &str, bytes: &[u8]) {
+    let path: PathBuf = [
+        env!("CARGO_MANIFEST_DIR"),
+        "tests",
+        "golden",
+        &format!("{name}.hex"),
+    ]
+    .iter()
+    .collect();
+    let dump = hex_dump(bytes);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, dump).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path).unwrap();
+    assert!(
+        dump == expected,
+        "{name} does not match {}:\n{dump}",
+        path.display()
+    );
+}
+
+#[test]
+fn test_row_layout() {
+    let bytes = <[u8; Row::MAX_SIZE]>::from(row(0x0102_0304_0506_0708));
+    assert_golden("row", &bytes);
+}
+
+#[test]
+fn test_header_layout() {
+    let bytes = <[u8; FileHeader::SIZE]>::from(FileHeader::new().with_nb_rows(0x0a0b));
+    assert_golden("header", &bytes);
+}
+
+/// La page du catalogue, une racine interne et ses deux feuilles. Les pages
+/// d'un fichier compressé dépendent de l'implémentation de LZ4 : seul le
+/// format sans compression est figé.
+#[cfg(not(feature = "compression"))]
+#[test]
+fn test_database_file_layout() {
+    use my_db::database::Database;
+
+    let database = Database::open_in_memory();
+    let table = database.get_table();
+    for id in [7, 3, 11, 1, 9, 5, 13, 2, 8, 4, 12, 6, 10, 14] {
+        table.borrow_mut().insert(row(id)).unwrap();
+    }
+    let mut bytes = Vec::new();
+    database.write_to(&mut bytes).unwrap();
+    assert_golden("database", &bytes);
+}
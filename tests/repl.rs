@@ -0,0 +1,561 @@
+/*
+ * Tests de bout en bout du REPL, à la manière des tests rspec du tutoriel :
+ * le programme lit un script sur son entrée standard et les lignes affichées
+ * sont comparées à celles attendues.
+ *
+ * Les messages sont en anglais et le dossier personnel est un dossier vide,
+ * pour que ni `.mydbrc` ni l'historique de l'utilisateur n'interviennent.
+ */
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+/// Dossier propre à chaque appel, les tests s'exécutant en parallèle.
+fn test_dir(name: &str) -> PathBuf {
+    static NB_DIRS: AtomicUsize = AtomicUsize::new(0);
+    let dir_num = NB_DIRS.fetch_add(1, Ordering::Relaxed);
+    let dir = std::env::temp_dir().join(format!(
+        "my_db_repl_{name}_{}_{dir_num}",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Exécute les commandes sur une base en mémoire et renvoie les lignes affichées.
+fn run_script(commands: &[&str]) -> Vec<String> {
+    let dir = test_dir("memory");
+    let output = run_script_on(&dir, ":memory:", commands);
+    std::fs::remove_dir_all(&dir).unwrap();
+    output
+}
+
+/// Exécute les commandes sur la base `path`, le dossier `home` servant de
+/// dossier personnel. La fin de l'entrée ferme le REPL.
+fn run_script_on(home: &Path, path: &str, commands: &[&str]) -> Vec<String> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_my_db"))
+        .arg(path)
+        .env("HOME", home)
+        .env("LANG", "C")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // L'entrée est écrite à part pour que la sortie ne remplisse pas le tube.
+    let mut stdin = child.stdin.take().unwrap();
+    let script: String = commands
+        .iter()
+        .map(|command| format!("{command}\n"))
+        .collect();
+    let writer = thread::spawn(move || stdin.write_all(script.as_bytes()));
+
+    let output = child.wait_with_output().unwrap();
+    writer.join().unwrap().unwrap();
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(str::to_owned)
+        .collect()
+}
+
+#[test]
+fn test_inserts_and_retrieves_a_row() {
+    let output = run_script(&["insert 1 user1 person1@example.com", "select"]);
+    assert_eq!(
+        output,
+        vec![
+            "Executed.",
+            "(1, user1, person1@example.com)",
+            "Executed.",
+            ""
+        ]
+    );
+}
+
+#[test]
+fn test_prints_error_message_when_table_is_full() {
+    let commands: Vec<String> = (1..=1400)
+        .map(|id| format!("insert {id} user{id} person{id}@example.com"))
+        .collect();
+    let commands: Vec<&str> = commands.iter().map(String::as_str).collect();
+    let output = run_script(&commands);
+    assert_eq!(output[output.len() - 2], "Error: Table full.");
+}
+
+#[test]
+fn test_allows_inserting_strings_of_maximum_length() {
+    let username = "a".repeat(32);
+    let email = "a".repeat(255);
+    let output = run_script(&[&format!("insert 1 {username} {email}"), "select"]);
+    assert_eq!(
+        output,
+        vec![
+            "Executed.".to_owned(),
+            format!("(1, {username}, {email})"),
+            "Executed.".to_owned(),
+            String::new(),
+        ]
+    );
+}
+
+#[test]
+fn test_prints_error_message_if_strings_are_too_long() {
+    let username = "a".repeat(33);
+    let email = "a".repeat(256);
+    let output = run_script(&[
+        &format!("insert 1 {username} a@b.c"),
+        &format!("insert 1 a {email}"),
+        "select",
+    ]);
+    assert_eq!(
+        output,
+        vec![
+            "'username' is too long, max: '32'.",
+            "'email' is too long, max: '255'.",
+            "Executed.",
+            ""
+        ]
+    );
+}
+
+#[test]
+fn test_prints_error_message_if_id_is_negative() {
+    let output = run_script(&["insert -1 cstack foo@bar.com", "select"]);
+    assert_eq!(output, vec!["Invalid id '-1'.", "Executed.", ""]);
+}
+
+#[test]
+fn test_prints_error_message_on_duplicate_id() {
+    let output = run_script(&[
+        "insert 1 user1 person1@example.com",
+        "insert 1 user1 person1@example.com",
+        "select",
+    ]);
+    assert_eq!(
+        output,
+        vec![
+            "Executed.",
+            "Error: Duplicate key.",
+            "(1, user1, person1@example.com)",
+            "Executed.",
+            ""
+        ]
+    );
+}
+
+#[test]
+fn test_keeps_data_after_closing_connection() {
+    let dir = test_dir("reopen");
+    let path = dir.join("test.db");
+    let path = path.to_str().unwrap();
+
+    let output = run_script_on(&dir, path, &["insert 1 user1 person1@example.com", ".save"]);
+    assert_eq!(output, vec!["Executed.", ""]);
+
+    let output = run_script_on(&dir, path, &["select"]);
+    assert_eq!(
+        output,
+        vec!["(1, user1, person1@example.com)", "Executed.", ""]
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_write_through_saves_without_save_command() {
+    let dir = test_dir("write_through");
+    let path = dir.join("test.db");
+    let path = path.to_str().unwrap();
+
+    let output = run_script_on(
+        &dir,
+        path,
+        &[
+            "pragma write_mode = write_through",
+            "insert 1 user1 person1@example.com",
+            "pragma write_mode",
+        ],
+    );
+    assert_eq!(
+        output,
+        vec!["Executed.", "Executed.", "write_through", "Executed.", ""]
+    );
+
+    let output = run_script_on(&dir, path, &["select"]);
+    assert_eq!(
+        output,
+        vec!["(1, user1, person1@example.com)", "Executed.", ""]
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_commit_delay_saves_pending_writes_on_exit() {
+    let dir = test_dir("commit_delay");
+    let path = dir.join("test.db");
+    let path = path.to_str().unwrap();
+
+    let output = run_script_on(
+        &dir,
+        path,
+        &[
+            "pragma write_mode = write_through",
+            "pragma commit_delay = 60000",
+            "insert 1 user1 person1@example.com; insert 2 user2 person2@example.com",
+        ],
+    );
+    assert_eq!(
+        output,
+        vec!["Executed.", "Executed.", "Executed.", "Executed.", ""]
+    );
+
+    let output = run_script_on(&dir, path, &["select"]);
+    assert_eq!(
+        output,
+        vec![
+            "(1, user1, person1@example.com)",
+            "(2, user2, person2@example.com)",
+            "Executed.",
+            ""
+        ]
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_snapshot_keeps_the_database_as_of_its_creation() {
+    let dir = test_dir("snapshot");
+    let path = dir.join("test.db");
+    let path = path.to_str().unwrap();
+
+    let output = run_script_on(
+        &dir,
+        path,
+        &[
+            "insert 1 user1 person1@example.com",
+            ".snapshot create before",
+            "insert 2 user2 person2@example.com",
+            ".snapshot open before",
+            "select",
+            "insert 3 user3 person3@example.com",
+            ".snapshot close",
+            "select",
+        ],
+    );
+    assert_eq!(
+        output,
+        vec![
+            "Executed.".to_owned(),
+            format!("Snapshot written to '{path}.before.snapshot'."),
+            "Executed.".to_owned(),
+            "Snapshot 'before' opened read-only, '.snapshot close' returns to the database."
+                .to_owned(),
+            "(1, user1, person1@example.com)".to_owned(),
+            "Executed.".to_owned(),
+            "Error: The database is read-only.".to_owned(),
+            "Back to the database.".to_owned(),
+            "(1, user1, person1@example.com)".to_owned(),
+            "(2, user2, person2@example.com)".to_owned(),
+            "Executed.".to_owned(),
+            String::new(),
+        ]
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_selects_from_a_csv_virtual_table() {
+    let dir = test_dir("csv");
+    let people_path = dir.join("people.csv");
+    std::fs::write(
+        &people_path,
+        "id,username,email\n3,carol,carol@mail.com\n1,\"alice, a\",alice@mail.com\n",
+    )
+    .unwrap();
+    let broken_path = dir.join("broken.csv");
+    std::fs::write(&broken_path, "1,alice,alice@mail.com\n\n2,bob\n").unwrap();
+    let (people_path, broken_path) = (people_path.to_str().unwrap(), broken_path.to_str().unwrap());
+
+    let output = run_script_on(
+        &dir,
+        ":memory:",
+        &[
+            &format!("create virtual table people using csv('{people_path}')"),
+            "select from people",
+            "select username from people where id between 2 and 3",
+            &format!("create virtual table broken using csv('{broken_path}')"),
+            "select from broken",
+            "create virtual table users using csv('users.csv')",
+        ],
+    );
+    assert_eq!(
+        output,
+        vec![
+            "Executed.",
+            "(1, alice, a, alice@mail.com)",
+            "(3, carol, carol@mail.com)",
+            "Executed.",
+            "(carol)",
+            "Executed.",
+            "Executed.",
+            "Error on line 3 of the CSV file:",
+            "Expected 3 values (id, username, email), found 2.",
+            "Table 'users' already exists.",
+            "",
+        ]
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_output_writes_results_to_a_file() {
+    let dir = test_dir("output");
+    let report_path = dir.join("report.txt");
+    let once_path = dir.join("once.txt");
+    let (report_path, once_path) = (report_path.to_str().unwrap(), once_path.to_str().unwrap());
+
+    let output = run_script_on(
+        &dir,
+        ":memory:",
+        &[
+            "insert 1 alice alice@mail.com",
+            &format!(".output {report_path}"),
+            "select",
+            ".mode table",
+            "select username",
+            ".output stdout",
+            &format!(".once {once_path}"),
+            "select id",
+            "select id",
+            ".once",
+        ],
+    );
+    assert_eq!(
+        output,
+        vec![
+            "Executed.",
+            "Executed.",
+            "Executed.",
+            "Executed.",
+            "id",
+            "--",
+            " 1",
+            "Executed.",
+            "No output file provided.",
+            "",
+        ]
+    );
+    assert_eq!(
+        std::fs::read_to_string(report_path).unwrap(),
+        "(1, alice, alice@mail.com)\nusername\n--------\nalice\n"
+    );
+    assert_eq!(std::fs::read_to_string(once_path).unwrap(), "id\n--\n 1\n");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_echo_prints_each_line_before_running_it() {
+    let output = run_script(&[
+        ".echo on",
+        "insert 1 alice alice@mail.com",
+        "select id",
+        ".echo off",
+        "select id",
+    ]);
+    assert_eq!(
+        output,
+        vec![
+            "insert 1 alice alice@mail.com",
+            "Executed.",
+            "select id",
+            "(1)",
+            "Executed.",
+            ".echo off",
+            "(1)",
+            "Executed.",
+            "",
+        ]
+    );
+}
+
+#[test]
+fn test_headers_apply_to_every_mode() {
+    let output = run_script(&[
+        "insert 1 alice alice@mail.com",
+        ".headers",
+        ".headers on",
+        "select email, id",
+        ".headers off",
+        ".mode table",
+        ".headers",
+        "select id",
+    ]);
+    assert_eq!(
+        output,
+        vec![
+            "Executed.",
+            "off",
+            "(email, id)",
+            "(alice@mail.com, 1)",
+            "Executed.",
+            "off",
+            " 1",
+            "Executed.",
+            "",
+        ]
+    );
+}
+
+#[test]
+fn test_import_json_reports_every_invalid_line() {
+    let dir = test_dir("import_json");
+    let valid_path = dir.join("valid.jsonl");
+    let invalid_path = dir.join("invalid.jsonl");
+    std::fs::write(
+        &valid_path,
+        "{\"id\": 2, \"username\": \"bob\", \"email\": \"bob@mail.com\"}\n\n\
+         {\"email\": \"carol@mail.com\", \"username\": \"carol\", \"id\": 3}\n",
+    )
+    .unwrap();
+    std::fs::write(
+        &invalid_path,
+        "{\"id\": 4, \"username\": \"dave\", \"email\": \"dave@mail.com\"}\n\
+         {\"id\": \"5\", \"username\": \"eve\", \"email\": \"eve@mail.com\"}\n\
+         {\"id\": 6, \"username\": \"frank\"}\n",
+    )
+    .unwrap();
+    let (valid_path, invalid_path) = (valid_path.to_str().unwrap(), invalid_path.to_str().unwrap());
+
+    let output = run_script_on(
+        &dir,
+        ":memory:",
+        &[
+            "insert 2 bob bob@mail.com",
+            &format!(".import-json {valid_path}"),
+            &format!(".import-json {invalid_path}"),
+            "select id",
+        ],
+    );
+    assert_eq!(
+        output,
+        vec![
+            "Executed.",
+            "Imported 1 rows, 1 rows skipped because their id already exists.",
+            "Error on line 2 of the JSON file:",
+            "Wrong type for 'id', expected a number for id and a string otherwise.",
+            "Error on line 3 of the JSON file:",
+            "Missing key 'email'.",
+            "(2)",
+            "(3)",
+            "Executed.",
+            "",
+        ]
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_points_at_the_offending_token() {
+    let output = run_script(&["select where (id > 5", "insert 1 'bob"]);
+    assert_eq!(
+        output,
+        vec![
+            "Expected ')', found the end of the statement.",
+            "select where (id > 5",
+            "                    ^",
+            "Unterminated string starting at column 10.",
+            "insert 1 'bob",
+            "         ^",
+            ""
+        ]
+    );
+}
+
+#[test]
+fn test_history_reruns_previous_lines() {
+    let output = run_script(&[
+        "insert 1 user1 person1@example.com",
+        "select",
+        "insert 2 user2 person2@example.com",
+        "!2",
+        ".history 2",
+        "!!",
+        "!9",
+    ]);
+    assert_eq!(
+        output,
+        vec![
+            "Executed.",
+            "(1, user1, person1@example.com)",
+            "Executed.",
+            "Executed.",
+            "select",
+            "(1, user1, person1@example.com)",
+            "(2, user2, person2@example.com)",
+            "Executed.",
+            "    4  select",
+            "    5  .history 2",
+            ".history 2",
+            // Une ligne identique à la précédente n'est gardée qu'une fois.
+            "    4  select",
+            "    5  .history 2",
+            "!9: no such line in the history.",
+            ""
+        ]
+    );
+}
+
+#[test]
+fn test_undo_reverts_the_last_statements() {
+    let output = run_script(&[
+        "insert 1 user1 person1@example.com",
+        "insert 2 user2 person2@example.com",
+        "delete",
+        ".undo",
+        ".undo",
+        "select",
+        ".undo",
+        ".undo",
+    ]);
+    assert_eq!(
+        output,
+        vec![
+            "Executed.",
+            "Executed.",
+            "Executed.",
+            "Delete undone, 2 rows restored.",
+            "Insert of the row 2 undone.",
+            "(1, user1, person1@example.com)",
+            "Executed.",
+            "Insert of the row 1 undone.",
+            "Nothing to undo.",
+            ""
+        ]
+    );
+}
+
+#[test]
+fn test_size_reports_pages_and_rows() {
+    let output = run_script(&[
+        "insert 1 user1 person1@example.com",
+        "insert 2 user2 person2@example.com",
+        ".size",
+    ]);
+    assert_eq!(
+        output,
+        vec![
+            "Executed.",
+            "Executed.",
+            "File: 0 bytes.",
+            "Cache: 1 page (4096 bytes).",
+            "Pages: 2 (8192 bytes).",
+            "Rows: 2.",
+            "Average: 2048.0 bytes per row, for rows of 295 bytes.",
+            ""
+        ]
+    );
+}